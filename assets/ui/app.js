@@ -0,0 +1,134 @@
+// Browser UI for `iab serve --ui`: a lazily expanded tree and a search box over the JSON API.
+"use strict";
+
+const taxonomySelect = document.getElementById("taxonomy");
+const searchInput = document.getElementById("search");
+const tree = document.getElementById("tree");
+const results = document.getElementById("results");
+const details = document.getElementById("details");
+
+let base = "";
+let selected = null;
+
+async function api(path) {
+  const response = await fetch(base + path);
+  const body = await response.json();
+  if (!response.ok) {
+    throw new Error(body.error || response.statusText);
+  }
+  return body;
+}
+
+function element(tag, className, text) {
+  const node = document.createElement(tag);
+  if (className) node.className = className;
+  if (text !== undefined) node.textContent = text;
+  return node;
+}
+
+function nodeItem(row) {
+  const item = element("li");
+  const line = element("div", "node");
+  const toggle = element("span", "toggle", "▸");
+  const label = element("span", "label", row.name);
+  line.append(toggle, label, element("span", "id", row.id));
+  item.append(line);
+
+  let children = null;
+  toggle.addEventListener("click", async () => {
+    if (children) {
+      children.hidden = !children.hidden;
+      toggle.textContent = children.hidden ? "▸" : "▾";
+      return;
+    }
+    const rows = await api(`/node/${encodeURIComponent(row.id)}/children`);
+    if (rows.length === 0) {
+      toggle.textContent = "";
+      toggle.style.cursor = "default";
+      return;
+    }
+    children = element("ul");
+    children.append(...rows.map(nodeItem));
+    item.append(children);
+    toggle.textContent = "▾";
+  });
+  label.addEventListener("click", () => {
+    if (selected) selected.classList.remove("selected");
+    selected = line;
+    line.classList.add("selected");
+    showDetails(row);
+  });
+  return item;
+}
+
+async function showDetails(row) {
+  const ancestors = await api(`/node/${encodeURIComponent(row.id)}/ancestors`);
+  const fields = [
+    ["Unique ID", row.id],
+    ["Parent ID", row.parent || ""],
+    ["Name", row.name],
+    ["Path", ancestors.map((ancestor) => ancestor.name).concat(row.name).join(" > ")],
+    ...row.tiers.map((tier, index) => [`Tier ${index + 1}`, tier]),
+  ];
+  if (row.extension) fields.push(["Extension", row.extension]);
+
+  const list = element("dl");
+  for (const [name, value] of fields) {
+    list.append(element("dt", "", name), element("dd", "", value));
+  }
+  details.replaceChildren(element("h2", "", row.name), list);
+}
+
+async function loadTree() {
+  tree.replaceChildren();
+  details.replaceChildren(element("p", "hint", "Select a category to see its details."));
+  const rows = await api("/roots");
+  tree.append(...rows.map(nodeItem));
+}
+
+let searchTimer = null;
+searchInput.addEventListener("input", () => {
+  clearTimeout(searchTimer);
+  searchTimer = setTimeout(runSearch, 200);
+});
+
+async function runSearch() {
+  const query = searchInput.value.trim();
+  if (!query) {
+    results.hidden = true;
+    tree.hidden = false;
+    return;
+  }
+  const rows = await api(`/search?q=${encodeURIComponent(query)}&limit=100`);
+  results.replaceChildren(
+    ...rows.map((row) => {
+      const item = element("li");
+      item.append(element("span", "", row.name + " "), element("span", "id", row.id));
+      item.append(element("div", "path", row.tiers.join(" > ")));
+      item.addEventListener("click", () => showDetails(row));
+      return item;
+    }),
+  );
+  if (rows.length === 0) results.append(element("li", "hint", "No matches"));
+  results.hidden = false;
+  tree.hidden = true;
+}
+
+taxonomySelect.addEventListener("change", () => {
+  base = taxonomySelect.value;
+  searchInput.value = "";
+  runSearch();
+  loadTree();
+});
+
+(async () => {
+  const taxonomies = await fetch("/taxonomies").then((response) => response.json());
+  for (const taxonomy of taxonomies) {
+    const option = element("option", "", `${taxonomy.name} ${taxonomy.version}`);
+    option.value = taxonomy.path;
+    taxonomySelect.append(option);
+  }
+  taxonomySelect.value = (taxonomies.find((taxonomy) => taxonomy.name === "Content") || taxonomies[0]).path;
+  base = taxonomySelect.value;
+  await loadTree();
+})().catch((error) => details.replaceChildren(element("p", "hint", `Failed to load: ${error.message}`)));
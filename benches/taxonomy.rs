@@ -0,0 +1,75 @@
+//! Parse, index and filter timings on the bundled taxonomies and a synthetic 100k-node one.
+//!
+//! Run with `cargo bench`; compare runs to catch regressions in the tree and filter code.
+
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, Criterion};
+use criterion::measurement::WallTime;
+use iab::taxonomy::read_tsv;
+use iab::{ChildIndex, Content, Haystack, Taxonomies, Taxonomy};
+use std::hint::black_box;
+
+const SYNTHETIC_NODES: usize = 100_000;
+const WORDS: [&str; 12] = [
+    "Sports", "Travel", "Finance", "Music", "Cricket", "Gardening", "Cars", "Health", "Movies", "Education", "Pets", "Food",
+];
+
+/// Content-style TSV with ten roots and ten children per node, names drawn from `WORDS`
+fn synthetic_tsv(nodes: usize) -> String {
+    let mut tsv = String::from("Unique ID\tParent\tName\tTier 1\tTier 2\tTier 3\tTier 4\tExtension\n");
+    let mut paths: Vec<Vec<String>> = Vec::with_capacity(nodes);
+    for id in 0..nodes {
+        let name = format!("{} {} {}", WORDS[id % WORDS.len()], WORDS[(id / 7) % WORDS.len()], id);
+        let parent = (id >= 10).then(|| (id - 10) / 10);
+        let mut path = parent.map(|parent| paths[parent].clone()).unwrap_or_default();
+        path.push(name.clone());
+        let mut tiers: Vec<&str> = path.iter().take(4).map(String::as_str).collect();
+        tiers.resize(4, "");
+        let parent = parent.map(|parent| parent.to_string()).unwrap_or_default();
+        tsv.push_str(&format!("{}\t{}\t{}\t{}\t\n", id, parent, name, tiers.join("\t")));
+        paths.push(path);
+    }
+    tsv
+}
+
+/// What the TUI does per filter change: match the haystacks, then add ancestors and descendants
+fn filter(haystacks: &[Haystack], index: &ChildIndex, filter_lower: &str) -> usize {
+    let matches = haystacks.iter().enumerate().filter(|(_, haystack)| haystack.matches(filter_lower));
+    index.expand_matches(matches.map(|(position, _)| position)).len()
+}
+
+fn bench_filters(group: &mut BenchmarkGroup<WallTime>, haystacks: &[Haystack], index: &ChildIndex) {
+    // A broad word matching many branches and a narrow one matching few
+    for query in ["sport", "cricket 4"] {
+        group.bench_function(format!("filter {:?}", query), |b| b.iter(|| filter(haystacks, index, black_box(query))));
+    }
+}
+
+fn bundled(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bundled");
+    group.bench_function("parse all", |b| b.iter(|| Taxonomies::load(|_| {}).unwrap()));
+
+    let taxonomies = Taxonomies::load(|_| {}).unwrap();
+    group.bench_function("index content", |b| b.iter(|| ChildIndex::new(black_box(&taxonomies.content))));
+    let index = taxonomies.child_index(Taxonomy::Content);
+    bench_filters(&mut group, &taxonomies.haystacks[Taxonomy::Content.index()], index);
+    group.finish();
+}
+
+fn synthetic(c: &mut Criterion) {
+    let tsv = synthetic_tsv(SYNTHETIC_NODES);
+    let mut group = c.benchmark_group("synthetic 100k");
+    group.sample_size(10);
+    group.bench_function("parse", |b| b.iter(|| read_tsv::<Content>(black_box(&tsv)).unwrap()));
+
+    let (items, _) = read_tsv::<Content>(&tsv).unwrap();
+    group.bench_function("index", |b| b.iter(|| ChildIndex::new(black_box(&items))));
+    group.bench_function("haystacks", |b| b.iter(|| items.iter().map(Haystack::new).collect::<Vec<_>>()));
+
+    let index = ChildIndex::new(&items);
+    let haystacks: Vec<Haystack> = items.iter().map(Haystack::new).collect();
+    bench_filters(&mut group, &haystacks, &index);
+    group.finish();
+}
+
+criterion_group!(benches, bundled, synthetic);
+criterion_main!(benches);
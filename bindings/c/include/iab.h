@@ -0,0 +1,36 @@
+/* C API for the IAB Tech Lab taxonomies, built from bindings/c (libiab_ffi.so / libiab_ffi.a).
+ *
+ * Taxonomies are addressed by their OpenRTB cattax value: 4 Audience 1.1, 7 Content 3.x, 8 Product 2.0.
+ * Returned strings belong to the caller and must be released with iab_string_free.
+ */
+#ifndef IAB_H
+#define IAB_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct IabTaxonomies IabTaxonomies;
+
+/* Parse the bundled taxonomies; NULL on failure. Release with iab_free. */
+IabTaxonomies *iab_load(void);
+void iab_free(IabTaxonomies *taxonomies);
+
+/* Category name, or NULL when the cattax or ID is unknown. */
+char *iab_lookup(const IabTaxonomies *taxonomies, uint32_t cattax, const char *id);
+
+/* Parent ID, or NULL for top-level and unknown categories. */
+char *iab_parent(const IabTaxonomies *taxonomies, uint32_t cattax, const char *id);
+
+/* Names from the root down joined with separator, e.g. "Sports > Cricket"; NULL when unknown. */
+char *iab_resolve_path(const IabTaxonomies *taxonomies, uint32_t cattax, const char *id, const char *separator);
+
+void iab_string_free(char *string);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,109 @@
+//! C API over the library, declared in `include/iab.h`
+//!
+//! Taxonomies are addressed by their OpenRTB `cattax` value. Every returned string is owned by the
+//! caller and must be released with `iab_string_free`.
+
+use iab::{Taxonomies, Taxonomy, TaxonomyItem};
+use std::ffi::{CStr, CString, c_char};
+use std::ptr;
+
+/// Opaque handle for C; wraps the parsed taxonomies
+pub struct IabTaxonomies(Taxonomies);
+
+/// Parse the bundled taxonomies, or return NULL on failure
+#[unsafe(no_mangle)]
+pub extern "C" fn iab_load() -> *mut IabTaxonomies {
+    match Taxonomies::load(|_| {}) {
+        Ok(taxonomies) => Box::into_raw(Box::new(IabTaxonomies(taxonomies))),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+/// `taxonomies` must come from `iab_load` and not be used afterwards; NULL is ignored.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iab_free(taxonomies: *mut IabTaxonomies) {
+    if !taxonomies.is_null() {
+        drop(unsafe { Box::from_raw(taxonomies) });
+    }
+}
+
+/// Name of the category, or NULL when the cattax or ID is unknown
+///
+/// # Safety
+/// `taxonomies` must come from `iab_load`; `id` must be a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iab_lookup(taxonomies: *const IabTaxonomies, cattax: u32, id: *const c_char) -> *mut c_char {
+    match unsafe { find(taxonomies, cattax, id) } {
+        Some((_, item)) => to_c(item.name()),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Parent ID of the category, or NULL for top-level and unknown categories
+///
+/// # Safety
+/// `taxonomies` must come from `iab_load`; `id` must be a NUL-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iab_parent(taxonomies: *const IabTaxonomies, cattax: u32, id: *const c_char) -> *mut c_char {
+    let parent = unsafe { find(taxonomies, cattax, id) }
+        .and_then(|(_, item)| item.parent().filter(|parent| !parent.is_empty() && *parent != item.unique_id()));
+    match parent {
+        Some(parent) => to_c(parent),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Names from the root down to the category joined with `separator` (e.g. "Sports > Cricket"),
+/// or NULL when the cattax or ID is unknown
+///
+/// # Safety
+/// `taxonomies` must come from `iab_load`; `id` and `separator` must be NUL-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iab_resolve_path(
+    taxonomies: *const IabTaxonomies,
+    cattax: u32,
+    id: *const c_char,
+    separator: *const c_char,
+) -> *mut c_char {
+    let (Some((taxonomy, item)), Some(separator)) = (unsafe { find(taxonomies, cattax, id) }, unsafe { str_arg(separator) })
+    else {
+        return ptr::null_mut();
+    };
+    let inner = unsafe { &(*taxonomies).0 };
+    let mut names: Vec<&str> = inner.ancestors(taxonomy, item.unique_id()).iter().map(|ancestor| ancestor.name()).collect();
+    names.push(item.name());
+    to_c(&names.join(separator))
+}
+
+/// # Safety
+/// `string` must come from this library and not be used afterwards; NULL is ignored.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn iab_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
+
+unsafe fn find<'a>(
+    taxonomies: *const IabTaxonomies,
+    cattax: u32,
+    id: *const c_char,
+) -> Option<(Taxonomy, &'a dyn TaxonomyItem)> {
+    let taxonomies = unsafe { taxonomies.as_ref() }?;
+    let taxonomy = Taxonomy::for_cattax(cattax).ok()?;
+    let id = unsafe { str_arg(id) }?;
+    Some((taxonomy, taxonomies.0.item(taxonomy, id)?))
+}
+
+unsafe fn str_arg<'a>(value: *const c_char) -> Option<&'a str> {
+    if value.is_null() {
+        return None;
+    }
+    unsafe { CStr::from_ptr(value) }.to_str().ok()
+}
+
+/// Taxonomy names never contain NUL, but fall back to NULL rather than panicking across the boundary
+fn to_c(value: &str) -> *mut c_char {
+    CString::new(value).map_or(ptr::null_mut(), CString::into_raw)
+}
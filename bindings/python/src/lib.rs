@@ -0,0 +1,105 @@
+//! Python bindings: the `iab` module built with maturin from this crate
+
+use iab::crosswalk;
+use iab::{Crosswalk, Crosswalks as CrosswalkSet, Taxonomies as TaxonomySet, Taxonomy, TaxonomyItem};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::path::PathBuf;
+
+fn value_error(error: anyhow::Error) -> PyErr {
+    PyValueError::new_err(format!("{:#}", error))
+}
+
+fn taxonomy(key: &str) -> PyResult<Taxonomy> {
+    Taxonomy::from_key(key)
+        .ok_or_else(|| PyValueError::new_err(format!("unknown taxonomy {} (use product, content or audience)", key)))
+}
+
+/// Nodes become plain dicts with `id`, `parent`, `name`, `tiers` and `extension`
+fn node<'py>(py: Python<'py>, item: &dyn TaxonomyItem) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("id", item.unique_id())?;
+    dict.set_item("parent", item.parent().filter(|parent| !parent.is_empty() && *parent != item.unique_id()))?;
+    dict.set_item("name", item.name())?;
+    dict.set_item("tiers", item.tiers())?;
+    dict.set_item("extension", item.extension().filter(|extension| !extension.is_empty()))?;
+    Ok(dict)
+}
+
+/// The bundled Product, Content and Audience taxonomies
+#[pyclass(frozen)]
+struct Taxonomies {
+    inner: TaxonomySet,
+}
+
+#[pymethods]
+impl Taxonomies {
+    /// Version of the bundled taxonomy, e.g. "3.1" for content
+    fn version(&self, taxonomy_key: &str) -> PyResult<&'static str> {
+        Ok(taxonomy(taxonomy_key)?.version())
+    }
+
+    /// The node with `id`, or None
+    fn lookup<'py>(&self, py: Python<'py>, taxonomy_key: &str, id: &str) -> PyResult<Option<Bound<'py, PyDict>>> {
+        self.inner.item(taxonomy(taxonomy_key)?, id).map(|item| node(py, item)).transpose()
+    }
+
+    /// Parents of `id` from the root down
+    fn ancestors<'py>(&self, py: Python<'py>, taxonomy_key: &str, id: &str) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.inner.ancestors(taxonomy(taxonomy_key)?, id).into_iter().map(|item| node(py, item)).collect()
+    }
+
+    /// Nodes whose ID or name contains `query`, ignoring case
+    #[pyo3(signature = (taxonomy_key, query, limit = 100))]
+    fn search<'py>(
+        &self,
+        py: Python<'py>,
+        taxonomy_key: &str,
+        query: &str,
+        limit: usize,
+    ) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        self.inner.search(taxonomy(taxonomy_key)?, query, limit).into_iter().map(|item| node(py, item)).collect()
+    }
+}
+
+/// Content crosswalk files, chained like `iab map`
+#[pyclass(frozen)]
+struct Crosswalks {
+    inner: CrosswalkSet,
+}
+
+#[pymethods]
+impl Crosswalks {
+    #[new]
+    fn new(paths: Vec<PathBuf>) -> PyResult<Self> {
+        let mut inner = CrosswalkSet::default();
+        for path in &paths {
+            inner.add(Crosswalk::load(path).map_err(value_error)?);
+        }
+        Ok(Self { inner })
+    }
+
+    /// Equivalent IDs of `id`; versions may also be given as Content cattax values ("1" for legacy IAB codes)
+    fn map(&self, from_version: &str, to_version: &str, id: &str) -> PyResult<Vec<String>> {
+        let from = crosswalk::resolve_version(from_version).map_err(value_error)?;
+        let to = crosswalk::resolve_version(to_version).map_err(value_error)?;
+        self.inner.map_category(&from, &to, id).map_err(value_error)
+    }
+}
+
+/// Parse the bundled taxonomies
+#[pyfunction]
+fn load() -> PyResult<Taxonomies> {
+    let inner = TaxonomySet::load(|_| {}).map_err(value_error)?;
+    Ok(Taxonomies { inner })
+}
+
+#[pymodule]
+#[pyo3(name = "iab")]
+fn iab_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(load, module)?)?;
+    module.add_class::<Taxonomies>()?;
+    module.add_class::<Crosswalks>()?;
+    Ok(())
+}
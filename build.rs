@@ -0,0 +1,10 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(feature = "grpc")]
+    {
+        // Use the vendored protoc so building the gRPC service needs no system install
+        // SAFETY: build scripts run single-threaded
+        unsafe { std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?) };
+        tonic_prost_build::compile_protos("proto/iab.proto")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use anyhow::*;
+use iab::Taxonomy;
+use iab::ortb2::{self, Channel};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::state;
+
+/// A node collected into the basket
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BasketEntry {
+    /// Taxonomy key, e.g. `content`
+    pub taxonomy: String,
+    pub id: String,
+}
+
+impl BasketEntry {
+    pub fn taxonomy(&self) -> Option<Taxonomy> {
+        Taxonomy::from_key(&self.taxonomy)
+    }
+}
+
+/// Nodes collected across taxonomies in the order they were added, persisted as JSON in the
+/// state directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Basket {
+    entries: Vec<BasketEntry>,
+}
+
+impl Basket {
+    /// Load the basket from disk; a missing file yields an empty one
+    pub fn load() -> Result<Self> {
+        Ok(state::BASKET.load()?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        state::BASKET.save(self)
+    }
+
+    pub fn entries(&self) -> &[BasketEntry] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add or remove a node, returning whether it is in the basket afterwards
+    pub fn toggle(&mut self, taxonomy: Taxonomy, id: &str) -> bool {
+        let entry = BasketEntry { taxonomy: taxonomy.key().to_string(), id: id.to_string() };
+        if let Some(pos) = self.entries.iter().position(|existing| *existing == entry) {
+            self.entries.remove(pos);
+            false
+        } else {
+            self.entries.push(entry);
+            true
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.entries.len() {
+            self.entries.remove(index);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// What the basket is exported and copied as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BasketFormat {
+    Csv,
+    Json,
+    /// OpenRTB `ortb2` fragment: content categories in `site.content`, Audience segments in `user.data`
+    Ortb2,
+}
+
+impl BasketFormat {
+    pub fn next(self) -> Self {
+        match self {
+            BasketFormat::Csv => BasketFormat::Json,
+            BasketFormat::Json => BasketFormat::Ortb2,
+            BasketFormat::Ortb2 => BasketFormat::Csv,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BasketFormat::Csv => "CSV",
+            BasketFormat::Json => "JSON",
+            BasketFormat::Ortb2 => "ortb2",
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            BasketFormat::Csv => "iab-basket.csv",
+            BasketFormat::Json => "iab-basket.json",
+            BasketFormat::Ortb2 => "iab-basket-ortb2.json",
+        }
+    }
+}
+
+/// A basket entry resolved against the loaded taxonomies
+#[derive(Debug, Serialize)]
+pub struct BasketRow {
+    pub taxonomy: &'static str,
+    pub id: String,
+    pub name: String,
+    pub tiers: Vec<String>,
+}
+
+/// The rows as text in `format`; entries of taxonomies not in `rows` are the caller's to skip
+pub fn render(rows: &[(Taxonomy, BasketRow)], format: BasketFormat) -> Result<String> {
+    match format {
+        BasketFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            writer.write_record(["taxonomy", "id", "name", "path"])?;
+            for (_, row) in rows {
+                writer.write_record([row.taxonomy, &row.id, &row.name, &row.tiers.join(" > ")])?;
+            }
+            Ok(String::from_utf8(writer.into_inner()?)?)
+        }
+        BasketFormat::Json => {
+            let rows: Vec<&BasketRow> = rows.iter().map(|(_, row)| row).collect();
+            Ok(serde_json::to_string_pretty(&rows)?)
+        }
+        BasketFormat::Ortb2 => Ok(serde_json::to_string_pretty(&ortb2_fragment(rows)?)?),
+    }
+}
+
+/// Content categories of one taxonomy under `site.content` plus Audience segments under `user.data`
+fn ortb2_fragment(rows: &[(Taxonomy, BasketRow)]) -> Result<Value> {
+    let ids = |taxonomy: Taxonomy| -> Vec<String> {
+        rows.iter().filter(|(of, _)| *of == taxonomy).map(|(_, row)| row.id.clone()).collect()
+    };
+    let content: Vec<Taxonomy> =
+        [Taxonomy::Product, Taxonomy::Content].into_iter().filter(|&taxonomy| !ids(taxonomy).is_empty()).collect();
+    let segments = ids(Taxonomy::Audience);
+
+    let mut fragment = match content.as_slice() {
+        [] => Value::Object(Default::default()),
+        [taxonomy] => ortb2::content_fragment(*taxonomy, &ids(*taxonomy), Channel::Site)?,
+        _ => bail!("ortb2 declares one cattax per request, so the basket may hold Product or Content categories, not both"),
+    };
+    if !segments.is_empty() {
+        fragment["user"] = ortb2::user_data_fragment(&segments, None)["user"].take();
+    }
+    if content.is_empty() && segments.is_empty() {
+        bail!("the basket is empty");
+    }
+    Ok(fragment)
+}
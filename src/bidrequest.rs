@@ -0,0 +1,184 @@
+//! Checks the category fields of OpenRTB 2.x bid requests against the bundled taxonomies.
+
+use anyhow::*;
+use serde_json::Value;
+use std::fmt;
+
+use crate::{Badge, Taxonomies, Taxonomy};
+
+/// OpenRTB's default when an object omits `cattax`
+const DEFAULT_CATTAX: u64 = 1;
+
+/// Objects that carry categories, each optionally holding a `content` object
+const DISTRIBUTION_CHANNELS: [&str; 3] = ["site", "app", "dooh"];
+const CHANNEL_CATEGORY_FIELDS: [&str; 3] = ["cat", "sectioncat", "pagecat"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// The ID does not exist in the declared taxonomy
+    Unknown,
+    /// The ID exists but is marked deprecated
+    Deprecated,
+    /// The declared `cattax` cannot be checked against a bundled taxonomy
+    Unchecked(String),
+}
+
+/// One offending category value, addressed by its JSON path
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub path: String,
+    pub id: String,
+    pub problem: Problem,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.problem {
+            Problem::Unknown => write!(f, "{} \"{}\": unknown ID", self.path, self.id),
+            Problem::Deprecated => write!(f, "{} \"{}\": deprecated ID", self.path, self.id),
+            Problem::Unchecked(reason) => write!(f, "{}: not checked, {}", self.path, reason),
+        }
+    }
+}
+
+/// Findings that apply to one impression of the request
+#[derive(Debug, Clone)]
+pub struct ImpReport {
+    pub imp_id: String,
+    pub findings: Vec<Finding>,
+}
+
+/// Validate `cat`, `sectioncat`, `pagecat` and `content.cat` of a bid request.
+/// Categories live on the distribution channel, so every impression shares its findings.
+pub fn validate(request: &Value, taxonomies: &Taxonomies) -> Result<Vec<ImpReport>> {
+    let request = request.as_object().context("bid request is not a JSON object")?;
+
+    let mut findings = Vec::new();
+    for channel in DISTRIBUTION_CHANNELS {
+        let Some(object) = request.get(channel) else {
+            continue;
+        };
+        check_object(object, channel, &CHANNEL_CATEGORY_FIELDS, taxonomies, &mut findings)?;
+        if let Some(content) = object.get("content") {
+            check_object(content, &format!("{}.content", channel), &["cat"], taxonomies, &mut findings)?;
+        }
+    }
+
+    let imp_ids: Vec<String> = match request.get("imp").and_then(Value::as_array) {
+        Some(imps) => imps
+            .iter()
+            .enumerate()
+            .map(|(index, imp)| match imp.get("id").and_then(Value::as_str) {
+                Some(id) => id.to_string(),
+                None => format!("#{}", index + 1),
+            })
+            .collect(),
+        None => bail!("bid request has no imp array"),
+    };
+
+    Ok(imp_ids
+        .into_iter()
+        .map(|imp_id| ImpReport { imp_id, findings: findings.clone() })
+        .collect())
+}
+
+fn check_object(
+    object: &Value,
+    path: &str,
+    fields: &[&str],
+    taxonomies: &Taxonomies,
+    findings: &mut Vec<Finding>,
+) -> Result<()> {
+    let declared = object.get("cattax").and_then(Value::as_u64);
+    let cattax = u32::try_from(declared.unwrap_or(DEFAULT_CATTAX)).unwrap_or(u32::MAX);
+    let taxonomy = Taxonomy::for_cattax(cattax).map_err(|err| match declared {
+        Some(_) => err.to_string(),
+        None => format!("{} (OpenRTB default when cattax is omitted)", err),
+    });
+
+    for field in fields {
+        let Some(values) = object.get(*field) else {
+            continue;
+        };
+        let field_path = format!("{}.{}", path, field);
+        let values = values.as_array().with_context(|| format!("{} is not an array", field_path))?;
+        if values.is_empty() {
+            continue;
+        }
+
+        let taxonomy = match &taxonomy {
+            Result::Ok(taxonomy) => *taxonomy,
+            Err(reason) => {
+                findings.push(Finding { path: field_path, id: String::new(), problem: Problem::Unchecked(reason.clone()) });
+                continue;
+            }
+        };
+
+        for (index, value) in values.iter().enumerate() {
+            let value_path = format!("{}[{}]", field_path, index);
+            let id = value.as_str().with_context(|| format!("{} is not a string", value_path))?;
+            let problem = match taxonomies.item(taxonomy, id) {
+                None => Problem::Unknown,
+                Some(item) if item.badge() == Some(Badge::Deprecated) => Problem::Deprecated,
+                Some(_) => continue,
+            };
+            findings.push(Finding { path: value_path, id: id.to_string(), problem });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn findings(request: Value) -> Vec<Finding> {
+        let taxonomies = Taxonomies::load(|_| {}).expect("bundled data loads");
+        let reports = validate(&request, &taxonomies).unwrap();
+        assert!(reports.iter().all(|report| report.findings.len() == reports[0].findings.len()));
+        reports.into_iter().next().expect("one report per impression").findings
+    }
+
+    #[test]
+    fn valid_request_has_no_findings() {
+        let request = json!({
+            "imp": [{ "id": "1" }, {}],
+            "site": { "cattax": 7, "cat": ["150"], "pagecat": ["151"], "content": { "cattax": 7, "cat": ["150"] } },
+        });
+        let taxonomies = Taxonomies::load(|_| {}).unwrap();
+        let reports = validate(&request, &taxonomies).unwrap();
+        let imp_ids: Vec<_> = reports.iter().map(|report| report.imp_id.as_str()).collect();
+        assert_eq!(imp_ids, ["1", "#2"]);
+        assert!(reports.iter().all(|report| report.findings.is_empty()));
+    }
+
+    #[test]
+    fn unknown_ids_are_reported_by_path() {
+        let found = findings(json!({ "imp": [{ "id": "1" }], "app": { "cattax": 7, "cat": ["150", "nope"] } }));
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].path.as_str(), found[0].id.as_str()), ("app.cat[1]", "nope"));
+        assert_eq!(found[0].problem, Problem::Unknown);
+    }
+
+    #[test]
+    fn unbundled_cattax_is_left_unchecked() {
+        for site in [json!({ "cattax": 99, "cat": ["150"] }), json!({ "cat": ["IAB1"] })] {
+            let found = findings(json!({ "imp": [{ "id": "1" }], "site": site }));
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].path, "site.cat");
+            assert!(matches!(found[0].problem, Problem::Unchecked(_)));
+        }
+    }
+
+    #[test]
+    fn missing_or_malformed_cat_arrays() {
+        assert!(findings(json!({ "imp": [{ "id": "1" }], "site": { "cattax": 7 } })).is_empty());
+        assert!(findings(json!({ "imp": [{ "id": "1" }], "site": { "cattax": 7, "cat": [] } })).is_empty());
+
+        let taxonomies = Taxonomies::load(|_| {}).unwrap();
+        let not_array = json!({ "imp": [{ "id": "1" }], "site": { "cattax": 7, "cat": "150" } });
+        assert!(validate(&not_array, &taxonomies).is_err());
+        assert!(validate(&json!({ "site": { "cattax": 7, "cat": ["150"] } }), &taxonomies).is_err());
+    }
+}
@@ -0,0 +1,39 @@
+use anyhow::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::state;
+
+/// Starred node IDs per taxonomy, persisted as JSON in the state directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Bookmarks {
+    taxonomies: BTreeMap<String, Vec<String>>,
+}
+
+impl Bookmarks {
+    /// Load bookmarks from disk; a missing file yields an empty set
+    pub fn load() -> Result<Self> {
+        Ok(state::BOOKMARKS.load()?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        state::BOOKMARKS.save(self)
+    }
+
+    pub fn ids(&self, taxonomy: &str) -> &[String] {
+        self.taxonomies.get(taxonomy).map(|ids| ids.as_slice()).unwrap_or(&[])
+    }
+
+    /// Star or unstar `id`, returning whether it is bookmarked afterwards
+    pub fn toggle(&mut self, taxonomy: &str, id: &str) -> bool {
+        let ids = self.taxonomies.entry(taxonomy.to_string()).or_default();
+        if let Some(pos) = ids.iter().position(|bookmarked| bookmarked == id) {
+            ids.remove(pos);
+            false
+        } else {
+            ids.push(id.to_string());
+            true
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! OpenRTB/AdCOM taxonomy identifiers, as used by the `cattax` field of bid requests
+//! and the `segtax` extension of `data` segments.
+
+use anyhow::*;
+
+use crate::Taxonomy;
+
+/// One entry of the AdCOM "Category Taxonomies" list
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CatTax {
+    pub value: u32,
+    pub name: &'static str,
+    /// The bundled taxonomy that answers for this value, if any
+    pub bundled: Option<Taxonomy>,
+}
+
+/// Values at or above this are reserved for vendor-specific taxonomies
+pub const VENDOR_SPECIFIC: u32 = 500;
+
+pub const CATTAX: [CatTax; 8] = [
+    CatTax { value: 1, name: "Content Category Taxonomy 1.0", bundled: None },
+    CatTax { value: 2, name: "Content Category Taxonomy 2.0", bundled: None },
+    CatTax { value: 3, name: "Ad Product Taxonomy 1.0", bundled: None },
+    CatTax { value: 4, name: "Audience Taxonomy 1.1", bundled: Some(Taxonomy::Audience) },
+    CatTax { value: 5, name: "Content Taxonomy 2.1", bundled: None },
+    CatTax { value: 6, name: "Content Taxonomy 2.2", bundled: None },
+    // Content 3.1 is a backwards compatible revision of 3.0, so the bundled file serves both
+    CatTax { value: 7, name: "Content Taxonomy 3.0", bundled: Some(Taxonomy::Content) },
+    CatTax { value: 8, name: "Ad Product Taxonomy 2.0", bundled: Some(Taxonomy::Product) },
+];
+
+/// Look up a `cattax` value in the AdCOM list
+pub fn describe(value: u32) -> Option<&'static CatTax> {
+    CATTAX.iter().find(|cattax| cattax.value == value)
+}
+
+/// Version of a Content taxonomy `cattax` value, whether bundled or not
+pub fn content_version(value: u32) -> Option<&'static str> {
+    match value {
+        1 => Some("1.0"),
+        2 => Some("2.0"),
+        5 => Some("2.1"),
+        6 => Some("2.2"),
+        7 => Some("3.0"),
+        _ => None,
+    }
+}
+
+impl Taxonomy {
+    /// Resolve an OpenRTB `cattax` value to a bundled taxonomy
+    pub fn for_cattax(value: u32) -> Result<Self> {
+        resolve("cattax", value)
+    }
+
+    /// Resolve a `segtax` value; IAB segment taxonomies share the `cattax` numbering
+    pub fn for_segtax(value: u32) -> Result<Self> {
+        resolve("segtax", value)
+    }
+
+    /// The `cattax` value identifying this taxonomy in OpenRTB
+    pub fn cattax(self) -> u32 {
+        match self {
+            Taxonomy::Product => 8,
+            Taxonomy::Content => 7,
+            Taxonomy::Audience => 4,
+        }
+    }
+}
+
+fn resolve(field: &str, value: u32) -> Result<Taxonomy> {
+    if value >= VENDOR_SPECIFIC {
+        bail!("{} {} is vendor specific", field, value);
+    }
+    let cattax = describe(value).with_context(|| format!("unknown {} {}", field, value))?;
+    cattax
+        .bundled
+        .with_context(|| format!("{} {} ({}) is not bundled", field, value, cattax.name))
+}
@@ -0,0 +1,137 @@
+//! Copy text to the system clipboard through the platform's copy command, so no clipboard
+//! library or display connection has to be linked in, or through the terminal with the OSC 52
+//! escape sequence, which reaches the local clipboard from an SSH session.
+
+use anyhow::*;
+use std::io::{ErrorKind, IsTerminal, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How `copy` reaches the clipboard, from `clipboard` in the config
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum Backend {
+    /// OSC 52 over SSH, else the copy commands with OSC 52 as the fallback
+    #[default]
+    Auto,
+    /// Only the platform's copy commands
+    Command,
+    /// Only the terminal, with OSC 52
+    Osc52,
+}
+
+static BACKEND: AtomicU8 = AtomicU8::new(0);
+
+pub fn set_backend(backend: Backend) {
+    BACKEND.store(backend as u8, Ordering::Relaxed);
+}
+
+fn backend() -> Backend {
+    match BACKEND.load(Ordering::Relaxed) {
+        1 => Backend::Command,
+        2 => Backend::Osc52,
+        _ => Backend::Auto,
+    }
+}
+
+/// Name `copy` returns for the escape sequence
+const OSC52: &str = "OSC 52";
+
+/// Copy commands in the order they are tried, with their arguments
+const COMMANDS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("clip.exe", &[]),
+];
+
+/// Copy `text`, returning the command (or `OSC 52`) that took it
+pub fn copy(text: &str) -> Result<&'static str> {
+    match backend() {
+        Backend::Command => copy_with_command(text),
+        Backend::Osc52 => copy_with_osc52(text),
+        // The copy commands would fill the remote machine's clipboard
+        Backend::Auto if over_ssh() => copy_with_osc52(text).or_else(|_| copy_with_command(text)),
+        Backend::Auto => copy_with_command(text).or_else(|err| copy_with_osc52(text).map_err(|_| err)),
+    }
+}
+
+fn over_ssh() -> bool {
+    ["SSH_TTY", "SSH_CONNECTION"].iter().any(|name| std::env::var_os(name).is_some_and(|value| !value.is_empty()))
+}
+
+fn copy_with_command(text: &str) -> Result<&'static str> {
+    for &(program, args) in COMMANDS {
+        let child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn();
+        let mut child = match child {
+            Result::Ok(child) => child,
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => return Err(err).with_context(|| format!("cannot run {}", program)),
+        };
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes()).with_context(|| format!("cannot write to {}", program))?;
+        }
+        let status = child.wait()?;
+        // Without a display, xclip and wl-copy exit with an error; try the next command
+        if status.success() {
+            return Ok(program);
+        }
+    }
+    bail!("no working clipboard command (install wl-clipboard, xclip or xsel)")
+}
+
+/// Ask the terminal to set its clipboard. It cannot confirm, so this only fails when there is no
+/// terminal to ask; terminals that ignore OSC 52 (or cap its length) drop the text silently.
+fn copy_with_osc52(text: &str) -> Result<&'static str> {
+    let mut stdout = std::io::stdout().lock();
+    if !stdout.is_terminal() {
+        bail!("OSC 52 needs a terminal on stdout");
+    }
+    let sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    // tmux passes sequences on to the outer terminal only inside its DCS wrapper
+    let sequence = if std::env::var_os("TMUX").is_some() {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    };
+    stdout.write_all(sequence.as_bytes()).context("cannot write to the terminal")?;
+    stdout.flush()?;
+    Ok(OSC52)
+}
+
+/// Standard base64 with padding, as OSC 52 expects
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let group = u32::from(bytes[0]) << 16 | u32::from(bytes[1]) << 8 | u32::from(bytes[2]);
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(group >> (18 - 6 * index) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_matches_rfc_4648() {
+        let vectors = [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v"), ("foob", "Zm9vYg=="), ("fooba", "Zm9vYmE="), ("foobar", "Zm9vYmFy")];
+        for (input, expected) in vectors {
+            assert_eq!(base64(input.as_bytes()), expected, "{:?}", input);
+        }
+        assert_eq!(base64(&[0xfb, 0xff, 0xfe]), "+//+");
+    }
+}
@@ -0,0 +1,180 @@
+mod json_schema;
+mod rust;
+mod typescript;
+
+use anyhow::*;
+use iab::{Taxonomies, Taxonomy};
+use std::collections::HashSet;
+use std::io::Write;
+
+use crate::export::{self, ExportRow};
+
+/// Target languages of `iab codegen`
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Language {
+    /// A module of `Category` constants with an `ALL` slice and `by_id`
+    Rust,
+    /// A `CategoryId` union, a const map of IDs and the `CATEGORIES` record
+    Typescript,
+    /// A JSON Schema accepting exactly the category IDs
+    JsonSchema,
+}
+
+/// Generate source for every node of `taxonomy`, parents before children
+pub fn generate<W: Write>(taxonomies: &Taxonomies, taxonomy: Taxonomy, language: Language, writer: W) -> Result<()> {
+    let rows = export::flatten(&export::tree(taxonomies, taxonomy, None, None)?);
+    match language {
+        Language::Rust => rust::write_module(&rows, taxonomy, writer),
+        Language::Typescript => typescript::write_module(&rows, taxonomy, writer),
+        Language::JsonSchema => json_schema::write_schema(&rows, taxonomy, writer),
+    }
+}
+
+/// Upper snake case identifiers from the tier path, suffixed with the ID where two paths collide,
+/// and with a counter should that name be taken as well. A path without any word is named after
+/// its ID.
+fn constant_names(rows: &[ExportRow]) -> Vec<String> {
+    let mut taken = HashSet::new();
+    rows.iter()
+        .map(|row| {
+            let path = if row.tiers.is_empty() { std::slice::from_ref(&row.name) } else { &row.tiers[..] };
+            let mut name = path.iter().map(|tier| screaming_snake(tier)).filter(|part| !part.is_empty()).collect::<Vec<_>>().join("_");
+            if name.is_empty() {
+                name = format!("CATEGORY_{}", screaming_snake(&row.id));
+            } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+                name = format!("CATEGORY_{}", name);
+            }
+            if taken.contains(&name) {
+                let suffixed = format!("{}_{}", name, screaming_snake(&row.id));
+                name = suffixed.clone();
+                let mut counter = 2;
+                while taken.contains(&name) {
+                    name = format!("{}_{}", suffixed, counter);
+                    counter += 1;
+                }
+            }
+            taken.insert(name.clone());
+            name
+        })
+        .collect()
+}
+
+/// Words of `value` in upper case, joined by underscores; accented Latin letters lose their accent
+/// and any other character separates words
+fn screaming_snake(value: &str) -> String {
+    let mut ascii = String::new();
+    for c in value.chars().flat_map(char::to_uppercase) {
+        match unaccented(c) {
+            Some(letters) => ascii.push_str(letters),
+            None => ascii.push(c),
+        }
+    }
+    ascii
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// The ASCII letters an accented upper case Latin letter is written with, e.g. `U` for `Ü`
+fn unaccented(c: char) -> Option<&'static str> {
+    let letters = match c {
+        'À' | 'Á' | 'Â' | 'Ã' | 'Ä' | 'Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'Æ' => "AE",
+        'Ç' | 'Ć' | 'Ĉ' | 'Ċ' | 'Č' => "C",
+        'Ď' | 'Đ' | 'Ð' => "D",
+        'È' | 'É' | 'Ê' | 'Ë' | 'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => "G",
+        'Ì' | 'Í' | 'Î' | 'Ï' | 'Ĩ' | 'Ī' | 'Ĭ' | 'Į' | 'İ' => "I",
+        'Ł' | 'Ĺ' | 'Ļ' | 'Ľ' => "L",
+        'Ñ' | 'Ń' | 'Ņ' | 'Ň' => "N",
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ö' | 'Ø' | 'Ō' | 'Ŏ' | 'Ő' => "O",
+        'Œ' => "OE",
+        'Ŕ' | 'Ŗ' | 'Ř' => "R",
+        'Ś' | 'Ŝ' | 'Ş' | 'Š' => "S",
+        'ẞ' => "SS",
+        'Ţ' | 'Ť' => "T",
+        'Ù' | 'Ú' | 'Û' | 'Ü' | 'Ũ' | 'Ū' | 'Ŭ' | 'Ů' | 'Ű' | 'Ų' => "U",
+        'Ý' | 'Ÿ' => "Y",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        _ => return None,
+    };
+    Some(letters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Names that sanitize alike, start with a digit, hold no word at all or accented letters
+    fn rows() -> Vec<ExportRow> {
+        let row = |id: &str, parent: Option<&str>, tiers: &[&str]| export::row(id, parent, tiers, None);
+        vec![
+            row("1", None, &["Sports & Fitness"]),
+            row("10", Some("1"), &["Sports & Fitness", "Yoga"]),
+            row("2", None, &["Sports/Fitness"]),
+            row("9", None, &["Sports Fitness 3"]),
+            row("3", None, &["Sports+Fitness"]),
+            row("4", None, &["3D Printing"]),
+            row("5", None, &["!!!"]),
+            row("6", None, &["???"]),
+            row("7", None, &["Über \"Quotes\""]),
+            row("8", None, &["Œuvres d’art & Çà et là"]),
+        ]
+    }
+
+    fn is_identifier(name: &str) -> bool {
+        name.starts_with(|c: char| c.is_ascii_uppercase() || c == '_') && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
+    #[test]
+    fn colliding_names_get_unique_identifiers() {
+        let names = constant_names(&rows());
+        assert_eq!(
+            names,
+            [
+                "SPORTS_FITNESS",
+                "SPORTS_FITNESS_YOGA",
+                "SPORTS_FITNESS_2",
+                "SPORTS_FITNESS_3",
+                "SPORTS_FITNESS_3_2",
+                "CATEGORY_3D_PRINTING",
+                "CATEGORY_5",
+                "CATEGORY_6",
+                "UBER_QUOTES",
+                "OEUVRES_D_ART_CA_ET_LA",
+            ]
+        );
+    }
+
+    #[test]
+    fn rust_module_declares_each_constant_once() {
+        let mut output = Vec::new();
+        rust::write_module(&rows(), Taxonomy::Content, &mut output).unwrap();
+        let source = String::from_utf8(output).unwrap();
+
+        let constants: Vec<&str> = source
+            .lines()
+            .filter_map(|line| line.strip_prefix("pub const ")?.split_once(": Category =").map(|(name, _)| name))
+            .collect();
+        assert_eq!(constants.len(), rows().len());
+        assert_eq!(constants.iter().collect::<HashSet<_>>().len(), constants.len());
+        assert!(constants.iter().all(|name| is_identifier(name)), "{:?}", constants);
+        assert!(source.contains("pub const UBER_QUOTES: Category = Category { id: \"7\", name: \"Über \\\"Quotes\\\"\", parent: None };"));
+        assert!(source.contains("pub const SPORTS_FITNESS_YOGA: Category = Category { id: \"10\", name: \"Yoga\", parent: Some(\"1\") };"));
+    }
+
+    /// Compared with `tests/codegen/colliding-names.ts`; `IAB_UPDATE_SNAPSHOTS=1` rewrites it
+    #[test]
+    fn typescript_module_matches_snapshot() {
+        let mut output = Vec::new();
+        typescript::write_module(&rows(), Taxonomy::Content, &mut output).unwrap();
+        let actual = String::from_utf8(output).unwrap();
+
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/codegen/colliding-names.ts");
+        if std::env::var_os("IAB_UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, &actual).unwrap();
+        }
+        assert_eq!(actual, std::fs::read_to_string(&path).unwrap());
+    }
+}
@@ -0,0 +1,33 @@
+use anyhow::*;
+use iab::Taxonomy;
+use serde_json::json;
+use std::io::Write;
+
+use crate::export::ExportRow;
+
+/// A string schema accepting exactly the category IDs, each annotated with its name and path
+pub fn write_schema<W: Write>(rows: &[ExportRow], taxonomy: Taxonomy, mut writer: W) -> Result<()> {
+    let ids: Vec<_> = rows
+        .iter()
+        .map(|row| {
+            json!({
+                "const": row.id,
+                "title": row.name,
+                "description": row.tiers.join(" > "),
+            })
+        })
+        .collect();
+
+    let schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": format!("urn:iab:{}:{}:category-id", taxonomy.key(), taxonomy.version()),
+        "title": format!("IAB Tech Lab {} Taxonomy {} category ID", taxonomy.name(), taxonomy.version()),
+        "description": format!("Generated by `iab codegen json-schema --taxonomy {}`; OpenRTB cattax {}", taxonomy.key(), taxonomy.cattax()),
+        "type": "string",
+        "oneOf": ids,
+    });
+
+    serde_json::to_writer_pretty(&mut writer, &schema)?;
+    writeln!(writer)?;
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use anyhow::*;
+use iab::Taxonomy;
+use std::io::Write;
+
+use super::constant_names;
+use crate::export::ExportRow;
+
+pub fn write_module<W: Write>(rows: &[ExportRow], taxonomy: Taxonomy, mut writer: W) -> Result<()> {
+    let names = constant_names(rows);
+
+    writeln!(writer, "//! IAB Tech Lab {} Taxonomy {}", taxonomy.name(), taxonomy.version())?;
+    writeln!(writer, "//!")?;
+    writeln!(writer, "//! Generated by `iab codegen rust --taxonomy {}`, do not edit.", taxonomy.key())?;
+    writeln!(writer)?;
+    writeln!(writer, "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]")?;
+    writeln!(writer, "pub struct Category {{")?;
+    writeln!(writer, "    pub id: &'static str,")?;
+    writeln!(writer, "    pub name: &'static str,")?;
+    writeln!(writer, "    pub parent: Option<&'static str>,")?;
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
+    writeln!(writer, "pub const VERSION: &str = {:?};", taxonomy.version())?;
+    writeln!(writer, "/// OpenRTB `cattax` (or `segtax`) value of this taxonomy")?;
+    writeln!(writer, "pub const CATTAX: u32 = {};", taxonomy.cattax())?;
+
+    for (row, name) in rows.iter().zip(&names) {
+        let parent = match row.parent.as_deref().filter(|parent| *parent != row.id) {
+            Some(parent) => format!("Some({:?})", parent),
+            None => "None".to_string(),
+        };
+        writeln!(writer)?;
+        writeln!(writer, "/// {}", doc_path(row))?;
+        writeln!(
+            writer,
+            "pub const {}: Category = Category {{ id: {:?}, name: {:?}, parent: {} }};",
+            name, row.id, row.name, parent
+        )?;
+    }
+
+    writeln!(writer)?;
+    writeln!(writer, "/// Every category, parents before children")?;
+    writeln!(writer, "pub const ALL: &[Category] = &[")?;
+    for name in &names {
+        writeln!(writer, "    {},", name)?;
+    }
+    writeln!(writer, "];")?;
+    writeln!(writer)?;
+    writeln!(writer, "pub fn by_id(id: &str) -> Option<&'static Category> {{")?;
+    writeln!(writer, "    ALL.iter().find(|category| category.id == id)")?;
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+fn doc_path(row: &ExportRow) -> String {
+    let path = if row.tiers.is_empty() { row.name.clone() } else { row.tiers.join(" > ") };
+    format!("{} ({})", path, row.id)
+}
@@ -0,0 +1,66 @@
+use anyhow::*;
+use iab::Taxonomy;
+use std::io::Write;
+
+use super::constant_names;
+use crate::export::ExportRow;
+
+pub fn write_module<W: Write>(rows: &[ExportRow], taxonomy: Taxonomy, mut writer: W) -> Result<()> {
+    let names = constant_names(rows);
+
+    writeln!(writer, "// IAB Tech Lab {} Taxonomy {}", taxonomy.name(), taxonomy.version())?;
+    writeln!(writer, "// Generated by `iab codegen typescript --taxonomy {}`, do not edit.", taxonomy.key())?;
+    writeln!(writer)?;
+    writeln!(writer, "export const VERSION = {};", literal(taxonomy.version()))?;
+    writeln!(writer, "/** OpenRTB `cattax` (or `segtax`) value of this taxonomy */")?;
+    writeln!(writer, "export const CATTAX = {};", taxonomy.cattax())?;
+    writeln!(writer)?;
+
+    writeln!(writer, "export type CategoryId =")?;
+    for row in rows {
+        writeln!(writer, "  | {}", literal(&row.id))?;
+    }
+    writeln!(writer, "  ;")?;
+    writeln!(writer)?;
+
+    writeln!(writer, "export interface Category {{")?;
+    writeln!(writer, "  readonly id: CategoryId;")?;
+    writeln!(writer, "  readonly name: string;")?;
+    writeln!(writer, "  readonly parent: CategoryId | null;")?;
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
+
+    writeln!(writer, "/** Category IDs by upper snake case tier path */")?;
+    writeln!(writer, "export const CATEGORY_IDS = {{")?;
+    for (row, name) in rows.iter().zip(&names) {
+        writeln!(writer, "  {}: {},", name, literal(&row.id))?;
+    }
+    writeln!(writer, "}} as const satisfies Record<string, CategoryId>;")?;
+    writeln!(writer)?;
+
+    writeln!(writer, "/** Every category by ID, parents before children */")?;
+    writeln!(writer, "export const CATEGORIES: Readonly<Record<CategoryId, Category>> = {{")?;
+    for row in rows {
+        let parent = row.parent.as_deref().filter(|parent| *parent != row.id).map_or("null".to_string(), literal);
+        writeln!(
+            writer,
+            "  {}: {{ id: {}, name: {}, parent: {} }},",
+            literal(&row.id),
+            literal(&row.id),
+            literal(&row.name),
+            parent
+        )?;
+    }
+    writeln!(writer, "}};")?;
+    writeln!(writer)?;
+
+    writeln!(writer, "export function isCategoryId(value: string): value is CategoryId {{")?;
+    writeln!(writer, "  return Object.prototype.hasOwnProperty.call(CATEGORIES, value);")?;
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+/// JSON string syntax is valid TypeScript
+fn literal(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
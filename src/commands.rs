@@ -0,0 +1,492 @@
+use anyhow::*;
+use iab::bidrequest::{self, Problem};
+use iab::compare;
+use iab::crosswalk;
+use iab::ortb2::{self, Channel};
+use iab::suggest::{Suggester, Synonyms};
+use iab::validate::{self, Finding, Severity};
+use iab::{Crosswalks, LoadOptions, MappingTable, Taxonomies, Taxonomy, TaxonomyFile};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::codegen::{self, Language};
+use crate::export::{self, ExportFormat};
+use crate::schema::{self, Schema};
+use crate::state;
+use crate::updates;
+
+/// Print per-impression findings; fails when any category value is unknown or deprecated
+pub fn validate_bidrequest(path: &Path) -> Result<()> {
+    let data = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    let request: serde_json::Value =
+        serde_json::from_str(&data).with_context(|| format!("cannot parse {}", path.display()))?;
+    let taxonomies = Taxonomies::load(|_| {})?;
+
+    let reports = bidrequest::validate(&request, &taxonomies)?;
+    for report in &reports {
+        if report.findings.is_empty() {
+            println!("imp {}: ok", report.imp_id);
+            continue;
+        }
+        println!("imp {}:", report.imp_id);
+        for finding in &report.findings {
+            println!("  {}", finding);
+        }
+    }
+
+    // Impressions share the request's categories, so count each offending value once
+    let invalid: HashSet<&str> = reports
+        .iter()
+        .flat_map(|report| &report.findings)
+        .filter(|finding| !matches!(finding.problem, Problem::Unchecked(_)))
+        .map(|finding| finding.path.as_str())
+        .collect();
+    if !invalid.is_empty() {
+        bail!("{} invalid category values", invalid.len());
+    }
+    Ok(())
+}
+
+/// Check the bundled taxonomies, or the given files in their place, for structural problems;
+/// fails when any are found
+pub fn validate(files: &[TaxonomyFile], options: &LoadOptions) -> Result<()> {
+    let taxonomies = Taxonomies::load_files(files, options, |_| {})?;
+    let mut count = 0;
+    for taxonomy in Taxonomy::ALL {
+        let findings = validate::validate(&taxonomies, taxonomy);
+        let label = format!("{} {}", taxonomy.name(), taxonomy.version());
+        // Repaired rows loaded fine, so they are reported without failing the check
+        for warning in &taxonomies.warnings[taxonomy.index()] {
+            eprintln!("warning: {}", warning);
+        }
+        if findings.is_empty() {
+            println!("{}: ok", label);
+            continue;
+        }
+        println!("{}:", label);
+        for finding in &findings {
+            println!("  {}", finding);
+        }
+        count += findings.len();
+    }
+    if count > 0 {
+        bail!("{} problems found", count);
+    }
+    Ok(())
+}
+
+/// Report formats of `iab lint`
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum LintFormat {
+    /// Findings per taxonomy, one per line, and a summary
+    Human,
+    /// One object with the findings per taxonomy and the error and warning counts, for CI tooling
+    Json,
+}
+
+/// The `--format json` document
+#[derive(Serialize)]
+struct LintOutput<'a> {
+    taxonomies: &'a [LintReport],
+    errors: usize,
+    warnings: usize,
+}
+
+/// Lint results of one taxonomy, as written by `--format json`
+#[derive(Serialize)]
+struct LintReport {
+    #[serde(skip)]
+    name: &'static str,
+    taxonomy: &'static str,
+    version: &'static str,
+    file: Option<String>,
+    /// Rows repaired while parsing the file
+    parse_warnings: Vec<String>,
+    findings: Vec<LintFinding>,
+}
+
+#[derive(Serialize)]
+struct LintFinding {
+    severity: Severity,
+    message: String,
+    #[serde(flatten)]
+    finding: Finding,
+}
+
+/// Run every check of `validate::lint` on the bundled taxonomies or `files`; fails on errors, and
+/// on warnings (repaired rows included) with `deny_warnings`
+pub fn lint(files: &[TaxonomyFile], options: &LoadOptions, format: LintFormat, deny_warnings: bool) -> Result<()> {
+    let taxonomies = Taxonomies::load_files(files, options, |_| {})?;
+    let reports: Vec<LintReport> = Taxonomy::ALL
+        .into_iter()
+        .map(|taxonomy| LintReport {
+            name: taxonomy.name(),
+            taxonomy: taxonomy.key(),
+            version: taxonomy.version(),
+            file: files
+                .iter()
+                .rev()
+                .find(|file| file.taxonomy == taxonomy)
+                .map(|file| file.path.display().to_string()),
+            parse_warnings: taxonomies.warnings[taxonomy.index()].clone(),
+            findings: validate::lint(&taxonomies, taxonomy)
+                .into_iter()
+                .map(|finding| LintFinding { severity: finding.issue.severity(), message: finding.to_string(), finding })
+                .collect(),
+        })
+        .collect();
+
+    let all_findings = || reports.iter().flat_map(|report| &report.findings);
+    let errors = all_findings().filter(|finding| finding.severity == Severity::Error).count();
+    let warnings = all_findings().filter(|finding| finding.severity == Severity::Warning).count()
+        + reports.iter().map(|report| report.parse_warnings.len()).sum::<usize>();
+
+    match format {
+        LintFormat::Json => {
+            let output = LintOutput { taxonomies: &reports, errors, warnings };
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+        LintFormat::Human => {
+            for report in &reports {
+                let source = report.file.as_deref().map_or_else(String::new, |file| format!(" ({})", file));
+                println!("{} {}{}:", report.name, report.version, source);
+                if report.parse_warnings.is_empty() && report.findings.is_empty() {
+                    println!("  ok");
+                }
+                for warning in &report.parse_warnings {
+                    println!("  warning  {}", warning);
+                }
+                for finding in &report.findings {
+                    let severity = match finding.severity {
+                        Severity::Error => "error  ",
+                        Severity::Warning => "warning",
+                    };
+                    println!("  {}  {}", severity, finding.message);
+                }
+            }
+            println!("{} errors, {} warnings", errors, warnings);
+        }
+    }
+
+    if errors > 0 || (deny_warnings && warnings > 0) {
+        bail!("lint failed with {} errors and {} warnings", errors, warnings);
+    }
+    Ok(())
+}
+
+/// Print the changes of `file` against the bundled release as a JSON `DiffReport`
+pub fn diff(file: &TaxonomyFile, options: &LoadOptions) -> Result<()> {
+    let official = Taxonomies::load(|_| {})?;
+    let custom = Taxonomies::load_files(std::slice::from_ref(file), options, |_| {})?;
+    let changes = compare::compare(&official.items(file.taxonomy), &custom.items(file.taxonomy));
+    let report = compare::DiffReport::new(file.taxonomy, file.path.display().to_string(), &changes);
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+/// Print the equivalents of each ID, naming those that exist in the bundled Content taxonomy
+pub fn map(crosswalks: &Crosswalks, from: &str, to: &str, ids: &[String]) -> Result<()> {
+    if crosswalks.is_empty() {
+        bail!("no crosswalk loaded, pass one with --crosswalk <FILE>");
+    }
+    let taxonomies = Taxonomies::load(|_| {})?;
+    let bundled = Taxonomy::Content.version();
+    let label = |version: &str, id: &str| {
+        let item = crosswalk::same_ids(version, bundled)
+            .then(|| taxonomies.item(Taxonomy::Content, id))
+            .flatten();
+        match item {
+            Some(item) => format!("{} {}", id, item.name()),
+            None => id.to_string(),
+        }
+    };
+
+    let mapper = crosswalks.mapper(from, to)?;
+    for id in ids {
+        let mapped = mapper.map(id);
+        let targets = if mapped.is_empty() {
+            "(no equivalent)".to_string()
+        } else {
+            mapped.iter().map(|target| label(to, target)).collect::<Vec<_>>().join(", ")
+        };
+        println!("{} {} → {} {}", from, label(from, id), to, targets);
+    }
+    Ok(())
+}
+
+/// Marks IDs without an equivalent in `map --stdin` output
+const NO_MAPPING: &str = "NO_MAPPING";
+
+/// Map the IDs on stdin, one per line, to `ID<TAB>target,target` lines for bulk migrations; blank
+/// lines are skipped
+pub fn map_stdin(crosswalks: &Crosswalks, from: &str, to: &str) -> Result<()> {
+    if crosswalks.is_empty() {
+        bail!("no crosswalk loaded, pass one with --crosswalk <FILE>");
+    }
+    let mapper = crosswalks.mapper(from, to)?;
+    let mut stdout = std::io::BufWriter::new(std::io::stdout().lock());
+    let (mut mapped, mut unmapped) = (0, 0);
+    for line in std::io::stdin().lock().lines() {
+        let line = line.context("cannot read stdin")?;
+        let id = line.trim();
+        if id.is_empty() {
+            continue;
+        }
+        let targets = mapper.map(id);
+        if targets.is_empty() {
+            unmapped += 1;
+            writeln!(stdout, "{}\t{}", id, NO_MAPPING)?;
+        } else {
+            mapped += 1;
+            writeln!(stdout, "{}\t{}", id, targets.join(","))?;
+        }
+    }
+    stdout.flush()?;
+    tracing::info!(from, to, mapped, unmapped, "mapped IDs from stdin");
+    Ok(())
+}
+
+/// Map IDs through third-party tables in whichever direction they appear: external IDs to IAB
+/// categories and IAB IDs to external categories
+pub fn map_tables(tables: &[MappingTable], ids: &[String]) -> Result<()> {
+    if tables.is_empty() {
+        bail!("no mapping table loaded, pass one with --table <FILE> (or use --from/--to with --crosswalk)");
+    }
+    let taxonomies = Taxonomies::load(|_| {})?;
+    let iab_label = |taxonomy: Taxonomy, id: &str| match taxonomies.item(taxonomy, id) {
+        Some(item) => format!("{} {}", id, item.name()),
+        None => id.to_string(),
+    };
+
+    for id in ids {
+        let id = id.trim();
+        let mut found = false;
+        for table in tables {
+            let iab = format!("IAB {}", table.taxonomy().name());
+            let pairs: Vec<_> = table.to_iab(id).collect();
+            if let Some(pair) = pairs.first() {
+                let targets: Vec<String> = pairs.iter().map(|pair| iab_label(table.taxonomy(), &pair.iab_id)).collect();
+                println!("{} {} → {} {}", table.system(), pair.external_label(), iab, targets.join(", "));
+                found = true;
+            }
+            let externals: Vec<String> = table.from_iab(id).map(|pair| pair.external_label()).collect();
+            if !externals.is_empty() {
+                println!("{} {} → {} {}", iab, iab_label(table.taxonomy(), id), table.system(), externals.join(", "));
+                found = true;
+            }
+        }
+        if !found {
+            println!("{} (not in any mapping table)", id);
+        }
+    }
+    Ok(())
+}
+
+/// Export the whole taxonomy or one branch to `output`, or stdout when no path is given
+pub fn export(
+    taxonomy: Taxonomy,
+    format: ExportFormat,
+    root: Option<&str>,
+    max_depth: Option<usize>,
+    csv: Option<export::CsvOptions>,
+    output: Option<&Path>,
+) -> Result<()> {
+    let taxonomies = Taxonomies::load(|_| {})?;
+    let nodes = export::tree(&taxonomies, taxonomy, root, max_depth)?;
+    tracing::info!(taxonomy = taxonomy.key(), format = format.name(), root, output = ?output, "exporting");
+
+    if let Some(options) = csv {
+        if !matches!(format, ExportFormat::Csv | ExportFormat::Flat) {
+            bail!("--delimiter, --columns and --quote only apply to --format csv and flat");
+        }
+        let rows = export::flatten(&nodes);
+        return match output {
+            Some(path) => {
+                let file = fs::File::create(path).with_context(|| format!("cannot create {}", path.display()))?;
+                let mut writer = std::io::BufWriter::new(file);
+                export::write_csv(&rows, &options, &mut writer)?;
+                writer.flush()?;
+                Ok(())
+            }
+            None => export::write_csv(&rows, &options, std::io::stdout().lock()),
+        };
+    }
+
+    match output {
+        Some(path) if format == ExportFormat::Dot && path.extension().is_some_and(|ext| ext == "svg") => {
+            let mut dot = Vec::new();
+            export::write_tree_to(&nodes, format, taxonomy, &mut dot)?;
+            render_svg(&dot, path)
+        }
+        Some(path) => export::write_tree(&nodes, format, taxonomy, path),
+        None => export::write_tree_to(&nodes, format, taxonomy, std::io::stdout().lock()),
+    }
+}
+
+/// Print the best matching categories with their score and tier path
+pub fn classify(taxonomy: Taxonomy, text: &str, limit: usize, synonyms: &Synonyms) -> Result<()> {
+    let taxonomies = Taxonomies::load(|_| {})?;
+    let suggestions = Suggester::new(taxonomies.items(taxonomy), synonyms).suggest(text, limit);
+    if suggestions.is_empty() {
+        bail!("no {} category matches the text", taxonomy.name());
+    }
+    for suggestion in suggestions {
+        let item = suggestion.item;
+        println!("{:.2}  {:<8} {}", suggestion.score, item.unique_id(), item.tiers().join(" > "));
+    }
+    Ok(())
+}
+
+/// Write generated source to `output`, or stdout when no path is given
+pub fn codegen(language: Language, taxonomy: Taxonomy, output: Option<&Path>) -> Result<()> {
+    let taxonomies = Taxonomies::load(|_| {})?;
+    match output {
+        Some(path) => {
+            let file = fs::File::create(path).with_context(|| format!("cannot create {}", path.display()))?;
+            let mut writer = std::io::BufWriter::new(file);
+            codegen::generate(&taxonomies, taxonomy, language, &mut writer)?;
+            writer.flush()?;
+            Ok(())
+        }
+        None => codegen::generate(&taxonomies, taxonomy, language, std::io::stdout().lock()),
+    }
+}
+
+/// Write a JSON Schema to `output`, or stdout when no path is given
+pub fn schema(kind: Schema, output: Option<&Path>) -> Result<()> {
+    match output {
+        Some(path) => {
+            let file = fs::File::create(path).with_context(|| format!("cannot create {}", path.display()))?;
+            let mut writer = std::io::BufWriter::new(file);
+            schema::write(kind, &mut writer)?;
+            writer.flush()?;
+            Ok(())
+        }
+        None => schema::write(kind, std::io::stdout().lock()),
+    }
+}
+
+/// Print the `ortb2` fragment for `ids` as pretty JSON: `user.data` for Audience, content categories otherwise
+pub fn ortb2(taxonomy: Taxonomy, ids: &[String], app: bool, name: Option<&str>) -> Result<()> {
+    let taxonomies = Taxonomies::load(|_| {})?;
+    let fragment = if taxonomy == Taxonomy::Audience {
+        if app {
+            bail!("--app only applies to content categories; Audience segments go into user.data");
+        }
+        ortb2::audience_segments(&taxonomies, ids, name)?
+    } else {
+        if name.is_some() {
+            bail!("--name only applies to Audience segments");
+        }
+        let channel = if app { Channel::App } else { Channel::Site };
+        ortb2::content_categories(&taxonomies, taxonomy, ids, channel)?
+    };
+    println!("{}", serde_json::to_string_pretty(&fragment)?);
+    Ok(())
+}
+
+/// Print each bundled taxonomy version next to the newest one IAB Tech Lab has published
+pub fn check_updates() -> Result<()> {
+    for release in updates::check()? {
+        let label = format!("{} {}", release.taxonomy.name(), release.bundled);
+        match &release.latest {
+            Some(latest) if release.outdated() => println!("{}: {} published", label, latest),
+            Some(_) => println!("{}: up to date", label),
+            None => println!("{}: no release found", label),
+        }
+    }
+    Ok(())
+}
+
+/// What `iab state` does
+#[derive(Debug, clap::Subcommand)]
+pub enum StateAction {
+    /// List the state files with their size and schema (the default)
+    List,
+    /// Print the state directory
+    Path,
+    /// Print the data of one store as JSON
+    Show { name: String },
+    /// Delete the named stores (`crashes` for crash reports), or everything with --all
+    Clear {
+        #[arg(required_unless_present = "all")]
+        names: Vec<String>,
+        #[arg(long, conflicts_with = "names")]
+        all: bool,
+    },
+}
+
+/// Inspect or delete what the TUI keeps in the state directory
+pub fn state(action: StateAction) -> Result<()> {
+    let dir = state::dir().context("no state directory (HOME is not set)")?;
+    match action {
+        StateAction::List => {
+            println!("{}", dir.display());
+            for store in state::STORES {
+                let size = store.path().and_then(|path| fs::metadata(path).ok()).map(|metadata| metadata.len());
+                let status = match (size, store.file_schema()) {
+                    (Some(size), Some(schema)) => format!("{} bytes, schema {}", size, schema),
+                    (Some(size), None) => format!("{} bytes, unreadable", size),
+                    (None, _) => "not saved".to_string(),
+                };
+                println!("  {:<10} {:<24} {}", store.name, status, store.description);
+            }
+            println!("  {:<10} {:<24} panic reports", "crashes", format!("{} saved", state::crash_reports()?.len()));
+        }
+        StateAction::Path => println!("{}", dir.display()),
+        StateAction::Show { name } if name == "crashes" => {
+            for path in state::crash_reports()? {
+                println!("{}", path.display());
+            }
+        }
+        StateAction::Show { name } => {
+            let store = state::find(&name)?;
+            match store.load::<serde_json::Value>()? {
+                Some(data) => println!("{}", serde_json::to_string_pretty(&data)?),
+                None => bail!("no {} saved in {}", store.name, dir.display()),
+            }
+        }
+        StateAction::Clear { names, all } => {
+            let names: Vec<&str> = if all {
+                state::STORES.iter().map(|store| store.name).chain(["crashes"]).collect()
+            } else {
+                names.iter().map(String::as_str).collect()
+            };
+            // Check every name before deleting anything
+            let stores = names.iter().filter(|name| **name != "crashes").map(|name| state::find(name)).collect::<Result<Vec<_>>>()?;
+            for store in stores {
+                if store.clear()? {
+                    println!("removed {}", store.file);
+                }
+            }
+            if names.contains(&"crashes") {
+                for path in state::crash_reports()? {
+                    fs::remove_file(&path).with_context(|| format!("cannot remove {}", path.display()))?;
+                    println!("removed {}", path.file_name().unwrap_or_default().to_string_lossy());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Pipe a DOT graph through GraphViz, which has to be on the PATH
+fn render_svg(dot: &[u8], path: &Path) -> Result<()> {
+    let mut child = Command::new("dot")
+        .arg("-Tsvg")
+        .arg("-o")
+        .arg(path)
+        .stdin(Stdio::piped())
+        .spawn()
+        .context("cannot run `dot`, install GraphViz or write a .dot file instead")?;
+    child.stdin.take().context("no stdin for `dot`")?.write_all(dot)?;
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("`dot` failed with {}", status);
+    }
+    Ok(())
+}
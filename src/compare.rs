@@ -0,0 +1,262 @@
+//! Differences between a custom taxonomy file and the official release it extends, and a merged
+//! file built from them with one decision per difference; and the changelog between two versions
+//! of a taxonomy, node by node.
+
+use crate::taxonomy::{RawTable, Taxonomy, TaxonomyItem};
+use anyhow::*;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// How one ID differs between the official and the custom rows
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangeKind {
+    /// Only in the custom file
+    Added,
+    /// Only in the official release
+    Removed,
+    /// In both, with the listed fields as (field, official value, custom value)
+    Conflict(Vec<(&'static str, String, String)>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub id: String,
+    /// Custom name, or the official one for removals
+    pub name: String,
+    pub kind: ChangeKind,
+    /// Whether the merged file takes this change: the custom row for additions and conflicts,
+    /// leaving the official row out for removals
+    pub accept: bool,
+}
+
+impl Change {
+    pub fn symbol(&self) -> &'static str {
+        match self.kind {
+            ChangeKind::Added => "+",
+            ChangeKind::Removed => "-",
+            ChangeKind::Conflict(_) => "~",
+        }
+    }
+}
+
+/// Fields compared between rows, by name
+fn fields(item: &dyn TaxonomyItem) -> [(&'static str, String); 4] {
+    [
+        ("parent", item.parent().unwrap_or_default().to_string()),
+        ("name", item.name().to_string()),
+        ("tiers", item.tiers().join(" > ")),
+        ("extension", item.extension().unwrap_or_default().trim().to_string()),
+    ]
+}
+
+/// Changes from `official` to `custom`: conflicts and removals in official file order, then
+/// additions in custom file order. Additions and conflicts start accepted, removals rejected,
+/// since a custom file missing official rows is usually just behind the release.
+pub fn compare(official: &[&dyn TaxonomyItem], custom: &[&dyn TaxonomyItem]) -> Vec<Change> {
+    let custom_by_id: HashMap<&str, &dyn TaxonomyItem> = custom.iter().rev().map(|item| (item.unique_id(), *item)).collect();
+    let official_by_id: HashMap<&str, &dyn TaxonomyItem> = official.iter().rev().map(|item| (item.unique_id(), *item)).collect();
+
+    let mut changes = Vec::new();
+    for item in official {
+        let change = match custom_by_id.get(item.unique_id()) {
+            None => Change { id: item.unique_id().to_string(), name: item.name().to_string(), kind: ChangeKind::Removed, accept: false },
+            Some(ours) => {
+                let differing: Vec<_> = fields(*item)
+                    .into_iter()
+                    .zip(fields(*ours))
+                    .filter(|((_, theirs), (_, ours))| theirs != ours)
+                    .map(|((field, theirs), (_, ours))| (field, theirs, ours))
+                    .collect();
+                if differing.is_empty() {
+                    continue;
+                }
+                Change { id: item.unique_id().to_string(), name: ours.name().to_string(), kind: ChangeKind::Conflict(differing), accept: true }
+            }
+        };
+        changes.push(change);
+    }
+    changes.extend(custom.iter().filter(|item| !official_by_id.contains_key(item.unique_id())).map(|item| Change {
+        id: item.unique_id().to_string(),
+        name: item.name().to_string(),
+        kind: ChangeKind::Added,
+        accept: true,
+    }));
+    changes
+}
+
+/// The `iab diff` document: the changes of a custom file against the bundled release
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffReport {
+    pub taxonomy: &'static str,
+    /// Version of the bundled release the file is compared with
+    pub version: &'static str,
+    pub file: String,
+    pub added: usize,
+    pub removed: usize,
+    pub conflicts: usize,
+    pub changes: Vec<DiffEntry>,
+}
+
+/// One change of a `DiffReport`
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffEntry {
+    pub id: String,
+    pub name: String,
+    pub change: &'static str,
+    /// Fields that differ, for conflicts
+    pub fields: Vec<FieldDiff>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldDiff {
+    pub field: &'static str,
+    pub official: String,
+    pub custom: String,
+}
+
+impl DiffReport {
+    pub fn new(taxonomy: Taxonomy, file: String, changes: &[Change]) -> Self {
+        let count = |kind: fn(&ChangeKind) -> bool| changes.iter().filter(|change| kind(&change.kind)).count();
+        Self {
+            taxonomy: taxonomy.key(),
+            version: taxonomy.version(),
+            file,
+            added: count(|kind| *kind == ChangeKind::Added),
+            removed: count(|kind| *kind == ChangeKind::Removed),
+            conflicts: count(|kind| matches!(kind, ChangeKind::Conflict(_))),
+            changes: changes
+                .iter()
+                .map(|change| {
+                    let (label, fields) = match &change.kind {
+                        ChangeKind::Added => ("added", Vec::new()),
+                        ChangeKind::Removed => ("removed", Vec::new()),
+                        ChangeKind::Conflict(fields) => (
+                            "conflict",
+                            fields
+                                .iter()
+                                .map(|(field, official, custom)| FieldDiff { field, official: official.clone(), custom: custom.clone() })
+                                .collect(),
+                        ),
+                    };
+                    DiffEntry { id: change.id.clone(), name: change.name.clone(), change: label, fields }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// One side of a comparison: rows in file order and their original cells
+pub struct Side<'a> {
+    pub items: Vec<&'a dyn TaxonomyItem>,
+    pub raw: &'a RawTable,
+}
+
+/// Cells of a row in the order of `taxonomy.columns()`; the original cells when the file has
+/// that layout, since the bundled Content file keeps extension notes in an unnamed column
+fn cells(taxonomy: Taxonomy, item: &dyn TaxonomyItem, raw: &RawTable) -> Vec<String> {
+    let columns = taxonomy.columns();
+    if raw.width() == columns.len()
+        && let Some(cells) = raw.cells(item.unique_id())
+    {
+        return cells;
+    }
+    let tier_columns = columns.iter().filter(|column| column.starts_with("Tier ")).count();
+    let mut tiers: Vec<String> = item.tiers().into_iter().map(str::to_string).collect();
+    tiers.resize(tier_columns, String::new());
+
+    let mut cells = vec![item.unique_id().to_string(), item.parent().unwrap_or_default().to_string(), item.name().to_string()];
+    cells.extend(tiers);
+    if cells.len() < columns.len() {
+        cells.push(item.extension().unwrap_or_default().to_string());
+    }
+    cells
+}
+
+/// The merged file as TSV with the taxonomy's columns: official rows in their order, each replaced
+/// by the custom row or left out when its change is accepted, then the accepted additions
+pub fn merge(taxonomy: Taxonomy, official: &Side, custom: &Side, changes: &[Change]) -> Result<String> {
+    let decisions: HashMap<&str, &Change> = changes.iter().map(|change| (change.id.as_str(), change)).collect();
+    let custom_by_id: HashMap<&str, &dyn TaxonomyItem> = custom.items.iter().rev().map(|item| (item.unique_id(), *item)).collect();
+
+    let mut writer = csv::WriterBuilder::new().delimiter(b'\t').from_writer(Vec::new());
+    writer.write_record(taxonomy.columns())?;
+    for item in &official.items {
+        let row = match decisions.get(item.unique_id()) {
+            Some(change) if change.accept && change.kind == ChangeKind::Removed => continue,
+            Some(change) if change.accept => match custom_by_id.get(item.unique_id()) {
+                Some(ours) => cells(taxonomy, *ours, custom.raw),
+                None => cells(taxonomy, *item, official.raw),
+            },
+            _ => cells(taxonomy, *item, official.raw),
+        };
+        writer.write_record(row)?;
+    }
+    for item in &custom.items {
+        if decisions.get(item.unique_id()).is_some_and(|change| change.accept && change.kind == ChangeKind::Added) {
+            writer.write_record(cells(taxonomy, *item, custom.raw))?;
+        }
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// How a node differs from the previous version of its taxonomy
+#[derive(Debug, Clone, PartialEq)]
+pub enum Revision {
+    Added,
+    Removed,
+    /// Kept under the same ID; the previous name when it changed, and the previous parent when the
+    /// node moved
+    Changed { old_name: Option<String>, old_parent: Option<Option<String>> },
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeChange {
+    pub id: String,
+    /// Name in the newer version, or the previous one for removals
+    pub name: String,
+    /// Parent in the newer version, or the previous one for removals
+    pub parent: Option<String>,
+    pub revision: Revision,
+}
+
+impl NodeChange {
+    pub fn renamed(&self) -> bool {
+        matches!(self.revision, Revision::Changed { old_name: Some(_), .. })
+    }
+
+    pub fn moved(&self) -> bool {
+        matches!(self.revision, Revision::Changed { old_parent: Some(_), .. })
+    }
+}
+
+/// Nodes added, removed, renamed or moved from `previous` to `current`: kept and added nodes in
+/// current file order, then removals in previous file order. Tier and extension edits alone are
+/// left out, since migrations only care where a category went and what it is called.
+pub fn changelog(previous: &[&dyn TaxonomyItem], current: &[&dyn TaxonomyItem]) -> Vec<NodeChange> {
+    let previous_by_id: HashMap<&str, &dyn TaxonomyItem> = previous.iter().rev().map(|item| (item.unique_id(), *item)).collect();
+    let current_by_id: HashMap<&str, &dyn TaxonomyItem> = current.iter().rev().map(|item| (item.unique_id(), *item)).collect();
+
+    let change = |item: &dyn TaxonomyItem, revision| NodeChange {
+        id: item.unique_id().to_string(),
+        name: item.name().to_string(),
+        parent: item.parent().map(str::to_string),
+        revision,
+    };
+    let mut changes = Vec::new();
+    for item in current {
+        let revision = match previous_by_id.get(item.unique_id()) {
+            None => Revision::Added,
+            Some(old) => {
+                let old_name = (old.name() != item.name()).then(|| old.name().to_string());
+                let old_parent = (old.parent() != item.parent()).then(|| old.parent().map(str::to_string));
+                if old_name.is_none() && old_parent.is_none() {
+                    continue;
+                }
+                Revision::Changed { old_name, old_parent }
+            }
+        };
+        changes.push(change(*item, revision));
+    }
+    changes.extend(previous.iter().filter(|item| !current_by_id.contains_key(item.unique_id())).map(|item| change(*item, Revision::Removed)));
+    changes
+}
@@ -0,0 +1,256 @@
+//! `~/.config/iab/config.toml` (or `--config`): startup defaults and files to load on every run.
+//! Command-line options win over the file; relative paths in it are read from its directory.
+//!
+//! ```toml
+//! datasource = "audience"
+//! theme = "light"
+//! sort = "name"
+//! depth-palette = ["blue", "green", "#ff8800"]
+//! export-format = "json"
+//! locale = "de"
+//! plain = true
+//! check-updates = true
+//! clipboard = "osc52"
+//! crosswalks = ["crosswalks/2.2-3.1.tsv"]
+//! tables = ["google.tsv"]
+//! synonyms = "synonyms.tsv"
+//! mapping = "mapping.csv"
+//! plugins = ["cms.rhai"]
+//!
+//! [files]
+//! content = "vendor-content.tsv"
+//!
+//! [translations]
+//! content = "content-de.tsv"
+//!
+//! [keys]
+//! "Alt+j" = "Down"
+//! "Alt+k" = "Up"
+//!
+//! [api-keys]
+//! dashboard = "c2VjcmV0LWtleQ"
+//! ```
+
+use anyhow::*;
+use clap::ValueEnum;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use iab::{Taxonomy, TaxonomyFile};
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::clipboard::Backend;
+use crate::export::ExportFormat;
+use crate::headless;
+use crate::i18n::Locale;
+use crate::theme::Theme;
+use crate::SortMode;
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// Keys of the browsing view rebound to the key they act as
+pub type Keymap = HashMap<(KeyCode, KeyModifiers), KeyEvent>;
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct ConfigFile {
+    datasource: Option<String>,
+    theme: Option<String>,
+    sort: Option<String>,
+    depth_palette: Option<Vec<String>>,
+    export_format: Option<String>,
+    locale: Option<String>,
+    plain: bool,
+    check_updates: bool,
+    clipboard: Option<String>,
+    files: BTreeMap<String, PathBuf>,
+    translations: BTreeMap<String, PathBuf>,
+    crosswalks: Vec<PathBuf>,
+    tables: Vec<PathBuf>,
+    synonyms: Option<PathBuf>,
+    mapping: Option<PathBuf>,
+    plugins: Vec<PathBuf>,
+    keys: BTreeMap<String, String>,
+    api_keys: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Config {
+    /// Taxonomy to start on, unless a session is resumed or --cattax/--segtax is given
+    pub datasource: Option<Taxonomy>,
+    /// Colors for a dark or light terminal, unless --theme is given
+    pub theme: Option<Theme>,
+    /// Sibling order to start with, unless a session is resumed
+    pub sort: Option<SortMode>,
+    /// Turns depth coloring on at start
+    pub depth_palette: Option<Vec<Color>>,
+    /// Preselected format of the export dialog
+    pub export_format: Option<ExportFormat>,
+    /// Language of the interface, unless `IAB_LANG` says otherwise
+    pub locale: Option<Locale>,
+    /// Start in the plain list mode for screen readers
+    pub plain: bool,
+    /// Look for newer IAB releases in the background on start
+    pub check_updates: bool,
+    /// How copying reaches the clipboard; detected when unset
+    pub clipboard: Option<Backend>,
+    pub files: Vec<TaxonomyFile>,
+    /// Translated names per taxonomy, switched to with Ctrl+j
+    pub translations: Vec<TaxonomyFile>,
+    pub crosswalks: Vec<PathBuf>,
+    pub tables: Vec<PathBuf>,
+    pub synonyms: Option<PathBuf>,
+    pub mapping: Option<PathBuf>,
+    /// Rhai scripts loaded besides those in the plugins directory
+    #[cfg(feature = "plugins")]
+    pub plugins: Vec<PathBuf>,
+    pub keymap: Keymap,
+    /// Keys `iab serve` accepts, by the name its request log uses
+    #[cfg(feature = "server")]
+    pub api_keys: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// The file in the config directory, or the defaults when there is none
+    pub fn load_default() -> Result<Self> {
+        match config_dir().map(|dir| dir.join(CONFIG_FILE)) {
+            Some(path) if path.exists() => Self::load(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        Self::parse(&data, base).with_context(|| format!("cannot parse {}", path.display()))
+    }
+
+    fn parse(data: &str, base: &Path) -> Result<Self> {
+        let file: ConfigFile = toml::from_str(data)?;
+        let resolve = |path: PathBuf| base.join(path);
+
+        let datasource = file.datasource.as_deref().map(crate::parse_taxonomy).transpose()?;
+        let theme = file
+            .theme
+            .map(|theme| Theme::from_str(&theme, true).map_err(|_| anyhow!("unknown theme {:?} (use dark or light)", theme)))
+            .transpose()?;
+        let sort = file
+            .sort
+            .map(|sort| SortMode::from_str(&sort, true).map_err(|_| anyhow!("unknown sort {:?} (use file-order, id, name or usage)", sort)))
+            .transpose()?;
+        let depth_palette = file
+            .depth_palette
+            .map(|colors| {
+                colors
+                    .iter()
+                    .map(|color| Color::from_str(color).map_err(|_| anyhow!("unknown color {:?} in depth-palette", color)))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .transpose()?;
+        let export_format = file
+            .export_format
+            .map(|format| ExportFormat::from_str(&format, true).map_err(|_| anyhow!("unknown export-format {:?}", format)))
+            .transpose()?;
+        let locale = file
+            .locale
+            .map(|locale| Locale::from_str(&locale, true).map_err(|_| anyhow!("unknown locale {:?}", locale)))
+            .transpose()?;
+        let clipboard = file
+            .clipboard
+            .map(|backend| Backend::from_str(&backend, true).map_err(|_| anyhow!("unknown clipboard {:?} (use auto, command or osc52)", backend)))
+            .transpose()?;
+        let taxonomy_files = |files: BTreeMap<String, PathBuf>| {
+            files
+                .into_iter()
+                .map(|(key, path)| {
+                    let taxonomy = crate::parse_taxonomy(&key)?;
+                    Ok(TaxonomyFile { taxonomy, path: resolve(path) })
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        let files = taxonomy_files(file.files)?;
+        let translations = taxonomy_files(file.translations)?;
+        let keymap = file
+            .keys
+            .iter()
+            .map(|(name, action)| {
+                let key = headless::parse_key(name)?;
+                let action = headless::parse_key(action).with_context(|| format!("in the binding of {:?}", name))?;
+                Ok((binding(&key), action))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            datasource,
+            theme,
+            sort,
+            depth_palette,
+            export_format,
+            locale,
+            plain: file.plain,
+            check_updates: file.check_updates,
+            clipboard,
+            files,
+            translations,
+            crosswalks: file.crosswalks.into_iter().map(resolve).collect(),
+            tables: file.tables.into_iter().map(resolve).collect(),
+            synonyms: file.synonyms.map(resolve),
+            mapping: file.mapping.map(resolve),
+            #[cfg(feature = "plugins")]
+            plugins: file.plugins.into_iter().map(resolve).collect(),
+            keymap,
+            #[cfg(feature = "server")]
+            api_keys: file.api_keys,
+        })
+    }
+}
+
+/// Keymap entry of a key; Shift is part of the character, so `J` matches however it is reported
+pub fn binding(key: &KeyEvent) -> (KeyCode, KeyModifiers) {
+    match key.code {
+        KeyCode::Char(_) => (key.code, key.modifiers - KeyModifiers::SHIFT),
+        code => (code, key.modifiers),
+    }
+}
+
+/// `plugins/` in the config directory, where every `*.rhai` script is loaded
+#[cfg(feature = "plugins")]
+pub fn plugin_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("plugins"))
+}
+
+/// `$XDG_CONFIG_HOME/iab`, falling back to `~/.config/iab`
+fn config_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("iab"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn startup_settings_are_read() {
+        let config = Config::parse(
+            "datasource = \"audience\"\ntheme = \"light\"\nsort = \"name\"\nexport-format = \"json\"\nclipboard = \"osc52\"\n\
+             [files]\ncontent = \"vendor.tsv\"\n[keys]\n\"Alt+j\" = \"Down\"\n",
+            Path::new("conf"),
+        )
+        .unwrap();
+        assert_eq!(config.datasource, Some(Taxonomy::Audience));
+        assert_eq!(config.theme, Some(Theme::Light));
+        assert_eq!(config.sort, Some(SortMode::Name));
+        assert_eq!(config.export_format, Some(ExportFormat::Json));
+        assert_eq!(config.clipboard, Some(Backend::Osc52));
+        assert_eq!(config.files[0].path, Path::new("conf").join("vendor.tsv"));
+        assert_eq!(config.keymap.len(), 1);
+        assert!(Config::parse("theme = \"neon\"", Path::new("")).is_err());
+        assert!(Config::parse("sort = \"random\"", Path::new("")).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+//! Panic hook for the TUI: leaves the terminal usable and writes a crash report to the state
+//! directory, so a bug ends with a readable shell and something to attach to an issue.
+
+use anyhow::*;
+use crossterm::ExecutableCommand;
+use crossterm::event::{KeyEvent, PopKeyboardEnhancementFlags};
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Datasource, SortMode};
+
+/// Whether the kitty keyboard flags are pushed and have to be popped on the way out
+static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
+
+/// Browsing state after the last key press, formatted only when a report is written
+static STATE: Mutex<Option<State>> = Mutex::new(None);
+
+/// The small pieces of the browsing state worth reporting, cheap to record on every key
+pub struct State {
+    pub key: KeyEvent,
+    pub datasource: Datasource,
+    pub sort_mode: SortMode,
+    pub filter: String,
+    pub zoom_root: Option<String>,
+    pub selected: Vec<Arc<str>>,
+    /// Number of expanded nodes; their paths are left out
+    pub opened: usize,
+}
+
+impl std::fmt::Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(f, "Last key: {:?} with {:?}", self.key.code, self.key.modifiers)?;
+        writeln!(f, "Datasource: {:?}", self.datasource)?;
+        writeln!(f, "Sort: {:?}", self.sort_mode)?;
+        writeln!(f, "Filter: {:?}", self.filter)?;
+        writeln!(f, "Zoom root: {:?}", self.zoom_root)?;
+        writeln!(f, "Selected: {}", self.selected.join(" > "))?;
+        write!(f, "Opened nodes: {}", self.opened)
+    }
+}
+
+/// Chain onto the current panic hook; call after `ratatui::init` so its terminal restore runs too
+pub fn install() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if KEYBOARD_ENHANCED.swap(false, Ordering::SeqCst) {
+            let _ = std::io::stdout().execute(PopKeyboardEnhancementFlags);
+        }
+        // Leaves raw mode and the alternate screen, then prints the panic message
+        previous(info);
+        tracing::error!("panic: {}", info);
+        match write_report(info) {
+            Result::Ok(path) => eprintln!("iab crashed; a report was written to {}", path.display()),
+            Err(err) => eprintln!("iab crashed and could not write a report: {:#}", err),
+        }
+    }));
+}
+
+pub fn set_keyboard_enhanced(enhanced: bool) {
+    KEYBOARD_ENHANCED.store(enhanced, Ordering::SeqCst);
+}
+
+/// Remember the state to report should the next key crash
+pub fn record_state(state: State) {
+    if let Result::Ok(mut current) = STATE.lock() {
+        *current = Some(state);
+    }
+}
+
+fn write_report(info: &PanicHookInfo) -> Result<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+    let dir = crate::state::dir().unwrap_or_else(std::env::temp_dir);
+    fs::create_dir_all(&dir).with_context(|| format!("cannot create {}", dir.display()))?;
+    let path = dir.join(format!("{}{}.txt", crate::state::CRASH_PREFIX, timestamp));
+
+    // The panic may have happened while the state was being recorded
+    let state = match STATE.try_lock().as_deref() {
+        Result::Ok(Some(state)) => state.to_string(),
+        Result::Ok(None) => "(no key pressed yet)".to_string(),
+        Err(_) => "(unavailable)".to_string(),
+    };
+    let terminal = crossterm::terminal::size().map_or_else(|_| "unknown".to_string(), |(w, h)| format!("{}x{}", w, h));
+    let report = format!(
+        "iab {} crashed at {} (unix time)\n\n\
+         Panic: {}\n\
+         Thread: {}\n\
+         Arguments: {}\n\
+         Platform: {} {}\n\
+         Terminal: {}\n\n\
+         State after the last key:\n{}\n\n\
+         Backtrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        timestamp,
+        info,
+        std::thread::current().name().unwrap_or("unnamed"),
+        std::env::args().collect::<Vec<_>>().join(" "),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        terminal,
+        state,
+        Backtrace::force_capture(),
+    );
+    fs::write(&path, report).with_context(|| format!("cannot write {}", path.display()))?;
+    Ok(path)
+}
@@ -0,0 +1,294 @@
+//! Mappings between Content Taxonomy versions, e.g. the IAB Tech Lab 2.2 → 3.0 crosswalk.
+//!
+//! A crosswalk is a TSV file whose first two columns hold source and target IDs. Their headers
+//! must mention the version (`Unique ID 2.2`, `3.0 ID`, ...); further columns are ignored.
+//! Content 1.0 IDs are the legacy `IAB17-12` style codes (OpenRTB cattax 1).
+
+use anyhow::*;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+use crate::cattax;
+
+/// Content Taxonomy 1.0, whose IDs are the legacy "IAB17-12" style codes
+const LEGACY_VERSION: &str = "1.0";
+
+/// ID pairs from one taxonomy version to another; an ID may map to several targets
+#[derive(Debug, Clone)]
+pub struct Crosswalk {
+    from: String,
+    to: String,
+    pairs: Vec<(String, String)>,
+}
+
+impl Crosswalk {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        Self::from_tsv(&data).with_context(|| format!("cannot parse {}", path.display()))
+    }
+
+    pub fn from_tsv(data: &str) -> Result<Self> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(b'\t')
+            .has_headers(true)
+            .flexible(true)
+            .from_reader(data.as_bytes());
+
+        let headers = reader.headers()?.clone();
+        let version = |column: usize| {
+            headers
+                .get(column)
+                .and_then(version_in)
+                .with_context(|| format!("header of column {} names no taxonomy version", column + 1))
+        };
+        let (from, to) = (version(0)?, version(1)?);
+
+        let mut pairs = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            let (Some(source), Some(target)) = (record.get(0), record.get(1)) else {
+                continue;
+            };
+            // Unmapped rows leave the target blank
+            if !source.trim().is_empty() && !target.trim().is_empty() {
+                pairs.push((normalize_id(source), normalize_id(target)));
+            }
+        }
+
+        Ok(Self { from, to, pairs })
+    }
+
+    pub fn from_version(&self) -> &str {
+        &self.from
+    }
+
+    pub fn to_version(&self) -> &str {
+        &self.to
+    }
+}
+
+/// All loaded crosswalks, usable in either direction and chained across versions
+#[derive(Debug, Clone, Default)]
+pub struct Crosswalks {
+    crosswalks: Vec<Crosswalk>,
+}
+
+/// One step of a route: the crosswalk and whether it is walked from source to target
+type Hop<'a> = (&'a Crosswalk, bool);
+
+/// The route between two versions, with each step indexed by source ID, for mapping many IDs
+pub struct Mapper<'a> {
+    hops: Vec<HashMap<&'a str, Vec<&'a str>>>,
+}
+
+impl Mapper<'_> {
+    /// IDs in the target version equivalent to `id`; empty when it has no counterpart
+    pub fn map(&self, id: &str) -> Vec<String> {
+        let id = normalize_id(id);
+        let mut ids = vec![id.as_str()];
+        for hop in &self.hops {
+            let mut next: Vec<&str> = Vec::new();
+            for value in ids.iter().filter_map(|id| hop.get(id)).flatten() {
+                if !next.contains(value) {
+                    next.push(value);
+                }
+            }
+            ids = next;
+        }
+        ids.into_iter().map(str::to_string).collect()
+    }
+}
+
+impl Crosswalks {
+    pub fn add(&mut self, crosswalk: Crosswalk) {
+        self.crosswalks.push(crosswalk);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.crosswalks.is_empty()
+    }
+
+    /// Versions that can be mapped to or from `version`, directly or through others
+    pub fn counterparts(&self, version: &str) -> Vec<&str> {
+        let mut versions: Vec<&str> = Vec::new();
+        for crosswalk in &self.crosswalks {
+            for candidate in [crosswalk.from.as_str(), crosswalk.to.as_str()] {
+                if !same_ids(candidate, version)
+                    && !versions.iter().any(|known| same_ids(known, candidate))
+                    && self.route(version, candidate).is_some()
+                {
+                    versions.push(candidate);
+                }
+            }
+        }
+        versions
+    }
+
+    /// IDs in `to_version` equivalent to `id` in `from_version`; empty when it has no counterpart.
+    /// Chains crosswalks when no direct one exists, e.g. 1.0 → 2.2 → 3.0.
+    pub fn map_category(&self, from_version: &str, to_version: &str, id: &str) -> Result<Vec<String>> {
+        Ok(self.mapper(from_version, to_version)?.map(id))
+    }
+
+    /// Resolve the route from `from_version` to `to_version` once, to map a batch of IDs along it
+    pub fn mapper(&self, from_version: &str, to_version: &str) -> Result<Mapper<'_>> {
+        let route = self
+            .route(from_version, to_version)
+            .with_context(|| format!("no crosswalk from {} to {}", from_version, to_version))?;
+
+        let hops = route
+            .into_iter()
+            .map(|(crosswalk, forward)| {
+                let mut index: HashMap<&str, Vec<&str>> = HashMap::new();
+                for (source, target) in &crosswalk.pairs {
+                    let (key, value) = if forward { (source, target) } else { (target, source) };
+                    index.entry(key).or_default().push(value);
+                }
+                index
+            })
+            .collect();
+        Ok(Mapper { hops })
+    }
+
+    /// Shortest chain of crosswalks leading from one version to another
+    fn route(&self, from_version: &str, to_version: &str) -> Option<Vec<Hop<'_>>> {
+        if same_ids(from_version, to_version) {
+            return Some(Vec::new());
+        }
+
+        let mut visited = vec![id_space(from_version)];
+        let mut queue = VecDeque::from([(from_version, Vec::new())]);
+        while let Some((version, route)) = queue.pop_front() {
+            for crosswalk in &self.crosswalks {
+                for (forward, source, target) in
+                    [(true, &crosswalk.from, &crosswalk.to), (false, &crosswalk.to, &crosswalk.from)]
+                {
+                    if !same_ids(source, version) || visited.contains(&id_space(target)) {
+                        continue;
+                    }
+                    let mut next: Vec<Hop> = route.clone();
+                    next.push((crosswalk, forward));
+                    if same_ids(target, to_version) {
+                        return Some(next);
+                    }
+                    visited.push(id_space(target));
+                    queue.push_back((target.as_str(), next));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Resolve a version given either directly ("2.2") or as an OpenRTB Content cattax value ("6")
+pub fn resolve_version(spec: &str) -> Result<String> {
+    if let Some(version) = version_in(spec) {
+        return Ok(version);
+    }
+    let cattax: u32 = spec.parse().with_context(|| format!("{} is neither a version nor a cattax value", spec))?;
+    cattax::content_version(cattax)
+        .map(str::to_string)
+        .with_context(|| format!("cattax {} is not a Content taxonomy", cattax))
+}
+
+/// How a version is labelled in the details view; 1.0 uses the legacy "IAB17-12" style codes
+pub fn version_label(version: &str) -> String {
+    if version == LEGACY_VERSION {
+        format!("Content {} (IAB code)", version)
+    } else {
+        format!("Content {}", version)
+    }
+}
+
+/// Legacy codes are matched case-insensitively ("iab17-12" is "IAB17-12")
+fn normalize_id(id: &str) -> String {
+    let id = id.trim();
+    if id.get(..3).is_some_and(|prefix| prefix.eq_ignore_ascii_case("iab")) {
+        id.to_ascii_uppercase()
+    } else {
+        id.to_string()
+    }
+}
+
+/// The first `major.minor` token of a header, e.g. "2.2" in "Unique ID (2.2)"
+fn version_in(header: &str) -> Option<String> {
+    header
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .find(|token| {
+            let mut parts = token.split('.');
+            matches!(
+                (parts.next(), parts.next(), parts.next()),
+                (Some(major), Some(minor), None) if !major.is_empty() && !minor.is_empty()
+            )
+        })
+        .map(|token| token.to_string())
+}
+
+/// Whether IDs of two versions are interchangeable; Content 3.1 only adds to 3.0
+pub fn same_ids(a: &str, b: &str) -> bool {
+    id_space(a) == id_space(b)
+}
+
+fn id_space(version: &str) -> &str {
+    if version.starts_with("3.") { "3" } else { version }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crosswalks(files: &[&str]) -> Crosswalks {
+        let mut crosswalks = Crosswalks::default();
+        for data in files {
+            crosswalks.add(Crosswalk::from_tsv(data).expect("valid fixture"));
+        }
+        crosswalks
+    }
+
+    const LEGACY_TO_22: &str = "IAB Code (1.0)\tUnique ID 2.2\tName\nIAB1\t42\tArts\nIAB1-1\t43\tBooks\nIAB1-2\t\tUnmapped\n";
+    const V22_TO_30: &str = "2.2 ID\t3.0 ID\n42\t42\n43\t43\n43\t1000\n";
+
+    #[test]
+    fn headers_name_the_versions() {
+        let crosswalk = Crosswalk::from_tsv(LEGACY_TO_22).expect("valid fixture");
+        assert_eq!((crosswalk.from_version(), crosswalk.to_version()), ("1.0", "2.2"));
+        assert!(Crosswalk::from_tsv("Source\tTarget\n1\t2\n").is_err());
+    }
+
+    #[test]
+    fn maps_directly_and_in_reverse() {
+        let crosswalks = crosswalks(&[LEGACY_TO_22]);
+        assert_eq!(crosswalks.map_category("1.0", "2.2", "iab1-1").unwrap(), ["43"]);
+        assert_eq!(crosswalks.map_category("2.2", "1.0", "42").unwrap(), ["IAB1"]);
+        assert!(crosswalks.map_category("1.0", "2.2", "IAB1-2").unwrap().is_empty());
+    }
+
+    #[test]
+    fn chains_crosswalks_across_versions() {
+        let crosswalks = crosswalks(&[V22_TO_30, LEGACY_TO_22]);
+        assert_eq!(crosswalks.map_category("1.0", "3.0", "IAB1-1").unwrap(), ["43", "1000"]);
+        assert_eq!(crosswalks.map_category("3.0", "1.0", "1000").unwrap(), ["IAB1-1"]);
+        // 3.1 shares the 3.0 IDs, so the 3.0 crosswalk serves it too
+        assert_eq!(crosswalks.map_category("1.0", "3.1", "IAB1").unwrap(), ["42"]);
+        assert_eq!(crosswalks.counterparts("1.0"), ["2.2", "3.0"]);
+    }
+
+    #[test]
+    fn mapper_maps_a_batch_along_one_route() {
+        let crosswalks = crosswalks(&[V22_TO_30, LEGACY_TO_22]);
+        let mapper = crosswalks.mapper("1.0", "3.0").unwrap();
+        assert_eq!(mapper.map("IAB1"), ["42"]);
+        assert_eq!(mapper.map("iab1-1"), ["43", "1000"]);
+        assert!(mapper.map("IAB1-2").is_empty());
+        assert!(mapper.map("IAB9").is_empty());
+    }
+
+    #[test]
+    fn unconnected_versions_have_no_route() {
+        let crosswalks = crosswalks(&[LEGACY_TO_22]);
+        assert!(crosswalks.map_category("1.0", "3.0", "IAB1").is_err());
+        assert!(crosswalks.counterparts("3.0").is_empty());
+        assert_eq!(crosswalks.map_category("2.2", "2.2", "42").unwrap(), ["42"]);
+    }
+}
@@ -0,0 +1,257 @@
+//! `iab daemon`: a warm process answering JSON-RPC 2.0 requests, one message per line, on stdio or
+//! a TCP socket, so editor plugins and long-running tools need not spawn the CLI for every lookup.
+//!
+//! ```text
+//! {"jsonrpc":"2.0","id":1,"method":"lookup","params":{"id":"150"}}
+//! {"jsonrpc":"2.0","id":2,"method":"search","params":{"query":"golf","taxonomy":"content","limit":5}}
+//! {"jsonrpc":"2.0","id":3,"method":"path","params":{"id":"151"}}
+//! {"jsonrpc":"2.0","id":4,"method":"map","params":{"from":"2.2","to":"3.1","ids":["1","2"]}}
+//! {"jsonrpc":"2.0","id":5,"method":"complete","params":{"prefix":"15"}}
+//! {"jsonrpc":"2.0","id":6,"method":"hover","params":{"id":"151"}}
+//! ```
+//!
+//! With `--lsp` it speaks the Language Server Protocol instead (see `lsp.rs`), offering the same
+//! completions and hovers to editors.
+
+use anyhow::*;
+use iab::{crosswalk, Crosswalks, Taxonomies, Taxonomy, TaxonomyItem};
+use serde_json::{json, Map, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+use crate::jsonrpc::{self, RpcResult, INVALID_PARAMS, METHOD_NOT_FOUND};
+use crate::mcp::node_json;
+
+const DEFAULT_LIMIT: usize = 20;
+
+/// Loaded data shared by every connection
+pub struct Daemon {
+    taxonomies: Taxonomies,
+    crosswalks: Crosswalks,
+}
+
+/// Answer requests on stdio, or on `listen` (`HOST:PORT`) with a thread per connection; `lsp`
+/// speaks the Language Server Protocol on stdio instead
+pub fn serve(listen: Option<&str>, lsp: bool, crosswalks: Crosswalks) -> Result<()> {
+    let daemon = Arc::new(Daemon { taxonomies: Taxonomies::load(|_| {})?, crosswalks });
+    if lsp {
+        return crate::lsp::serve(&daemon, std::io::stdin().lock(), std::io::stdout().lock());
+    }
+    let Some(address) = listen else {
+        return daemon.answer(std::io::stdin().lock(), std::io::stdout().lock());
+    };
+
+    let listener = TcpListener::bind(address).with_context(|| format!("cannot listen on {}", address))?;
+    eprintln!("Answering JSON-RPC on {}", listener.local_addr()?);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Result::Ok(stream) => stream,
+            Err(err) => {
+                tracing::warn!("cannot accept a connection: {}", err);
+                continue;
+            }
+        };
+        let daemon = Arc::clone(&daemon);
+        thread::spawn(move || {
+            let peer = stream.peer_addr().map(|peer| peer.to_string()).unwrap_or_default();
+            tracing::info!(peer, "client connected");
+            if let Err(err) = connection(&daemon, stream) {
+                tracing::warn!(peer, "{:#}", err);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn connection(daemon: &Daemon, stream: TcpStream) -> Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+    daemon.answer(reader, stream)
+}
+
+impl Daemon {
+    /// Answer each line of `input` on `output` until the input ends
+    fn answer(&self, input: impl BufRead, output: impl Write) -> Result<()> {
+        jsonrpc::serve_lines(input, output, |message| self.handle(message))
+    }
+
+    fn handle(&self, message: &Value) -> Option<Value> {
+        let empty = Map::new();
+        let params = message.get("params").and_then(Value::as_object).unwrap_or(&empty);
+        jsonrpc::reply(message, |method| match method {
+            "ping" => Result::Ok(json!({})),
+            "version" => Result::Ok(self.version()),
+            "lookup" => self.lookup(params),
+            "search" => self.search(params),
+            "path" => self.path(params),
+            "map" => self.map(params),
+            "complete" => self.complete(params),
+            "hover" => self.hover(params),
+            method => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        })
+    }
+
+    fn version(&self) -> Value {
+        let taxonomies: Vec<Value> = Taxonomy::ALL
+            .into_iter()
+            .map(|taxonomy| json!({ "taxonomy": taxonomy.key(), "version": taxonomy.version(), "nodes": self.taxonomies.items(taxonomy).len() }))
+            .collect();
+        json!({ "iab": env!("CARGO_PKG_VERSION"), "taxonomies": taxonomies })
+    }
+
+    /// The node with its ancestors from the top down, or null for an unknown ID
+    fn lookup(&self, params: &Map<String, Value>) -> RpcResult {
+        let taxonomy = taxonomy(params)?;
+        let id = string(params, "id")?;
+        let Some(item) = self.taxonomies.item(taxonomy, id) else {
+            return Result::Ok(Value::Null);
+        };
+        let mut node = node_json(item);
+        node["ancestors"] = self.taxonomies.ancestors(taxonomy, id).into_iter().map(node_json).collect();
+        Result::Ok(node)
+    }
+
+    fn search(&self, params: &Map<String, Value>) -> RpcResult {
+        let taxonomy = taxonomy(params)?;
+        let limit = params.get("limit").and_then(Value::as_u64).map_or(DEFAULT_LIMIT, |limit| limit as usize);
+        Result::Ok(self.taxonomies.search(taxonomy, string(params, "query")?, limit).into_iter().map(node_json).collect())
+    }
+
+    /// The breadcrumb of a node and the nodes along it, or null for an unknown ID
+    fn path(&self, params: &Map<String, Value>) -> RpcResult {
+        let taxonomy = taxonomy(params)?;
+        let id = string(params, "id")?;
+        let Some(item) = self.taxonomies.item(taxonomy, id) else {
+            return Result::Ok(Value::Null);
+        };
+        let mut nodes = self.taxonomies.ancestors(taxonomy, id);
+        nodes.push(item);
+        let names: Vec<&str> = nodes.iter().map(|node| node.name()).collect();
+        Result::Ok(json!({ "path": names.join(" > "), "nodes": nodes.into_iter().map(node_json).collect::<Vec<_>>() }))
+    }
+
+    /// Content IDs of `from` mapped to `to` through the crosswalks: each ID to its targets
+    fn map(&self, params: &Map<String, Value>) -> RpcResult {
+        if self.crosswalks.is_empty() {
+            return Err((INVALID_PARAMS, "no crosswalk loaded, start the daemon with --crosswalk <FILE>".to_string()));
+        }
+        let version = |name: &str| crosswalk::resolve_version(string(params, name)?).map_err(|err| (INVALID_PARAMS, format!("{:#}", err)));
+        let (from, to) = (version("from")?, version("to")?);
+        let ids = params
+            .get("ids")
+            .and_then(Value::as_array)
+            .ok_or((INVALID_PARAMS, "missing array argument ids".to_string()))?;
+
+        let mapper = self.crosswalks.mapper(&from, &to).map_err(|err| (INVALID_PARAMS, format!("{:#}", err)))?;
+        let mut mapped = Map::new();
+        for id in ids {
+            let id = id.as_str().ok_or((INVALID_PARAMS, "ids must be strings".to_string()))?;
+            mapped.insert(id.to_string(), json!(mapper.map(id)));
+        }
+        Result::Ok(Value::Object(mapped))
+    }
+}
+
+impl Daemon {
+    /// Nodes for a partly typed ID or name: IDs starting with `prefix` first, then other ID or
+    /// name matches, in file order
+    pub(crate) fn completions(&self, taxonomy: Taxonomy, prefix: &str, limit: usize) -> Vec<&dyn TaxonomyItem> {
+        let lowered = prefix.to_lowercase();
+        let mut items: Vec<&dyn TaxonomyItem> = self
+            .taxonomies
+            .items(taxonomy)
+            .into_iter()
+            .filter(|item| item.unique_id().to_lowercase().starts_with(&lowered))
+            .take(limit)
+            .collect();
+        if items.len() < limit && !prefix.is_empty() {
+            for item in self.taxonomies.search(taxonomy, prefix, limit) {
+                if items.len() == limit {
+                    break;
+                }
+                if !items.iter().any(|known| known.unique_id() == item.unique_id()) {
+                    items.push(item);
+                }
+            }
+        }
+        items
+    }
+
+    /// Markdown describing a node for hovers: its name, ID, breadcrumb and taxonomy
+    pub(crate) fn describe(&self, taxonomy: Taxonomy, id: &str) -> Option<String> {
+        let item = self.taxonomies.item(taxonomy, id)?;
+        Some(format!(
+            "**{}** (`{}`)\n\n{}\n\n{} Taxonomy {}",
+            item.name(),
+            item.unique_id(),
+            item.tiers().join(" > "),
+            taxonomy.name(),
+            taxonomy.version()
+        ))
+    }
+
+    fn complete(&self, params: &Map<String, Value>) -> RpcResult {
+        let taxonomy = taxonomy(params)?;
+        let limit = params.get("limit").and_then(Value::as_u64).map_or(DEFAULT_LIMIT, |limit| limit as usize);
+        let prefix = params.get("prefix").and_then(Value::as_str).unwrap_or_default();
+        Result::Ok(self.completions(taxonomy, prefix, limit).into_iter().map(node_json).collect())
+    }
+
+    /// The node's description as markdown, or null for an unknown ID
+    fn hover(&self, params: &Map<String, Value>) -> RpcResult {
+        let taxonomy = taxonomy(params)?;
+        let id = string(params, "id")?;
+        Result::Ok(self.describe(taxonomy, id).map_or(Value::Null, |markdown| json!({ "id": id, "markdown": markdown })))
+    }
+}
+
+fn string<'a>(params: &'a Map<String, Value>, name: &str) -> std::result::Result<&'a str, (i64, String)> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or((INVALID_PARAMS, format!("missing string argument {}", name)))
+}
+
+/// The `taxonomy` parameter, Content when omitted
+pub(crate) fn taxonomy(params: &Map<String, Value>) -> std::result::Result<Taxonomy, (i64, String)> {
+    match params.get("taxonomy").and_then(Value::as_str) {
+        None => Result::Ok(Taxonomy::Content),
+        Some(key) => Taxonomy::from_key(key).ok_or((INVALID_PARAMS, format!("unknown taxonomy {}", key))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonrpc::{INVALID_REQUEST, MAX_MESSAGE, PARSE_ERROR};
+
+    fn daemon() -> Daemon {
+        Daemon { taxonomies: Taxonomies::load(|_| {}).expect("bundled data loads"), crosswalks: Crosswalks::default() }
+    }
+
+    fn responses(input: &[u8]) -> Vec<Value> {
+        let mut output = Vec::new();
+        daemon().answer(input, &mut output).unwrap();
+        output.split(|&byte| byte == b'\n').filter(|line| !line.is_empty()).map(|line| serde_json::from_slice(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn oversized_line_is_skipped_before_allocating() {
+        let mut input = vec![b' '; MAX_MESSAGE + 1];
+        input.extend_from_slice(b"\n{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n");
+        let responses = responses(&input);
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["error"]["code"], INVALID_REQUEST);
+        assert_eq!(responses[1]["id"], 1);
+        assert!(responses[1].get("result").is_some());
+    }
+
+    #[test]
+    fn bad_line_is_answered_and_reading_goes_on() {
+        let responses = responses(b"{not json\n\n{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"ping\"}");
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["error"]["code"], PARSE_ERROR);
+        assert_eq!(responses[1]["id"], 2);
+    }
+}
@@ -0,0 +1,460 @@
+mod dot;
+mod skos;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod toml;
+mod xml;
+
+use anyhow::*;
+use iab::{ChildIndex, Taxonomies, Taxonomy};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::TaxonomyItem;
+
+// Output formats for exported rows
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    /// CSV with the full breadcrumb path and depth of every node instead of parent pointers
+    Flat,
+    Json,
+    /// SKOS concepts in Turtle
+    Turtle,
+    /// SKOS concepts in RDF/XML
+    RdfXml,
+    /// GraphViz graph of the parent/child edges
+    Dot,
+    /// Nested `<node>` elements
+    Xml,
+    /// One table per node, keyed by ID
+    Toml,
+    /// SQLite database with node and ancestry tables
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl ExportFormat {
+    const ALL: &[ExportFormat] = &[
+        ExportFormat::Csv,
+        ExportFormat::Flat,
+        ExportFormat::Json,
+        ExportFormat::Turtle,
+        ExportFormat::RdfXml,
+        ExportFormat::Dot,
+        ExportFormat::Xml,
+        ExportFormat::Toml,
+        #[cfg(feature = "sqlite")]
+        ExportFormat::Sqlite,
+    ];
+
+    pub fn next(self) -> Self {
+        let position = Self::ALL.iter().position(|format| *format == self).unwrap_or(0);
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Flat => "Flattened CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Turtle => "SKOS Turtle",
+            ExportFormat::RdfXml => "SKOS RDF/XML",
+            ExportFormat::Dot => "GraphViz DOT",
+            ExportFormat::Xml => "XML",
+            ExportFormat::Toml => "TOML",
+            #[cfg(feature = "sqlite")]
+            ExportFormat::Sqlite => "SQLite",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv | ExportFormat::Flat => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Turtle => "ttl",
+            ExportFormat::RdfXml => "rdf",
+            ExportFormat::Dot => "dot",
+            ExportFormat::Xml => "xml",
+            ExportFormat::Toml => "toml",
+            #[cfg(feature = "sqlite")]
+            ExportFormat::Sqlite => "db",
+        }
+    }
+}
+
+// Which part of the current view gets exported
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportScope {
+    /// Only the rows currently visible, respecting expansion state
+    Visible,
+    /// Every node of the (filtered) tree, expanded or not
+    Full,
+    /// The selected node and all its descendants, ignoring the filter
+    Subtree,
+}
+
+impl ExportScope {
+    pub fn next(self) -> Self {
+        match self {
+            ExportScope::Visible => ExportScope::Full,
+            ExportScope::Full => ExportScope::Subtree,
+            ExportScope::Subtree => ExportScope::Visible,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportScope::Visible => "Visible rows",
+            ExportScope::Full => "Full tree",
+            ExportScope::Subtree => "Selected subtree",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct ExportRow {
+    pub id: String,
+    pub parent: Option<String>,
+    pub name: String,
+    pub depth: usize,
+    pub tiers: Vec<String>,
+    pub extension: Option<String>,
+}
+
+impl ExportRow {
+    pub fn from_item<T: TaxonomyItem + ?Sized>(item: &T, depth: usize) -> Self {
+        Self {
+            id: item.unique_id().to_string(),
+            parent: item.parent().filter(|p| !p.is_empty()).map(|p| p.to_string()),
+            name: item.name().to_string(),
+            depth,
+            tiers: item.tiers().iter().map(|t| t.to_string()).collect(),
+            extension: item.extension().filter(|e| !e.is_empty()).map(|e| e.to_string()),
+        }
+    }
+}
+
+/// A test row named after the last of its `tiers`
+#[cfg(test)]
+pub(crate) fn row(id: &str, parent: Option<&str>, tiers: &[&str], extension: Option<&str>) -> ExportRow {
+    ExportRow {
+        id: id.to_string(),
+        parent: parent.map(str::to_string),
+        name: tiers.last().unwrap().to_string(),
+        depth: tiers.len() - 1,
+        tiers: tiers.iter().map(|tier| tier.to_string()).collect(),
+        extension: extension.map(str::to_string),
+    }
+}
+
+/// A row together with its children, for nested output
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportNode {
+    #[serde(flatten)]
+    pub row: ExportRow,
+    pub children: Vec<ExportNode>,
+}
+
+impl ExportNode {
+    pub fn count(&self) -> usize {
+        1 + self.children.iter().map(ExportNode::count).sum::<usize>()
+    }
+
+    fn flatten_into(&self, rows: &mut Vec<ExportRow>) {
+        rows.push(self.row.clone());
+        for child in &self.children {
+            child.flatten_into(rows);
+        }
+    }
+}
+
+/// Build the export tree straight from the taxonomy, either whole or below `root`,
+/// keeping nodes up to `max_depth` levels under the top ones
+pub fn tree(
+    taxonomies: &Taxonomies,
+    taxonomy: Taxonomy,
+    root: Option<&str>,
+    max_depth: Option<usize>,
+) -> Result<Vec<ExportNode>> {
+    let items = taxonomies.items(taxonomy);
+    let index = taxonomies.child_index(taxonomy);
+    let tops = match root {
+        Some(id) => vec![index.position(id).with_context(|| format!("no {} category with ID {}", taxonomy.key(), id))?],
+        // Orphans with an unknown parent are exported as top nodes too
+        None => (0..items.len()).filter(|&position| index.parent(position).is_none()).collect(),
+    };
+
+    fn build(
+        items: &[&dyn TaxonomyItem],
+        index: &ChildIndex,
+        position: usize,
+        depth: usize,
+        max_depth: Option<usize>,
+        seen: &mut Vec<usize>,
+    ) -> ExportNode {
+        seen.push(position);
+        let mut nodes = Vec::new();
+        if max_depth.is_none_or(|max| depth < max) {
+            // The `seen` path guards against parent cycles in edited taxonomy files
+            for child in index.children(position) {
+                if !seen.contains(&child) {
+                    nodes.push(build(items, index, child, depth + 1, max_depth, seen));
+                }
+            }
+        }
+        seen.pop();
+        ExportNode { row: ExportRow::from_item(items[position], depth), children: nodes }
+    }
+
+    Ok(tops
+        .into_iter()
+        .map(|top| build(&items, index, top, 0, max_depth, &mut Vec::new()))
+        .collect())
+}
+
+/// Write nested nodes: JSON keeps the hierarchy, other formats fall back to depth-first rows
+pub fn write_tree(nodes: &[ExportNode], format: ExportFormat, taxonomy: Taxonomy, path: &Path) -> Result<()> {
+    if format != ExportFormat::Json {
+        return write_rows(&flatten(nodes), format, taxonomy, path);
+    }
+    let file = File::create(path).with_context(|| format!("cannot create {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    write_tree_to(nodes, format, taxonomy, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_tree_to<W: Write>(nodes: &[ExportNode], format: ExportFormat, taxonomy: Taxonomy, mut writer: W) -> Result<()> {
+    match format {
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, nodes)?;
+            writeln!(writer)?;
+            Ok(())
+        }
+        _ => write_rows_to(&flatten(nodes), format, taxonomy, writer),
+    }
+}
+
+/// Depth-first rows of the nodes and their descendants
+pub fn flatten(nodes: &[ExportNode]) -> Vec<ExportRow> {
+    let mut rows = Vec::new();
+    for node in nodes {
+        node.flatten_into(&mut rows);
+    }
+    rows
+}
+
+pub fn write_rows(rows: &[ExportRow], format: ExportFormat, taxonomy: Taxonomy, path: &Path) -> Result<()> {
+    #[cfg(feature = "sqlite")]
+    if format == ExportFormat::Sqlite {
+        return sqlite::write_sqlite(rows, taxonomy, path);
+    }
+    let file = File::create(path).with_context(|| format!("cannot create {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    write_rows_to(rows, format, taxonomy, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+pub fn write_rows_to<W: Write>(rows: &[ExportRow], format: ExportFormat, taxonomy: Taxonomy, mut writer: W) -> Result<()> {
+    match format {
+        ExportFormat::Csv => write_csv(rows, &CsvOptions::default(), &mut writer)?,
+        ExportFormat::Flat => write_csv(rows, &CsvOptions::flat(), &mut writer)?,
+        ExportFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, rows)?;
+            writeln!(writer)?;
+        }
+        ExportFormat::Turtle => skos::write_turtle(rows, taxonomy, &mut writer)?,
+        ExportFormat::RdfXml => skos::write_rdf_xml(rows, taxonomy, &mut writer)?,
+        ExportFormat::Dot => dot::write_dot(rows, taxonomy, &mut writer)?,
+        ExportFormat::Xml => xml::write_xml(rows, taxonomy, &mut writer)?,
+        ExportFormat::Toml => toml::write_toml(rows, taxonomy, &mut writer)?,
+        #[cfg(feature = "sqlite")]
+        ExportFormat::Sqlite => bail!("SQLite export needs an output file"),
+    }
+    Ok(())
+}
+
+/// A column of the CSV export
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsvColumn {
+    Id,
+    Parent,
+    Name,
+    Depth,
+    /// One column per tier, as many as the deepest exported row needs
+    Tiers,
+    /// A single tier, counted from 1
+    Tier(usize),
+    /// The tiers joined with " > "
+    Path,
+    Extension,
+}
+
+impl std::str::FromStr for CsvColumn {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let value = value.trim().to_ascii_lowercase();
+        let column = match value.as_str() {
+            "id" => CsvColumn::Id,
+            "parent" => CsvColumn::Parent,
+            "name" => CsvColumn::Name,
+            "depth" => CsvColumn::Depth,
+            "tiers" => CsvColumn::Tiers,
+            "path" => CsvColumn::Path,
+            "extension" => CsvColumn::Extension,
+            _ => {
+                let tier = value.strip_prefix("tier").map(|tier| tier.trim_start_matches('_')).and_then(|tier| tier.parse().ok());
+                match tier {
+                    Some(tier) if tier > 0 => CsvColumn::Tier(tier),
+                    _ => bail!("unknown column {:?} (use id, parent, name, depth, tiers, tier1, tier2, ..., path or extension)", value),
+                }
+            }
+        };
+        Ok(column)
+    }
+}
+
+/// When fields of the CSV export are quoted
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum CsvQuote {
+    /// Only fields containing the delimiter, quotes or line breaks
+    #[default]
+    Necessary,
+    Always,
+    /// Every field that is not a number
+    NonNumeric,
+    /// Never; fields are written as they are
+    Never,
+}
+
+/// Layout of the CSV export, so it matches what a loader expects without post-processing
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    pub delimiter: u8,
+    pub columns: Vec<CsvColumn>,
+    pub quote: CsvQuote,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            columns: vec![CsvColumn::Id, CsvColumn::Parent, CsvColumn::Name, CsvColumn::Depth, CsvColumn::Tiers, CsvColumn::Extension],
+            quote: CsvQuote::Necessary,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Denormalized rows for BI tools: the breadcrumb (`Tier 1 > Tier 2 > Name`) and depth, no parent
+    pub fn flat() -> Self {
+        Self { columns: vec![CsvColumn::Id, CsvColumn::Name, CsvColumn::Path, CsvColumn::Depth], ..Self::default() }
+    }
+}
+
+/// A delimiter given on the command line: one ASCII character, or `tab`/`\t`
+pub fn parse_delimiter(value: &str) -> Result<u8> {
+    match value {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ if value.len() == 1 && value.is_ascii() => Ok(value.as_bytes()[0]),
+        _ => bail!("the delimiter must be a single ASCII character or \"tab\""),
+    }
+}
+
+pub fn write_csv<W: Write>(rows: &[ExportRow], options: &CsvOptions, writer: W) -> Result<()> {
+    let tier_count = rows.iter().map(|row| row.tiers.len()).max().unwrap_or(0);
+    let quote = match options.quote {
+        CsvQuote::Necessary => csv::QuoteStyle::Necessary,
+        CsvQuote::Always => csv::QuoteStyle::Always,
+        CsvQuote::NonNumeric => csv::QuoteStyle::NonNumeric,
+        CsvQuote::Never => csv::QuoteStyle::Never,
+    };
+    let mut csv_writer = csv::WriterBuilder::new().delimiter(options.delimiter).quote_style(quote).from_writer(writer);
+
+    let mut header = Vec::new();
+    for column in &options.columns {
+        match column {
+            CsvColumn::Id => header.push("id".to_string()),
+            CsvColumn::Parent => header.push("parent".to_string()),
+            CsvColumn::Name => header.push("name".to_string()),
+            CsvColumn::Depth => header.push("depth".to_string()),
+            CsvColumn::Tiers => header.extend((1..=tier_count).map(|i| format!("tier_{}", i))),
+            CsvColumn::Tier(tier) => header.push(format!("tier_{}", tier)),
+            CsvColumn::Path => header.push("path".to_string()),
+            CsvColumn::Extension => header.push("extension".to_string()),
+        }
+    }
+    csv_writer.write_record(&header)?;
+
+    for row in rows {
+        let mut record = Vec::new();
+        for column in &options.columns {
+            match column {
+                CsvColumn::Id => record.push(row.id.clone()),
+                CsvColumn::Parent => record.push(row.parent.clone().unwrap_or_default()),
+                CsvColumn::Name => record.push(row.name.clone()),
+                CsvColumn::Depth => record.push(row.depth.to_string()),
+                CsvColumn::Tiers => record.extend((0..tier_count).map(|i| row.tiers.get(i).cloned().unwrap_or_default())),
+                CsvColumn::Tier(tier) => record.push(row.tiers.get(tier - 1).cloned().unwrap_or_default()),
+                CsvColumn::Path => record.push(row.tiers.join(" > ")),
+                CsvColumn::Extension => record.push(row.extension.clone().unwrap_or_default()),
+            }
+        }
+        csv_writer.write_record(&record)?;
+    }
+
+    csv_writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> Vec<ExportRow> {
+        vec![row("1", None, &["Books, Comics"], None), row("2", Some("1"), &["Books, Comics", "Say \"Hi\""], Some("x"))]
+    }
+
+    fn csv(options: &CsvOptions) -> String {
+        let mut output = Vec::new();
+        write_csv(&rows(), options, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn columns_and_delimiters_parse() {
+        let columns: Vec<CsvColumn> = ["ID", " path ", "tier2", "tier_3", "tiers"].iter().map(|column| column.parse().unwrap()).collect();
+        assert_eq!(columns, [CsvColumn::Id, CsvColumn::Path, CsvColumn::Tier(2), CsvColumn::Tier(3), CsvColumn::Tiers]);
+        assert!("tier0".parse::<CsvColumn>().is_err());
+        assert!("label".parse::<CsvColumn>().is_err());
+
+        assert_eq!(parse_delimiter("\\t").unwrap(), b'\t');
+        assert_eq!(parse_delimiter("tab").unwrap(), b'\t');
+        assert_eq!(parse_delimiter(";").unwrap(), b';');
+        for invalid in ["", ";;", "\\n", "§"] {
+            assert!(parse_delimiter(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn csv_writes_the_selected_columns() {
+        assert_eq!(
+            csv(&CsvOptions::default()),
+            "id,parent,name,depth,tier_1,tier_2,extension\n\
+             1,,\"Books, Comics\",0,\"Books, Comics\",,\n\
+             2,1,\"Say \"\"Hi\"\"\",1,\"Books, Comics\",\"Say \"\"Hi\"\"\",x\n"
+        );
+        let options = CsvOptions { delimiter: b';', columns: vec![CsvColumn::Id, CsvColumn::Tier(2), CsvColumn::Path], quote: CsvQuote::Necessary };
+        assert_eq!(csv(&options), "id;tier_2;path\n1;;Books, Comics\n2;\"Say \"\"Hi\"\"\";\"Books, Comics > Say \"\"Hi\"\"\"\n");
+        let options = CsvOptions { delimiter: b'\t', columns: vec![CsvColumn::Id, CsvColumn::Depth, CsvColumn::Name], quote: CsvQuote::NonNumeric };
+        assert_eq!(csv(&options), "\"id\"\t\"depth\"\t\"name\"\n1\t0\t\"Books, Comics\"\n2\t1\t\"Say \"\"Hi\"\"\"\n");
+        let options = CsvOptions { quote: CsvQuote::Never, ..CsvOptions::flat() };
+        assert_eq!(csv(&options), "id,name,path,depth\n1,Books, Comics,Books, Comics,0\n2,Say \"Hi\",Books, Comics > Say \"Hi\",1\n");
+    }
+}
@@ -0,0 +1,36 @@
+use anyhow::*;
+use iab::Taxonomy;
+use std::collections::HashSet;
+use std::io::Write;
+
+use super::ExportRow;
+
+/// A directed graph of the rows; edges only connect rows that were both exported
+pub fn write_dot<W: Write>(rows: &[ExportRow], taxonomy: Taxonomy, mut writer: W) -> Result<()> {
+    let ids: HashSet<&str> = rows.iter().map(|row| row.id.as_str()).collect();
+
+    writeln!(writer, "digraph {} {{", dot_string(&format!("IAB {} {}", taxonomy.name(), taxonomy.version())))?;
+    writeln!(writer, "    rankdir=LR;")?;
+    writeln!(writer, "    node [shape=box, style=rounded, fontname=\"Helvetica\"];")?;
+
+    for row in rows {
+        let label = format!("{}\n{}", row.name, row.id);
+        writeln!(writer, "    {} [label={}];", dot_string(&row.id), dot_string(&label))?;
+    }
+    for row in rows {
+        if let Some(parent) = row.parent.as_deref()
+            && parent != row.id
+            && ids.contains(parent)
+        {
+            writeln!(writer, "    {} -> {};", dot_string(parent), dot_string(&row.id))?;
+        }
+    }
+
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+fn dot_string(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
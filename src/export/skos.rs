@@ -0,0 +1,148 @@
+use anyhow::*;
+use iab::Taxonomy;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use super::ExportRow;
+
+const SKOS: &str = "http://www.w3.org/2004/02/skos/core#";
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+/// IAB publishes no IRIs, so concepts live under a URN per taxonomy version
+fn scheme_iri(taxonomy: Taxonomy) -> String {
+    format!("urn:iab:{}:{}", taxonomy.key(), taxonomy.version())
+}
+
+fn scheme_label(taxonomy: Taxonomy) -> String {
+    format!("IAB Tech Lab {} Taxonomy {}", taxonomy.name(), taxonomy.version())
+}
+
+/// Concepts with `broader`/`narrower` limited to the exported rows, so the graph stays closed
+struct Concept<'a> {
+    row: &'a ExportRow,
+    broader: Option<&'a str>,
+    narrower: Vec<&'a str>,
+}
+
+fn concepts(rows: &[ExportRow]) -> Vec<Concept<'_>> {
+    let ids: HashSet<&str> = rows.iter().map(|row| row.id.as_str()).collect();
+
+    let mut narrower: HashMap<&str, Vec<&str>> = HashMap::new();
+    for row in rows {
+        if let Some(parent) = broader(row, &ids) {
+            narrower.entry(parent).or_default().push(&row.id);
+        }
+    }
+
+    rows.iter()
+        .map(|row| Concept {
+            row,
+            broader: broader(row, &ids),
+            narrower: narrower.remove(row.id.as_str()).unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// The parent, unless it is the row itself or was not exported
+fn broader<'a>(row: &'a ExportRow, ids: &HashSet<&str>) -> Option<&'a str> {
+    row.parent
+        .as_deref()
+        .filter(|parent| *parent != row.id && ids.contains(parent))
+}
+
+pub fn write_turtle<W: Write>(rows: &[ExportRow], taxonomy: Taxonomy, mut writer: W) -> Result<()> {
+    let scheme = scheme_iri(taxonomy);
+    let concepts = concepts(rows);
+
+    writeln!(writer, "@prefix skos: <{}> .", SKOS)?;
+    writeln!(writer, "@prefix iab: <{}:> .", scheme)?;
+    writeln!(writer)?;
+    writeln!(writer, "<{}> a skos:ConceptScheme ;", scheme)?;
+    write!(writer, "    skos:prefLabel {}@en", turtle_string(&scheme_label(taxonomy)))?;
+    for concept in concepts.iter().filter(|concept| concept.broader.is_none()) {
+        write!(writer, " ;\n    skos:hasTopConcept {}", turtle_ref(&scheme, &concept.row.id))?;
+    }
+    writeln!(writer, " .")?;
+
+    for concept in &concepts {
+        writeln!(writer)?;
+        writeln!(writer, "{} a skos:Concept ;", turtle_ref(&scheme, &concept.row.id))?;
+        writeln!(writer, "    skos:inScheme <{}> ;", scheme)?;
+        writeln!(writer, "    skos:notation {} ;", turtle_string(&concept.row.id))?;
+        write!(writer, "    skos:prefLabel {}@en", turtle_string(&concept.row.name))?;
+        match concept.broader {
+            Some(parent) => write!(writer, " ;\n    skos:broader {}", turtle_ref(&scheme, parent))?,
+            None => write!(writer, " ;\n    skos:topConceptOf <{}>", scheme)?,
+        }
+        for child in &concept.narrower {
+            write!(writer, " ;\n    skos:narrower {}", turtle_ref(&scheme, child))?;
+        }
+        if let Some(extension) = &concept.row.extension {
+            write!(writer, " ;\n    skos:note {}", turtle_string(extension))?;
+        }
+        writeln!(writer, " .")?;
+    }
+    Ok(())
+}
+
+pub fn write_rdf_xml<W: Write>(rows: &[ExportRow], taxonomy: Taxonomy, mut writer: W) -> Result<()> {
+    let scheme = scheme_iri(taxonomy);
+    let concepts = concepts(rows);
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<rdf:RDF xmlns:rdf="{}" xmlns:skos="{}">"#, RDF, SKOS)?;
+    writeln!(writer, r#"  <skos:ConceptScheme rdf:about="{}">"#, scheme)?;
+    writeln!(writer, r#"    <skos:prefLabel xml:lang="en">{}</skos:prefLabel>"#, xml_escape(&scheme_label(taxonomy)))?;
+    for concept in concepts.iter().filter(|concept| concept.broader.is_none()) {
+        writeln!(writer, r#"    <skos:hasTopConcept rdf:resource="{}:{}"/>"#, scheme, xml_escape(&concept.row.id))?;
+    }
+    writeln!(writer, "  </skos:ConceptScheme>")?;
+
+    for concept in &concepts {
+        let id = xml_escape(&concept.row.id);
+        writeln!(writer, r#"  <skos:Concept rdf:about="{}:{}">"#, scheme, id)?;
+        writeln!(writer, r#"    <skos:inScheme rdf:resource="{}"/>"#, scheme)?;
+        writeln!(writer, "    <skos:notation>{}</skos:notation>", id)?;
+        writeln!(writer, r#"    <skos:prefLabel xml:lang="en">{}</skos:prefLabel>"#, xml_escape(&concept.row.name))?;
+        match concept.broader {
+            Some(parent) => writeln!(writer, r#"    <skos:broader rdf:resource="{}:{}"/>"#, scheme, xml_escape(parent))?,
+            None => writeln!(writer, r#"    <skos:topConceptOf rdf:resource="{}"/>"#, scheme)?,
+        }
+        for child in &concept.narrower {
+            writeln!(writer, r#"    <skos:narrower rdf:resource="{}:{}"/>"#, scheme, xml_escape(child))?;
+        }
+        if let Some(extension) = &concept.row.extension {
+            writeln!(writer, "    <skos:note>{}</skos:note>", xml_escape(extension))?;
+        }
+        writeln!(writer, "  </skos:Concept>")?;
+    }
+
+    writeln!(writer, "</rdf:RDF>")?;
+    Ok(())
+}
+
+/// Prefixed name when the ID is a plain Turtle local name, full IRI otherwise
+fn turtle_ref(scheme: &str, id: &str) -> String {
+    if id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        format!("iab:{}", id)
+    } else {
+        format!("<{}:{}>", scheme, id)
+    }
+}
+
+fn turtle_string(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r");
+    format!("\"{}\"", escaped)
+}
+
+pub(super) fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
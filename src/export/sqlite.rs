@@ -0,0 +1,87 @@
+use anyhow::*;
+use iab::Taxonomy;
+use rusqlite::{Connection, params};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::ExportRow;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS taxonomies (
+    taxonomy TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    version TEXT NOT NULL,
+    cattax INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS nodes (
+    taxonomy TEXT NOT NULL REFERENCES taxonomies (taxonomy),
+    id TEXT NOT NULL,
+    parent_id TEXT,
+    name TEXT NOT NULL,
+    depth INTEGER NOT NULL,
+    extension TEXT,
+    PRIMARY KEY (taxonomy, id)
+);
+CREATE INDEX IF NOT EXISTS nodes_parent ON nodes (taxonomy, parent_id);
+CREATE INDEX IF NOT EXISTS nodes_name ON nodes (name);
+CREATE TABLE IF NOT EXISTS ancestry (
+    taxonomy TEXT NOT NULL,
+    ancestor_id TEXT NOT NULL,
+    descendant_id TEXT NOT NULL,
+    distance INTEGER NOT NULL,
+    PRIMARY KEY (taxonomy, ancestor_id, descendant_id)
+);
+CREATE INDEX IF NOT EXISTS ancestry_descendant ON ancestry (taxonomy, descendant_id);
+";
+
+/// Write the rows into `path`, replacing only this taxonomy's rows so one database can hold all three
+pub fn write_sqlite(rows: &[ExportRow], taxonomy: Taxonomy, path: &Path) -> Result<()> {
+    let mut connection = Connection::open(path).with_context(|| format!("cannot open {}", path.display()))?;
+    connection.execute_batch(SCHEMA)?;
+
+    let transaction = connection.transaction()?;
+    let key = taxonomy.key();
+    for table in ["ancestry", "nodes", "taxonomies"] {
+        transaction.execute(&format!("DELETE FROM {} WHERE taxonomy = ?1", table), params![key])?;
+    }
+    transaction.execute(
+        "INSERT INTO taxonomies (taxonomy, name, version, cattax) VALUES (?1, ?2, ?3, ?4)",
+        params![key, taxonomy.name(), taxonomy.version(), taxonomy.cattax()],
+    )?;
+
+    // Ancestry stays within the exported rows, like the exported tree itself
+    let ids: HashSet<&str> = rows.iter().map(|row| row.id.as_str()).collect();
+    let parents: HashMap<&str, &str> = rows
+        .iter()
+        .filter_map(|row| Some((row.id.as_str(), row.parent.as_deref()?)))
+        .filter(|(id, parent)| id != parent && ids.contains(parent))
+        .collect();
+
+    {
+        let mut insert_node = transaction.prepare(
+            "INSERT OR REPLACE INTO nodes (taxonomy, id, parent_id, name, depth, extension) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        let mut insert_ancestry = transaction.prepare(
+            "INSERT OR IGNORE INTO ancestry (taxonomy, ancestor_id, descendant_id, distance) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for row in rows {
+            let parent = row.parent.as_deref().filter(|parent| *parent != row.id);
+            insert_node.execute(params![key, row.id, parent, row.name, row.depth, row.extension])?;
+
+            // Closure rows up to the root, including the node itself at distance 0
+            let mut chain = vec![row.id.as_str()];
+            while let Some(&parent) = chain.last().and_then(|id| parents.get(id)) {
+                if chain.contains(&parent) {
+                    break;
+                }
+                chain.push(parent);
+            }
+            for (distance, ancestor) in chain.iter().enumerate() {
+                insert_ancestry.execute(params![key, ancestor, row.id, distance])?;
+            }
+        }
+    }
+
+    transaction.commit()?;
+    Ok(())
+}
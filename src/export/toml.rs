@@ -0,0 +1,53 @@
+use anyhow::*;
+use iab::Taxonomy;
+use serde::Serialize;
+use serde::ser::SerializeMap;
+use std::io::Write;
+
+use super::ExportRow;
+
+/// The whole document: taxonomy details, then one `[nodes."<id>"]` table per row
+#[derive(Serialize)]
+struct Document<'a> {
+    taxonomy: &'static str,
+    version: &'static str,
+    cattax: u32,
+    nodes: Nodes<'a>,
+}
+
+/// Rows keyed by ID, in export order rather than sorted
+struct Nodes<'a>(&'a [ExportRow]);
+
+#[derive(Serialize)]
+struct Node<'a> {
+    name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent: Option<&'a str>,
+    depth: usize,
+    tiers: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extension: Option<&'a str>,
+}
+
+impl Serialize for Nodes<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for row in self.0 {
+            let node = Node {
+                name: &row.name,
+                parent: row.parent.as_deref(),
+                depth: row.depth,
+                tiers: &row.tiers,
+                extension: row.extension.as_deref(),
+            };
+            map.serialize_entry(&row.id, &node)?;
+        }
+        map.end()
+    }
+}
+
+pub fn write_toml<W: Write>(rows: &[ExportRow], taxonomy: Taxonomy, mut writer: W) -> Result<()> {
+    let document = Document { taxonomy: taxonomy.name(), version: taxonomy.version(), cattax: taxonomy.cattax(), nodes: Nodes(rows) };
+    writer.write_all(::toml::to_string(&document)?.as_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use anyhow::*;
+use iab::Taxonomy;
+use std::io::Write;
+
+use super::ExportRow;
+use super::skos::xml_escape;
+
+/// Nested `<node id name>` elements below a `<taxonomy>` root. Rows come depth first, so a row
+/// nests under the closest open row that is its parent; rows whose parent was not exported start
+/// at the top.
+pub fn write_xml<W: Write>(rows: &[ExportRow], taxonomy: Taxonomy, mut writer: W) -> Result<()> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<taxonomy name="{}" version="{}" cattax="{}">"#,
+        xml_escape(taxonomy.name()),
+        xml_escape(taxonomy.version()),
+        taxonomy.cattax()
+    )?;
+
+    // IDs of the elements still open, outermost first
+    let mut open: Vec<&str> = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        match row.parent.as_deref().and_then(|parent| open.iter().rposition(|id| *id == parent)) {
+            Some(position) => close(&mut writer, &mut open, position + 1)?,
+            None => close(&mut writer, &mut open, 0)?,
+        }
+        let indent = "  ".repeat(open.len() + 1);
+        write!(writer, r#"{}<node id="{}" name="{}""#, indent, xml_escape(&row.id), xml_escape(&row.name))?;
+        if let Some(extension) = &row.extension {
+            write!(writer, r#" extension="{}""#, xml_escape(extension))?;
+        }
+        let has_children = rows.get(i + 1).is_some_and(|next| next.parent.as_deref() == Some(row.id.as_str()));
+        if has_children {
+            writeln!(writer, ">")?;
+            open.push(&row.id);
+        } else {
+            writeln!(writer, "/>")?;
+        }
+    }
+    close(&mut writer, &mut open, 0)?;
+
+    writeln!(writer, "</taxonomy>")?;
+    Ok(())
+}
+
+/// Close open elements until `depth` remain
+fn close<W: Write>(writer: &mut W, open: &mut Vec<&str>, depth: usize) -> Result<()> {
+    while open.len() > depth {
+        open.pop();
+        writeln!(writer, "{}</node>", "  ".repeat(open.len() + 1))?;
+    }
+    Ok(())
+}
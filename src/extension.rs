@@ -0,0 +1,77 @@
+//! Custom nodes under official categories, kept in an overlay TSV (`--extension`) that is loaded
+//! on top of the official taxonomy. Their IDs come from a range no IAB release uses, so an
+//! overlay never shadows an official node and survives taxonomy updates.
+
+use crate::taxonomy::{Taxonomy, TaxonomyItem};
+use anyhow::*;
+use serde::de::DeserializeOwned;
+
+/// Lowest and highest ID handed out to custom nodes
+pub const FIRST_ID: u32 = 900_000;
+pub const LAST_ID: u32 = 999_999;
+
+/// Value of the extension column of custom nodes, which shows them with an `[ext]` badge
+const MARKER: &str = "Custom extension";
+
+/// Whether `id` lies in the range reserved for custom nodes
+pub fn is_custom(id: &str) -> bool {
+    id.parse::<u32>().is_ok_and(|id| (FIRST_ID..=LAST_ID).contains(&id))
+}
+
+/// One past the highest custom ID among `ids`, or the first of the range
+pub fn next_id<'a>(ids: impl IntoIterator<Item = &'a str>) -> Result<String> {
+    let highest = ids.into_iter().filter(|id| is_custom(id)).filter_map(|id| id.parse::<u32>().ok()).max();
+    match highest {
+        None => Ok(FIRST_ID.to_string()),
+        Some(LAST_ID) => bail!("all custom IDs from {} to {} are taken", FIRST_ID, LAST_ID),
+        Some(id) => Ok((id + 1).to_string()),
+    }
+}
+
+/// Refuse names that are empty or would break the TSV
+pub fn check_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        bail!("the name is empty");
+    }
+    if name.contains(['\t', '\n', '\r']) {
+        bail!("the name contains a tab or line break");
+    }
+    Ok(())
+}
+
+/// Cells of a custom node in the order of `taxonomy.columns()`: its parent's tiers with the name
+/// appended as the next tier
+pub fn record(taxonomy: Taxonomy, parent: &dyn TaxonomyItem, id: &str, name: &str) -> Result<Vec<String>> {
+    let columns = taxonomy.columns();
+    let tier_columns = columns.iter().filter(|column| column.starts_with("Tier ")).count();
+    let mut tiers: Vec<String> = parent.tiers().into_iter().map(str::to_string).collect();
+    if tiers.len() >= tier_columns {
+        bail!("{} {} is at tier {}, the deepest {} has", parent.unique_id(), parent.name(), tiers.len(), taxonomy.name());
+    }
+    tiers.push(name.trim().to_string());
+    tiers.resize(tier_columns, String::new());
+
+    let mut cells = vec![id.to_string(), parent.unique_id().to_string(), name.trim().to_string()];
+    cells.extend(tiers);
+    if cells.len() < columns.len() {
+        cells.push(MARKER.to_string());
+    }
+    Ok(cells)
+}
+
+/// Read `cells` in the order of `taxonomy.columns()` into that taxonomy's row struct
+pub fn parse_record<T: DeserializeOwned>(taxonomy: Taxonomy, cells: &[String]) -> Result<T> {
+    let headers = csv::StringRecord::from(taxonomy.columns().to_vec());
+    let record = csv::StringRecord::from(cells.to_vec());
+    Ok(record.deserialize(Some(&headers))?)
+}
+
+/// The overlay file: the taxonomy's column headers followed by one line per custom node
+pub fn to_tsv(taxonomy: Taxonomy, rows: impl IntoIterator<Item = Vec<String>>) -> Result<String> {
+    let mut writer = csv::WriterBuilder::new().delimiter(b'\t').from_writer(Vec::new());
+    writer.write_record(taxonomy.columns())?;
+    for row in rows {
+        writer.write_record(&row)?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
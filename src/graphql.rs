@@ -0,0 +1,196 @@
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Enum, Object, Schema, SimpleObject};
+use async_graphql_axum::GraphQL;
+use axum::response::Html;
+use axum::routing::get;
+use axum::Router;
+use iab::Taxonomy;
+use std::sync::Arc;
+
+use crate::export::ExportRow;
+use crate::server::{Live, ServerState, DEFAULT_SEARCH_LIMIT};
+
+/// Deepest selection accepted, enough for a node with several levels of parents or children
+const MAX_DEPTH: usize = 8;
+
+/// Cost a query may have, counting list fields as often as the items they can return, so nested
+/// `descendants { descendants { ... } }` is refused before it runs
+const MAX_COMPLEXITY: usize = 2_000;
+
+type IabSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "Taxonomy")]
+enum TaxonomyKind {
+    Product,
+    Content,
+    Audience,
+}
+
+impl From<TaxonomyKind> for Taxonomy {
+    fn from(kind: TaxonomyKind) -> Self {
+        match kind {
+            TaxonomyKind::Product => Taxonomy::Product,
+            TaxonomyKind::Content => Taxonomy::Content,
+            TaxonomyKind::Audience => Taxonomy::Audience,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+struct TaxonomyInfo {
+    name: &'static str,
+    version: &'static str,
+    cattax: u32,
+    nodes: usize,
+}
+
+/// A taxonomy node; relations resolve lazily against the shared state
+struct Node {
+    taxonomy: Taxonomy,
+    row: ExportRow,
+}
+
+impl Node {
+    fn wrap(taxonomy: Taxonomy, rows: Vec<ExportRow>) -> Vec<Node> {
+        rows.into_iter().map(|row| Node { taxonomy, row }).collect()
+    }
+}
+
+fn state(ctx: &Context<'_>) -> Arc<ServerState> {
+    ctx.data_unchecked::<Arc<Live>>().current()
+}
+
+#[Object]
+impl Node {
+    async fn id(&self) -> &str {
+        &self.row.id
+    }
+
+    async fn name(&self) -> &str {
+        &self.row.name
+    }
+
+    /// Number of ancestors; roots are 0
+    async fn depth(&self) -> usize {
+        self.row.depth
+    }
+
+    async fn tiers(&self) -> &[String] {
+        &self.row.tiers
+    }
+
+    async fn extension(&self) -> Option<&str> {
+        self.row.extension.as_deref()
+    }
+
+    async fn parent(&self, ctx: &Context<'_>) -> Option<Node> {
+        let parent = self.row.parent.as_deref()?;
+        let row = state(ctx).node(self.taxonomy, parent)?;
+        Some(Node { taxonomy: self.taxonomy, row })
+    }
+
+    #[graphql(complexity = "child_complexity.saturating_mul(50)")]
+    async fn children(&self, ctx: &Context<'_>) -> Vec<Node> {
+        Node::wrap(self.taxonomy, state(ctx).children(self.taxonomy, &self.row.id))
+    }
+
+    /// Parents from the root down
+    #[graphql(complexity = "child_complexity.saturating_mul(4)")]
+    async fn ancestors(&self, ctx: &Context<'_>) -> Vec<Node> {
+        Node::wrap(self.taxonomy, state(ctx).ancestors(self.taxonomy, &self.row.id))
+    }
+
+    /// Every node below this one, depth first
+    #[graphql(complexity = "child_complexity.saturating_mul(200)")]
+    async fn descendants(&self, ctx: &Context<'_>) -> Vec<Node> {
+        Node::wrap(self.taxonomy, state(ctx).descendants(self.taxonomy, &self.row.id))
+    }
+}
+
+struct Query;
+
+#[Object]
+impl Query {
+    async fn taxonomies(&self, ctx: &Context<'_>) -> Vec<TaxonomyInfo> {
+        Taxonomy::ALL
+            .into_iter()
+            .map(|taxonomy| TaxonomyInfo {
+                name: taxonomy.name(),
+                version: taxonomy.version(),
+                cattax: taxonomy.cattax(),
+                nodes: state(ctx).taxonomies.items(taxonomy).len(),
+            })
+            .collect()
+    }
+
+    async fn node(&self, ctx: &Context<'_>, taxonomy: TaxonomyKind, id: String) -> Option<Node> {
+        let taxonomy = taxonomy.into();
+        let row = state(ctx).node(taxonomy, &id)?;
+        Some(Node { taxonomy, row })
+    }
+
+    /// Nodes whose ID or name contains `query`, case-insensitive, at most 100 of them
+    #[graphql(complexity = "search_limit(limit).saturating_mul(child_complexity)")]
+    async fn search(&self, ctx: &Context<'_>, taxonomy: TaxonomyKind, query: String, limit: Option<usize>) -> Vec<Node> {
+        let taxonomy = taxonomy.into();
+        Node::wrap(taxonomy, state(ctx).search(taxonomy, &query, search_limit(limit)))
+    }
+}
+
+/// The client's `limit`, capped so the query's cost is known before it runs
+fn search_limit(limit: Option<usize>) -> usize {
+    limit.unwrap_or(DEFAULT_SEARCH_LIMIT).min(DEFAULT_SEARCH_LIMIT)
+}
+
+async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+fn schema(state: Arc<Live>) -> IabSchema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(state)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// `POST /graphql` for queries, `GET /graphql` for the GraphiQL playground
+pub fn router(state: Arc<Live>) -> Router {
+    Router::new().route("/graphql", get(graphiql).post_service(GraphQL::new(schema(state))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iab::Taxonomies;
+
+    async fn errors(query: &str) -> Vec<String> {
+        let live = Arc::new(Live::new(ServerState::new(Taxonomies::load(|_| {}).expect("bundled data loads"))));
+        schema(live).execute(query).await.errors.into_iter().map(|error| error.message).collect()
+    }
+
+    #[tokio::test]
+    async fn reasonable_queries_run() {
+        assert!(errors(r#"{ node(taxonomy: CONTENT, id: "150") { id descendants { id name tiers } } }"#).await.is_empty());
+        assert!(errors(r#"{ node(taxonomy: CONTENT, id: "151") { parent { children { id name } } } }"#).await.is_empty());
+        assert!(errors(r#"{ search(taxonomy: CONTENT, query: "sport") { id ancestors { name } } }"#).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn over_deep_and_expanding_queries_are_refused() {
+        let deep = r#"{ node(taxonomy: CONTENT, id: "151") { parent { parent { parent { parent { parent { parent { parent { id } } } } } } } } }"#;
+        assert_eq!(errors(deep).await, ["Query is nested too deep."]);
+        let nested = r#"{ node(taxonomy: CONTENT, id: "150") { descendants { descendants { id } } } }"#;
+        assert_eq!(errors(nested).await, ["Query is too complex."]);
+    }
+
+    #[tokio::test]
+    async fn huge_search_limits_are_capped() {
+        // Capped at the default rather than multiplied out, which would overflow
+        let huge = r#"{ search(taxonomy: CONTENT, query: "a", limit: 9223372036854775807) { id } }"#;
+        assert!(errors(huge).await.is_empty());
+        let expanding = r#"{ search(taxonomy: CONTENT, query: "a", limit: 9223372036854775807) { descendants { id } } }"#;
+        assert_eq!(errors(expanding).await, ["Query is too complex."]);
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::*;
+use iab::{crosswalk, Crosswalks, Taxonomies, Taxonomy};
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use crate::export::ExportRow;
+use crate::server::{ServerState, DEFAULT_SEARCH_LIMIT};
+
+mod proto {
+    tonic::include_proto!("iab.v1");
+}
+
+use proto::taxonomy_service_server::{TaxonomyService, TaxonomyServiceServer};
+use proto::{LookupRequest, MapRequest, MapResponse, Mapping, Node, NodeList, SearchRequest};
+
+struct Service {
+    state: Arc<ServerState>,
+    crosswalks: Crosswalks,
+}
+
+impl From<ExportRow> for Node {
+    fn from(row: ExportRow) -> Self {
+        Node {
+            id: row.id,
+            parent: row.parent,
+            name: row.name,
+            depth: row.depth as u32,
+            tiers: row.tiers,
+            extension: row.extension,
+        }
+    }
+}
+
+fn taxonomy(cattax: u32) -> std::result::Result<Taxonomy, Status> {
+    Taxonomy::for_cattax(cattax).map_err(|err| Status::not_found(err.to_string()))
+}
+
+fn node_list(rows: Vec<ExportRow>) -> Response<NodeList> {
+    Response::new(NodeList { nodes: rows.into_iter().map(Node::from).collect() })
+}
+
+#[tonic::async_trait]
+impl TaxonomyService for Service {
+    async fn lookup(&self, request: Request<LookupRequest>) -> std::result::Result<Response<Node>, Status> {
+        let request = request.into_inner();
+        let row = self
+            .state
+            .node(taxonomy(request.cattax)?, &request.id)
+            .ok_or_else(|| Status::not_found(format!("unknown node {}", request.id)))?;
+        std::result::Result::Ok(Response::new(row.into()))
+    }
+
+    async fn search(&self, request: Request<SearchRequest>) -> std::result::Result<Response<NodeList>, Status> {
+        let request = request.into_inner();
+        let limit = if request.limit == 0 { DEFAULT_SEARCH_LIMIT } else { request.limit as usize };
+        std::result::Result::Ok(node_list(self.state.search(taxonomy(request.cattax)?, &request.query, limit)))
+    }
+
+    async fn descendants(&self, request: Request<LookupRequest>) -> std::result::Result<Response<NodeList>, Status> {
+        let request = request.into_inner();
+        let taxonomy = taxonomy(request.cattax)?;
+        if self.state.node(taxonomy, &request.id).is_none() {
+            return Err(Status::not_found(format!("unknown node {}", request.id)));
+        }
+        std::result::Result::Ok(node_list(self.state.descendants(taxonomy, &request.id)))
+    }
+
+    async fn map(&self, request: Request<MapRequest>) -> std::result::Result<Response<MapResponse>, Status> {
+        let request = request.into_inner();
+        let version = |spec: &str| crosswalk::resolve_version(spec).map_err(|err| Status::invalid_argument(err.to_string()));
+        let (from, to) = (version(&request.from_version)?, version(&request.to_version)?);
+
+        let mapper = self.crosswalks.mapper(&from, &to).map_err(|err| Status::failed_precondition(err.to_string()))?;
+        let mappings = request.ids.into_iter().map(|id| Mapping { targets: mapper.map(&id), id }).collect();
+        std::result::Result::Ok(Response::new(MapResponse { mappings }))
+    }
+}
+
+/// Serve the gRPC service until Ctrl-C
+pub fn serve(host: &str, port: u16, crosswalks: Crosswalks) -> Result<()> {
+    let state = Arc::new(ServerState::new(Taxonomies::load(|_| {})?));
+    let address = format!("{}:{}", host, port)
+        .parse()
+        .with_context(|| format!("invalid address {}:{}", host, port))?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        eprintln!("Serving gRPC on {}", address);
+        tonic::transport::Server::builder()
+            .add_service(TaxonomyServiceServer::new(Service { state, crosswalks }))
+            .serve_with_shutdown(address, async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await?;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::Code;
+
+    fn service() -> Service {
+        let state = Arc::new(ServerState::new(Taxonomies::load(|_| {}).expect("bundled data loads")));
+        Service { state, crosswalks: Crosswalks::default() }
+    }
+
+    fn lookup_request(cattax: u32, id: &str) -> Request<LookupRequest> {
+        Request::new(LookupRequest { cattax, id: id.to_string() })
+    }
+
+    #[tokio::test]
+    async fn lookup_answers_nodes_and_not_found() {
+        let service = service();
+        let node = service.lookup(lookup_request(7, "155")).await.unwrap().into_inner();
+        assert_eq!((node.id.as_str(), node.parent.as_deref(), node.name.as_str(), node.depth), ("155", Some("150"), "Museums & Galleries", 1));
+        assert_eq!(node.tiers, ["Attractions", "Museums & Galleries"]);
+
+        assert_eq!(service.lookup(lookup_request(7, "nope")).await.unwrap_err().code(), Code::NotFound);
+        assert_eq!(service.lookup(lookup_request(99, "155")).await.unwrap_err().code(), Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn search_applies_the_limit() {
+        let service = service();
+        let search = |limit| Request::new(SearchRequest { cattax: 7, query: "museum".to_string(), limit });
+        let nodes = service.search(search(0)).await.unwrap().into_inner().nodes;
+        assert_eq!(nodes.iter().map(|node| node.id.as_str()).collect::<Vec<_>>(), ["155"]);
+
+        let broad = |limit| Request::new(SearchRequest { cattax: 7, query: "a".to_string(), limit });
+        assert_eq!(service.search(broad(3)).await.unwrap().into_inner().nodes.len(), 3);
+        assert_eq!(service.search(broad(0)).await.unwrap().into_inner().nodes.len(), DEFAULT_SEARCH_LIMIT);
+    }
+}
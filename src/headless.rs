@@ -0,0 +1,135 @@
+//! `--headless`: drive the TUI with scripted keys on ratatui's `TestBackend` and print the final
+//! screen or state, for snapshot tests and demos.
+//!
+//! A script is a list of whitespace-separated keys: names such as `Down`, `Enter`, `Esc`, `Tab`,
+//! `PageDown` or `F8`, optionally prefixed with `Ctrl+`, `Alt+` or `Shift+`; single characters;
+//! and text, typed character by character. Quote text that would read as a key name (`"End"`).
+//! `#` starts a comment running to the end of the line.
+
+use anyhow::*;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+
+use crate::App;
+
+pub fn parse_keys(script: &str) -> Result<Vec<KeyEvent>> {
+    let mut keys = Vec::new();
+    for line in script.lines() {
+        let mut rest = line.trim_start();
+        while !rest.is_empty() && !rest.starts_with('#') {
+            if let Some(quoted) = rest.strip_prefix('"') {
+                let (text, after) = quoted.split_once('"').with_context(|| format!("unclosed quote in {:?}", line))?;
+                keys.extend(text.chars().map(typed));
+                rest = after.trim_start();
+                continue;
+            }
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            keys.extend(parse_token(&rest[..end]));
+            rest = rest[end..].trim_start();
+        }
+    }
+    Ok(keys)
+}
+
+/// Exactly one key in the script syntax, e.g. `Alt+j` or `PageDown`
+pub fn parse_key(token: &str) -> Result<KeyEvent> {
+    match parse_token(token).as_slice() {
+        [key] => Ok(*key),
+        _ => bail!("unknown key {:?}", token),
+    }
+}
+
+/// One key, or the characters of the token when it names none
+fn parse_token(token: &str) -> Vec<KeyEvent> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut name = token;
+    loop {
+        let lower = name.to_ascii_lowercase();
+        let (modifier, length) = if lower.starts_with("ctrl+") {
+            (KeyModifiers::CONTROL, 5)
+        } else if lower.starts_with("alt+") {
+            (KeyModifiers::ALT, 4)
+        } else if lower.starts_with("shift+") {
+            (KeyModifiers::SHIFT, 6)
+        } else {
+            break;
+        };
+        if name.len() == length {
+            break;
+        }
+        modifiers |= modifier;
+        name = &name[length..];
+    }
+
+    match key_code(name) {
+        Some(KeyCode::Tab) if modifiers.contains(KeyModifiers::SHIFT) => {
+            vec![KeyEvent::new(KeyCode::BackTab, modifiers)]
+        }
+        Some(code) => vec![KeyEvent::new(code, modifiers)],
+        None => token.chars().map(typed).collect(),
+    }
+}
+
+fn key_code(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c));
+    }
+    let code = match name.to_ascii_lowercase().as_str() {
+        "enter" => KeyCode::Enter,
+        "esc" | "escape" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" => KeyCode::Insert,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "space" => KeyCode::Char(' '),
+        function => KeyCode::F(function.strip_prefix('f')?.parse().ok().filter(|n| (1..=12).contains(n))?),
+    };
+    Some(code)
+}
+
+/// A typed character, with Shift on capitals as terminals report them
+fn typed(c: char) -> KeyEvent {
+    let modifiers = if c.is_uppercase() { KeyModifiers::SHIFT } else { KeyModifiers::NONE };
+    KeyEvent::new(KeyCode::Char(c), modifiers)
+}
+
+/// Feed `keys` to the app, drawing after each as the event loop does, and return the final screen
+/// as text; stops early when a key quits
+pub fn run(app: &mut App, keys: &[KeyEvent], width: u16, height: u16) -> Result<String> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    terminal.draw(|frame| crate::ui(frame, app))?;
+    for &key in keys {
+        if !app.handle_key(key) {
+            break;
+        }
+        #[cfg(feature = "plugins")]
+        app.run_select_hook();
+        terminal.draw(|frame| crate::ui(frame, app))?;
+    }
+
+    let buffer = terminal.backend().buffer();
+    let lines: Vec<String> = (0..buffer.area.height)
+        .map(|y| {
+            let line: String = (0..buffer.area.width).map(|x| buffer[(x, y)].symbol()).collect();
+            line.trim_end().to_string()
+        })
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+/// Parse `120x40` into columns and rows
+pub fn parse_size(value: &str) -> Result<(u16, u16)> {
+    let (width, height) = value.split_once('x').context("expected <columns>x<rows>, e.g. 120x40")?;
+    Ok((width.parse()?, height.parse()?))
+}
@@ -0,0 +1,362 @@
+//! Strings of the TUI (help bar, titles, popup labels and hints) by locale. English is the
+//! source; templates fill each `{}` with the next argument. The locale comes from `IAB_LANG`,
+//! the config file's `locale`, or the usual `LC_ALL` / `LC_MESSAGES` / `LANG` variables, in
+//! that order. Toasts and command-line output stay English.
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+const LOCALE_ENV: &str = "IAB_LANG";
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+}
+
+impl Locale {
+    /// Read a locale from a language tag such as `de`, `de_DE.UTF-8` or `en-US`
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag.split(['_', '-', '.', '@']).next().unwrap_or_default().to_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            _ => None,
+        }
+    }
+}
+
+static LOCALE: AtomicU8 = AtomicU8::new(0);
+
+pub fn set(locale: Locale) {
+    LOCALE.store(locale as u8, Ordering::Relaxed);
+}
+
+fn current() -> Locale {
+    match LOCALE.load(Ordering::Relaxed) {
+        1 => Locale::De,
+        _ => Locale::En,
+    }
+}
+
+/// `IAB_LANG` first, then the configured locale, then the system's language variables
+pub fn detect(configured: Option<Locale>) -> Locale {
+    let from_env = |name: &str| std::env::var(name).ok().and_then(|tag| Locale::from_tag(&tag));
+    from_env(LOCALE_ENV)
+        .or(configured)
+        .or_else(|| ["LC_ALL", "LC_MESSAGES", "LANG"].into_iter().find_map(from_env))
+        .unwrap_or_default()
+}
+
+/// A translatable string of the interface
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Text {
+    HelpBrowse,
+    #[cfg(feature = "plugins")]
+    HelpPlugins,
+    HelpQuit,
+    HelpDetails,
+    Datasource,
+    Filter,
+    FilterPlaceholder,
+    Results,
+    Zoom,
+    HiddenDuplicates,
+    TreeError,
+    Siblings,
+    Details,
+    BookmarksPane,
+    MappingTitle,
+    MappingKeys,
+    DetailsTitle,
+    RawRecordTitle,
+    Unknown,
+    NoEquivalent,
+    UniqueId,
+    ParentId,
+    ParentName,
+    Ancestors,
+    Name,
+    Translation,
+    Tier,
+    Note,
+    Tags,
+    Usage,
+    UsageLine,
+    Extension,
+    SortFileOrder,
+    SortId,
+    SortName,
+    SortUsage,
+    SortUsageMetric,
+    Legend,
+    LegendKeys,
+    LegendTabs,
+    LegendTab,
+    LegendBadges,
+    LegendBookmark,
+    LegendNote,
+    LegendTag,
+    LegendDeprecated,
+    LegendExtension,
+    LegendTree,
+    LegendExpand,
+    LegendGuides,
+    LegendMatchSample,
+    LegendMatch,
+    LegendScrollbar,
+    ClassifyLabel,
+    ClassifyHint,
+    ClassifyNone,
+    ClassifyTitle,
+    ClassifyKeys,
+    BasketPane,
+    BasketEmpty,
+    BasketTitle,
+    BasketKeys,
+    CompareAdd,
+    CompareSkip,
+    CompareDrop,
+    CompareKeepOfficial,
+    CompareUseOurs,
+    CompareTitle,
+    CompareKeys,
+    ChangelogTitle,
+    ChangelogKeys,
+    ChangelogEmpty,
+    ChangelogRenamed,
+    ChangelogMoved,
+    ChangelogTopLevel,
+    ChangesAll,
+    ChangesAdded,
+    ChangesRemoved,
+    ChangesRenamed,
+    ChangesMoved,
+    NoteTitle,
+    TagsTitle,
+    ChildTitle,
+    NoteKeys,
+    TagsKeys,
+    ChildKeys,
+    DiagnosticsNone,
+    DiagnosticsTitle,
+    DiagnosticsKeys,
+    Ortb2SelectedNode,
+    Ortb2Bookmarks,
+    Ortb2Keys,
+    Ortb2AudienceKeys,
+    ListBookmarks,
+    ListRecent,
+    ListBookmarksEmpty,
+    ListRecentEmpty,
+    ListKeys,
+    ExportTitle,
+    ExportFormat,
+    ExportScope,
+    ExportPath,
+    ExportKeys,
+    #[cfg(feature = "plugins")]
+    PaletteTitle,
+    #[cfg(feature = "plugins")]
+    PaletteKeys,
+    PlainHeader,
+    PlainFilter,
+    PlainFilterEmpty,
+    PlainTop,
+    PlainLevel,
+    PlainPosition,
+    PlainEmpty,
+    PlainChild,
+    PlainChildren,
+    PlainExpanded,
+    PlainCollapsed,
+    PlainBookmarked,
+    PlainNoted,
+    PlainTagged,
+    PlainUsage,
+    PlainKeys,
+    PlainDetailsKeys,
+}
+
+impl Text {
+    /// English and German
+    fn strings(self) -> [&'static str; 2] {
+        match self {
+            Text::HelpBrowse => [
+                "Tab/Shift+Tab: Switch | ↑↓: Navigate | ←→: Collapse/Expand | Enter: Details | Ctrl-n/p: Sibling | Ctrl-o: Parent | Ctrl-z/x: Zoom in/out | Ctrl-e: Export | Ctrl-b/g: Bookmark/List | Ctrl-r: Recent | Ctrl-s: Sort | Ctrl-j: Language | Ctrl-f: Classify | F7: ortb2 | F8: Diagnostics | F9/F10: Note/Tags | Ctrl-y/F11: Basket | F12/Del: Custom node | Ctrl-v: Compare",
+                "Tab/Shift+Tab: Wechseln | ↑↓: Navigieren | ←→: Zu-/Aufklappen | Enter: Details | Ctrl-n/p: Geschwister | Ctrl-o: Eltern | Ctrl-z/x: Zoom rein/raus | Ctrl-e: Export | Ctrl-b/g: Lesezeichen/Liste | Ctrl-r: Zuletzt | Ctrl-s: Sortierung | Ctrl-j: Sprache | Ctrl-f: Klassifizieren | F7: ortb2 | F8: Diagnose | F9/F10: Notiz/Tags | Ctrl-y/F11: Korb | F12/Del: Eigener Knoten | Ctrl-v: Vergleich",
+            ],
+            #[cfg(feature = "plugins")]
+            Text::HelpPlugins => [" | Alt-Enter: Plugins", " | Alt-Enter: Plugins"],
+            Text::HelpQuit => [" | F1: Legend | ESC/Ctrl-q: Quit", " | F1: Legende | ESC/Ctrl-q: Beenden"],
+            Text::HelpDetails => [
+                "ESC/Enter: Close | r: Raw record | n: Note | t: Tags | Ctrl-q: Quit",
+                "ESC/Enter: Schließen | r: Rohdaten | n: Notiz | t: Tags | Ctrl-q: Beenden",
+            ],
+            Text::Datasource => ["Datasource", "Datenquelle"],
+            Text::Filter => ["Filter", "Filter"],
+            Text::FilterPlaceholder => ["Type to filter...", "Tippen zum Filtern..."],
+            Text::Results => ["Results ({} items) | Sort: {}", "Ergebnisse ({} Einträge) | Sortierung: {}"],
+            Text::Zoom => ["Zoom: {}", "Zoom: {}"],
+            Text::HiddenDuplicates => ["⚠ {} nodes with a repeated ID hidden", "⚠ {} Knoten mit wiederholter ID ausgeblendet"],
+            Text::TreeError => ["Cannot show the tree: {}", "Der Baum kann nicht angezeigt werden: {}"],
+            Text::Siblings => ["Siblings ({})", "Geschwister ({})"],
+            Text::Details => ["Details", "Details"],
+            Text::BookmarksPane => ["Bookmarks ({}) | Ctrl-g: Jump", "Lesezeichen ({}) | Ctrl-g: Springen"],
+            Text::MappingTitle => ["Mapping {} ({}/{})", "Zuordnung {} ({}/{})"],
+            Text::MappingKeys => [" Alt-↑↓: Entry | Ctrl-a: Assign ", " Alt-↑↓: Eintrag | Ctrl-a: Zuordnen "],
+            Text::DetailsTitle => [" {} Details ", " {} Details "],
+            Text::RawRecordTitle => [" {} Raw Record ", " {} Rohdatensatz "],
+            Text::Unknown => ["(unknown)", "(unbekannt)"],
+            Text::NoEquivalent => ["(no equivalent)", "(keine Entsprechung)"],
+            Text::UniqueId => ["Unique ID", "Eindeutige ID"],
+            Text::ParentId => ["Parent ID", "Eltern-ID"],
+            Text::ParentName => ["Parent Name", "Elternname"],
+            Text::Ancestors => ["Ancestors", "Vorfahren"],
+            Text::Name => ["Name", "Name"],
+            Text::Translation => ["Name ({})", "Name ({})"],
+            Text::Tier => ["Tier {}", "Ebene {}"],
+            Text::Note => ["Note", "Notiz"],
+            Text::Tags => ["Tags", "Tags"],
+            Text::Usage => ["Usage", "Nutzung"],
+            Text::UsageLine => ["{}: {} ({} with descendants)", "{}: {} ({} mit Nachfahren)"],
+            Text::Extension => ["Extension", "Erweiterung"],
+            Text::SortFileOrder => ["File order", "Dateireihenfolge"],
+            Text::SortId => ["ID", "ID"],
+            Text::SortName => ["Name", "Name"],
+            Text::SortUsage => ["Usage", "Nutzung"],
+            Text::SortUsageMetric => ["Usage ({})", "Nutzung ({})"],
+            Text::Legend => [" Legend ", " Legende "],
+            Text::LegendKeys => [" ESC/F1: Close ", " ESC/F1: Schließen "],
+            Text::LegendTabs => ["Tabs", "Reiter"],
+            Text::LegendTab => ["taxonomy tab, also used for borders", "Taxonomie-Reiter, auch für Rahmen"],
+            Text::LegendBadges => ["Badges", "Markierungen"],
+            Text::LegendBookmark => ["bookmarked node", "Knoten mit Lesezeichen"],
+            Text::LegendNote => ["node with a note (F9)", "Knoten mit Notiz (F9)"],
+            Text::LegendTag => ["one per tag, colored by name (F10, filter with tag:<name>)", "je Tag einer, nach Name gefärbt (F10, Filter tag:<Name>)"],
+            Text::LegendDeprecated => ["deprecated node, shown dimmed", "veralteter Knoten, abgeblendet"],
+            Text::LegendExtension => ["organization-specific extension", "organisationseigene Erweiterung"],
+            Text::LegendTree => ["Tree", "Baum"],
+            Text::LegendExpand => ["collapsed / expanded node", "zu- / aufgeklappter Knoten"],
+            Text::LegendGuides => ["sibling, last child, ancestor guide", "Geschwister, letztes Kind, Vorfahrenlinie"],
+            Text::LegendMatchSample => ["match", "Treffer"],
+            Text::LegendMatch => ["filter match", "Filtertreffer"],
+            Text::LegendScrollbar => ["scrollbar, thumb marks the visible rows", "Bildlaufleiste, der Balken zeigt die sichtbaren Zeilen"],
+            Text::ClassifyLabel => ["Text: ", "Text: "],
+            Text::ClassifyHint => ["Type or paste article text, a title or a URL", "Artikeltext, Titel oder URL eingeben oder einfügen"],
+            Text::ClassifyNone => ["No matching categories", "Keine passenden Kategorien"],
+            Text::ClassifyTitle => [" Classify against {} ", " Gegen {} klassifizieren "],
+            Text::ClassifyKeys => [" ↑↓: Select | Enter: Jump | ESC: Close ", " ↑↓: Auswählen | Enter: Springen | ESC: Schließen "],
+            Text::BasketPane => ["Basket ({}) | Ctrl-y: Add/Remove | F11: Open", "Korb ({}) | Ctrl-y: Hinzufügen/Entfernen | F11: Öffnen"],
+            Text::BasketEmpty => ["The basket is empty (Ctrl-y adds the selected node)", "Der Korb ist leer (Ctrl-y legt den ausgewählten Knoten hinein)"],
+            Text::BasketTitle => [" Basket ({}) | Format: {} ", " Korb ({}) | Format: {} "],
+            Text::BasketKeys => [
+                " ↑↓: Select | Enter: Jump | Del: Remove | x: Clear | Tab: Format | c: Copy | s: Save | ESC: Close ",
+                " ↑↓: Auswählen | Enter: Springen | Del: Entfernen | x: Leeren | Tab: Format | c: Kopieren | s: Speichern | ESC: Schließen ",
+            ],
+            Text::CompareAdd => ["add", "hinzufügen"],
+            Text::CompareSkip => ["skip", "auslassen"],
+            Text::CompareDrop => ["drop", "entfernen"],
+            Text::CompareKeepOfficial => ["keep official", "offizielle behalten"],
+            Text::CompareUseOurs => ["use ours", "unsere übernehmen"],
+            Text::CompareTitle => [
+                " {} against the official {}: {} added, {} removed, {} changed ",
+                " {} gegen das offizielle {}: {} hinzugefügt, {} entfernt, {} geändert ",
+            ],
+            Text::CompareKeys => [
+                " ↑↓: Select | Space: Take / leave change | Enter: Jump | s: Save {} | ESC: Close ",
+                " ↑↓: Auswählen | Leertaste: Änderung übernehmen / lassen | Enter: Springen | s: {} speichern | ESC: Schließen ",
+            ],
+            Text::ChangelogTitle => [
+                " Changes from {} to {} {}: {} added, {} removed, {} renamed, {} moved ",
+                " Änderungen von {} zu {} {}: {} hinzugefügt, {} entfernt, {} umbenannt, {} verschoben ",
+            ],
+            Text::ChangelogKeys => [
+                " ↑↓←→: Navigate | Tab: Filter ({}) | Enter: Jump | c: Compare with official | ESC: Close ",
+                " ↑↓←→: Navigieren | Tab: Filter ({}) | Enter: Springen | c: Mit offiziellem vergleichen | ESC: Schließen ",
+            ],
+            Text::ChangelogEmpty => ["No changes of this kind", "Keine Änderungen dieser Art"],
+            Text::ChangelogRenamed => ["renamed from \"{}\"", "umbenannt von „{}“"],
+            Text::ChangelogMoved => ["moved from {}", "verschoben von {}"],
+            Text::ChangelogTopLevel => ["the top level", "der obersten Ebene"],
+            Text::ChangesAll => ["all", "alle"],
+            Text::ChangesAdded => ["added", "hinzugefügte"],
+            Text::ChangesRemoved => ["removed", "entfernte"],
+            Text::ChangesRenamed => ["renamed", "umbenannte"],
+            Text::ChangesMoved => ["moved", "verschobene"],
+            Text::NoteTitle => [" Note on {} {} ", " Notiz zu {} {} "],
+            Text::TagsTitle => [" Tags of {} {} ", " Tags von {} {} "],
+            Text::ChildTitle => [" New child of {} {} ", " Neues Kind von {} {} "],
+            Text::NoteKeys => [" Enter: Save (empty removes) | ESC: Cancel ", " Enter: Speichern (leer entfernt) | ESC: Abbrechen "],
+            Text::TagsKeys => [
+                " Enter: Save (comma separated, empty removes) | ESC: Cancel ",
+                " Enter: Speichern (durch Komma getrennt, leer entfernt) | ESC: Abbrechen ",
+            ],
+            Text::ChildKeys => [" Enter: Add with the next custom ID | ESC: Cancel ", " Enter: Mit der nächsten eigenen ID anlegen | ESC: Abbrechen "],
+            Text::DiagnosticsNone => ["No problems found", "Keine Probleme gefunden"],
+            Text::DiagnosticsTitle => [" Diagnostics · {} {} · {} ", " Diagnose · {} {} · {} "],
+            Text::DiagnosticsKeys => [" ↑↓: Scroll | ESC/F8: Close ", " ↑↓: Blättern | ESC/F8: Schließen "],
+            Text::Ortb2SelectedNode => ["selected node", "ausgewählter Knoten"],
+            Text::Ortb2Bookmarks => ["bookmarks", "Lesezeichen"],
+            Text::Ortb2Keys => [" Tab: site/app | ESC/F7: Close ", " Tab: site/app | ESC/F7: Schließen "],
+            Text::Ortb2AudienceKeys => [" ESC/F7: Close ", " ESC/F7: Schließen "],
+            Text::ListBookmarks => ["Bookmarks", "Lesezeichen"],
+            Text::ListRecent => ["Recently Viewed", "Zuletzt angesehen"],
+            Text::ListBookmarksEmpty => ["No bookmarks yet (Ctrl-b stars the selected node)", "Noch keine Lesezeichen (Ctrl-b markiert den ausgewählten Knoten)"],
+            Text::ListRecentEmpty => ["No recently viewed items (Enter opens details)", "Noch nichts angesehen (Enter öffnet die Details)"],
+            Text::ListKeys => [" Enter: Jump | Del: Remove | ESC: Close ", " Enter: Springen | Del: Entfernen | ESC: Schließen "],
+            Text::ExportTitle => [" Export {} View ", " Ansicht {} exportieren "],
+            Text::ExportFormat => ["Format", "Format"],
+            Text::ExportScope => ["Scope", "Umfang"],
+            Text::ExportPath => ["Path", "Pfad"],
+            #[cfg(feature = "plugins")]
+            Text::PaletteTitle => [" Plugin Commands ", " Plugin-Befehle "],
+            #[cfg(feature = "plugins")]
+            Text::PaletteKeys => [" ↑↓: Select | Enter: Run on the selected node | ESC: Close ", " ↑↓: Auswählen | Enter: Auf dem ausgewählten Knoten ausführen | ESC: Schließen "],
+            Text::PlainHeader => ["{} taxonomy {}, cattax {}. Sort: {}", "Taxonomie {} {}, cattax {}. Sortierung: {}"],
+            Text::PlainFilter => ["Filter: {}", "Filter: {}"],
+            Text::PlainFilterEmpty => ["Filter: none, type to filter", "Filter: keiner, tippen zum Filtern"],
+            Text::PlainTop => ["Top level", "Oberste Ebene"],
+            Text::PlainLevel => ["Inside {}", "Unter {}"],
+            Text::PlainPosition => ["Item {} of {}", "Eintrag {} von {}"],
+            Text::PlainEmpty => ["No matching items", "Keine passenden Einträge"],
+            Text::PlainChild => ["1 child", "1 Kind"],
+            Text::PlainChildren => ["{} children", "{} Kinder"],
+            Text::PlainExpanded => ["open", "geöffnet"],
+            Text::PlainCollapsed => ["closed", "geschlossen"],
+            Text::PlainBookmarked => ["bookmarked", "mit Lesezeichen"],
+            Text::PlainNoted => ["has a note", "mit Notiz"],
+            Text::PlainTagged => ["tags {}", "Tags {}"],
+            Text::PlainUsage => ["usage {}", "Nutzung {}"],
+            Text::PlainKeys => [
+                "Up and Down: move. Right: open. Left: close. Enter: details. Type to filter. F1: legend. Escape: quit.",
+                "Hoch und Runter: bewegen. Rechts: öffnen. Links: schließen. Enter: Details. Tippen zum Filtern. F1: Legende. Escape: beenden.",
+            ],
+            Text::PlainDetailsKeys => [
+                "Escape or Enter: back to the list. r: raw record. n: note. t: tags.",
+                "Escape oder Enter: zurück zur Liste. r: Rohdaten. n: Notiz. t: Tags.",
+            ],
+            Text::ExportKeys => ["↑↓: Field | ←→: Change | Enter: Export | ESC: Cancel", "↑↓: Feld | ←→: Ändern | Enter: Exportieren | ESC: Abbrechen"],
+        }
+    }
+}
+
+/// `text` in the current locale
+pub fn tr(text: Text) -> &'static str {
+    text.strings()[current() as usize]
+}
+
+/// `text` in the current locale with each `{}` replaced by the next of `args`
+pub fn trf(text: Text, args: &[&dyn Display]) -> String {
+    let mut args = args.iter();
+    let mut parts = tr(text).split("{}");
+    let mut filled = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        if let Some(arg) = args.next() {
+            filled.push_str(&arg.to_string());
+        }
+        filled.push_str(part);
+    }
+    filled
+}
@@ -0,0 +1,112 @@
+//! JSON-RPC 2.0 shared by `iab mcp` and `iab daemon`: one message per line, read with a size cap,
+//! with each request's result wrapped in a response. `lsp.rs` frames its messages differently but
+//! reuses the error codes and responses.
+
+use anyhow::*;
+use serde_json::{json, Value};
+use std::io::{BufRead, Read, Write};
+
+pub(crate) const PARSE_ERROR: i64 = -32700;
+pub(crate) const INVALID_REQUEST: i64 = -32600;
+pub(crate) const METHOD_NOT_FOUND: i64 = -32601;
+pub(crate) const INVALID_PARAMS: i64 = -32602;
+
+/// Largest request read, a line here or an LSP body; longer ones are skipped and answered with an
+/// error, so a client cannot make the process buffer without bound
+pub(crate) const MAX_MESSAGE: usize = 16 * 1024 * 1024;
+
+/// A method's result, or the error code and message to answer with
+pub(crate) type RpcResult = std::result::Result<Value, (i64, String)>;
+
+/// What [`read_line`] found
+enum Line {
+    /// A request line is in the buffer
+    Read,
+    /// The line was over `MAX_MESSAGE` bytes and has been skipped
+    Oversized,
+    /// The input has ended
+    End,
+}
+
+/// Read the next line of `input` into `line`, buffering at most `MAX_MESSAGE` bytes of it
+fn read_line(input: &mut impl BufRead, line: &mut Vec<u8>) -> Result<Line> {
+    line.clear();
+    if input.take(MAX_MESSAGE as u64 + 1).read_until(b'\n', line)? == 0 {
+        return Ok(Line::End);
+    }
+    if line.len() > MAX_MESSAGE && line.last() != Some(&b'\n') {
+        // Drop the rest of the line so the next request is read in step
+        input.skip_until(b'\n')?;
+        line.clear();
+        return Ok(Line::Oversized);
+    }
+    Ok(Line::Read)
+}
+
+/// Answer each line of `input` on `output` until the input ends. `handle` answers one message,
+/// with `None` for notifications; lines that are too long or not JSON are answered here.
+pub(crate) fn serve_lines(mut input: impl BufRead, mut output: impl Write, handle: impl Fn(&Value) -> Option<Value>) -> Result<()> {
+    let mut line = Vec::new();
+    loop {
+        let response = match read_line(&mut input, &mut line)? {
+            Line::End => return Ok(()),
+            Line::Oversized => Some(error(INVALID_REQUEST, format!("request over {} bytes", MAX_MESSAGE))),
+            Line::Read if line.trim_ascii().is_empty() => continue,
+            Line::Read => match serde_json::from_slice::<Value>(&line) {
+                Err(err) => Some(error(PARSE_ERROR, format!("parse error: {}", err))),
+                Result::Ok(message) => handle(&message),
+            },
+        };
+        if let Some(response) = response {
+            writeln!(output, "{}", response)?;
+            output.flush()?;
+        }
+    }
+}
+
+/// Answer a request with `call(method)`; notifications (messages without an id) get no response
+pub(crate) fn reply(message: &Value, call: impl FnOnce(&str) -> RpcResult) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    Some(response(id, call(message.get("method").and_then(Value::as_str).unwrap_or_default())))
+}
+
+/// The response to request `id`
+pub(crate) fn response(id: Value, result: RpcResult) -> Value {
+    match result {
+        Result::Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }),
+    }
+}
+
+/// An error answering a message whose id could not be read
+pub(crate) fn error(code: i64, message: String) -> Value {
+    response(Value::Null, Err((code, message)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn responses(input: &[u8]) -> Vec<Value> {
+        let mut output = Vec::new();
+        serve_lines(input, &mut output, |message| reply(message, |_| Result::Ok(json!({})))).unwrap();
+        output.split(|&byte| byte == b'\n').filter(|line| !line.is_empty()).map(|line| serde_json::from_slice(line).unwrap()).collect()
+    }
+
+    #[test]
+    fn oversized_line_is_answered_and_reading_goes_on() {
+        let mut input = vec![b' '; MAX_MESSAGE + 1];
+        input.extend_from_slice(b"\n{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"ping\"}\n");
+        let responses = responses(&input);
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["error"]["code"], INVALID_REQUEST);
+        assert_eq!(responses[1]["id"], 1);
+        assert!(responses[1].get("result").is_some());
+    }
+
+    #[test]
+    fn notifications_get_no_response() {
+        let responses = responses(b"{\"jsonrpc\":\"2.0\",\"method\":\"ping\"}\n{\"jsonrpc\":\"2.0\",\"id\":\"a\",\"method\":\"ping\"}\n");
+        assert_eq!(responses, [json!({ "jsonrpc": "2.0", "id": "a", "result": {} })]);
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::*;
+use serde::{Deserialize, Serialize};
+
+use crate::state;
+
+/// Terminal width from which `Auto` switches to the wide layout
+const WIDE_MIN_WIDTH: u16 = 140;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LayoutMode {
+    /// Wide layout on large terminals, stacked otherwise
+    Auto,
+    /// Tree only, details in a popup
+    Stacked,
+    /// Tree left, details right, bookmarks below the tree
+    Wide,
+}
+
+impl LayoutMode {
+    pub fn next(self) -> Self {
+        match self {
+            LayoutMode::Auto => LayoutMode::Stacked,
+            LayoutMode::Stacked => LayoutMode::Wide,
+            LayoutMode::Wide => LayoutMode::Auto,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LayoutMode::Auto => "Auto",
+            LayoutMode::Stacked => "Stacked",
+            LayoutMode::Wide => "Wide",
+        }
+    }
+}
+
+/// Pane visibility and split ratio, persisted as JSON in the state directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LayoutConfig {
+    pub mode: LayoutMode,
+    pub show_details: bool,
+    pub show_bookmarks: bool,
+    /// Width of the tree pane in percent when the details pane is shown
+    pub split_percent: u16,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            mode: LayoutMode::Auto,
+            show_details: true,
+            show_bookmarks: true,
+            split_percent: 60,
+        }
+    }
+}
+
+impl LayoutConfig {
+    /// Load the layout from disk; a missing file yields the defaults
+    pub fn load() -> Result<Self> {
+        Ok(state::LAYOUT.load()?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        state::LAYOUT.save(self)
+    }
+
+    pub fn is_wide(&self, terminal_width: u16) -> bool {
+        match self.mode {
+            LayoutMode::Auto => terminal_width >= WIDE_MIN_WIDTH,
+            LayoutMode::Stacked => false,
+            LayoutMode::Wide => true,
+        }
+    }
+
+    pub fn resize(&mut self, delta: i16) {
+        self.split_percent = self.split_percent.saturating_add_signed(delta).clamp(20, 80);
+    }
+}
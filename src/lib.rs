@@ -0,0 +1,18 @@
+//! Parsed IAB Tech Lab taxonomies (Product, Content, Audience), shared by the `iab` browser.
+
+pub mod bidrequest;
+pub mod cattax;
+pub mod compare;
+pub mod crosswalk;
+pub mod extension;
+pub mod mapping_table;
+pub mod ortb2;
+pub mod suggest;
+pub mod taxonomy;
+pub mod translation;
+pub mod validate;
+
+pub use crosswalk::{Crosswalk, Crosswalks};
+pub use mapping_table::MappingTable;
+pub use translation::Translation;
+pub use taxonomy::{Audience, Badge, ChildIndex, ColumnMap, Content, FileError, Haystack, LoadOptions, NodeSet, Product, Quoting, RawTable, Taxonomies, Taxonomy, TaxonomyFile, TaxonomyItem};
@@ -0,0 +1,122 @@
+//! Protection of `iab serve` against overly busy or careless clients: a per-IP request rate
+//! (`--rate-limit`) and a cap on request bodies (`--max-body`).
+
+use axum::body::{self, Body};
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Clients tracked before idle ones are forgotten
+const MAX_CLIENTS: usize = 10_000;
+
+/// Default cap on request bodies, far above any GraphQL query
+pub(crate) const DEFAULT_MAX_BODY: usize = 64 * 1024;
+
+/// Requests left to a client, refilled continuously
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets per client address: `per_minute` requests a minute, in bursts of up to as many
+pub(crate) struct RateLimiter {
+    per_minute: u32,
+    clients: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(per_minute: u32) -> Self {
+        Self { per_minute, clients: Mutex::new(HashMap::new()) }
+    }
+
+    /// Take a request from `client`'s bucket at `now`, or return the seconds until one is available
+    fn acquire(&self, client: IpAddr, now: Instant) -> std::result::Result<(), u64> {
+        let capacity = f64::from(self.per_minute);
+        let per_second = capacity / 60.0;
+        let mut clients = self.clients.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if clients.len() >= MAX_CLIENTS && !clients.contains_key(&client) {
+            clients.retain(|_, bucket| bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * per_second < capacity);
+        }
+        let bucket = clients.entry(client).or_insert(Bucket { tokens: capacity, updated: now });
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * per_second).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            std::result::Result::Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / per_second).ceil() as u64)
+        }
+    }
+}
+
+/// `429 Too Many Requests` with `Retry-After` once a client used up its rate
+pub(crate) async fn rate_limit(State(limiter): State<Arc<RateLimiter>>, request: Request, next: Next) -> Response {
+    let Some(ConnectInfo(address)) = request.extensions().get::<ConnectInfo<SocketAddr>>().copied() else {
+        return next.run(request).await;
+    };
+    match limiter.acquire(address.ip(), Instant::now()) {
+        std::result::Result::Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            tracing::warn!(client = %address.ip(), "rate limited");
+            let mut response = (StatusCode::TOO_MANY_REQUESTS, axum::Json(serde_json::json!({ "error": "too many requests" }))).into_response();
+            if let Result::Ok(value) = HeaderValue::from_str(&retry_after.max(1).to_string()) {
+                response.headers_mut().insert(header::RETRY_AFTER, value);
+            }
+            response
+        }
+    }
+}
+
+/// `413 Payload Too Large` for a body over `max` bytes, read before any handler sees it
+pub(crate) async fn limit_body(State(max): State<usize>, request: Request, next: Next) -> Response {
+    let too_large = || (StatusCode::PAYLOAD_TOO_LARGE, axum::Json(serde_json::json!({ "error": format!("request body over {} bytes", max) }))).into_response();
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if declared.is_some_and(|length| length > max) {
+        return too_large();
+    }
+    let (parts, body) = request.into_parts();
+    match body::to_bytes(body, max).await {
+        Result::Ok(bytes) => next.run(Request::from_parts(parts, Body::from(bytes))).await,
+        Err(_) => too_large(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+    use std::time::Duration;
+
+    const CLIENT: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+    #[test]
+    fn client_is_refused_after_the_burst_and_refilled_over_time() {
+        let limiter = RateLimiter::new(6);
+        let start = Instant::now();
+        for _ in 0..6 {
+            assert_eq!(limiter.acquire(CLIENT, start), std::result::Result::Ok(()));
+        }
+        // Six a minute refill one token every ten seconds
+        assert_eq!(limiter.acquire(CLIENT, start), Err(10));
+        assert_eq!(limiter.acquire(CLIENT, start + Duration::from_secs(4)), Err(6));
+        assert_eq!(limiter.acquire(CLIENT, start + Duration::from_secs(10)), std::result::Result::Ok(()));
+        assert_eq!(limiter.acquire(CLIENT, start + Duration::from_secs(10)), Err(10));
+
+        // Other clients have their own bucket, and an idle one fills up to the burst only
+        assert_eq!(limiter.acquire(IpAddr::V4(Ipv4Addr::LOCALHOST), start), std::result::Result::Ok(()));
+        let later = start + Duration::from_secs(3600);
+        for _ in 0..6 {
+            assert_eq!(limiter.acquire(CLIENT, later), std::result::Result::Ok(()));
+        }
+        assert!(limiter.acquire(CLIENT, later).is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use anyhow::*;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
+    DefaultTerminal,
+};
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use crate::Datasource;
+use iab::{FileError, LoadOptions, Taxonomies, Taxonomy, TaxonomyFile};
+
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const FRAME_INTERVAL: Duration = Duration::from_millis(80);
+
+enum LoadEvent {
+    Parsed(Taxonomy),
+    Done(Box<Result<Taxonomies>>),
+}
+
+/// Parse the taxonomies (bundled, or the given files) on a background thread while animating a
+/// progress screen. A `--file` that fails to parse shows an error screen that retries on `r`.
+/// Returns `None` when the user quits before loading has finished.
+pub fn load_with_spinner(terminal: &mut DefaultTerminal, files: Vec<TaxonomyFile>, options: &LoadOptions) -> Result<Option<Taxonomies>> {
+    loop {
+        let error = match load_once(terminal, files.clone(), options.clone())? {
+            Some(Result::Ok(taxonomies)) => return Ok(Some(taxonomies)),
+            Some(Err(error)) => error,
+            None => return Ok(None),
+        };
+        let Some(file_error) = error.downcast_ref::<FileError>() else {
+            return Err(error);
+        };
+        if !show_error(terminal, file_error)? {
+            return Ok(None);
+        }
+    }
+}
+
+/// One loading attempt; `None` when cancelled
+fn load_once(terminal: &mut DefaultTerminal, files: Vec<TaxonomyFile>, options: LoadOptions) -> Result<Option<Result<Taxonomies>>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let progress = sender.clone();
+        let result = Taxonomies::load_files(&files, &options, |taxonomy| {
+            let _ = progress.send(LoadEvent::Parsed(taxonomy));
+        });
+        let _ = sender.send(LoadEvent::Done(Box::new(result)));
+    });
+
+    let mut parsed = Vec::new();
+    let mut frame_index = 0;
+    loop {
+        loop {
+            match receiver.try_recv() {
+                Result::Ok(LoadEvent::Parsed(taxonomy)) => parsed.push(taxonomy),
+                Result::Ok(LoadEvent::Done(result)) => return Ok(Some(*result)),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => bail!("taxonomy loader stopped unexpectedly"),
+            }
+        }
+
+        terminal.draw(|frame| render(frame, &parsed, frame_index))?;
+        frame_index = (frame_index + 1) % SPINNER_FRAMES.len();
+
+        if event::poll(FRAME_INTERVAL)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && (key.code == KeyCode::Esc
+                || (key.code == KeyCode::Char('q') && key.modifiers.contains(KeyModifiers::CONTROL)))
+        {
+            return Ok(None);
+        }
+    }
+}
+
+/// Show where a file failed to parse until the user retries (`true`) or quits
+fn show_error(terminal: &mut DefaultTerminal, error: &FileError) -> Result<bool> {
+    loop {
+        terminal.draw(|frame| render_error(frame, error))?;
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('r') | KeyCode::Enter => return Ok(true),
+                KeyCode::Esc | KeyCode::Char('q') => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn render_error(frame: &mut Frame, error: &FileError) {
+    let area = Rect::centered(frame.area(), Constraint::Percentage(70), Constraint::Length(8));
+    let position = |value: Option<u64>| value.map_or_else(|| "-".to_string(), |value| value.to_string());
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Cyan).bold());
+
+    let lines = vec![
+        Line::from(vec![label("File:    "), Span::raw(error.path.display().to_string())]),
+        Line::from(vec![label("Line:    "), Span::raw(position(error.line))]),
+        Line::from(vec![label("Column:  "), Span::raw(position(error.column))]),
+        Line::from(vec![label("Error:   "), Span::styled(error.message.clone(), Style::default().fg(Color::Red))]),
+    ];
+
+    let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .title(" Cannot load taxonomy file ")
+            .title_bottom(" r/Enter: Retry | ESC: Quit ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Red))
+            .padding(Padding::horizontal(1)),
+    );
+
+    frame.render_widget(Clear, frame.area());
+    frame.render_widget(paragraph, area);
+}
+
+fn render(frame: &mut Frame, parsed: &[Taxonomy], frame_index: usize) {
+    let area = Rect::centered(frame.area(), Constraint::Length(36), Constraint::Length(7));
+
+    let lines: Vec<Line> = Taxonomy::ALL
+        .into_iter()
+        .map(|taxonomy| {
+            let (marker, style) = if parsed.contains(&taxonomy) {
+                ("✓", Style::default().fg(Datasource::from(taxonomy).color()))
+            } else {
+                (SPINNER_FRAMES[frame_index], Style::default().fg(Color::DarkGray))
+            };
+            Line::from(vec![
+                Span::styled(format!(" {} ", marker), style.bold()),
+                Span::styled(format!("{} {}", taxonomy.name(), taxonomy.version()), style),
+            ])
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(" Loading taxonomies ")
+            .title_bottom(" ESC: Cancel ")
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White)),
+    );
+
+    frame.render_widget(paragraph, area);
+}
@@ -0,0 +1,33 @@
+//! `--log-file`: `tracing` events appended to a file, since the TUI owns the terminal. The level is
+//! info, debug with `-v` and trace with `-vv`, unless `IAB_LOG` gives a filter such as `iab=debug`.
+
+use anyhow::*;
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing_subscriber::EnvFilter;
+
+const FILTER_ENV: &str = "IAB_LOG";
+
+pub fn init(path: &Path, verbose: u8) -> Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("cannot open log file {}", path.display()))?;
+    let level = match verbose {
+        0 => "info",
+        1 => "debug",
+        _ => "trace",
+    };
+    let filter = match std::env::var(FILTER_ENV) {
+        Result::Ok(directives) => EnvFilter::try_new(&directives).with_context(|| format!("invalid {}", FILTER_ENV))?,
+        Err(_) => EnvFilter::new(level),
+    };
+    tracing_subscriber::fmt()
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .with_env_filter(filter)
+        .try_init()
+        .map_err(|err| anyhow!(err))
+}
@@ -0,0 +1,270 @@
+//! `iab daemon --lsp`: a small Language Server Protocol server over stdio, so editors can complete
+//! IAB IDs in campaign config files and show a category's name and path on hover.
+//!
+//! It keeps the open documents in full (`textDocumentSync` 1) and answers
+//! `textDocument/completion` and `textDocument/hover` for the ID-like word at the cursor. The
+//! taxonomy comes from `initializationOptions`, e.g. `{"taxonomy": "audience"}`, and defaults to
+//! Content.
+
+use anyhow::*;
+use iab::Taxonomy;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Read, Write};
+
+use crate::daemon::{self, Daemon};
+use crate::jsonrpc::{self, RpcResult, INVALID_REQUEST, MAX_MESSAGE, METHOD_NOT_FOUND, PARSE_ERROR};
+
+/// Completions offered at once; the editor asks again as more is typed
+const COMPLETION_LIMIT: usize = 100;
+
+/// LSP `CompletionItemKind.Value`
+const KIND_VALUE: u32 = 12;
+
+struct Server<'a> {
+    daemon: &'a Daemon,
+    taxonomy: Taxonomy,
+    /// Text of the open documents by URI
+    documents: HashMap<String, String>,
+    shutdown: bool,
+}
+
+/// Answer LSP messages from `input` until the client sends `exit`
+pub fn serve(daemon: &Daemon, mut input: impl BufRead, mut output: impl Write) -> Result<()> {
+    let mut server = Server { daemon, taxonomy: Taxonomy::Content, documents: HashMap::new(), shutdown: false };
+    while let Some(message) = read_message(&mut input)? {
+        // A broken message is answered like jsonrpc.rs answers a bad line, then the next one is read
+        let message = match message {
+            std::result::Result::Ok(message) => message,
+            Err((code, message)) => {
+                write_message(&mut output, &jsonrpc::error(code, message))?;
+                continue;
+            }
+        };
+        if message.get("method").and_then(Value::as_str) == Some("exit") {
+            break;
+        }
+        if let Some(response) = server.handle(&message) {
+            write_message(&mut output, &response)?;
+        }
+    }
+    Ok(())
+}
+
+/// One message framed by a `Content-Length` header; `None` at the end of the input. A body that is
+/// too large or not JSON is consumed and returned as the JSON-RPC error to answer it with.
+fn read_message(input: &mut impl BufRead) -> Result<Option<RpcResult>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            length = Some(value.trim().parse::<usize>().context("invalid Content-Length")?);
+        }
+    }
+    let length = length.context("message without Content-Length")?;
+    if length > MAX_MESSAGE {
+        // Skip the body so the next header is read in step
+        std::io::copy(&mut input.take(length as u64), &mut std::io::sink())?;
+        let message = format!("message of {} bytes exceeds the limit of {}", length, MAX_MESSAGE);
+        return Ok(Some(Err((INVALID_REQUEST, message))));
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body).map_err(|err| (PARSE_ERROR, format!("parse error: {}", err)))))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()?;
+    Ok(())
+}
+
+impl Server<'_> {
+    /// Answer a request; notifications only update the documents
+    fn handle(&mut self, message: &Value) -> Option<Value> {
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let method = message.get("method").and_then(Value::as_str).unwrap_or_default();
+        let Some(id) = message.get("id").cloned() else {
+            self.notification(method, &params);
+            return None;
+        };
+
+        let result = match method {
+            _ if self.shutdown => Err((INVALID_REQUEST, "the server is shutting down".to_string())),
+            "initialize" => self.initialize(&params),
+            "shutdown" => {
+                self.shutdown = true;
+                std::result::Result::Ok(Value::Null)
+            }
+            "textDocument/completion" => std::result::Result::Ok(self.completion(&params)),
+            "textDocument/hover" => std::result::Result::Ok(self.hover(&params)),
+            method => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+        };
+        Some(jsonrpc::response(id, result))
+    }
+
+    fn initialize(&mut self, params: &Value) -> RpcResult {
+        if let Some(options) = params.get("initializationOptions").and_then(Value::as_object) {
+            self.taxonomy = daemon::taxonomy(options)?;
+        }
+        std::result::Result::Ok(json!({
+            "capabilities": {
+                "textDocumentSync": 1,
+                "completionProvider": { "triggerCharacters": ["\"", "'"] },
+                "hoverProvider": true,
+            },
+            "serverInfo": { "name": "iab", "version": env!("CARGO_PKG_VERSION") },
+        }))
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) {
+        let uri = params.pointer("/textDocument/uri").and_then(Value::as_str).map(str::to_string);
+        match (method, uri) {
+            ("textDocument/didOpen", Some(uri)) => {
+                let text = params.pointer("/textDocument/text").and_then(Value::as_str).unwrap_or_default();
+                self.documents.insert(uri, text.to_string());
+            }
+            ("textDocument/didChange", Some(uri)) => {
+                // Full sync: the last change holds the whole text
+                let text = params.get("contentChanges").and_then(Value::as_array).and_then(|changes| changes.last());
+                if let Some(text) = text.and_then(|change| change.get("text")).and_then(Value::as_str) {
+                    self.documents.insert(uri, text.to_string());
+                }
+            }
+            ("textDocument/didClose", Some(uri)) => {
+                self.documents.remove(&uri);
+            }
+            _ => {}
+        }
+    }
+
+    /// The line and UTF-16 column of a `TextDocumentPositionParams`
+    fn position<'a>(&'a self, params: &Value) -> Option<(&'a str, u64, usize)> {
+        let uri = params.pointer("/textDocument/uri").and_then(Value::as_str)?;
+        let line = params.pointer("/position/line").and_then(Value::as_u64)?;
+        let character = params.pointer("/position/character").and_then(Value::as_u64)?;
+        let text = self.documents.get(uri)?.lines().nth(line as usize).unwrap_or_default();
+        Some((text, line, byte_index(text, character as usize)))
+    }
+
+    fn completion(&self, params: &Value) -> Value {
+        let Some((text, line, cursor)) = self.position(params) else {
+            return Value::Null;
+        };
+        let start = word_start(text, cursor);
+        let prefix = &text[start..cursor];
+        let range = json!({
+            "start": { "line": line, "character": utf16_len(&text[..start]) },
+            "end": { "line": line, "character": utf16_len(&text[..cursor]) },
+        });
+        let items: Vec<Value> = self
+            .daemon
+            .completions(self.taxonomy, prefix, COMPLETION_LIMIT)
+            .into_iter()
+            .map(|item| {
+                json!({
+                    "label": item.unique_id(),
+                    "kind": KIND_VALUE,
+                    "detail": item.tiers().join(" > "),
+                    "filterText": format!("{} {}", item.unique_id(), item.name()),
+                    "textEdit": { "range": range, "newText": item.unique_id() },
+                })
+            })
+            .collect();
+        json!({ "isIncomplete": items.len() == COMPLETION_LIMIT, "items": items })
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let Some((text, line, cursor)) = self.position(params) else {
+            return Value::Null;
+        };
+        let (start, end) = (word_start(text, cursor), word_end(text, cursor));
+        match self.daemon.describe(self.taxonomy, &text[start..end]) {
+            Some(markdown) => json!({
+                "contents": { "kind": "markdown", "value": markdown },
+                "range": {
+                    "start": { "line": line, "character": utf16_len(&text[..start]) },
+                    "end": { "line": line, "character": utf16_len(&text[..end]) },
+                },
+            }),
+            None => Value::Null,
+        }
+    }
+}
+
+/// Characters of an ID: letters, digits and the dash of legacy `IAB17-12` codes
+fn is_id_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-'
+}
+
+fn word_start(text: &str, cursor: usize) -> usize {
+    text[..cursor].char_indices().rev().find(|(_, c)| !is_id_char(*c)).map_or(0, |(index, c)| index + c.len_utf8())
+}
+
+fn word_end(text: &str, cursor: usize) -> usize {
+    text[cursor..].find(|c: char| !is_id_char(c)).map_or(text.len(), |position| cursor + position)
+}
+
+/// Byte offset of a UTF-16 column, as LSP positions count them
+fn byte_index(text: &str, utf16: usize) -> usize {
+    let mut units = 0;
+    for (index, c) in text.char_indices() {
+        if units >= utf16 {
+            return index;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn framed(body: &str) -> String {
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    #[test]
+    fn bad_json_is_answered_and_reading_goes_on() {
+        let input = format!("{}{}", framed("{not json"), framed(r#"{"jsonrpc":"2.0","method":"exit"}"#));
+        let mut input = input.as_bytes();
+        let first = read_message(&mut input).unwrap().unwrap();
+        assert!(matches!(first, Err((PARSE_ERROR, _))));
+        let second = read_message(&mut input).unwrap().unwrap().unwrap();
+        assert_eq!(second["method"], "exit");
+        assert!(read_message(&mut input).unwrap().is_none());
+    }
+
+    #[test]
+    fn oversized_body_is_skipped_before_allocating() {
+        let header = format!("Content-Length: {}\r\n\r\n", MAX_MESSAGE + 1);
+        let input = format!("{}{}{}", header, " ".repeat(MAX_MESSAGE + 1), framed(r#"{"method":"exit"}"#));
+        let mut input = input.as_bytes();
+        assert!(matches!(read_message(&mut input).unwrap().unwrap(), Err((INVALID_REQUEST, _))));
+        assert_eq!(read_message(&mut input).unwrap().unwrap().unwrap()["method"], "exit");
+    }
+
+    #[test]
+    fn word_bounds_stop_at_multi_byte_characters() {
+        for text in ["café150", "→150", "é150é"] {
+            let start = word_start(text, text.find("150").unwrap() + 2);
+            assert_eq!(&text[start..word_end(text, start)], "150");
+        }
+        assert_eq!(word_start("150", 3), 0);
+    }
+}
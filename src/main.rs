@@ -1,5 +1,54 @@
+mod basket;
+mod bookmarks;
+mod clipboard;
+mod codegen;
+mod commands;
+mod config;
+mod crash;
+mod daemon;
+mod export;
+#[cfg(feature = "graphql")]
+mod graphql;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod headless;
+mod i18n;
+mod jsonrpc;
+mod layout;
+#[cfg(feature = "server")]
+mod limits;
+mod loading;
+mod lsp;
+mod logging;
+mod mapping;
+mod mcp;
+#[cfg(feature = "server")]
+mod metrics;
+mod notes;
+#[cfg(feature = "plugins")]
+mod plugins;
+mod recent;
+mod report;
+mod schema;
+mod script;
+#[cfg(feature = "server")]
+mod server;
+mod session;
+mod state;
+mod tags;
+mod taxonomy_store;
+mod theme;
+mod updates;
+mod usage;
+
 use anyhow::*;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use clap::{Parser, Subcommand};
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+    PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+};
+use crossterm::ExecutableCommand;
+use crossterm::terminal::supports_keyboard_enhancement;
 use ratatui::{
     prelude::*,
     text::{Line, Span},
@@ -7,217 +56,40 @@ use ratatui::{
     DefaultTerminal,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
-use tui_tree_widget::{Tree, TreeItem, TreeState};
-
-const PRODUCT_TSV: &str = include_str!("../product-2.0.tsv");
-const CONTENT_TSV: &str = include_str!("../content-3.1.tsv");
-const AUDIENCE_TSV: &str = include_str!("../audience-1.1.tsv");
-
-// Data structures
-trait TaxonomyItem {
-    fn unique_id(&self) -> &str;
-    fn parent(&self) -> Option<&str>;
-    fn name(&self) -> &str;
-    fn tiers(&self) -> Vec<&str>;
-    fn extension(&self) -> Option<&str>;
-}
-
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-pub struct Content {
-    #[serde(rename = "Unique ID")]
-    unique_id: String,
-    #[serde(rename = "Parent")]
-    parent: Option<String>,
-    #[serde(rename = "Name")]
-    name: String,
-    #[serde(rename = "Tier 1")]
-    tier_1: Option<String>,
-    #[serde(rename = "Tier 2")]
-    tier_2: Option<String>,
-    #[serde(rename = "Tier 3")]
-    tier_3: Option<String>,
-    #[serde(rename = "Tier 4")]
-    tier_4: Option<String>,
-    #[serde(rename = "Extension")]
-    ext: Option<String>,
-}
-
-impl TaxonomyItem for Content {
-    fn unique_id(&self) -> &str {
-        &self.unique_id
-    }
-    fn parent(&self) -> Option<&str> {
-        self.parent.as_deref()
-    }
-    fn name(&self) -> &str {
-        &self.name
-    }
-    fn tiers(&self) -> Vec<&str> {
-        [
-            self.tier_1.as_deref(),
-            self.tier_2.as_deref(),
-            self.tier_3.as_deref(),
-            self.tier_4.as_deref(),
-        ]
-        .iter()
-        .filter_map(|&t| t.filter(|s| !s.is_empty()))
-        .collect()
-    }
-    fn extension(&self) -> Option<&str> {
-        self.ext.as_deref()
-    }
-}
-
-impl TaxonomyItem for &Content {
-    fn unique_id(&self) -> &str {
-        (*self).unique_id()
-    }
-    fn parent(&self) -> Option<&str> {
-        (*self).parent()
-    }
-    fn name(&self) -> &str {
-        (*self).name()
-    }
-    fn tiers(&self) -> Vec<&str> {
-        (*self).tiers()
-    }
-    fn extension(&self) -> Option<&str> {
-        (*self).extension()
-    }
-}
-
-#[derive(Serialize, Deserialize, Default, Debug, Clone)]
-pub struct Product {
-    #[serde(rename = "Unique ID")]
-    unique_id: String,
-    #[serde(rename = "Parent ID")]
-    parent: Option<String>,
-    #[serde(rename = "Name")]
-    name: String,
-    #[serde(rename = "Tier 1")]
-    tier_1: Option<String>,
-    #[serde(rename = "Tier 2")]
-    tier_2: Option<String>,
-    #[serde(rename = "Tier 3")]
-    tier_3: Option<String>,
-}
-
-impl TaxonomyItem for Product {
-    fn unique_id(&self) -> &str {
-        &self.unique_id
-    }
-    fn parent(&self) -> Option<&str> {
-        self.parent.as_deref()
-    }
-    fn name(&self) -> &str {
-        &self.name
-    }
-    fn tiers(&self) -> Vec<&str> {
-        [
-            self.tier_1.as_deref(),
-            self.tier_2.as_deref(),
-            self.tier_3.as_deref(),
-        ]
-        .iter()
-        .filter_map(|&t| t.filter(|s| !s.is_empty()))
-        .collect()
-    }
-    fn extension(&self) -> Option<&str> {
-        None
-    }
-}
-
-impl TaxonomyItem for &Product {
-    fn unique_id(&self) -> &str {
-        (*self).unique_id()
-    }
-    fn parent(&self) -> Option<&str> {
-        (*self).parent()
-    }
-    fn name(&self) -> &str {
-        (*self).name()
-    }
-    fn tiers(&self) -> Vec<&str> {
-        (*self).tiers()
-    }
-    fn extension(&self) -> Option<&str> {
-        (*self).extension()
-    }
-}
-
-#[derive(Serialize, Deserialize, Default, Debug, Clone)]
-pub struct Audience {
-    #[serde(rename = "Unique ID")]
-    unique_id: String,
-    #[serde(rename = "Parent ID")]
-    parent: Option<String>,
-    #[serde(rename = "Condensed Name (1st, 2nd, Last Tier)")]
-    name: String,
-    #[serde(rename = "Tier 1")]
-    tier_1: Option<String>,
-    #[serde(rename = "Tier 2")]
-    tier_2: Option<String>,
-    #[serde(rename = "Tier 3")]
-    tier_3: Option<String>,
-    #[serde(rename = "Tier 4")]
-    tier_4: Option<String>,
-    #[serde(rename = "Tier 5")]
-    tier_5: Option<String>,
-    #[serde(rename = "Tier 6")]
-    tier_6: Option<String>,
-    #[serde(rename = "*Extension Notes")]
-    ext: Option<String>,
-}
-
-impl TaxonomyItem for Audience {
-    fn unique_id(&self) -> &str {
-        &self.unique_id
-    }
-    fn parent(&self) -> Option<&str> {
-        self.parent.as_deref()
-    }
-    fn name(&self) -> &str {
-        &self.name
-    }
-    fn tiers(&self) -> Vec<&str> {
-        [
-            self.tier_1.as_deref(),
-            self.tier_2.as_deref(),
-            self.tier_3.as_deref(),
-            self.tier_4.as_deref(),
-            self.tier_5.as_deref(),
-            self.tier_6.as_deref(),
-        ]
-        .iter()
-        .filter_map(|&t| t.filter(|s| !s.is_empty()))
-        .collect()
-    }
-    fn extension(&self) -> Option<&str> {
-        self.ext.as_deref()
-    }
-}
-
-impl TaxonomyItem for &Audience {
-    fn unique_id(&self) -> &str {
-        (*self).unique_id()
-    }
-    fn parent(&self) -> Option<&str> {
-        (*self).parent()
-    }
-    fn name(&self) -> &str {
-        (*self).name()
-    }
-    fn tiers(&self) -> Vec<&str> {
-        (*self).tiers()
-    }
-    fn extension(&self) -> Option<&str> {
-        (*self).extension()
-    }
-}
+use basket::{Basket, BasketFormat, BasketRow};
+use bookmarks::Bookmarks;
+use config::{Config, Keymap};
+use iab::compare::{self, Change, ChangeKind, NodeChange, Revision};
+use iab::extension;
+use iab::ortb2::{self, Channel};
+use iab::suggest::{self, SuggestIndex, Synonyms};
+use iab::validate::{self, Finding, Issue};
+use iab::{Audience, Badge, ChildIndex, ColumnMap, Content, Haystack, LoadOptions, NodeSet, Crosswalk, Crosswalks, MappingTable, Product, Quoting, RawTable, Taxonomies, Taxonomy, TaxonomyFile, TaxonomyItem, Translation};
+use export::{ExportFormat, ExportNode, ExportRow, ExportScope};
+use i18n::Text;
+use layout::LayoutConfig;
+use mapping::Mapping;
+use notes::Notes;
+#[cfg(feature = "plugins")]
+use plugins::Plugins;
+use recent::Recent;
+use report::SessionReport;
+use session::Session;
+use tags::Tags;
+use theme::Theme;
+use usage::Usage;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, mpsc};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use tui_tree_widget::{Flattened, Tree, TreeItem, TreeState};
 
 // Datasource enum
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum Datasource {
     Product,
     Content,
@@ -265,96 +137,713 @@ impl Datasource {
         }
     }
 
+    /// Stable lowercase key used for persisted state
+    fn key(self) -> &'static str {
+        self.taxonomy().key()
+    }
+
+    fn taxonomy(self) -> Taxonomy {
+        match self {
+            Datasource::Product => Taxonomy::Product,
+            Datasource::Content => Taxonomy::Content,
+            Datasource::Audience => Taxonomy::Audience,
+        }
+    }
+
     fn index(self) -> usize {
+        self.taxonomy().index()
+    }
+}
+
+impl From<Taxonomy> for Datasource {
+    fn from(taxonomy: Taxonomy) -> Self {
+        match taxonomy {
+            Taxonomy::Product => Datasource::Product,
+            Taxonomy::Content => Datasource::Content,
+            Taxonomy::Audience => Datasource::Audience,
+        }
+    }
+}
+
+// Sort order for sibling nodes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+enum SortMode {
+    FileOrder,
+    Id,
+    Name,
+    /// Busiest branch first by the selected `--usage` metric
+    Usage,
+}
+
+impl SortMode {
+    fn next(self) -> Self {
+        match self {
+            SortMode::FileOrder => SortMode::Id,
+            SortMode::Id => SortMode::Name,
+            SortMode::Name => SortMode::Usage,
+            SortMode::Usage => SortMode::FileOrder,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SortMode::FileOrder => i18n::tr(Text::SortFileOrder),
+            SortMode::Id => i18n::tr(Text::SortId),
+            SortMode::Name => i18n::tr(Text::SortName),
+            SortMode::Usage => i18n::tr(Text::SortUsage),
+        }
+    }
+
+    fn compare<T: TaxonomyItem>(self, a: &T, b: &T) -> std::cmp::Ordering {
+        match self {
+            // Usage totals live outside the rows; `build_tree_recursive` sorts by them
+            SortMode::FileOrder | SortMode::Usage => std::cmp::Ordering::Equal,
+            // Numeric IDs compare by value, alphanumeric ones (Content) lexically
+            SortMode::Id => match (a.unique_id().parse::<u64>().ok(), b.unique_id().parse::<u64>().ok()) {
+                (Some(x), Some(y)) => x.cmp(&y),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => a.unique_id().cmp(b.unique_id()),
+            },
+            SortMode::Name => a.name().to_lowercase().cmp(&b.name().to_lowercase()),
+        }
+    }
+}
+
+// Options applied while building tree items
+struct TreeOptions<'a> {
+    filter: &'a str,
+    sort_mode: SortMode,
+    bookmarks: &'a [String],
+    /// Notes of the taxonomy, marked in the tree
+    notes: Option<&'a BTreeMap<String, String>>,
+    /// Tags of the taxonomy, shown as colored markers
+    tags: Option<&'a BTreeMap<String, Vec<String>>>,
+    /// Usage numbers of the taxonomy and the metric shown and sorted by
+    usage: Option<(&'a Usage, usize)>,
+    /// Names shown instead of the original ones where the translation has them
+    translation: Option<&'a Translation>,
+    /// Tint node names by tier depth, cycling through the palette
+    depth_palette: Option<&'a [Color]>,
+    /// Drop deprecated nodes (and thereby their descendants) instead of dimming them
+    hide_deprecated: bool,
+    /// Columns scrolled off the left edge of every label
+    scroll_x: usize,
+    /// Nodes left out because a sibling already had their identifier
+    collisions: Cell<usize>,
+}
+
+/// Everything `filtered_tree_items` depends on besides the taxonomy data itself
+#[derive(Clone, PartialEq)]
+struct TreeKey {
+    datasource: Datasource,
+    filter: String,
+    sort_mode: SortMode,
+    bookmarks: Vec<String>,
+    noted: Vec<String>,
+    tags: BTreeMap<String, Vec<String>>,
+    usage_metric: Option<usize>,
+    translated: bool,
+    depth_palette: Option<Vec<Color>>,
+    hide_deprecated: bool,
+    scroll_x: usize,
+    zoom_root: Option<String>,
+}
+
+/// Datasource and lowercased filter text the included positions were computed for
+type FilterKey = (Datasource, String);
+
+/// Datasource and synonyms version a `SuggestIndex` was built for
+type SuggestKey = (Datasource, usize);
+
+/// Classification suggestions as (ID, tier path, score)
+type Suggestions = Rc<Vec<(String, String, f64)>>;
+
+/// Tree identifier of a node: its ID, interned in the `ChildIndex` so building a tree clones no strings
+type NodeId = Arc<str>;
+
+/// Built tree items, shared between the cache and the current frame
+type SharedTree = Rc<Vec<TreeItem<'static, NodeId>>>;
+
+/// Identifier of the virtual branch holding rows whose parent does not exist; TSV IDs cannot contain tabs
+const ORPHANS_ID: &str = "\torphans";
+
+const HORIZONTAL_SCROLL_STEP: usize = 8;
+
+const DEFAULT_DEPTH_PALETTE: [Color; 6] = [
+    Color::LightBlue,
+    Color::LightGreen,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::LightYellow,
+    Color::LightRed,
+];
+
+// Export dialog state
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportField {
+    Format,
+    Scope,
+    Path,
+}
+
+impl ExportField {
+    fn next(self) -> Self {
+        match self {
+            ExportField::Format => ExportField::Scope,
+            ExportField::Scope => ExportField::Path,
+            ExportField::Path => ExportField::Format,
+        }
+    }
+
+    fn previous(self) -> Self {
+        match self {
+            ExportField::Format => ExportField::Path,
+            ExportField::Scope => ExportField::Format,
+            ExportField::Path => ExportField::Scope,
+        }
+    }
+}
+
+/// Free text to classify against the current taxonomy, and the highlighted suggestion
+#[derive(Debug, Default)]
+struct ClassifyPrompt {
+    text: String,
+    selected: usize,
+}
+
+const CLASSIFY_LIMIT: usize = 10;
+
+/// Open basket popup (F11): highlighted entry and the format to copy or save as
+#[derive(Debug, Clone, Copy)]
+struct BasketPopup {
+    selected: usize,
+    format: BasketFormat,
+}
+
+/// Open compare view (Ctrl+v): the current taxonomy against the bundled release it extends
+struct ComparePopup {
+    official: Box<Taxonomies>,
+    changes: Vec<Change>,
+    selected: usize,
+}
+
+/// Kinds of change the changelog view shows, cycled with Tab
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+enum ChangeFilter {
+    #[default]
+    All,
+    Added,
+    Removed,
+    Renamed,
+    Moved,
+}
+
+impl ChangeFilter {
+    fn next(self) -> Self {
+        match self {
+            ChangeFilter::All => ChangeFilter::Added,
+            ChangeFilter::Added => ChangeFilter::Removed,
+            ChangeFilter::Removed => ChangeFilter::Renamed,
+            ChangeFilter::Renamed => ChangeFilter::Moved,
+            ChangeFilter::Moved => ChangeFilter::All,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ChangeFilter::All => i18n::tr(Text::ChangesAll),
+            ChangeFilter::Added => i18n::tr(Text::ChangesAdded),
+            ChangeFilter::Removed => i18n::tr(Text::ChangesRemoved),
+            ChangeFilter::Renamed => i18n::tr(Text::ChangesRenamed),
+            ChangeFilter::Moved => i18n::tr(Text::ChangesMoved),
+        }
+    }
+
+    fn matches(self, change: &NodeChange) -> bool {
         match self {
-            Datasource::Product => 0,
-            Datasource::Content => 1,
-            Datasource::Audience => 2,
+            ChangeFilter::All => true,
+            ChangeFilter::Added => change.revision == Revision::Added,
+            ChangeFilter::Removed => change.revision == Revision::Removed,
+            ChangeFilter::Renamed => change.renamed(),
+            ChangeFilter::Moved => change.moved(),
+        }
+    }
+}
+
+/// A node of the changelog tree: changed, or an unchanged ancestor giving it context
+struct ChangelogNode {
+    id: NodeId,
+    name: String,
+    parent: Option<String>,
+}
+
+/// Open changelog view (Ctrl+v with --previous): the changes from the previous version as a tree
+struct ChangelogPopup {
+    /// File name of the previous version
+    label: String,
+    changes: Vec<NodeChange>,
+    /// Every node of both versions, current ones first, so ancestors can be shown
+    nodes: Vec<ChangelogNode>,
+    filter: ChangeFilter,
+    items: Vec<TreeItem<'static, NodeId>>,
+    /// Interior, since popups are drawn from a shared `App`
+    state: RefCell<TreeState<NodeId>>,
+}
+
+impl ChangelogPopup {
+    /// Rebuild the tree for the current filter, with every branch open
+    fn rebuild(&mut self) {
+        self.items = build_changelog_tree(&self.changes, &self.nodes, self.filter);
+        let mut state = TreeState::default();
+        fn open_all(items: &[TreeItem<'static, NodeId>], path: &mut Vec<NodeId>, state: &mut TreeState<NodeId>) {
+            for item in items {
+                path.push(Arc::clone(item.identifier()));
+                if !item.children().is_empty() {
+                    state.open(path.clone());
+                    open_all(item.children(), path, state);
+                }
+                path.pop();
+            }
         }
+        open_all(&self.items, &mut Vec::new(), &mut state);
+        state.select_first();
+        self.state = RefCell::new(state);
     }
 }
 
-// Data loading functions
-fn load_products() -> Result<Vec<Product>> {
-    let mut reader = csv::ReaderBuilder::new()
-        .delimiter(b'\t')
-        .has_headers(true)
-        .from_reader(PRODUCT_TSV.as_bytes());
+/// What an `AnnotationEditor` writes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Annotation {
+    /// Free text (F9, or `n` in the details popup)
+    Note,
+    /// Comma-separated tags (F10, or `t` in the details popup)
+    Tags,
+    /// Name of a new custom child node (F12)
+    Child,
+}
+
+/// Note, tags or new child name being written for a node
+#[derive(Debug)]
+struct AnnotationEditor {
+    annotation: Annotation,
+    id: String,
+    text: String,
+}
+
+struct ExportDialog {
+    format: ExportFormat,
+    scope: ExportScope,
+    path: String,
+    field: ExportField,
+    error: Option<String>,
+}
 
-    let mut items = Vec::new();
-    for result in reader.deserialize() {
-        items.push(result?);
+impl ExportDialog {
+    fn new(datasource: Datasource, scope: ExportScope, selected_id: Option<&str>, format: ExportFormat) -> Self {
+        let stem = match (scope, selected_id) {
+            (ExportScope::Subtree, Some(id)) => format!("iab-{}-{}", datasource.name().to_lowercase(), id),
+            _ => format!("iab-{}", datasource.name().to_lowercase()),
+        };
+        Self {
+            format,
+            scope,
+            path: format!("{}.{}", stem, format.extension()),
+            field: ExportField::Format,
+            error: None,
+        }
     }
 
-    Ok(items)
+    fn cycle_format(&mut self) {
+        let old_extension = format!(".{}", self.format.extension());
+        self.format = self.format.next();
+        // Keep the file extension in sync with the chosen format
+        if let Some(stem) = self.path.strip_suffix(&old_extension) {
+            self.path = format!("{}.{}", stem, self.format.extension());
+        }
+    }
 }
 
-fn load_content() -> Result<Vec<Content>> {
-    let mut lines = CONTENT_TSV.lines();
-    // Skip first line (section header)
-    lines.next();
+// Lists of node IDs shown in a jump-to popup
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ListPopup {
+    Bookmarks,
+    Recent,
+}
 
-    // Keep second line (actual column headers) and all data lines
-    let remaining_content = lines.collect::<Vec<_>>().join("\n");
+impl ListPopup {
+    fn title(self) -> &'static str {
+        match self {
+            ListPopup::Bookmarks => i18n::tr(Text::ListBookmarks),
+            ListPopup::Recent => i18n::tr(Text::ListRecent),
+        }
+    }
 
-    let mut reader = csv::ReaderBuilder::new()
-        .delimiter(b'\t')
-        .has_headers(true)
-        .from_reader(remaining_content.as_bytes());
+    fn marker(self) -> &'static str {
+        match self {
+            ListPopup::Bookmarks => "★",
+            ListPopup::Recent => "•",
+        }
+    }
 
-    let mut items = Vec::new();
-    for result in reader.deserialize() {
-        items.push(result?);
+    fn empty_message(self) -> &'static str {
+        match self {
+            ListPopup::Bookmarks => i18n::tr(Text::ListBookmarksEmpty),
+            ListPopup::Recent => i18n::tr(Text::ListRecentEmpty),
+        }
     }
+}
 
-    Ok(items)
+// Transient feedback messages shown in place of the help bar
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ToastLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+// Badges are plain data in the lib; the terminal colors live here with the rest of the palette
+fn badge_color(badge: Badge) -> Color {
+    match badge {
+        Badge::Deprecated => Color::LightRed,
+        Badge::Extension => Color::Magenta,
+    }
 }
 
-fn load_audience() -> Result<Vec<Audience>> {
-    let mut reader = csv::ReaderBuilder::new()
-        .delimiter(b'\t')
-        .has_headers(true)
-        .from_reader(AUDIENCE_TSV.as_bytes());
+impl ToastLevel {
+    fn color(self) -> Color {
+        match self {
+            ToastLevel::Info => Color::White,
+            ToastLevel::Success => Color::LightGreen,
+            ToastLevel::Warning => Color::LightYellow,
+            ToastLevel::Error => Color::LightRed,
+        }
+    }
+
+    /// Spelled-out level for the plain list, where colors carry no meaning
+    fn prefix(self) -> &'static str {
+        match self {
+            ToastLevel::Info | ToastLevel::Success => "",
+            ToastLevel::Warning => "Warning: ",
+            ToastLevel::Error => "Error: ",
+        }
+    }
 
-    let mut items = Vec::new();
-    for result in reader.deserialize() {
-        items.push(result?);
+    /// Errors stay up longer so they can actually be read
+    fn duration(self) -> Duration {
+        match self {
+            ToastLevel::Info | ToastLevel::Success => Duration::from_secs(3),
+            ToastLevel::Warning | ToastLevel::Error => Duration::from_secs(6),
+        }
     }
+}
+
+struct Toast {
+    level: ToastLevel,
+    message: String,
+    shown_at: Instant,
+}
 
-    Ok(items)
+impl Toast {
+    fn is_expired(&self) -> bool {
+        self.shown_at.elapsed() >= self.level.duration()
+    }
 }
 
 // App state
 struct App {
     datasource: Datasource,
+    sort_mode: SortMode,
+    zoom_root: Option<String>,
     filter_input: String,
     products: Vec<Product>,
     content: Vec<Content>,
     audience: Vec<Audience>,
-    tree_state: TreeState<String>,
+    /// Original rows per datasource, indexed by `Datasource::index`
+    raw_tables: [RawTable; 3],
+    /// Child indexes in `Taxonomy::ALL` order, built at load
+    indexes: [ChildIndex; 3],
+    /// Lowercased filter fields by item position, in `Taxonomy::ALL` order
+    haystacks: [Vec<Haystack>; 3],
+    tree_state: TreeState<NodeId>,
+    viewport_height: usize,
+    viewport_width: usize,
+    scroll_x: usize,
+    show_siblings: bool,
+    depth_colors: bool,
+    depth_palette: Vec<Color>,
+    hide_deprecated: bool,
     show_popup: bool,
     popup_content: Vec<(String, String)>,
+    popup_raw: Vec<(String, String)>,
+    popup_show_raw: bool,
+    export_dialog: Option<ExportDialog>,
+    /// Format the export dialog opens with
+    export_format: ExportFormat,
+    bookmarks: Bookmarks,
+    notes: Notes,
+    tags: Tags,
+    annotation_editor: Option<AnnotationEditor>,
+    /// Overlay TSVs custom nodes are saved to, from `--extension`, in `Taxonomy::ALL` order
+    extension_files: [Option<PathBuf>; 3],
+    /// `--usage` numbers per taxonomy, in `Taxonomy::ALL` order
+    usage: [Option<Usage>; 3],
+    /// Metric shown next to nodes and sorted by, cycled with the sort order
+    usage_metric: usize,
+    /// `--translation` names per taxonomy, in `Taxonomy::ALL` order
+    translations: [Option<Translation>; 3],
+    /// Show translated names in the tree and panes (Ctrl+j)
+    show_translation: bool,
+    /// Nodes collected across taxonomies for delivery (Ctrl+y)
+    basket: Basket,
+    basket_popup: Option<BasketPopup>,
+    compare_popup: Option<ComparePopup>,
+    changelog_popup: Option<ChangelogPopup>,
+    /// Earlier versions given with --previous; taxonomies without one hold the bundled release
+    previous: Option<Box<Taxonomies>>,
+    previous_files: Vec<TaxonomyFile>,
+    recent: Recent,
+    list_popup: Option<(ListPopup, usize)>,
+    show_legend: bool,
+    /// Rhai scripts with hooks and palette commands (--plugin, ~/.config/iab/plugins)
+    #[cfg(feature = "plugins")]
+    plugins: Plugins,
+    /// Open command palette of plugin commands and the highlighted entry (Alt+Enter)
+    #[cfg(feature = "plugins")]
+    plugin_palette: Option<usize>,
+    /// Node `on_select` last ran for
+    #[cfg(feature = "plugins")]
+    hooked_selection: Option<NodeId>,
+    /// Startup release check still running on its thread (`check-updates = true`)
+    update_check: Option<mpsc::Receiver<Result<Vec<updates::Release>>>>,
+    /// Exports, the clipboard, editing and plugins are refused (--kiosk)
+    kiosk: bool,
+    /// Screen-reader-friendly numbered list of the current level instead of the framed tree (--plain)
+    plain: bool,
+    /// Colors of the drawn frame (--theme or the config's `theme`)
+    theme: Theme,
+    /// Open `ortb2` snippet popup and the channel it targets
+    ortb2_popup: Option<Channel>,
+    /// Load-time problems per taxonomy, e.g. orphans and parent cycles
+    diagnostics: [Vec<Finding>; 3],
+    /// Rows of `--file` taxonomies repaired while parsing, shown above the diagnostics
+    parse_warnings: [Vec<String>; 3],
+    /// Open diagnostics popup and its scroll offset
+    diagnostics_popup: Option<u16>,
+    /// Mapping workspace opened with `--mapping`
+    mapping: Option<Mapping>,
+    /// Open classification prompt (Ctrl+f)
+    classify: Option<ClassifyPrompt>,
+    synonyms: Synonyms,
+    /// Bumped whenever `synonyms` is replaced, invalidating `suggest_cache`
+    synonyms_version: usize,
+    report: SessionReport,
+    crosswalks: Crosswalks,
+    /// Third-party mapping tables given with `--table`
+    mapping_tables: Vec<MappingTable>,
+    /// Browsing-view keys rebound in the config file
+    keymap: Keymap,
+    toast: Option<Toast>,
+    layout: LayoutConfig,
+    /// Tree built for the last drawn state; building it on every frame is the main cost of a redraw
+    tree_cache: RefCell<Option<(TreeKey, SharedTree)>>,
+    /// Positions kept by the filter, per datasource and lowercased filter; survives sort, zoom and bookmark changes
+    filter_cache: RefCell<Option<(FilterKey, Rc<NodeSet>)>>,
+    /// Word weights of the current datasource for the classification prompt; building them walks every node
+    suggest_cache: RefCell<Option<(SuggestKey, Rc<SuggestIndex>)>>,
+    /// Suggestions for the last classification text, shared by its redraws and Enter
+    classify_cache: RefCell<Option<((SuggestKey, String), Suggestions)>>,
+    /// Nodes the last tree build left out for repeating a sibling's identifier
+    tree_collisions: Cell<usize>,
 }
 
 impl App {
-    fn new() -> Result<Self> {
+    fn new(taxonomies: Taxonomies) -> Result<Self> {
         let mut tree_state = TreeState::default();
         tree_state.select_first();
 
-        Ok(Self {
+        let diagnostics = Taxonomy::ALL.map(|taxonomy| validate::validate(&taxonomies, taxonomy));
+        let Taxonomies { products, content, audience, raw_tables, indexes, haystacks, warnings: parse_warnings } = taxonomies;
+
+        let mut warnings = Vec::new();
+        let bookmarks = Bookmarks::load().unwrap_or_else(|err| {
+            warnings.push(format!("Bookmarks not loaded: {:#}", err));
+            Bookmarks::default()
+        });
+        let notes = Notes::load().unwrap_or_else(|err| {
+            warnings.push(format!("Notes not loaded: {:#}", err));
+            Notes::default()
+        });
+        let tags = Tags::load().unwrap_or_else(|err| {
+            warnings.push(format!("Tags not loaded: {:#}", err));
+            Tags::default()
+        });
+        let basket = Basket::load().unwrap_or_else(|err| {
+            warnings.push(format!("Basket not loaded: {:#}", err));
+            Basket::default()
+        });
+        let recent = Recent::load().unwrap_or_else(|err| {
+            warnings.push(format!("Recent items not loaded: {:#}", err));
+            Recent::default()
+        });
+        let layout = LayoutConfig::load().unwrap_or_else(|err| {
+            warnings.push(format!("Layout not loaded: {:#}", err));
+            LayoutConfig::default()
+        });
+
+        let mut app = Self {
             datasource: Datasource::Product,
+            sort_mode: SortMode::FileOrder,
+            zoom_root: None,
             filter_input: String::new(),
-            products: load_products()?,
-            content: load_content()?,
-            audience: load_audience()?,
+            products,
+            content,
+            audience,
+            raw_tables,
+            indexes,
+            haystacks,
             tree_state,
+            viewport_height: 10,
+            viewport_width: 80,
+            scroll_x: 0,
+            show_siblings: false,
+            depth_colors: false,
+            depth_palette: DEFAULT_DEPTH_PALETTE.to_vec(),
+            hide_deprecated: false,
             show_popup: false,
             popup_content: Vec::new(),
-        })
+            popup_raw: Vec::new(),
+            popup_show_raw: false,
+            export_dialog: None,
+            export_format: ExportFormat::Csv,
+            bookmarks,
+            notes,
+            tags,
+            annotation_editor: None,
+            extension_files: Default::default(),
+            usage: Default::default(),
+            usage_metric: 0,
+            translations: Default::default(),
+            show_translation: false,
+            basket,
+            basket_popup: None,
+            compare_popup: None,
+            changelog_popup: None,
+            previous: None,
+            previous_files: Vec::new(),
+            recent,
+            list_popup: None,
+            show_legend: false,
+            #[cfg(feature = "plugins")]
+            plugins: Plugins::default(),
+            #[cfg(feature = "plugins")]
+            plugin_palette: None,
+            kiosk: false,
+            #[cfg(feature = "plugins")]
+            hooked_selection: None,
+            update_check: None,
+            plain: false,
+            theme: Theme::Dark,
+            ortb2_popup: None,
+            diagnostics,
+            parse_warnings,
+            diagnostics_popup: None,
+            mapping: None,
+            classify: None,
+            synonyms: Synonyms::new(),
+            synonyms_version: 0,
+            report: SessionReport::default(),
+            crosswalks: Crosswalks::default(),
+            mapping_tables: Vec::new(),
+            keymap: Keymap::new(),
+            toast: None,
+            layout,
+            tree_cache: RefCell::new(None),
+            filter_cache: RefCell::new(None),
+            suggest_cache: RefCell::new(None),
+            classify_cache: RefCell::new(None),
+            tree_collisions: Cell::new(0),
+        };
+
+        let problems: usize = app.diagnostics.iter().map(Vec::len).sum();
+        if problems > 0 {
+            warnings.push(format!("{} problems in the taxonomy data, F8: Diagnostics", problems));
+        } else if app.parse_warnings.iter().any(|warnings| !warnings.is_empty()) {
+            warnings.push("Rows repaired while parsing --file, F8: Diagnostics".to_string());
+        }
+        if !warnings.is_empty() {
+            app.notify(ToastLevel::Warning, warnings.join("; "));
+        }
+
+        Ok(app)
+    }
+
+    fn notify(&mut self, level: ToastLevel, message: impl Into<String>) {
+        let message = message.into();
+        match level {
+            ToastLevel::Error => tracing::error!("{}", message),
+            ToastLevel::Warning => tracing::warn!("{}", message),
+            ToastLevel::Info | ToastLevel::Success => tracing::debug!("{}", message),
+        }
+        self.toast = Some(Toast {
+            level,
+            message,
+            shown_at: Instant::now(),
+        });
+    }
+
+    /// Report a failed persistence or export step without interrupting the user
+    fn notify_result(&mut self, result: Result<()>, action: &str) {
+        if let Err(err) = result {
+            self.notify(ToastLevel::Error, format!("{} failed: {:#}", action, err));
+        }
+    }
+
+    fn session(&self) -> Session {
+        let mut opened: Vec<Vec<String>> = self.tree_state.opened().iter().map(|path| path_strings(path)).collect();
+        opened.sort();
+        Session {
+            datasource: self.datasource,
+            sort_mode: self.sort_mode,
+            filter: self.filter_input.clone(),
+            zoom_root: self.zoom_root.clone(),
+            opened,
+            selected: path_strings(self.tree_state.selected()),
+        }
+    }
+
+    /// The key just handled and the small state fields it led to, for crash reports
+    fn crash_state(&self, key: KeyEvent) -> crash::State {
+        crash::State {
+            key,
+            datasource: self.datasource,
+            sort_mode: self.sort_mode,
+            filter: self.filter_input.clone(),
+            zoom_root: self.zoom_root.clone(),
+            selected: self.tree_state.selected().to_vec(),
+            opened: self.tree_state.opened().len(),
+        }
+    }
+
+    fn restore_session(&mut self, session: Session) {
+        self.datasource = session.datasource;
+        self.sort_mode = session.sort_mode;
+        self.filter_input = session.filter;
+        self.zoom_root = session.zoom_root;
+        self.tree_state = TreeState::default();
+        for path in session.opened {
+            self.tree_state.open(node_path(path));
+        }
+        self.tree_state.select(node_path(session.selected));
+        self.unzoom_if_filtered_out();
     }
 
     fn switch_datasource(&mut self, datasource: Datasource) {
         self.datasource = datasource;
+        self.zoom_root = None;
+        self.scroll_x = 0;
         self.tree_state = TreeState::default();
         self.tree_state.select_first();
         if !self.filter_input.is_empty() {
@@ -362,620 +851,3751 @@ impl App {
         }
     }
 
-    fn filtered_tree_items(&self) -> Vec<TreeItem<'static, String>> {
-        let filter_lower = self.filter_input.to_lowercase();
-
-        // If no filter, build full tree
-        if filter_lower.is_empty() {
-            return match self.datasource {
-                Datasource::Product => build_tree_items(&self.products, ""),
-                Datasource::Content => build_tree_items(&self.content, ""),
-                Datasource::Audience => build_tree_items(&self.audience, ""),
-            };
+    fn tree_options<'a>(&'a self, filter: &'a str) -> TreeOptions<'a> {
+        TreeOptions {
+            filter,
+            sort_mode: self.sort_mode,
+            bookmarks: self.bookmarks.ids(self.datasource.key()),
+            notes: self.notes.of(self.datasource.taxonomy()),
+            tags: self.tags.of(self.datasource.taxonomy()),
+            usage: self.usage(),
+            translation: self.translation(),
+            depth_palette: self.depth_colors.then_some(self.depth_palette.as_slice()),
+            hide_deprecated: self.hide_deprecated,
+            scroll_x: self.scroll_x,
+            collisions: Cell::new(0),
         }
+    }
 
-        // Filter items and build tree with full path + descendants
-        match self.datasource {
-            Datasource::Product => self.filtered_tree_from_items(&self.products, &filter_lower),
-            Datasource::Content => self.filtered_tree_from_items(&self.content, &filter_lower),
-            Datasource::Audience => self.filtered_tree_from_items(&self.audience, &filter_lower),
+    fn tree_key(&self) -> TreeKey {
+        TreeKey {
+            datasource: self.datasource,
+            filter: self.filter_input.clone(),
+            sort_mode: self.sort_mode,
+            bookmarks: self.bookmarks.ids(self.datasource.key()).to_vec(),
+            noted: self.notes.ids(self.datasource.taxonomy()),
+            tags: self.tags.of(self.datasource.taxonomy()).cloned().unwrap_or_default(),
+            usage_metric: self.usage().map(|(_, metric)| metric),
+            translated: self.translation().is_some(),
+            depth_palette: self.depth_colors.then(|| self.depth_palette.clone()),
+            hide_deprecated: self.hide_deprecated,
+            scroll_x: self.scroll_x,
+            zoom_root: self.zoom_root.clone(),
         }
     }
 
-    fn filtered_tree_from_items<T: TaxonomyItem + Clone>(&self, items: &[T], filter_lower: &str) -> Vec<TreeItem<'static, String>> {
-        // Find all matching items
-        let matching_ids: HashSet<String> = items
-            .iter()
-            .filter(|item| self.matches_all_fields(*item, filter_lower))
-            .map(|item| item.unique_id().to_string())
-            .collect();
-
-        if matching_ids.is_empty() {
-            return vec![];
+    /// The tree as currently shown, rebuilt only when one of its inputs changed since the last call
+    fn filtered_tree_items(&self) -> SharedTree {
+        let key = self.tree_key();
+        if let Some((cached_key, items)) = &*self.tree_cache.borrow()
+            && *cached_key == key
+        {
+            return Rc::clone(items);
         }
 
-        // Build parent map for ancestor lookup
-        let parent_map: HashMap<String, Option<String>> = items
-            .iter()
-            .map(|item| (item.unique_id().to_string(), item.parent().map(|s| s.to_string())))
-            .collect();
+        let tree_items = self.unzoomed_tree_items();
+        // Re-root the view at the zoomed node, if any
+        let items = Rc::new(match &self.zoom_root {
+            Some(root_id) => find_tree_item(&tree_items, root_id).into_iter().collect(),
+            None => tree_items,
+        });
+        *self.tree_cache.borrow_mut() = Some((key, Rc::clone(&items)));
+        items
+    }
 
-        // Collect all IDs to include: matches + all ancestors + all descendants
-        let mut included_ids: HashSet<String> = HashSet::new();
+    fn unzoomed_tree_items(&self) -> Vec<TreeItem<'static, NodeId>> {
+        let filter_lower = self.filter_input.to_lowercase();
+        let (text, _) = split_tag_filter(&filter_lower);
 
-        // Add matches
-        included_ids.extend(matching_ids.iter().cloned());
+        // If no filter, build full tree; otherwise keep matches with their full path and descendants
+        let included = (!filter_lower.is_empty()).then(|| self.included_positions(&filter_lower));
+        let options = self.tree_options(&text);
+        let items = match self.datasource {
+            Datasource::Product => build_tree_items(&self.products, self.child_index(), included.as_deref(), &options),
+            Datasource::Content => build_tree_items(&self.content, self.child_index(), included.as_deref(), &options),
+            Datasource::Audience => build_tree_items(&self.audience, self.child_index(), included.as_deref(), &options),
+        };
+        self.tree_collisions.set(options.collisions.get());
+        items
+    }
 
-        // Add all ancestors of matches
-        for match_id in &matching_ids {
-            let mut current_id = match_id.clone();
-            let mut visited = HashSet::new();
-            while let Some(Some(parent_id)) = parent_map.get(&current_id) {
-                // Prevent infinite loop on circular references
-                if visited.contains(&current_id) {
-                    break;
-                }
-                visited.insert(current_id.clone());
-                included_ids.insert(parent_id.clone());
-                current_id = parent_id.clone();
-            }
-        }
+    /// Child index of the current datasource
+    fn child_index(&self) -> &ChildIndex {
+        &self.indexes[self.datasource.taxonomy().index()]
+    }
 
-        // Add all descendants of matches
-        for match_id in &matching_ids {
-            self.add_all_descendants(match_id, items, &mut included_ids);
+    /// Positions of the matches plus all their ancestors and descendants, memoized for the current
+    /// datasource and filter
+    fn included_positions(&self, filter_lower: &str) -> Rc<NodeSet> {
+        let key: FilterKey = (self.datasource, filter_lower.to_string());
+        if let Some((cached_key, positions)) = &*self.filter_cache.borrow()
+            && *cached_key == key
+        {
+            return Rc::clone(positions);
         }
 
-        // Filter items to only included IDs
-        let filtered_items: Vec<T> = items
+        let started = Instant::now();
+        let (text, tags) = split_tag_filter(filter_lower);
+        let index = self.child_index();
+        let tagged = self.tags.of(self.datasource.taxonomy());
+        let has_tags = |position: usize| {
+            let node_tags = tagged.and_then(|tagged| tagged.get(&**index.id(position))).map_or(&[][..], Vec::as_slice);
+            tags.iter().all(|tag| node_tags.iter().any(|node_tag| node_tag == tag))
+        };
+        let haystacks = &self.haystacks[self.datasource.taxonomy().index()];
+        let matches = haystacks
             .iter()
-            .filter(|item| included_ids.contains(item.unique_id()))
-            .cloned()
-            .collect();
-
-        // Build tree from filtered items
-        build_tree_items(&filtered_items, filter_lower)
+            .enumerate()
+            .filter(|&(position, haystack)| haystack.matches(&text) && has_tags(position));
+        let included = Rc::new(index.expand_matches(matches.map(|(position, _)| position)));
+        tracing::debug!(
+            datasource = self.datasource.name(),
+            filter = filter_lower,
+            included = included.len(),
+            elapsed = ?started.elapsed(),
+            "filtered tree"
+        );
+        *self.filter_cache.borrow_mut() = Some((key, Rc::clone(&included)));
+        included
     }
 
-    fn add_all_descendants<T: TaxonomyItem>(&self, parent_id: &str, items: &[T], included_ids: &mut HashSet<String>) {
-        for item in items {
-            if let Some(item_parent) = item.parent() {
-                if item_parent == parent_id {
-                    let child_id = item.unique_id().to_string();
-                    // Prevent infinite recursion on circular references
-                    if !included_ids.contains(&child_id) {
-                        included_ids.insert(child_id.clone());
-                        self.add_all_descendants(&child_id, items, included_ids);
-                    }
-                }
-            }
+    fn select_sibling(&mut self, forward: bool) {
+        let mut path = self.tree_state.selected().to_vec();
+        let tree_items = self.filtered_tree_items();
+        let siblings = sibling_ids(&tree_items, &path);
+        let Some(pos) = siblings.iter().position(|id| Some(id) == path.last()) else {
+            return;
+        };
+        let target = if forward { siblings.get(pos + 1) } else { pos.checked_sub(1).and_then(|i| siblings.get(i)) };
+        if let Some(id) = target {
+            path.pop();
+            path.push(id.clone());
+            self.tree_state.select(path);
         }
     }
 
-    fn expand_filtered_nodes(&mut self) {
-        if !self.filter_input.is_empty() {
-            let tree_items = self.filtered_tree_items();
-            let all_paths = collect_all_tree_paths(&tree_items, vec![]);
-            for path in all_paths {
-                self.tree_state.open(path);
-            }
-        }
-    }
+    /// Jump to the next sibling (wrapping around) whose name starts with `letter`
+    fn jump_to_sibling_starting_with(&mut self, letter: char) {
+        let mut path = self.tree_state.selected().to_vec();
+        let tree_items = self.filtered_tree_items();
+        let siblings = sibling_ids(&tree_items, &path);
+        let current = siblings.iter().position(|id| Some(id) == path.last()).unwrap_or(0);
+        let letter = letter.to_lowercase().to_string();
 
-    fn matches_all_fields<T: TaxonomyItem + ?Sized>(&self, item: &T, filter_lower: &str) -> bool {
-        if filter_lower.is_empty() {
-            return true;
+        let target = (1..=siblings.len())
+            .map(|offset| &siblings[(current + offset) % siblings.len()])
+            .find(|id| {
+                self.display_name(id)
+                    .is_some_and(|name| name.to_lowercase().starts_with(&letter))
+            });
+        if let Some(id) = target {
+            path.pop();
+            path.push(id.clone());
+            self.tree_state.select(path);
         }
+    }
 
-        // Search in unique_id (exact match)
-        if item.unique_id().to_lowercase() == filter_lower {
-            return true;
+    fn select_parent(&mut self) {
+        let mut path = self.tree_state.selected().to_vec();
+        if path.len() > 1 {
+            path.pop();
+            self.tree_state.select(path);
         }
+    }
 
-        // Search in parent (exact match)
-        if let Some(parent) = item.parent() {
-            if parent.to_lowercase() == filter_lower {
-                return true;
-            }
-        }
+    /// Whether the selected row is cut off at the right edge of the tree view
+    fn selected_label_overflows(&self) -> bool {
+        let path = self.tree_state.selected();
+        let Some(id) = path.last() else {
+            return false;
+        };
+        let name_width = self.display_name(id).map(|name| name.chars().count()).unwrap_or(0);
+        // Indentation, open/closed symbol, ID, separator, name and room for a badge
+        let label_width = path.len() * 2 + id.chars().count() + 1 + name_width + 14;
+        label_width.saturating_sub(self.scroll_x) > self.viewport_width
+    }
 
-        // Search in name
-        if item.name().to_lowercase().contains(filter_lower) {
-            return true;
-        }
+    /// Name as shown: the translated one while translations are on and the node has one
+    fn display_name(&self, id: &str) -> Option<&str> {
+        let name = self.item_name(id)?;
+        Some(self.translation().and_then(|translation| translation.name(id)).unwrap_or(name))
+    }
 
-        // Search in tiers
-        for tier in item.tiers() {
-            if tier.to_lowercase().contains(filter_lower) {
-                return true;
-            }
+    fn item_name(&self, id: &str) -> Option<&str> {
+        let position = self.child_index().position(id)?;
+        match self.datasource {
+            Datasource::Product => self.products.get(position).map(|item| item.name()),
+            Datasource::Content => self.content.get(position).map(|item| item.name()),
+            Datasource::Audience => self.audience.get(position).map(|item| item.name()),
         }
+    }
 
-        // Search in extension
-        if let Some(ext) = item.extension() {
-            if ext.to_lowercase().contains(filter_lower) {
-                return true;
-            }
+    fn toggle_bookmark(&mut self) {
+        if let Some(id) = self.tree_state.selected().last().cloned()
+            && self.item_name(&id).is_some()
+        {
+            let starred = self.bookmarks.toggle(self.datasource.key(), &id);
+            let name = self.item_name(&id).unwrap_or_default().to_string();
+            self.report.record_bookmark(self.datasource.name(), &id, &name, starred);
+            let message = if starred { format!("Bookmarked {}", id) } else { format!("Removed bookmark {}", id) };
+            self.notify(ToastLevel::Info, message);
+            // A failed save replaces the confirmation
+            let result = self.bookmarks.save();
+            self.notify_result(result, "Saving bookmarks");
         }
-
-        false
     }
 
-    fn show_item_details(&mut self) {
-        // Get the selected item's unique ID from the tree state
-        let selected_path = self.tree_state.selected();
-        let selected_id = match selected_path.last() {
-            Some(id) => id,
-            None => return,
+    /// Select `id`, clearing filter and zoom so the node is guaranteed to be visible
+    fn jump_to(&mut self, id: &str) {
+        self.filter_input.clear();
+        self.zoom_root = None;
+        self.tree_state = TreeState::default();
+        let path = self.path_to(id);
+        self.tree_state.select(path);
+    }
+
+    /// Pair the current mapping entry with the selected node, or unpair it when it already points there
+    fn toggle_mapping(&mut self) {
+        if self.kiosk_refuses("Editing the mapping") {
+            return;
+        }
+        let Some(id) = self.tree_state.selected().last().cloned() else {
+            return;
+        };
+        // The virtual orphans branch is not a node
+        let Some(name) = self.item_name(&id).map(str::to_string) else {
+            return;
+        };
+        let Some(mapping) = self.mapping.as_mut() else {
+            self.notify(ToastLevel::Warning, "No mapping file, start with --mapping <FILE>");
+            return;
+        };
+        let internal = mapping.current().map(|entry| entry.internal.clone()).unwrap_or_default();
+        let message = if mapping.toggle(self.datasource.key(), &id, &name) {
+            format!("Mapped \"{}\" → {} {}", internal, id, name)
+        } else {
+            format!("Unmapped \"{}\"", internal)
+        };
+        let result = mapping.save();
+        self.notify(ToastLevel::Info, message);
+        self.notify_result(result, "Saving mapping");
+    }
+
+    /// Move to the previous or next mapping entry, showing the node it is mapped to for review
+    fn select_mapping_entry(&mut self, forward: bool) {
+        let Some(mapping) = self.mapping.as_mut() else {
+            return;
         };
+        mapping.select_relative(forward);
+        let target = mapping
+            .current()
+            .and_then(|entry| Some((Taxonomy::from_key(entry.taxonomy.as_deref()?)?, entry.id.clone()?)));
+        if let Some((taxonomy, id)) = target {
+            if self.datasource.taxonomy() != taxonomy {
+                self.switch_datasource(taxonomy.into());
+            }
+            self.jump_to(&id);
+        }
+    }
 
-        let details = match self.datasource {
-            Datasource::Product => {
-                let item = self.products
-                    .iter()
-                    .find(|item| item.unique_id() == selected_id);
+    /// Nodes of the current datasource in file order
+    fn items(&self) -> Vec<&dyn TaxonomyItem> {
+        match self.datasource {
+            Datasource::Product => self.products.iter().map(|item| item as &dyn TaxonomyItem).collect(),
+            Datasource::Content => self.content.iter().map(|item| item as &dyn TaxonomyItem).collect(),
+            Datasource::Audience => self.audience.iter().map(|item| item as &dyn TaxonomyItem).collect(),
+        }
+    }
 
-                if let Some(item) = item {
-                    self.format_item_details(item)
-                } else {
+    fn set_synonyms(&mut self, synonyms: Synonyms) {
+        self.synonyms = synonyms;
+        self.synonyms_version += 1;
+    }
+
+    /// Word weights of the current datasource, built on first use after a datasource or synonyms change
+    fn suggest_index(&self, key: SuggestKey) -> Rc<SuggestIndex> {
+        if let Some((cached_key, index)) = &*self.suggest_cache.borrow()
+            && *cached_key == key
+        {
+            return Rc::clone(index);
+        }
+        let index = Rc::new(SuggestIndex::new(&self.items(), &self.synonyms));
+        *self.suggest_cache.borrow_mut() = Some((key, Rc::clone(&index)));
+        index
+    }
+
+    /// Suggestions for the classification prompt text, memoized for the last text
+    fn classify_suggestions(&self, text: &str) -> Suggestions {
+        let key = ((self.datasource, self.synonyms_version), text.to_string());
+        if let Some((cached_key, suggestions)) = &*self.classify_cache.borrow()
+            && *cached_key == key
+        {
+            return Rc::clone(suggestions);
+        }
+        let items = self.items();
+        let suggestions: Suggestions = Rc::new(
+            self.suggest_index(key.0)
+                .rank(text, CLASSIFY_LIMIT)
+                .into_iter()
+                .map(|(position, score)| {
+                    let item = items[position];
+                    (item.unique_id().to_string(), item.tiers().join(" > "), score)
+                })
+                .collect(),
+        );
+        *self.classify_cache.borrow_mut() = Some((key, Rc::clone(&suggestions)));
+        suggestions
+    }
+
+    /// Start editing the note or tags of the selected node, prefilled with the current ones
+    fn open_annotation_editor(&mut self, annotation: Annotation) {
+        if self.kiosk_refuses("Editing") {
+            return;
+        }
+        let Some(id) = self.tree_state.selected().last() else {
+            return;
+        };
+        let taxonomy = self.datasource.taxonomy();
+        let text = match annotation {
+            Annotation::Note => self.notes.get(taxonomy, id).unwrap_or_default().to_string(),
+            Annotation::Tags => self.tags.get(taxonomy, id).join(", "),
+            Annotation::Child => String::new(),
+        };
+        self.annotation_editor = Some(AnnotationEditor { annotation, id: id.to_string(), text });
+    }
+
+    fn handle_annotation_key(&mut self, key: KeyEvent) {
+        let Some(editor) = self.annotation_editor.as_mut() else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc => self.annotation_editor = None,
+            KeyCode::Backspace => {
+                editor.text.pop();
+            }
+            KeyCode::Char(c) => editor.text.push(c),
+            KeyCode::Enter => {
+                if editor.annotation == Annotation::Child {
+                    let (parent, name) = (editor.id.clone(), editor.text.clone());
+                    // Keep the prompt open so an invalid name can be corrected
+                    match self.add_custom_node(&parent, &name) {
+                        Result::Ok(id) => {
+                            self.annotation_editor = None;
+                            self.notify(ToastLevel::Success, format!("Added {} {} under {}", id, name.trim(), parent));
+                            let result = self.save_extension().map(|_| ());
+                            self.notify_result(result, "Saving extension");
+                            self.jump_to(&id);
+                        }
+                        Err(err) => self.notify(ToastLevel::Error, format!("Cannot add the node: {:#}", err)),
+                    }
                     return;
                 }
-            }
-            Datasource::Content => {
-                let item = self.content
-                    .iter()
-                    .find(|item| item.unique_id() == selected_id);
-
-                if let Some(item) = item {
-                    self.format_item_details(item)
-                } else {
+                let Some(AnnotationEditor { annotation, id, text }) = self.annotation_editor.take() else {
                     return;
+                };
+                let taxonomy = self.datasource.taxonomy();
+                let removed = text.trim().is_empty();
+                let result = match annotation {
+                    Annotation::Note => {
+                        self.notes.set(taxonomy, &id, &text);
+                        let message = if removed { format!("Removed note on {}", id) } else { format!("Saved note on {}", id) };
+                        self.notify(ToastLevel::Success, message);
+                        self.notes.save()
+                    }
+                    Annotation::Tags => {
+                        self.tags.set(taxonomy, &id, &text);
+                        // A `tag:` filter may now match other nodes
+                        *self.filter_cache.borrow_mut() = None;
+                        let tags = self.tags.get(taxonomy, &id).join(", ");
+                        let message = if removed { format!("Removed tags of {}", id) } else { format!("Tagged {}: {}", id, tags) };
+                        self.notify(ToastLevel::Success, message);
+                        self.tags.save()
+                    }
+                    Annotation::Child => Ok(()),
+                };
+                let action = if annotation == Annotation::Note { "Saving notes" } else { "Saving tags" };
+                self.notify_result(result, action);
+                // Show the new text in an open details popup
+                if self.show_popup
+                    && let Some(details) = self.item_details(&id)
+                {
+                    self.popup_content = details;
                 }
             }
-            Datasource::Audience => {
-                let item = self.audience
-                    .iter()
-                    .find(|item| item.unique_id() == selected_id);
+            _ => {}
+        }
+    }
 
-                if let Some(item) = item {
-                    self.format_item_details(item)
-                } else {
-                    return;
+    fn handle_classify_key(&mut self, key: KeyEvent) {
+        let Some(prompt) = self.classify.as_mut() else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc => self.classify = None,
+            KeyCode::Up => prompt.selected = prompt.selected.saturating_sub(1),
+            KeyCode::Down => prompt.selected = (prompt.selected + 1).min(CLASSIFY_LIMIT - 1),
+            KeyCode::Backspace => {
+                prompt.text.pop();
+                prompt.selected = 0;
+            }
+            KeyCode::Char(c) => {
+                prompt.text.push(c);
+                prompt.selected = 0;
+            }
+            KeyCode::Enter => {
+                let (text, selected) = (prompt.text.clone(), prompt.selected);
+                if let Some((id, _, _)) = self.classify_suggestions(&text).get(selected) {
+                    let id = id.clone();
+                    self.classify = None;
+                    self.jump_to(&id);
                 }
             }
+            _ => {}
+        }
+    }
+
+    /// Bookmarked IDs of the current taxonomy that still exist, or the selected node when there are none
+    fn ortb2_ids(&self) -> Vec<String> {
+        let bookmarked: Vec<String> =
+            self.bookmarks.ids(self.datasource.key()).iter().filter(|id| self.item_name(id).is_some()).cloned().collect();
+        if !bookmarked.is_empty() {
+            return bookmarked;
+        }
+        self.tree_state.selected().last().map(|id| id.to_string()).into_iter().collect()
+    }
+
+    fn ortb2_snippet(&self, channel: Channel) -> Result<String> {
+        let ids = self.ortb2_ids();
+        if ids.is_empty() {
+            bail!("select or bookmark categories first");
+        }
+        let fragment = match self.datasource.taxonomy() {
+            Taxonomy::Audience => ortb2::user_data_fragment(&ids, None),
+            taxonomy => ortb2::content_fragment(taxonomy, &ids, channel)?,
         };
+        Ok(serde_json::to_string_pretty(&fragment)?)
+    }
 
-        self.popup_content = details;
-        self.show_popup = true;
+    fn list_ids(&self, list: ListPopup) -> &[String] {
+        match list {
+            ListPopup::Bookmarks => self.bookmarks.ids(self.datasource.key()),
+            ListPopup::Recent => self.recent.ids(self.datasource.key()),
+        }
     }
 
-    fn format_item_details<T: TaxonomyItem>(&self, item: &T) -> Vec<(String, String)> {
-        let mut details = vec![
-            ("Unique ID".to_string(), item.unique_id().to_string()),
-            ("Parent ID".to_string(), item.parent().unwrap_or("").to_string()),
-            ("Name".to_string(), item.name().to_string()),
-        ];
+    /// Add a custom node named `name` under `parent` in the current taxonomy, returning its ID
+    fn add_custom_node(&mut self, parent: &str, name: &str) -> Result<String> {
+        extension::check_name(name)?;
+        let taxonomy = self.datasource.taxonomy();
+        let index = self.child_index();
+        let position = index.position(parent).with_context(|| format!("unknown parent {}", parent))?;
+        let items = self.items();
+        if let Some(sibling) = index
+            .children(position)
+            .filter_map(|child| items.get(child))
+            .find(|sibling| sibling.name().eq_ignore_ascii_case(name.trim()))
+        {
+            bail!("{} {} already exists under {}", sibling.unique_id(), sibling.name(), parent);
+        }
+        let id = extension::next_id(items.iter().map(|item| item.unique_id()))?;
+        let cells = extension::record(taxonomy, items[position], &id, name)?;
 
-        let tiers = item.tiers();
-        for (i, tier) in tiers.iter().enumerate() {
-            details.push((format!("Tier {}", i + 1), tier.to_string()));
+        match taxonomy {
+            Taxonomy::Product => self.products.push(extension::parse_record(taxonomy, &cells)?),
+            Taxonomy::Content => self.content.push(extension::parse_record(taxonomy, &cells)?),
+            Taxonomy::Audience => self.audience.push(extension::parse_record(taxonomy, &cells)?),
         }
+        self.raw_tables[taxonomy.index()].insert(&id, cells);
+        self.reindex();
+        Ok(id)
+    }
 
-        if let Some(ext) = item.extension() {
-            if !ext.is_empty() {
-                details.push(("Extension".to_string(), ext.to_string()));
-            }
+    /// Remove the selected custom node when it has no children
+    fn remove_custom_node(&mut self) {
+        if self.kiosk_refuses("Editing") {
+            return;
+        }
+        let Some(id) = self.tree_state.selected().last().map(|id| id.to_string()) else {
+            return;
+        };
+        if !extension::is_custom(&id) {
+            self.notify(ToastLevel::Warning, format!("{} is an official node; only custom nodes (F12) can be removed", id));
+            return;
+        }
+        let Some(position) = self.child_index().position(&id) else {
+            return;
+        };
+        if self.child_index().children(position).next().is_some() {
+            self.notify(ToastLevel::Warning, format!("Remove the children of {} first", id));
+            return;
+        }
+        let taxonomy = self.datasource.taxonomy();
+        match taxonomy {
+            Taxonomy::Product => drop(self.products.remove(position)),
+            Taxonomy::Content => drop(self.content.remove(position)),
+            Taxonomy::Audience => drop(self.audience.remove(position)),
         }
+        self.raw_tables[taxonomy.index()].remove(&id);
+        self.reindex();
+        let parent = self.tree_state.selected().iter().rev().nth(1).map(|id| id.to_string());
+        if let Some(parent) = parent {
+            self.jump_to(&parent);
+        }
+        self.notify(ToastLevel::Success, format!("Removed custom node {}", id));
+        let result = self.save_extension().map(|_| ());
+        self.notify_result(result, "Saving extension");
+    }
 
-        details
+    /// Usage of the current taxonomy and the metric to show, if a `--usage` file was given for it
+    fn usage(&self) -> Option<(&Usage, usize)> {
+        let usage = self.usage[self.datasource.taxonomy().index()].as_ref()?;
+        Some((usage, self.usage_metric.min(usage.metrics().len().saturating_sub(1))))
     }
 
-    fn handle_key(&mut self, key: KeyEvent) -> bool {
-        // Handle popup-specific keys first
-        if self.show_popup {
-            match key.code {
-                KeyCode::Esc | KeyCode::Enter => {
-                    self.show_popup = false;
-                    return true;
-                }
-                KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    return false;
+    /// Attach `--usage` numbers to a taxonomy, warning about IDs it does not have
+    fn set_usage(&mut self, taxonomy: Taxonomy, mut usage: Usage) {
+        let len = match taxonomy {
+            Taxonomy::Product => self.products.len(),
+            Taxonomy::Content => self.content.len(),
+            Taxonomy::Audience => self.audience.len(),
+        };
+        let unknown = usage.roll_up(&self.indexes[taxonomy.index()], len);
+        if !unknown.is_empty() {
+            let shown: Vec<&str> = unknown.iter().take(5).map(String::as_str).collect();
+            let more = if unknown.len() > shown.len() { format!(" and {} more", unknown.len() - shown.len()) } else { String::new() };
+            self.notify(ToastLevel::Warning, format!("Usage for unknown {} IDs ignored: {}{}", taxonomy.name(), shown.join(", "), more));
+        }
+        self.usage[taxonomy.index()] = Some(usage);
+    }
+
+    /// Translated names of the current taxonomy while they are switched on
+    fn translation(&self) -> Option<&Translation> {
+        self.translations[self.datasource.taxonomy().index()].as_ref().filter(|_| self.show_translation)
+    }
+
+    /// Attach `--translation` names to a taxonomy, warning about IDs it does not have
+    fn set_translation(&mut self, taxonomy: Taxonomy, translation: Translation) {
+        let index = &self.indexes[taxonomy.index()];
+        let unknown = translation.ids().filter(|id| index.position(id).is_none()).count();
+        if unknown > 0 {
+            self.notify(ToastLevel::Warning, format!("{} translated {} IDs not in the taxonomy ignored", unknown, taxonomy.name()));
+        }
+        self.translations[taxonomy.index()] = Some(translation);
+        self.rebuild_haystacks(taxonomy);
+        *self.filter_cache.borrow_mut() = None;
+    }
+
+    /// Filter fields of a taxonomy, including translated names so the filter matches either language
+    fn rebuild_haystacks(&mut self, taxonomy: Taxonomy) {
+        let translation = self.translations[taxonomy.index()].as_ref();
+        let haystack = |item: &dyn TaxonomyItem| {
+            Haystack::new(item).with_translation(translation.and_then(|translation| translation.name(item.unique_id())))
+        };
+        self.haystacks[taxonomy.index()] = match taxonomy {
+            Taxonomy::Product => self.products.iter().map(|item| haystack(item)).collect(),
+            Taxonomy::Content => self.content.iter().map(|item| haystack(item)).collect(),
+            Taxonomy::Audience => self.audience.iter().map(|item| haystack(item)).collect(),
+        };
+    }
+
+    /// Switch node names between the original and the loaded translation
+    fn toggle_translation(&mut self) {
+        let taxonomy = self.datasource.taxonomy();
+        let Some(translation) = &self.translations[taxonomy.index()] else {
+            self.notify(ToastLevel::Warning, format!("No translation for {}, start with --translation {}=<FILE>", taxonomy.name(), taxonomy.key()));
+            return;
+        };
+        self.show_translation = !self.show_translation;
+        let message = if self.show_translation {
+            format!("Names: {} ({} translated)", translation.label(), translation.len())
+        } else {
+            "Names: original".to_string()
+        };
+        self.notify(ToastLevel::Info, message);
+    }
+
+    /// Next sort order; with usage loaded, the usage order steps through each metric first
+    fn cycle_sort(&mut self) {
+        let metrics = self.usage().map_or(0, |(usage, _)| usage.metrics().len());
+        if self.sort_mode == SortMode::Usage && self.usage_metric + 1 < metrics {
+            self.usage_metric += 1;
+        } else {
+            self.sort_mode = self.sort_mode.next();
+            if self.sort_mode == SortMode::Usage {
+                self.usage_metric = 0;
+                if metrics == 0 {
+                    self.sort_mode = self.sort_mode.next();
                 }
-                _ => return true,
             }
         }
+    }
+
+    /// Sort order for titles and toasts, naming the usage metric
+    fn sort_label(&self) -> String {
+        match (self.sort_mode, self.usage()) {
+            (SortMode::Usage, Some((usage, metric))) => i18n::trf(Text::SortUsageMetric, &[&usage.metrics()[metric]]),
+            (mode, _) => mode.name().to_string(),
+        }
+    }
+
+    /// Rebuild the child index and filter fields of the current taxonomy after its rows changed
+    fn reindex(&mut self) {
+        let taxonomy = self.datasource.taxonomy();
+        self.indexes[taxonomy.index()] = match taxonomy {
+            Taxonomy::Product => ChildIndex::new(&self.products),
+            Taxonomy::Content => ChildIndex::new(&self.content),
+            Taxonomy::Audience => ChildIndex::new(&self.audience),
+        };
+        self.rebuild_haystacks(taxonomy);
+        let len = self.items().len();
+        if let Some(usage) = &mut self.usage[taxonomy.index()] {
+            usage.roll_up(&self.indexes[taxonomy.index()], len);
+        }
+        *self.tree_cache.borrow_mut() = None;
+        *self.filter_cache.borrow_mut() = None;
+        *self.suggest_cache.borrow_mut() = None;
+        *self.classify_cache.borrow_mut() = None;
+    }
+
+    /// Write the custom nodes of the current taxonomy to its `--extension` file, or
+    /// `iab-<taxonomy>-extension.tsv` when none was given, returning the path
+    fn save_extension(&mut self) -> Result<PathBuf> {
+        let taxonomy = self.datasource.taxonomy();
+        let path = self.extension_files[taxonomy.index()]
+            .get_or_insert_with(|| PathBuf::from(format!("iab-{}-extension.tsv", taxonomy.key())))
+            .clone();
+        let raw = &self.raw_tables[taxonomy.index()];
+        let rows = self.items().into_iter().map(|item| item.unique_id()).filter(|id| extension::is_custom(id)).filter_map(|id| raw.cells(id));
+        std::fs::write(&path, extension::to_tsv(taxonomy, rows)?).with_context(|| format!("cannot write {}", path.display()))?;
+        tracing::info!(taxonomy = taxonomy.key(), path = %path.display(), "saved extension");
+        Ok(path)
+    }
+
+    fn toggle_basket(&mut self) {
+        let Some(id) = self.tree_state.selected().last().map(|id| id.to_string()) else {
+            return;
+        };
+        let added = self.basket.toggle(self.datasource.taxonomy(), &id);
+        let count = self.basket.entries().len();
+        let message = if added {
+            format!("Added {} to the basket ({})", id, count)
+        } else {
+            format!("Removed {} from the basket ({})", id, count)
+        };
+        self.notify(ToastLevel::Info, message);
+        let result = self.basket.save();
+        self.notify_result(result, "Saving basket");
+    }
+
+    /// Basket entries found in the loaded taxonomies, with their names and tier paths
+    fn basket_rows(&self) -> Vec<(Taxonomy, BasketRow)> {
+        self.basket
+            .entries()
+            .iter()
+            .filter_map(|entry| {
+                let taxonomy = entry.taxonomy()?;
+                let position = self.indexes[taxonomy.index()].position(&entry.id)?;
+                let item: &dyn TaxonomyItem = match taxonomy {
+                    Taxonomy::Product => self.products.get(position)?,
+                    Taxonomy::Content => self.content.get(position)?,
+                    Taxonomy::Audience => self.audience.get(position)?,
+                };
+                let row = BasketRow {
+                    taxonomy: taxonomy.key(),
+                    id: entry.id.clone(),
+                    name: item.name().to_string(),
+                    tiers: item.tiers().into_iter().map(str::to_string).collect(),
+                };
+                Some((taxonomy, row))
+            })
+            .collect()
+    }
+
+    fn handle_basket_key(&mut self, key: KeyEvent) {
+        let Some(popup) = self.basket_popup else {
+            return;
+        };
+        let count = self.basket.entries().len();
 
-        // Handle normal navigation
         match key.code {
-            KeyCode::Esc => return false,
-            KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
-            KeyCode::Enter => {
-                self.show_item_details();
+            KeyCode::Esc | KeyCode::F(11) => self.basket_popup = None,
+            KeyCode::Down => self.basket_popup = Some(BasketPopup { selected: (popup.selected + 1).min(count.saturating_sub(1)), ..popup }),
+            KeyCode::Up => self.basket_popup = Some(BasketPopup { selected: popup.selected.saturating_sub(1), ..popup }),
+            KeyCode::Tab => self.basket_popup = Some(BasketPopup { format: popup.format.next(), ..popup }),
+            KeyCode::Delete => {
+                self.basket.remove(popup.selected);
+                self.basket_popup = Some(BasketPopup { selected: popup.selected.min(count.saturating_sub(2)), ..popup });
+                let result = self.basket.save();
+                self.notify_result(result, "Saving basket");
             }
-            KeyCode::Tab => {
-                if key.modifiers.contains(KeyModifiers::SHIFT) {
-                    self.switch_datasource(self.datasource.previous());
-                } else {
-                    self.switch_datasource(self.datasource.next());
+            KeyCode::Char('x') => {
+                self.basket.clear();
+                self.basket_popup = Some(BasketPopup { selected: 0, ..popup });
+                self.notify(ToastLevel::Info, format!("Basket cleared ({} removed)", count));
+                let result = self.basket.save();
+                self.notify_result(result, "Saving basket");
+            }
+            KeyCode::Char('c') if self.kiosk_refuses("Copying") => {}
+            KeyCode::Char('s') if self.kiosk_refuses("Saving files") => {}
+            KeyCode::Char('c') => {
+                let copied = basket::render(&self.basket_rows(), popup.format).and_then(|text| clipboard::copy(&text));
+                match copied {
+                    Result::Ok(_) => self.notify(ToastLevel::Success, format!("Copied the basket as {}", popup.format.name())),
+                    Err(err) => self.notify(ToastLevel::Error, format!("Copying the basket failed: {:#}", err)),
                 }
             }
-            KeyCode::Char(c) => {
-                self.filter_input.push(c);
-                self.tree_state = TreeState::default();
-                self.tree_state.select_first();
-                self.expand_filtered_nodes();
+            KeyCode::Char('s') => {
+                let path = popup.format.file_name();
+                let rows = self.basket_rows();
+                let written = basket::render(&rows, popup.format)
+                    .and_then(|text| std::fs::write(path, text).with_context(|| format!("cannot write {}", path)));
+                match written {
+                    Result::Ok(()) => {
+                        tracing::info!(format = popup.format.name(), path, rows = rows.len(), "exported basket");
+                        self.report.record_export(format!("Basket {} nodes ({}) to {}", rows.len(), popup.format.name(), path));
+                        self.notify(ToastLevel::Success, format!("Saved {} basket nodes to {}", rows.len(), path));
+                    }
+                    Err(err) => self.notify(ToastLevel::Error, format!("Saving the basket failed: {:#}", err)),
+                }
             }
-            KeyCode::Backspace => {
-                self.filter_input.pop();
-                self.tree_state = TreeState::default();
-                self.tree_state.select_first();
-                self.expand_filtered_nodes();
+            KeyCode::Enter => {
+                if let Some(entry) = self.basket.entries().get(popup.selected).cloned()
+                    && let Some(taxonomy) = entry.taxonomy()
+                {
+                    self.basket_popup = None;
+                    if self.datasource.taxonomy() != taxonomy {
+                        self.switch_datasource(taxonomy.into());
+                    }
+                    self.jump_to(&entry.id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Diff the current taxonomy, e.g. a `--file` or one with custom nodes, against the bundled release
+    fn open_compare(&mut self) {
+        let taxonomy = self.datasource.taxonomy();
+        let official = match Taxonomies::load(|_| {}) {
+            Result::Ok(official) => Box::new(official),
+            Err(err) => {
+                self.notify(ToastLevel::Error, format!("Loading the official {} failed: {:#}", taxonomy.name(), err));
+                return;
             }
+        };
+        let changes = compare::compare(&official.items(taxonomy), &self.items());
+        if changes.is_empty() {
+            self.notify(ToastLevel::Info, format!("{} matches the official {} {}", taxonomy.name(), taxonomy.name(), taxonomy.version()));
+            return;
+        }
+        tracing::debug!(taxonomy = taxonomy.key(), changes = changes.len(), "compared with the official release");
+        self.compare_popup = Some(ComparePopup { official, changes, selected: 0 });
+    }
+
+    /// List the changes from the --previous version of the current taxonomy
+    fn open_changelog(&mut self) {
+        let taxonomy = self.datasource.taxonomy();
+        let (Some(previous), Some(file)) = (&self.previous, self.previous_files.iter().find(|file| file.taxonomy == taxonomy)) else {
+            return;
+        };
+        let label = file.path.file_name().map_or_else(|| file.path.display().to_string(), |name| name.to_string_lossy().into_owned());
+        let previous_items = previous.items(taxonomy);
+        let current_items = self.items();
+        let changes = compare::changelog(&previous_items, &current_items);
+        if changes.is_empty() {
+            self.notify(ToastLevel::Info, format!("No node was added, removed, renamed or moved since {}", label));
+            return;
+        }
+
+        let current_ids: HashSet<&str> = current_items.iter().map(|item| item.unique_id()).collect();
+        let nodes = current_items
+            .iter()
+            .chain(previous_items.iter().filter(|item| !current_ids.contains(item.unique_id())))
+            .map(|item| ChangelogNode { id: NodeId::from(item.unique_id()), name: item.name().to_string(), parent: item.parent().map(str::to_string) })
+            .collect();
+        tracing::debug!(taxonomy = taxonomy.key(), changes = changes.len(), previous = %file.path.display(), "listed changes");
+        let mut popup = ChangelogPopup { label, changes, nodes, filter: ChangeFilter::All, items: Vec::new(), state: RefCell::default() };
+        popup.rebuild();
+        self.changelog_popup = Some(popup);
+    }
+
+    fn handle_changelog_key(&mut self, key: KeyEvent) {
+        let Some(popup) = self.changelog_popup.as_mut() else {
+            return;
+        };
+        let state = popup.state.get_mut();
+
+        match key.code {
+            KeyCode::Esc => self.changelog_popup = None,
             KeyCode::Down => {
-                self.tree_state.key_down();
+                state.key_down();
             }
             KeyCode::Up => {
-                self.tree_state.key_up();
+                state.key_up();
             }
             KeyCode::Left => {
-                self.tree_state.key_left();
+                state.key_left();
             }
             KeyCode::Right => {
-                self.tree_state.key_right();
+                state.key_right();
             }
-            KeyCode::PageDown => {
-                for _ in 0..10 {
-                    self.tree_state.key_down();
+            KeyCode::Home => {
+                state.select_first();
+            }
+            KeyCode::End => {
+                state.select_last();
+            }
+            KeyCode::Tab => {
+                popup.filter = popup.filter.next();
+                popup.rebuild();
+            }
+            KeyCode::Char('c') => {
+                self.changelog_popup = None;
+                self.open_compare();
+            }
+            KeyCode::Enter => {
+                // Removed nodes are not in the current taxonomy
+                let Some(id) = state.selected().last().cloned() else {
+                    return;
+                };
+                let removed = popup.changes.iter().any(|change| *change.id == *id && change.revision == Revision::Removed);
+                if !removed {
+                    self.changelog_popup = None;
+                    self.jump_to(&id);
                 }
             }
-            KeyCode::PageUp => {
-                for _ in 0..10 {
-                    self.tree_state.key_up();
+            _ => {}
+        }
+    }
+
+    /// Write the official rows with the taken changes applied to `iab-<taxonomy>-merged.tsv`
+    fn save_merged(&mut self) {
+        if self.kiosk_refuses("Saving files") {
+            return;
+        }
+        let Some(popup) = &self.compare_popup else {
+            return;
+        };
+        let taxonomy = self.datasource.taxonomy();
+        let path = format!("iab-{}-merged.tsv", taxonomy.key());
+        let official = compare::Side { items: popup.official.items(taxonomy), raw: &popup.official.raw_tables[taxonomy.index()] };
+        let custom = compare::Side { items: self.items(), raw: &self.raw_tables[taxonomy.index()] };
+        let merged = compare::merge(taxonomy, &official, &custom, &popup.changes);
+        let written = merged.and_then(|text| std::fs::write(&path, text).with_context(|| format!("cannot write {}", path)));
+        match written {
+            Result::Ok(()) => {
+                tracing::info!(taxonomy = taxonomy.key(), path, "exported merged taxonomy");
+                self.report.record_export(format!("Merged {} to {}", taxonomy.name(), path));
+                self.notify(ToastLevel::Success, format!("Saved the merged {} to {}", taxonomy.name(), path));
+            }
+            Err(err) => self.notify(ToastLevel::Error, format!("Saving the merged file failed: {:#}", err)),
+        }
+    }
+
+    fn handle_compare_key(&mut self, key: KeyEvent) {
+        let Some(popup) = self.compare_popup.as_mut() else {
+            return;
+        };
+        let last = popup.changes.len().saturating_sub(1);
+
+        match key.code {
+            KeyCode::Esc => self.compare_popup = None,
+            KeyCode::Down => popup.selected = (popup.selected + 1).min(last),
+            KeyCode::Up => popup.selected = popup.selected.saturating_sub(1),
+            KeyCode::PageDown => popup.selected = (popup.selected + 10).min(last),
+            KeyCode::PageUp => popup.selected = popup.selected.saturating_sub(10),
+            KeyCode::Char(' ') => {
+                if let Some(change) = popup.changes.get_mut(popup.selected) {
+                    change.accept = !change.accept;
+                }
+            }
+            KeyCode::Char('s') => self.save_merged(),
+            KeyCode::Enter => {
+                // Removed rows are not in the current taxonomy
+                let Some(change) = popup.changes.get(popup.selected) else {
+                    return;
+                };
+                if change.kind != ChangeKind::Removed {
+                    let id = change.id.clone();
+                    self.compare_popup = None;
+                    self.jump_to(&id);
                 }
             }
             _ => {}
         }
+    }
+
+    /// The selected node as handed to plugins
+    #[cfg(feature = "plugins")]
+    fn selected_plugin_node(&self) -> Option<rhai::Map> {
+        let id = self.tree_state.selected().last()?;
+        let position = self.child_index().position(id)?;
+        let items = self.items();
+        Some(plugins::node(self.datasource.taxonomy(), *items.get(position)?))
+    }
+
+    /// In kiosk mode, refuse `action` with a toast; true when it was refused
+    fn kiosk_refuses(&mut self, action: &str) -> bool {
+        if self.kiosk {
+            self.notify(ToastLevel::Warning, format!("{} is disabled in kiosk mode", action));
+        }
+        self.kiosk
+    }
+
+    /// Show plugin errors, or else the last message a plugin passed to `notify`
+    #[cfg(feature = "plugins")]
+    fn notify_plugins(&mut self, results: Vec<Result<()>>) {
+        let messages = self.plugins.take_messages();
+        if let Some(err) = results.into_iter().find_map(Result::err) {
+            tracing::warn!("{:#}", err);
+            self.notify(ToastLevel::Error, format!("{:#}", err));
+        } else if let Some(message) = messages.into_iter().last() {
+            self.notify(ToastLevel::Info, message);
+        }
+    }
+
+    /// Run `on_select` when the selection moved to another node since the last key
+    #[cfg(feature = "plugins")]
+    fn run_select_hook(&mut self) {
+        if self.plugins.is_empty() {
+            return;
+        }
+        let selected = self.tree_state.selected().last().cloned();
+        if selected == self.hooked_selection {
+            return;
+        }
+        self.hooked_selection = selected;
+        if let Some(node) = self.selected_plugin_node() {
+            let results = self.plugins.on_select(node);
+            self.notify_plugins(results);
+        }
+    }
+
+    /// Toast the outdated taxonomies once the startup release check has finished; true when it did
+    fn poll_update_check(&mut self) -> bool {
+        let Some(receiver) = &self.update_check else {
+            return false;
+        };
+        let result = match receiver.try_recv() {
+            Result::Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return false,
+            Err(mpsc::TryRecvError::Disconnected) => Err(anyhow!("the check stopped without a result")),
+        };
+        self.update_check = None;
+        // Being offline is no reason to interrupt browsing, so failures only reach the log
+        let releases = match result {
+            Result::Ok(releases) => releases,
+            Err(err) => {
+                tracing::warn!("update check failed: {:#}", err);
+                return false;
+            }
+        };
+        let outdated: Vec<String> = releases
+            .iter()
+            .filter(|release| release.outdated())
+            .map(|release| format!("{} {} (bundled {})", release.taxonomy.name(), release.latest.as_deref().unwrap_or_default(), release.bundled))
+            .collect();
+        if outdated.is_empty() {
+            tracing::info!("bundled taxonomies are up to date");
+            return false;
+        }
+        self.notify(ToastLevel::Info, format!("Newer IAB releases: {}", outdated.join(", ")));
         true
     }
+
+    #[cfg(feature = "plugins")]
+    fn open_plugin_palette(&mut self) {
+        if self.kiosk_refuses("Running plugins") {
+            return;
+        }
+        if self.plugins.commands().is_empty() {
+            self.notify(ToastLevel::Warning, "No plugin commands; add .rhai scripts to ~/.config/iab/plugins or pass --plugin");
+            return;
+        }
+        self.plugin_palette = Some(0);
+    }
+
+    #[cfg(feature = "plugins")]
+    fn handle_palette_key(&mut self, key: KeyEvent) {
+        let Some(index) = self.plugin_palette else {
+            return;
+        };
+        let commands = self.plugins.commands();
+
+        match key.code {
+            KeyCode::Esc => self.plugin_palette = None,
+            KeyCode::Down => self.plugin_palette = Some((index + 1).min(commands.len().saturating_sub(1))),
+            KeyCode::Up => self.plugin_palette = Some(index.saturating_sub(1)),
+            KeyCode::Enter => {
+                self.plugin_palette = None;
+                let Some(command) = commands.get(index) else {
+                    return;
+                };
+                let Some(node) = self.selected_plugin_node() else {
+                    self.notify(ToastLevel::Warning, "Select a node first");
+                    return;
+                };
+                let result = self.plugins.run(command, node);
+                self.notify_plugins(vec![result]);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_list_key(&mut self, key: KeyEvent) {
+        let Some((list, index)) = self.list_popup else {
+            return;
+        };
+        let ids = self.list_ids(list).to_vec();
+
+        match key.code {
+            KeyCode::Esc => self.list_popup = None,
+            KeyCode::Down => self.list_popup = Some((list, (index + 1).min(ids.len().saturating_sub(1)))),
+            KeyCode::Up => self.list_popup = Some((list, index.saturating_sub(1))),
+            KeyCode::Delete => {
+                if let Some(id) = ids.get(index) {
+                    match list {
+                        ListPopup::Bookmarks => {
+                            self.bookmarks.toggle(self.datasource.key(), id);
+                            let result = self.bookmarks.save();
+                            self.notify_result(result, "Saving bookmarks");
+                        }
+                        ListPopup::Recent => {
+                            self.recent.remove(self.datasource.key(), id);
+                            let result = self.recent.save();
+                            self.notify_result(result, "Saving recent items");
+                        }
+                    }
+                    self.list_popup = Some((list, index.min(ids.len().saturating_sub(2))));
+                }
+            }
+            KeyCode::Enter => {
+                self.list_popup = None;
+                if let Some(id) = ids.get(index) {
+                    self.jump_to(id);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn zoom_in(&mut self) {
+        if let Some(id) = self.tree_state.selected().last() {
+            self.set_zoom(Some(id.to_string()));
+        }
+    }
+
+    fn zoom_out(&mut self) {
+        let Some(root_id) = self.zoom_root.clone() else {
+            return;
+        };
+        let chain = self.ancestor_chain(&root_id);
+        // Step up to the parent of the current root, or leave zoom at the top level
+        let parent = chain.iter().rev().nth(1).map(|(id, _)| id.clone());
+        self.set_zoom(parent);
+        // Keep the previous root selected so zooming out doesn't lose the place
+        let path = self.path_to(&root_id);
+        self.tree_state.select(path);
+    }
+
+    fn set_zoom(&mut self, root: Option<String>) {
+        self.zoom_root = root;
+        self.tree_state = TreeState::default();
+        if let Some(root_id) = &self.zoom_root {
+            let root = NodeId::from(root_id.as_str());
+            self.tree_state.select(vec![root.clone()]);
+            self.tree_state.open(vec![root]);
+        } else {
+            self.tree_state.select_first();
+        }
+        self.expand_filtered_nodes();
+    }
+
+    /// Chain of (ID, name) pairs from the root down to and including `id`
+    fn ancestor_chain(&self, id: &str) -> Vec<(String, String)> {
+        match self.datasource {
+            Datasource::Product => ancestor_chain(&self.products, self.child_index(), id),
+            Datasource::Content => ancestor_chain(&self.content, self.child_index(), id),
+            Datasource::Audience => ancestor_chain(&self.audience, self.child_index(), id),
+        }
+    }
+
+    /// Tree path of `id` in the current (possibly zoomed) tree, opening its ancestors
+    fn path_to(&mut self, id: &str) -> Vec<NodeId> {
+        let index = self.child_index();
+        let Some(position) = index.position(id) else {
+            return Vec::new();
+        };
+        let chain: Vec<usize> = index.ancestors(position).into_iter().chain(std::iter::once(position)).collect();
+        // Orphaned branches hang below the virtual orphans node
+        let orphaned = chain.first().is_some_and(|&top| index.is_orphan(top));
+        let mut path: Vec<NodeId> = orphaned
+            .then(|| NodeId::from(ORPHANS_ID))
+            .into_iter()
+            .chain(chain.into_iter().map(|position| Arc::clone(index.id(position))))
+            .collect();
+        if let Some(root_id) = &self.zoom_root
+            && let Some(start) = path.iter().position(|id| **id == **root_id)
+        {
+            path.drain(..start);
+        }
+        for depth in 1..path.len() {
+            self.tree_state.open(path[..depth].to_vec());
+        }
+        path
+    }
+
+    /// Leave zoom when the filter hides its root, which would otherwise leave the view empty
+    fn unzoom_if_filtered_out(&mut self) {
+        let Some(root_id) = self.zoom_root.clone() else {
+            return;
+        };
+        if !self.filtered_tree_items().is_empty() {
+            return;
+        }
+        self.zoom_root = None;
+        self.notify(ToastLevel::Info, format!("Zoom on {} left: the filter hides it", root_id));
+    }
+
+    fn expand_filtered_nodes(&mut self) {
+        self.unzoom_if_filtered_out();
+        if !self.filter_input.is_empty() {
+            let tree_items = self.filtered_tree_items();
+            let all_paths = collect_all_tree_paths(&tree_items, vec![]);
+            for path in all_paths {
+                self.tree_state.open(path);
+            }
+        }
+    }
+
+    fn show_item_details(&mut self) {
+        // Get the selected item's unique ID from the tree state
+        let selected_path = self.tree_state.selected();
+        let selected_id = match selected_path.last() {
+            Some(id) => id,
+            None => return,
+        };
+
+        let Some(details) = self.item_details(selected_id) else {
+            return;
+        };
+
+        self.popup_content = details;
+        self.popup_raw = self.raw_tables[self.datasource.index()].row(selected_id).unwrap_or_default();
+        self.popup_show_raw = false;
+        self.show_popup = true;
+
+        let name = self.item_name(selected_id).unwrap_or_default().to_string();
+        self.report.record_view(self.datasource.name(), selected_id, &name);
+        self.recent.record(self.datasource.key(), selected_id);
+        let result = self.recent.save();
+        self.notify_result(result, "Saving recent items");
+    }
+
+    fn item_details(&self, id: &str) -> Option<Vec<(String, String)>> {
+        match self.datasource {
+            Datasource::Product => self.products.iter().find(|item| item.unique_id() == id).map(|item| self.format_item_details(item)),
+            Datasource::Content => self.content.iter().find(|item| item.unique_id() == id).map(|item| self.format_item_details(item)),
+            Datasource::Audience => self.audience.iter().find(|item| item.unique_id() == id).map(|item| self.format_item_details(item)),
+        }
+    }
+
+    fn format_item_details<T: TaxonomyItem>(&self, item: &T) -> Vec<(String, String)> {
+        let mut details = vec![
+            (i18n::tr(Text::UniqueId).to_string(), item.unique_id().to_string()),
+            (i18n::tr(Text::ParentId).to_string(), item.parent().unwrap_or("").to_string()),
+        ];
+
+        // Resolve the parent and the full chain above this node
+        let mut ancestors = self.ancestor_chain(item.unique_id());
+        ancestors.pop();
+        if let Some((_, parent_name)) = ancestors.last() {
+            details.push((i18n::tr(Text::ParentName).to_string(), parent_name.clone()));
+        }
+        if !ancestors.is_empty() {
+            let chain: Vec<String> = ancestors
+                .iter()
+                .map(|(id, name)| format!("{} {}", id, name))
+                .collect();
+            details.push((i18n::tr(Text::Ancestors).to_string(), chain.join(" > ")));
+        }
+
+        details.push((i18n::tr(Text::Name).to_string(), item.name().to_string()));
+        if let Some(translation) = &self.translations[self.datasource.taxonomy().index()]
+            && let Some(name) = translation.name(item.unique_id())
+        {
+            details.push((i18n::trf(Text::Translation, &[&translation.label()]), name.to_string()));
+        }
+
+        let tiers = item.tiers();
+        for (i, tier) in tiers.iter().enumerate() {
+            details.push((i18n::trf(Text::Tier, &[&(i + 1)]), tier.to_string()));
+        }
+
+        if let Some(note) = self.notes.get(self.datasource.taxonomy(), item.unique_id()) {
+            details.push((i18n::tr(Text::Note).to_string(), note.to_string()));
+        }
+        let tags = self.tags.get(self.datasource.taxonomy(), item.unique_id());
+        if !tags.is_empty() {
+            details.push((i18n::tr(Text::Tags).to_string(), tags.join(", ")));
+        }
+
+        if let Some((usage, _)) = self.usage()
+            && let Some(position) = self.child_index().position(item.unique_id())
+        {
+            let lines: Vec<String> = usage
+                .metrics()
+                .iter()
+                .enumerate()
+                .map(|(metric, name)| {
+                    let own = usage.own(item.unique_id(), metric).unwrap_or(0.0);
+                    i18n::trf(Text::UsageLine, &[name, &usage::compact(own), &usage::compact(usage.total(position, metric))])
+                })
+                .collect();
+            details.push((i18n::tr(Text::Usage).to_string(), lines.join("; ")));
+        }
+
+        if let Some(ext) = item.extension()
+            && !ext.is_empty()
+        {
+            details.push((i18n::tr(Text::Extension).to_string(), ext.to_string()));
+        }
+
+        // Equivalents in other Content versions, from crosswalks given with --crosswalk
+        if self.datasource == Datasource::Content {
+            let version = Taxonomy::Content.version();
+            for other in self.crosswalks.counterparts(version) {
+                let ids = self.crosswalks.map_category(version, other, item.unique_id()).unwrap_or_default();
+                let value = if ids.is_empty() { i18n::tr(Text::NoEquivalent).to_string() } else { ids.join(", ") };
+                details.push((iab::crosswalk::version_label(other), value));
+            }
+        }
+
+        // Third-party categories from tables given with --table
+        for table in self.mapping_tables.iter().filter(|table| table.taxonomy() == self.datasource.taxonomy()) {
+            let labels: Vec<String> = table.from_iab(item.unique_id()).map(|pair| pair.external_label()).collect();
+            let value = if labels.is_empty() { i18n::tr(Text::NoEquivalent).to_string() } else { labels.join(", ") };
+            details.push((table.system().to_string(), value));
+        }
+
+        details
+    }
+
+    /// Move the selection by `rows` visible items (negative moves up)
+    fn scroll_selection(&mut self, rows: isize) {
+        let step = rows.unsigned_abs().max(1);
+        self.tree_state.select_relative(|current| match current {
+            Some(current) if rows < 0 => current.saturating_sub(step),
+            Some(current) => current.saturating_add(step),
+            None => 0,
+        });
+    }
+
+    /// Write the part of the taxonomy `scope` picks to `path`, returning the number of exported rows
+    fn export(&self, format: ExportFormat, scope: ExportScope, path: &Path) -> Result<usize> {
+        match scope {
+            ExportScope::Visible => self.export_view(format, true, path),
+            ExportScope::Full => self.export_view(format, false, path),
+            ExportScope::Subtree => self.export_subtree(format, path),
+        }
+    }
+
+    /// Write the filtered tree: only the rows on screen with `visible_only`, else every node
+    fn export_view(&self, format: ExportFormat, visible_only: bool, path: &Path) -> Result<usize> {
+        let tree_items = self.filtered_tree_items();
+        let paths: Vec<Vec<NodeId>> = if visible_only {
+            self.tree_state.flatten(&tree_items).into_iter().map(|flattened| flattened.identifier).collect()
+        } else {
+            collect_all_tree_paths(&tree_items, vec![])
+        };
+
+        let rows: Vec<ExportRow> = paths
+            .iter()
+            .filter_map(|path| self.export_row(path.last()?, path.len() - 1))
+            .collect();
+
+        export::write_rows(&rows, format, self.datasource.taxonomy(), path)?;
+        Ok(rows.len())
+    }
+
+    /// Write the selected node with all descendants from the unfiltered taxonomy
+    fn export_subtree(&self, format: ExportFormat, path: &Path) -> Result<usize> {
+        let selected_id = self.tree_state.selected().last().context("no node selected")?;
+        let options = self.tree_options("");
+        let full_tree = match self.datasource {
+            Datasource::Product => build_tree_items(&self.products, self.child_index(), None, &options),
+            Datasource::Content => build_tree_items(&self.content, self.child_index(), None, &options),
+            Datasource::Audience => build_tree_items(&self.audience, self.child_index(), None, &options),
+        };
+        let root = find_tree_item(&full_tree, selected_id).context("selected node not found")?;
+        let node = self.export_node(&root, 0).context("selected node not found")?;
+
+        export::write_tree(std::slice::from_ref(&node), format, self.datasource.taxonomy(), path)?;
+        Ok(node.count())
+    }
+
+    fn export_node(&self, tree_item: &TreeItem<'static, NodeId>, depth: usize) -> Option<ExportNode> {
+        Some(ExportNode {
+            row: self.export_row(tree_item.identifier(), depth)?,
+            children: tree_item
+                .children()
+                .iter()
+                .filter_map(|child| self.export_node(child, depth + 1))
+                .collect(),
+        })
+    }
+
+    fn export_row(&self, id: &str, depth: usize) -> Option<ExportRow> {
+        let position = self.child_index().position(id)?;
+        match self.datasource {
+            Datasource::Product => self.products.get(position).map(|item| ExportRow::from_item(item, depth)),
+            Datasource::Content => self.content.get(position).map(|item| ExportRow::from_item(item, depth)),
+            Datasource::Audience => self.audience.get(position).map(|item| ExportRow::from_item(item, depth)),
+        }
+    }
+
+    fn handle_export_key(&mut self, key: KeyEvent) {
+        let Some(dialog) = self.export_dialog.as_mut() else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc => {
+                self.export_dialog = None;
+            }
+            KeyCode::Enter => {
+                let (format, scope, path) = (dialog.format, dialog.scope, dialog.path.clone());
+                match self.export(format, scope, Path::new(&path)) {
+                    Err(err) => {
+                        tracing::warn!(format = format.name(), scope = scope.name(), path, "export failed: {:#}", err);
+                        if let Some(dialog) = self.export_dialog.as_mut() {
+                            dialog.error = Some(format!("{:#}", err));
+                        }
+                    }
+                    Result::Ok(count) => {
+                        tracing::info!(format = format.name(), scope = scope.name(), path, rows = count, "exported");
+                        self.export_dialog = None;
+                        self.report.record_export(format!(
+                            "{} {} rows ({}, {}) to {}",
+                            self.datasource.name(),
+                            count,
+                            format.name(),
+                            scope.name(),
+                            path
+                        ));
+                        self.notify(ToastLevel::Success, format!("Exported {} rows to {}", count, path));
+                        #[cfg(feature = "plugins")]
+                        {
+                            let export = plugins::export(self.datasource.taxonomy(), format.name(), scope.name(), &path, count);
+                            let results = self.plugins.on_export(export);
+                            self.notify_plugins(results);
+                        }
+                    }
+                }
+            }
+            KeyCode::Down | KeyCode::Tab => dialog.field = dialog.field.next(),
+            KeyCode::Up | KeyCode::BackTab => dialog.field = dialog.field.previous(),
+            KeyCode::Left | KeyCode::Right if dialog.field == ExportField::Format => dialog.cycle_format(),
+            KeyCode::Left | KeyCode::Right if dialog.field == ExportField::Scope => dialog.scope = dialog.scope.next(),
+            KeyCode::Char(c) if dialog.field == ExportField::Path => dialog.path.push(c),
+            KeyCode::Backspace if dialog.field == ExportField::Path => {
+                dialog.path.pop();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_layout_key(&mut self, key: KeyCode) {
+        let message = match key {
+            KeyCode::F(2) => {
+                self.layout.mode = self.layout.mode.next();
+                format!("Layout: {}", self.layout.mode.name())
+            }
+            KeyCode::F(3) => {
+                self.layout.show_details = !self.layout.show_details;
+                format!("Details pane {}", if self.layout.show_details { "shown" } else { "hidden" })
+            }
+            KeyCode::F(4) => {
+                self.layout.show_bookmarks = !self.layout.show_bookmarks;
+                format!("Bookmarks pane {}", if self.layout.show_bookmarks { "shown" } else { "hidden" })
+            }
+            KeyCode::F(5) => {
+                self.layout.resize(-5);
+                format!("Tree pane {}%", self.layout.split_percent)
+            }
+            KeyCode::F(6) => {
+                self.layout.resize(5);
+                format!("Tree pane {}%", self.layout.split_percent)
+            }
+            _ => return,
+        };
+        self.notify(ToastLevel::Info, message);
+        let result = self.layout.save();
+        self.notify_result(result, "Saving layout");
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if self.export_dialog.is_some() {
+            if key.code == KeyCode::Char('q') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return false;
+            }
+            self.handle_export_key(key);
+            return true;
+        }
+
+        if self.classify.is_some() {
+            if key.code == KeyCode::Char('q') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return false;
+            }
+            self.handle_classify_key(key);
+            return true;
+        }
+
+        if self.annotation_editor.is_some() {
+            if key.code == KeyCode::Char('q') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return false;
+            }
+            self.handle_annotation_key(key);
+            return true;
+        }
+
+        if self.list_popup.is_some() {
+            if key.code == KeyCode::Char('q') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return false;
+            }
+            self.handle_list_key(key);
+            return true;
+        }
+
+        if self.compare_popup.is_some() {
+            if key.code == KeyCode::Char('q') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return false;
+            }
+            self.handle_compare_key(key);
+            return true;
+        }
+
+        if self.changelog_popup.is_some() {
+            if key.code == KeyCode::Char('q') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return false;
+            }
+            self.handle_changelog_key(key);
+            return true;
+        }
+
+        if self.basket_popup.is_some() {
+            if key.code == KeyCode::Char('q') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return false;
+            }
+            self.handle_basket_key(key);
+            return true;
+        }
+
+        #[cfg(feature = "plugins")]
+        if self.plugin_palette.is_some() {
+            if key.code == KeyCode::Char('q') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                return false;
+            }
+            self.handle_palette_key(key);
+            return true;
+        }
+
+        if self.show_legend {
+            match key.code {
+                KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+                KeyCode::Esc | KeyCode::Enter | KeyCode::F(1) => self.show_legend = false,
+                _ => {}
+            }
+            return true;
+        }
+
+        if let Some(channel) = self.ortb2_popup {
+            match key.code {
+                KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+                KeyCode::Esc | KeyCode::Enter | KeyCode::F(7) => self.ortb2_popup = None,
+                KeyCode::Tab | KeyCode::BackTab => {
+                    self.ortb2_popup = Some(match channel {
+                        Channel::Site => Channel::App,
+                        Channel::App => Channel::Site,
+                    });
+                }
+                _ => {}
+            }
+            return true;
+        }
+
+        if let Some(scroll) = self.diagnostics_popup {
+            match key.code {
+                KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+                KeyCode::Esc | KeyCode::Enter | KeyCode::F(8) => self.diagnostics_popup = None,
+                KeyCode::Up => self.diagnostics_popup = Some(scroll.saturating_sub(1)),
+                KeyCode::Down => self.diagnostics_popup = Some(scroll.saturating_add(1)),
+                KeyCode::PageUp => self.diagnostics_popup = Some(scroll.saturating_sub(self.viewport_height as u16)),
+                KeyCode::PageDown => self.diagnostics_popup = Some(scroll.saturating_add(self.viewport_height as u16)),
+                _ => {}
+            }
+            return true;
+        }
+
+        // Handle popup-specific keys first
+        if self.show_popup {
+            match key.code {
+                KeyCode::Esc | KeyCode::Enter => {
+                    self.show_popup = false;
+                    return true;
+                }
+                KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    return false;
+                }
+                KeyCode::Char('r') => {
+                    self.popup_show_raw = !self.popup_show_raw;
+                    return true;
+                }
+                KeyCode::Char('n') => {
+                    self.open_annotation_editor(Annotation::Note);
+                    return true;
+                }
+                KeyCode::Char('t') => {
+                    self.open_annotation_editor(Annotation::Tags);
+                    return true;
+                }
+                _ => return true,
+            }
+        }
+
+        // Handle normal navigation
+        let key = self.keymap.get(&config::binding(&key)).copied().unwrap_or(key);
+        match key.code {
+            KeyCode::Esc => return false,
+            KeyCode::Char('q') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            #[cfg(feature = "plugins")]
+            KeyCode::Enter if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.open_plugin_palette();
+            }
+            KeyCode::Enter => {
+                self.show_item_details();
+            }
+            // Most terminals report Shift+Tab as BackTab; enhanced protocols may send Tab + SHIFT
+            KeyCode::BackTab => {
+                self.switch_datasource(self.datasource.previous());
+            }
+            KeyCode::Tab => {
+                if key.modifiers.contains(KeyModifiers::SHIFT) {
+                    self.switch_datasource(self.datasource.previous());
+                } else {
+                    self.switch_datasource(self.datasource.next());
+                }
+            }
+            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll_selection((self.viewport_height / 2) as isize);
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.scroll_selection(-((self.viewport_height / 2) as isize));
+            }
+            KeyCode::Char('e' | 't') if key.modifiers.contains(KeyModifiers::CONTROL) && self.kiosk_refuses("Exporting") => {}
+            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.export_dialog = Some(ExportDialog::new(self.datasource, ExportScope::Visible, None, self.export_format));
+            }
+            KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let selected_id = self.tree_state.selected().last().map(|id| id.as_ref());
+                self.export_dialog = Some(ExportDialog::new(self.datasource, ExportScope::Subtree, selected_id, self.export_format));
+            }
+            KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.toggle_bookmark();
+            }
+            KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if self.previous_files.iter().any(|file| file.taxonomy == self.datasource.taxonomy()) {
+                    self.open_changelog();
+                } else {
+                    self.open_compare();
+                }
+            }
+            KeyCode::Char('y') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.toggle_basket();
+            }
+            KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.list_popup = Some((ListPopup::Bookmarks, 0));
+            }
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.list_popup = Some((ListPopup::Recent, 0));
+            }
+            KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_sibling(true);
+            }
+            KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_sibling(false);
+            }
+            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.select_parent();
+            }
+            KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.show_siblings = !self.show_siblings;
+            }
+            KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.zoom_in();
+            }
+            KeyCode::Char('x') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.zoom_out();
+            }
+            KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.depth_colors = !self.depth_colors;
+                let state = if self.depth_colors { "on" } else { "off" };
+                self.notify(ToastLevel::Info, format!("Depth colors {}", state));
+            }
+            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.hide_deprecated = !self.hide_deprecated;
+                let state = if self.hide_deprecated { "hidden" } else { "shown (dimmed)" };
+                self.notify(ToastLevel::Info, format!("Deprecated nodes {}", state));
+            }
+            KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.toggle_mapping();
+            }
+            KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.classify = Some(ClassifyPrompt::default());
+            }
+            KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.toggle_translation();
+            }
+            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.cycle_sort();
+                self.notify(ToastLevel::Info, format!("Sort: {}", self.sort_label()));
+            }
+            // Alt+letter jumps between siblings, plain letters keep feeding the filter
+            KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.jump_to_sibling_starting_with(c);
+            }
+            KeyCode::Char(c) => {
+                self.filter_input.push(c);
+                self.tree_state = TreeState::default();
+                self.tree_state.select_first();
+                self.expand_filtered_nodes();
+            }
+            KeyCode::Backspace => {
+                self.filter_input.pop();
+                self.tree_state = TreeState::default();
+                self.tree_state.select_first();
+                self.expand_filtered_nodes();
+            }
+            KeyCode::Down if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.select_mapping_entry(true);
+            }
+            KeyCode::Up if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.select_mapping_entry(false);
+            }
+            KeyCode::Down => {
+                self.tree_state.key_down();
+            }
+            KeyCode::Up => {
+                self.tree_state.key_up();
+            }
+            KeyCode::Left => {
+                // Scroll back before collapsing
+                if self.scroll_x > 0 {
+                    self.scroll_x = self.scroll_x.saturating_sub(HORIZONTAL_SCROLL_STEP);
+                } else {
+                    self.tree_state.key_left();
+                }
+            }
+            KeyCode::Right => {
+                // At the edge (leaf or already open) scroll long labels instead
+                let expanded = self.tree_state.key_right();
+                if !expanded && self.selected_label_overflows() {
+                    self.scroll_x += HORIZONTAL_SCROLL_STEP;
+                }
+            }
+            KeyCode::PageDown => {
+                self.scroll_selection(self.viewport_height as isize);
+            }
+            KeyCode::PageUp => {
+                self.scroll_selection(-(self.viewport_height as isize));
+            }
+            KeyCode::F(1) => {
+                self.show_legend = true;
+            }
+            KeyCode::F(7) => {
+                self.ortb2_popup = Some(Channel::Site);
+            }
+            KeyCode::F(8) => {
+                self.diagnostics_popup = Some(0);
+            }
+            KeyCode::F(9) => {
+                self.open_annotation_editor(Annotation::Note);
+            }
+            KeyCode::F(10) => {
+                self.open_annotation_editor(Annotation::Tags);
+            }
+            KeyCode::F(11) => {
+                self.basket_popup = Some(BasketPopup { selected: 0, format: BasketFormat::Csv });
+            }
+            KeyCode::F(12) => {
+                self.open_annotation_editor(Annotation::Child);
+            }
+            KeyCode::Delete => {
+                self.remove_custom_node();
+            }
+            KeyCode::F(2..=6) => {
+                self.handle_layout_key(key.code);
+            }
+            KeyCode::Home => {
+                self.tree_state.select_first();
+            }
+            KeyCode::End => {
+                self.tree_state.select_last();
+            }
+            _ => {}
+        }
+        true
+    }
+}
+
+// Tree building helpers
+/// Tree of the nodes in `included` (all when `None`), walking the child index from the roots
+fn build_tree_items<T: TaxonomyItem>(
+    items: &[T],
+    index: &ChildIndex,
+    included: Option<&NodeSet>,
+    options: &TreeOptions,
+) -> Vec<TreeItem<'static, NodeId>> {
+    let mut tree = build_tree_recursive(items, index, included, index.roots(), 0, options);
+
+    // Rows with an unknown parent would otherwise vanish; collect them under a virtual branch
+    let orphans = build_tree_recursive(items, index, included, index.orphans(), 1, options);
+    if !orphans.is_empty() {
+        let label = Line::from(vec![
+            Span::styled("⚠ Orphans", Style::default().fg(Color::Yellow).bold()),
+            Span::styled(format!(" ({}, parent not found)", orphans.len()), Style::default().fg(Color::DarkGray)),
+        ]);
+        let label = Line::from(skip_columns(label.spans, options.scroll_x));
+        tree.extend(TreeItem::new(NodeId::from(ORPHANS_ID), label, orphans).ok());
+    }
+    unique_siblings(tree, &options.collisions)
+}
+
+/// Siblings without those repeating an earlier identifier, which `TreeItem::new` and `Tree::new`
+/// refuse; the child index keeps duplicate IDs out already, so this only guards the widget
+fn unique_siblings(items: Vec<TreeItem<'static, NodeId>>, collisions: &Cell<usize>) -> Vec<TreeItem<'static, NodeId>> {
+    let count = items.len();
+    let mut seen = HashSet::with_capacity(count);
+    let unique: Vec<_> = items.into_iter().filter(|item| seen.insert(Arc::clone(item.identifier()))).collect();
+    collisions.set(collisions.get() + count - unique.len());
+    unique
+}
+
+fn build_tree_recursive<T: TaxonomyItem>(
+    items: &[T],
+    index: &ChildIndex,
+    included: Option<&NodeSet>,
+    positions: impl Iterator<Item = usize>,
+    depth: usize,
+    options: &TreeOptions,
+) -> Vec<TreeItem<'static, NodeId>> {
+    // Hidden deprecated nodes take their descendants with them
+    let mut children: Vec<(usize, &T)> = positions
+        .filter(|&position| included.is_none_or(|included| included.contains(position)))
+        .map(|position| (position, &items[position]))
+        .filter(|(_, item)| !(options.hide_deprecated && item.badge() == Some(Badge::Deprecated)))
+        .collect();
+
+    // Order siblings; stable sort keeps file order for ties
+    match (options.sort_mode, options.usage) {
+        (SortMode::FileOrder, _) | (SortMode::Usage, None) => {}
+        (SortMode::Usage, Some((usage, metric))) => {
+            children.sort_by(|(a, _), (b, _)| usage.total(*b, metric).total_cmp(&usage.total(*a, metric)));
+        }
+        (SortMode::Name, _) if let Some(translation) = options.translation => {
+            children.sort_by_cached_key(|(_, item)| translation.name(item.unique_id()).unwrap_or(item.name()).to_lowercase());
+        }
+        (sort_mode, _) => children.sort_by(|(_, a), (_, b)| sort_mode.compare(*a, *b)),
+    }
+
+    children.into_iter().filter_map(|(position, item)| {
+        let id = index.id(position);
+        let node_children = build_tree_recursive(items, index, included, index.children(position), depth + 1, options);
+        let node_children = unique_siblings(node_children, &options.collisions);
+
+        // Format: [bold ID] name with highlighted matches
+        let mut display_spans = Vec::new();
+        if options.bookmarks.iter().any(|bookmark| **bookmark == **id) {
+            display_spans.push(Span::styled("★ ", Style::default().fg(Color::Yellow)));
+        }
+        if options.notes.is_some_and(|notes| notes.contains_key(&**id)) {
+            display_spans.push(Span::styled("✎ ", Style::default().fg(Color::Cyan)));
+        }
+        for tag in options.tags.and_then(|tags| tags.get(&**id)).into_iter().flatten() {
+            display_spans.push(Span::styled("● ", Style::default().fg(tags::color(tag))));
+        }
+        // Add highlighted ID spans with bold style
+        for span in highlight_match(id, options.filter) {
+            display_spans.push(span.bold());
+        }
+        display_spans.push(Span::raw(" "));
+        // Add highlighted name spans
+        let name = options.translation.and_then(|translation| translation.name(item.unique_id())).unwrap_or(item.name());
+        let name_spans = highlight_match(name, options.filter);
+        match options.depth_palette.filter(|palette| !palette.is_empty()) {
+            Some(palette) => {
+                let color = palette[depth % palette.len()];
+                // Leave filter highlights untouched, tint the rest
+                display_spans.extend(name_spans.into_iter().map(|span| {
+                    if span.style == Style::default() { span.fg(color) } else { span }
+                }));
+            }
+            None => display_spans.extend(name_spans),
+        }
+        if let Some(badge) = item.badge() {
+            display_spans.push(Span::raw(" "));
+            display_spans.push(Span::styled(badge.label(), Style::default().fg(badge_color(badge))));
+        }
+        if let Some((usage, metric)) = options.usage
+            && let Some(heat) = usage.heat(position, metric)
+        {
+            display_spans.push(Span::styled(format!("  {}", usage::compact(usage.total(position, metric))), Style::default().fg(heat)));
+        }
+        let mut display_text = Line::from(skip_columns(display_spans, options.scroll_x));
+        if item.badge() == Some(Badge::Deprecated) {
+            display_text = display_text.patch_style(Style::default().add_modifier(Modifier::DIM));
+        }
+
+        TreeItem::new(Arc::clone(id), display_text, node_children).ok()
+    }).collect()
+}
+
+fn find_tree_item(items: &[TreeItem<'static, NodeId>], id: &str) -> Option<TreeItem<'static, NodeId>> {
+    for item in items {
+        if **item.identifier() == *id {
+            return Some(item.clone());
+        }
+        if let Some(found) = find_tree_item(item.children(), id) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// Identifiers of the nodes sharing a parent with the node at `path`
+fn sibling_ids(items: &[TreeItem<'static, NodeId>], path: &[NodeId]) -> Vec<NodeId> {
+    let mut level = items;
+    for id in path.iter().take(path.len().saturating_sub(1)) {
+        match level.iter().find(|item| item.identifier() == id) {
+            Some(item) => level = item.children(),
+            None => return vec![],
+        }
+    }
+    level.iter().map(|item| item.identifier().clone()).collect()
+}
+
+/// Overlay │ ├ └ depth guides onto the indentation the tree widget leaves blank
+fn draw_tree_guides(
+    buf: &mut Buffer,
+    area: Rect,
+    items: &[TreeItem<'static, NodeId>],
+    visible: &[Flattened<'_, NodeId>],
+    offset: usize,
+) {
+    let guide_style = Style::default().fg(Color::DarkGray);
+
+    for (row, flattened) in visible.iter().skip(offset).take(area.height as usize).enumerate() {
+        let path = &flattened.identifier;
+        let y = area.y + row as u16;
+
+        for level in 1..path.len() {
+            let x = area.x + ((level - 1) * 2) as u16;
+            if x + 2 > area.right() {
+                break;
+            }
+            let is_last = sibling_ids(items, &path[..=level]).last() == Some(&path[level]);
+            let guide = match (level == path.len() - 1, is_last) {
+                (true, true) => "└─",
+                (true, false) => "├─",
+                (false, true) => "  ",
+                (false, false) => "│ ",
+            };
+            buf.set_string(x, y, guide, guide_style);
+        }
+    }
+}
+
+fn ancestor_chain<T: TaxonomyItem>(items: &[T], index: &ChildIndex, id: &str) -> Vec<(String, String)> {
+    let Some(position) = index.position(id) else {
+        return Vec::new();
+    };
+    index
+        .ancestors(position)
+        .into_iter()
+        .chain(std::iter::once(position))
+        .map(|position| (items[position].unique_id().to_string(), items[position].name().to_string()))
+        .collect()
+}
+
+/// Tree path as plain strings, for the session file
+fn path_strings(path: &[NodeId]) -> Vec<String> {
+    path.iter().map(|id| id.to_string()).collect()
+}
+
+/// Tree path from the session file
+fn node_path(path: Vec<String>) -> Vec<NodeId> {
+    path.into_iter().map(NodeId::from).collect()
+}
+
+/// Drop the first `skip` characters across `spans`, keeping each span's style
+fn skip_columns(spans: Vec<Span<'static>>, mut skip: usize) -> Vec<Span<'static>> {
+    if skip == 0 {
+        return spans;
+    }
+    spans
+        .into_iter()
+        .filter_map(|span| {
+            let len = span.content.chars().count();
+            if skip >= len {
+                skip -= len;
+                return None;
+            }
+            let rest: String = span.content.chars().skip(skip).collect();
+            skip = 0;
+            Some(Span::styled(rest, span.style))
+        })
+        .collect()
+}
+
+/// Nodes in the tree, not counting the virtual orphans branch
+fn count_tree_items(items: &[TreeItem<NodeId>]) -> usize {
+    items.iter().map(|item| {
+        usize::from(**item.identifier() != *ORPHANS_ID) + count_tree_items(item.children())
+    }).sum()
+}
+
+fn collect_all_tree_paths(items: &[TreeItem<NodeId>], current_path: Vec<NodeId>) -> Vec<Vec<NodeId>> {
+    let mut paths = Vec::new();
+    for item in items {
+        let mut path = current_path.clone();
+        path.push(item.identifier().clone());
+        paths.push(path.clone());
+        // Recursively collect child paths
+        paths.extend(collect_all_tree_paths(item.children(), path));
+    }
+    paths
+}
+
+fn highlight_match(text: &str, filter: &str) -> Vec<Span<'static>> {
+    let Some((start, end)) = match_range(text, filter) else {
+        return vec![Span::raw(text.to_string())];
+    };
+
+    let mut spans = Vec::new();
+    if start > 0 {
+        spans.push(Span::raw(text[..start].to_string()));
+    }
+    spans.push(Span::styled(
+        text[start..end].to_string(),
+        Style::default().fg(Color::Black).bg(Color::Yellow)
+    ));
+    if end < text.len() {
+        spans.push(Span::raw(text[end..].to_string()));
+    }
+    spans
+}
+
+/// Split `tag:<name>` terms off a lowercased filter, returning the remaining text and the tags a
+/// node must all carry
+fn split_tag_filter(filter_lower: &str) -> (String, Vec<&str>) {
+    let mut tags = Vec::new();
+    let mut text = Vec::new();
+    for word in filter_lower.split(' ') {
+        match word.strip_prefix("tag:") {
+            Some(tag) => tags.extend((!tag.is_empty()).then_some(tag)),
+            None => text.push(word),
+        }
+    }
+    if tags.is_empty() {
+        return (filter_lower.to_string(), tags);
+    }
+    (text.join(" ").trim().to_string(), tags)
+}
+
+/// Byte range of the first case-insensitive match of `filter` in `text`, widened to whole
+/// characters. Lowercasing can change byte lengths ("İ" becomes two characters), so the match is
+/// found in the lowercased text and mapped back through the original character boundaries.
+fn match_range(text: &str, filter: &str) -> Option<(usize, usize)> {
+    if filter.is_empty() {
+        return None;
+    }
+    let filter_lower = filter.to_lowercase();
+
+    let mut text_lower = String::with_capacity(text.len());
+    // Original character range behind every byte of the lowercased text
+    let mut origins = Vec::with_capacity(text.len());
+    for (position, character) in text.char_indices() {
+        text_lower.extend(character.to_lowercase());
+        origins.resize(text_lower.len(), (position, position + character.len_utf8()));
+    }
+
+    let found = text_lower.find(&filter_lower)?;
+    Some((origins[found].0, origins[found + filter_lower.len() - 1].1))
+}
+
+// TUI rendering
+fn ui(frame: &mut Frame, app: &mut App) {
+    draw_screen(frame, app);
+    app.theme.apply(frame.buffer_mut());
+}
+
+fn draw_screen(frame: &mut Frame, app: &mut App) {
+    let area = frame.area();
+
+    if app.toast.as_ref().is_some_and(Toast::is_expired) {
+        app.toast = None;
+    }
+    if app.plain {
+        render_plain(frame, app);
+        return;
+    }
+
+    // Split into sections: header, filter, list, help
+    let layout = Layout::vertical([
+        Constraint::Length(3), // Header with datasource tabs
+        Constraint::Length(3), // Filter input
+        Constraint::Min(0),     // List
+        Constraint::Length(1),  // Help bar
+    ]);
+    let chunks: [Rect; 4] = area.layout(&layout);
+
+    // Header with datasource tabs, naming the OpenRTB identifier of the current one
+    let taxonomy = app.datasource.taxonomy();
+    let tabs = Tabs::new(vec!["Product", "Content", "Audience"])
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(i18n::tr(Text::Datasource))
+                .title(Line::from(format!(" {} {} · cattax {} ", taxonomy.name(), taxonomy.version(), taxonomy.cattax())).right_aligned()),
+        )
+        .select(app.datasource.index())
+        .style(Style::default().fg(Color::Gray))
+        .highlight_style(Style::default().fg(app.datasource.color()).bold())
+        .divider("|");
+
+    frame.render_widget(tabs, chunks[0]);
+
+    // Filter input
+    let filter_text = if app.filter_input.is_empty() {
+        i18n::tr(Text::FilterPlaceholder).to_string()
+    } else {
+        app.filter_input.clone()
+    };
+
+    let filter = Paragraph::new(filter_text)
+        .style(Style::default().fg(Color::White))
+        .block(Block::default().borders(Borders::ALL).title(i18n::tr(Text::Filter)));
+
+    frame.render_widget(filter, chunks[1]);
+
+    // Tree of filtered items
+    let tree_items = app.filtered_tree_items();
+    let total_count = count_tree_items(&tree_items);
+
+    let mut title = i18n::trf(Text::Results, &[&total_count, &app.sort_label()]);
+    if let Some(root_id) = &app.zoom_root {
+        let breadcrumb: Vec<String> = app
+            .ancestor_chain(root_id)
+            .into_iter()
+            .map(|(id, name)| format!("{} {}", id, name))
+            .collect();
+        title = format!("{} | {}", title, i18n::trf(Text::Zoom, &[&breadcrumb.join(" > ")]));
+    }
+    let collisions = app.tree_collisions.get();
+    if collisions > 0 {
+        title = format!("{} | {}", title, i18n::trf(Text::HiddenDuplicates, &[&collisions]));
+    }
+
+    let tree_block = Block::default().borders(Borders::ALL).title(title);
+    // Siblings are deduplicated while building, so this only fails if that ever breaks
+    let tree = Tree::new(&tree_items).map(|tree| {
+        tree.block(tree_block.clone())
+            .highlight_style(
+                Style::default()
+                    .fg(app.datasource.bright_color())
+                    .bg(Color::Rgb(30, 30, 30))
+                    .bold()
+            )
+            .node_closed_symbol("▶ ")
+            .node_open_symbol("▼ ")
+            .node_no_children_symbol("  ")
+    });
+
+    // Wide layout: tree left, details right, bookmarks below the tree
+    let mut main_area = chunks[2];
+    let mut details_area = None;
+    let mut bookmarks_area = None;
+    let mut basket_area = None;
+    if app.layout.is_wide(area.width) {
+        if app.layout.show_details {
+            let split = app.layout.split_percent;
+            let [left, right] = main_area.layout(&Layout::horizontal([
+                Constraint::Percentage(split),
+                Constraint::Percentage(100 - split),
+            ]));
+            main_area = left;
+            details_area = Some(right);
+            // The basket sits below the details once it holds something
+            if !app.basket.is_empty() {
+                let height = (app.basket.entries().len() as u16 + 2).min(right.height / 3);
+                let [details, basket] = right.layout(&Layout::vertical([Constraint::Min(0), Constraint::Length(height)]));
+                details_area = Some(details);
+                basket_area = Some(basket);
+            }
+        }
+        if app.layout.show_bookmarks {
+            let [top, bottom] = main_area.layout(&Layout::vertical([Constraint::Min(0), Constraint::Length(8)]));
+            main_area = top;
+            bookmarks_area = Some(bottom);
+        }
+    }
+
+    // Mapping workspace: internal categories left of the tree
+    if app.mapping.is_some() {
+        let [mapping_area, rest] =
+            main_area.layout(&Layout::horizontal([Constraint::Percentage(30), Constraint::Min(0)]));
+        render_mapping_pane(frame, app, mapping_area);
+        main_area = rest;
+    }
+
+    // Optional siblings panel to the right of the tree
+    let (tree_area, siblings_area) = if app.show_siblings {
+        let [tree_area, siblings_area] =
+            main_area.layout(&Layout::horizontal([Constraint::Min(0), Constraint::Percentage(30)]));
+        (tree_area, Some(siblings_area))
+    } else {
+        (main_area, None)
+    };
+
+    match tree {
+        Result::Ok(tree) => frame.render_stateful_widget(tree, tree_area, &mut app.tree_state),
+        Err(err) => frame.render_widget(
+            Paragraph::new(i18n::trf(Text::TreeError, &[&err])).style(Style::default().fg(Color::Red)).block(tree_block),
+            tree_area,
+        ),
+    }
+    // Rows the widget just rendered, shared by the guides and the scrollbar
+    let visible = app.tree_state.flatten(&tree_items);
+    draw_tree_guides(frame.buffer_mut(), tree_area.inner(Margin::new(1, 1)), &tree_items, &visible, app.tree_state.get_offset());
+
+    if let Some(siblings_area) = siblings_area {
+        render_siblings(frame, app, &tree_items, siblings_area);
+    }
+    if let Some(details_area) = details_area {
+        render_details_pane(frame, app, details_area);
+    }
+    if let Some(bookmarks_area) = bookmarks_area {
+        render_bookmarks_pane(frame, app, bookmarks_area);
+    }
+
+    if let Some(basket_area) = basket_area {
+        render_basket_pane(frame, app, basket_area);
+    }
+
+    // Render scrollbar
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(Some("↑"))
+        .end_symbol(Some("↓"))
+        .thumb_symbol("█")
+        .track_symbol(Some("│"))
+        .thumb_style(Style::default().fg(app.datasource.color()))
+        .track_style(Style::default().fg(Color::DarkGray));
+
+    let viewport_height = tree_area.height.saturating_sub(2) as usize; // Subtract borders
+    app.viewport_height = viewport_height;
+    app.viewport_width = tree_area.width.saturating_sub(2) as usize;
+
+    // Use what the tree widget actually rendered: visible rows and its scroll offset
+    let mut scrollbar_state = ScrollbarState::new(visible.len().saturating_sub(viewport_height))
+        .viewport_content_length(viewport_height)
+        .position(app.tree_state.get_offset());
+
+    frame.render_stateful_widget(scrollbar, tree_area, &mut scrollbar_state);
+
+    // Help bar, replaced by the current toast message while it is shown
+    let help_text = if app.show_popup {
+        i18n::tr(Text::HelpDetails).to_string()
+    } else {
+        let mut help = i18n::tr(Text::HelpBrowse).to_string();
+        #[cfg(feature = "plugins")]
+        help.push_str(i18n::tr(Text::HelpPlugins));
+        help.push_str(i18n::tr(Text::HelpQuit));
+        help
+    };
+    let help = match &app.toast {
+        Some(toast) => Paragraph::new(toast.message.as_str()).style(Style::default().fg(toast.level.color()).bold()),
+        None => Paragraph::new(help_text).style(Style::default().fg(Color::DarkGray)),
+    };
+
+    frame.render_widget(help, chunks[3]);
+
+    // Render popup if active
+    if app.show_popup {
+        render_popup(frame, app);
+    }
+
+    render_overlays(frame, app);
+}
+
+/// Popups and dialogs other than the details, shared by the tree view and the plain list
+fn render_overlays(frame: &mut Frame, app: &App) {
+    if let Some((list, index)) = app.list_popup {
+        render_list_popup(frame, app, list, index);
+    }
+
+    if let Some(dialog) = &app.export_dialog {
+        render_export_dialog(frame, app.datasource, dialog);
+    }
+
+    if app.show_legend {
+        render_legend(frame, app);
+    }
+
+    if let Some(channel) = app.ortb2_popup {
+        render_ortb2_popup(frame, app, channel);
+    }
+
+    if let Some(scroll) = app.diagnostics_popup {
+        render_diagnostics_popup(frame, app, scroll);
+    }
+
+    if let Some(prompt) = &app.classify {
+        render_classify_prompt(frame, app, prompt);
+    }
+
+    if let Some(popup) = &app.compare_popup {
+        render_compare_popup(frame, app, popup);
+    }
+
+    if let Some(popup) = &app.changelog_popup {
+        render_changelog_popup(frame, app, popup);
+    }
+
+    if let Some(popup) = app.basket_popup {
+        render_basket_popup(frame, app, popup);
+    }
+
+    #[cfg(feature = "plugins")]
+    if let Some(selected) = app.plugin_palette {
+        render_plugin_palette(frame, app, selected);
+    }
+
+    if let Some(editor) = &app.annotation_editor {
+        render_annotation_editor(frame, app, editor);
+    }
+}
+
+#[cfg(feature = "plugins")]
+fn render_plugin_palette(frame: &mut Frame, app: &App, selected: usize) {
+    let commands = app.plugins.commands();
+    let popup_area = Rect::centered(frame.area(), Constraint::Percentage(50), Constraint::Length(commands.len() as u16 + 2));
+
+    frame.render_widget(Clear, popup_area);
+
+    let lines: Vec<Line> = commands
+        .iter()
+        .enumerate()
+        .map(|(i, command)| {
+            let style = if i == selected {
+                Style::default().fg(app.datasource.bright_color()).bg(Color::Rgb(30, 30, 30)).bold()
+            } else {
+                Style::default().fg(Color::White)
+            };
+            Line::styled(command.label.clone(), style)
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(i18n::tr(Text::PaletteTitle))
+            .title_bottom(i18n::tr(Text::PaletteKeys))
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::Black).fg(app.datasource.color())),
+    );
+
+    frame.render_widget(paragraph, popup_area);
+}
+
+fn render_siblings(frame: &mut Frame, app: &App, tree_items: &[TreeItem<'static, NodeId>], area: Rect) {
+    let selected = app.tree_state.selected();
+    let siblings = sibling_ids(tree_items, selected);
+
+    let lines: Vec<Line> = siblings
+        .iter()
+        .map(|id| {
+            let text = format!("{} {}", id, app.display_name(id).unwrap_or(""));
+            if Some(id) == selected.last() {
+                Line::from(Span::styled(text, Style::default().fg(app.datasource.bright_color()).bold()))
+            } else {
+                Line::from(Span::styled(text, Style::default().fg(Color::Gray)))
+            }
+        })
+        .collect();
+
+    // Keep the current sibling in view
+    let inner_height = area.height.saturating_sub(2) as usize;
+    let position = siblings.iter().position(|id| Some(id) == selected.last()).unwrap_or(0);
+    let scroll = position.saturating_sub(inner_height.saturating_sub(1)) as u16;
+
+    let panel = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(i18n::trf(Text::Siblings, &[&siblings.len()])))
+        .scroll((scroll, 0));
+
+    frame.render_widget(panel, area);
+}
+
+fn detail_lines(details: &[(String, String)], label_color: Color) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    for (label, value) in details {
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("{}: ", label),
+                Style::default().fg(label_color).bold(),
+            ),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!("  {}", value),
+                Style::default().fg(Color::White),
+            ),
+        ]));
+        lines.push(Line::from("")); // Empty line for spacing
+    }
+    lines
+}
+
+/// Live details of the selected node, shown beside the tree in the wide layout
+fn render_details_pane(frame: &mut Frame, app: &App, area: Rect) {
+    let details = app
+        .tree_state
+        .selected()
+        .last()
+        .and_then(|id| app.item_details(id))
+        .unwrap_or_default();
+
+    let paragraph = Paragraph::new(detail_lines(&details, app.datasource.color()))
+        .block(Block::default().borders(Borders::ALL).title(i18n::tr(Text::Details)))
+        .wrap(ratatui::widgets::Wrap { trim: false });
+
+    frame.render_widget(paragraph, area);
+}
+
+fn render_bookmarks_pane(frame: &mut Frame, app: &App, area: Rect) {
+    let selected = app.tree_state.selected().last();
+    let ids = app.bookmarks.ids(app.datasource.key());
+    let lines: Vec<Line> = ids
+        .iter()
+        .map(|id| {
+            let text = format!("★ {} {}", id, app.display_name(id).unwrap_or(i18n::tr(Text::Unknown)));
+            if selected.is_some_and(|selected| **selected == **id) {
+                Line::from(Span::styled(text, Style::default().fg(app.datasource.bright_color()).bold()))
+            } else {
+                Line::from(Span::styled(text, Style::default().fg(Color::Gray)))
+            }
+        })
+        .collect();
+
+    let panel = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(i18n::trf(Text::BookmarksPane, &[&ids.len()])));
+
+    frame.render_widget(panel, area);
+}
+
+fn render_mapping_pane(frame: &mut Frame, app: &App, area: Rect) {
+    let Some(mapping) = &app.mapping else {
+        return;
+    };
+    let lines: Vec<Line> = mapping
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let text = match (&entry.id, &entry.name) {
+                (Some(id), name) => format!("✓ {} → {} {}", entry.internal, id, name.as_deref().unwrap_or("")),
+                (None, _) => format!("· {}", entry.internal),
+            };
+            let style = if i == mapping.selected {
+                Style::default().fg(app.datasource.bright_color()).bg(Color::Rgb(30, 30, 30)).bold()
+            } else if entry.is_mapped() {
+                Style::default().fg(Color::Gray)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            Line::from(Span::styled(text, style))
+        })
+        .collect();
+
+    let inner_height = area.height.saturating_sub(2) as usize;
+    let scroll = mapping.selected.saturating_sub(inner_height.saturating_sub(1)) as u16;
+
+    let panel = Paragraph::new(lines).scroll((scroll, 0)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(i18n::trf(Text::MappingTitle, &[&mapping.file_name(), &mapping.mapped_count(), &mapping.entries.len()]))
+            .title_bottom(i18n::tr(Text::MappingKeys)),
+    );
+
+    frame.render_widget(panel, area);
+}
+
+fn render_popup(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+
+    // Create centered popup (60% width, 80% height)
+    let popup_area = Rect::centered(area, Constraint::Percentage(60), Constraint::Percentage(80));
+
+    // Clear the background
+    frame.render_widget(Clear, popup_area);
+
+    // Render the popup block
+    let block = Block::default()
+        .title(if app.popup_show_raw {
+            i18n::trf(Text::RawRecordTitle, &[&app.datasource.name()])
+        } else {
+            i18n::trf(Text::DetailsTitle, &[&app.datasource.name()])
+        })
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::Black).fg(app.datasource.color()));
+
+    frame.render_widget(block, popup_area);
+
+    // Render the content
+    let inner_area = Rect {
+        x: popup_area.x + 2,
+        y: popup_area.y + 2,
+        width: popup_area.width.saturating_sub(4),
+        height: popup_area.height.saturating_sub(3),
+    };
+
+    // The raw view lists every TSV column verbatim, including unmodelled ones
+    let content = if app.popup_show_raw { &app.popup_raw } else { &app.popup_content };
+    let lines = detail_lines(content, app.datasource.color());
+
+    let paragraph = Paragraph::new(lines)
+        .style(Style::default().bg(Color::Black))
+        .wrap(ratatui::widgets::Wrap { trim: false });
+
+    frame.render_widget(paragraph, inner_area);
+}
+
+fn render_legend(frame: &mut Frame, app: &App) {
+    let popup_area = Rect::centered(frame.area(), Constraint::Length(62), Constraint::Length(21));
+
+    frame.render_widget(Clear, popup_area);
+
+    let heading = |text: &'static str| Line::from(Span::styled(text, Style::default().fg(Color::White).bold()));
+    let entry = |symbol: Span<'static>, text: &'static str| {
+        Line::from(vec![Span::raw("  "), symbol, Span::raw("  "), Span::styled(text, Style::default().fg(Color::Gray))])
+    };
+
+    let mut lines = vec![heading(i18n::tr(Text::LegendTabs))];
+    for datasource in [Datasource::Product, Datasource::Content, Datasource::Audience] {
+        lines.push(entry(
+            Span::styled(format!("{:<12}", datasource.name()), Style::default().fg(datasource.color()).bold()),
+            i18n::tr(Text::LegendTab),
+        ));
+    }
+
+    lines.push(Line::raw(""));
+    lines.push(heading(i18n::tr(Text::LegendBadges)));
+    lines.push(entry(Span::styled(format!("{:<12}", "★"), Style::default().fg(Color::Yellow)), i18n::tr(Text::LegendBookmark)));
+    lines.push(entry(Span::styled(format!("{:<12}", "✎"), Style::default().fg(Color::Cyan)), i18n::tr(Text::LegendNote)));
+    lines.push(entry(Span::styled(format!("{:<12}", "●"), Style::default().fg(tags::color("tag"))), i18n::tr(Text::LegendTag)));
+    for badge in [Badge::Deprecated, Badge::Extension] {
+        let description = match badge {
+            Badge::Deprecated => i18n::tr(Text::LegendDeprecated),
+            Badge::Extension => i18n::tr(Text::LegendExtension),
+        };
+        lines.push(entry(Span::styled(format!("{:<12}", badge.label()), Style::default().fg(badge_color(badge))), description));
+    }
+
+    lines.push(Line::raw(""));
+    lines.push(heading(i18n::tr(Text::LegendTree)));
+    let symbol = |text: &'static str| Span::styled(format!("{:<12}", text), Style::default().fg(Color::DarkGray));
+    lines.push(entry(symbol("▶ / ▼"), i18n::tr(Text::LegendExpand)));
+    lines.push(entry(symbol("├─ └─ │"), i18n::tr(Text::LegendGuides)));
+    lines.push(entry(
+        Span::styled(format!("{:<12}", i18n::tr(Text::LegendMatchSample)), Style::default().fg(Color::Black).bg(Color::Yellow)),
+        i18n::tr(Text::LegendMatch),
+    ));
+    lines.push(entry(symbol("↑ █ ↓"), i18n::tr(Text::LegendScrollbar)));
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(i18n::tr(Text::Legend))
+            .title_bottom(i18n::tr(Text::LegendKeys))
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::Black).fg(app.datasource.color())),
+    );
+
+    frame.render_widget(paragraph, popup_area);
+}
+
+fn render_classify_prompt(frame: &mut Frame, app: &App, prompt: &ClassifyPrompt) {
+    let popup_area = Rect::centered(frame.area(), Constraint::Percentage(70), Constraint::Length(CLASSIFY_LIMIT as u16 + 6));
+
+    frame.render_widget(Clear, popup_area);
+
+    let mut lines = vec![
+        Line::from(vec![
+            Span::styled(i18n::tr(Text::ClassifyLabel), Style::default().fg(app.datasource.color()).bold()),
+            Span::styled(format!("{}▏", prompt.text), Style::default().fg(Color::White)),
+        ]),
+        Line::raw(""),
+    ];
+    let suggestions = app.classify_suggestions(&prompt.text);
+    if suggestions.is_empty() {
+        let hint = i18n::tr(if prompt.text.is_empty() { Text::ClassifyHint } else { Text::ClassifyNone });
+        lines.push(Line::from(Span::styled(hint, Style::default().fg(Color::DarkGray))));
+    }
+    for (i, (id, path, score)) in suggestions.iter().enumerate() {
+        let text = format!("{:>4.2}  {} {}", score, id, path);
+        let style = if i == prompt.selected {
+            Style::default().fg(app.datasource.bright_color()).bg(Color::Rgb(30, 30, 30)).bold()
+        } else {
+            Style::default().fg(Color::White)
+        };
+        lines.push(Line::from(Span::styled(text, style)));
+    }
+
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .title(i18n::trf(Text::ClassifyTitle, &[&app.datasource.name()]))
+            .title_bottom(i18n::tr(Text::ClassifyKeys))
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::Black).fg(app.datasource.color())),
+    );
+
+    frame.render_widget(paragraph, popup_area);
+}
+
+/// Basket entries as `taxonomy ID name` lines, highlighting `selected`
+fn basket_lines(app: &App, selected: Option<usize>) -> Vec<Line<'static>> {
+    let rows = app.basket_rows();
+    app.basket
+        .entries()
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let name = rows
+                .iter()
+                .find(|(_, row)| row.id == entry.id && row.taxonomy == entry.taxonomy)
+                .map_or(i18n::tr(Text::Unknown), |(_, row)| row.name.as_str());
+            let color = entry.taxonomy().map_or(Color::Gray, |taxonomy| Datasource::from(taxonomy).color());
+            let style = if Some(i) == selected {
+                Style::default().fg(app.datasource.bright_color()).bg(Color::Rgb(30, 30, 30)).bold()
+            } else {
+                Style::default().fg(Color::White)
+            };
+            Line::from(vec![
+                Span::styled(format!("{:<9}", entry.taxonomy), Style::default().fg(color)),
+                Span::styled(format!("{} {}", entry.id, name), style),
+            ])
+        })
+        .collect()
+}
+
+fn render_basket_pane(frame: &mut Frame, app: &App, area: Rect) {
+    let panel = Paragraph::new(basket_lines(app, None)).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(i18n::trf(Text::BasketPane, &[&app.basket.entries().len()])),
+    );
+
+    frame.render_widget(panel, area);
+}
+
+fn render_basket_popup(frame: &mut Frame, app: &App, popup: BasketPopup) {
+    let popup_area = Rect::centered(frame.area(), Constraint::Percentage(70), Constraint::Percentage(60));
+
+    frame.render_widget(Clear, popup_area);
+
+    let mut lines = basket_lines(app, Some(popup.selected));
+    if lines.is_empty() {
+        lines.push(Line::styled(i18n::tr(Text::BasketEmpty), Style::default().fg(Color::DarkGray)));
+    }
+    // Keep the highlighted entry in view
+    let inner_height = popup_area.height.saturating_sub(2) as usize;
+    let scroll = popup.selected.saturating_sub(inner_height.saturating_sub(1)) as u16;
+
+    let paragraph = Paragraph::new(lines).scroll((scroll, 0)).block(
+        Block::default()
+            .title(i18n::trf(Text::BasketTitle, &[&app.basket.entries().len(), &popup.format.name()]))
+            .title_bottom(i18n::tr(Text::BasketKeys))
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::Black).fg(app.datasource.color())),
+    );
+
+    frame.render_widget(paragraph, popup_area);
+}
+
+fn render_compare_popup(frame: &mut Frame, app: &App, popup: &ComparePopup) {
+    let popup_area = Rect::centered(frame.area(), Constraint::Percentage(80), Constraint::Percentage(70));
+
+    frame.render_widget(Clear, popup_area);
+
+    let mut lines = Vec::new();
+    let mut selected_line = 0;
+    for (i, change) in popup.changes.iter().enumerate() {
+        let (color, decision) = match (&change.kind, change.accept) {
+            (ChangeKind::Added, true) => (Color::Green, Text::CompareAdd),
+            (ChangeKind::Added, false) => (Color::Green, Text::CompareSkip),
+            (ChangeKind::Removed, true) => (Color::Red, Text::CompareDrop),
+            (ChangeKind::Removed, false) => (Color::Red, Text::CompareKeepOfficial),
+            (ChangeKind::Conflict(_), true) => (Color::Yellow, Text::CompareUseOurs),
+            (ChangeKind::Conflict(_), false) => (Color::Yellow, Text::CompareKeepOfficial),
+        };
+        let style = if i == popup.selected {
+            Style::default().fg(app.datasource.bright_color()).bg(Color::Rgb(30, 30, 30)).bold()
+        } else {
+            Style::default().fg(Color::White)
+        };
+        if i == popup.selected {
+            selected_line = lines.len();
+        }
+        lines.push(Line::from(vec![
+            Span::styled(format!("{} ", change.symbol()), Style::default().fg(color).bold()),
+            Span::styled(format!("{} {}", change.id, change.name), style),
+            Span::styled(format!("  → {}", i18n::tr(decision)), Style::default().fg(if change.accept { color } else { Color::DarkGray })),
+        ]));
+        if let ChangeKind::Conflict(fields) = &change.kind {
+            for (field, official, ours) in fields {
+                lines.push(Line::from(vec![
+                    Span::styled(format!("    {}: ", field), Style::default().fg(Color::DarkGray)),
+                    Span::styled(format!("\"{}\"", official), Style::default().fg(Color::Gray)),
+                    Span::raw(" → "),
+                    Span::styled(format!("\"{}\"", ours), Style::default().fg(Color::White)),
+                ]));
+            }
+        }
+    }
+    // Keep the highlighted change in view
+    let inner_height = popup_area.height.saturating_sub(2) as usize;
+    let scroll = selected_line.saturating_sub(inner_height.saturating_sub(1)) as u16;
+
+    let count = |kind: fn(&ChangeKind) -> bool| popup.changes.iter().filter(|change| kind(&change.kind)).count();
+    let taxonomy = app.datasource.taxonomy();
+    let paragraph = Paragraph::new(lines).scroll((scroll, 0)).block(
+        Block::default()
+            .title(i18n::trf(
+                Text::CompareTitle,
+                &[
+                    &taxonomy.name(),
+                    &taxonomy.version(),
+                    &count(|kind| *kind == ChangeKind::Added),
+                    &count(|kind| *kind == ChangeKind::Removed),
+                    &count(|kind| matches!(kind, ChangeKind::Conflict(_))),
+                ],
+            ))
+            .title_bottom(i18n::trf(Text::CompareKeys, &[&format!("iab-{}-merged.tsv", taxonomy.key())]))
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::Black).fg(app.datasource.color())),
+    );
+
+    frame.render_widget(paragraph, popup_area);
+}
+
+fn render_changelog_popup(frame: &mut Frame, app: &App, popup: &ChangelogPopup) {
+    let popup_area = Rect::centered(frame.area(), Constraint::Percentage(80), Constraint::Percentage(70));
+
+    frame.render_widget(Clear, popup_area);
+
+    let count = |filter: ChangeFilter| popup.changes.iter().filter(|change| filter.matches(change)).count();
+    let taxonomy = app.datasource.taxonomy();
+    let block = Block::default()
+        .title(i18n::trf(
+            Text::ChangelogTitle,
+            &[
+                &popup.label,
+                &taxonomy.name(),
+                &taxonomy.version(),
+                &count(ChangeFilter::Added),
+                &count(ChangeFilter::Removed),
+                &count(ChangeFilter::Renamed),
+                &count(ChangeFilter::Moved),
+            ],
+        ))
+        .title_bottom(i18n::trf(Text::ChangelogKeys, &[&popup.filter.name()]))
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::Black).fg(app.datasource.color()));
+
+    // Tree::new only fails on repeated IDs, which building the items leaves out
+    match Tree::new(&popup.items).ok().filter(|_| !popup.items.is_empty()) {
+        Some(tree) => {
+            let tree = tree
+                .block(block)
+                .highlight_style(Style::default().fg(app.datasource.bright_color()).bg(Color::Rgb(30, 30, 30)).bold())
+                .node_closed_symbol("▶ ")
+                .node_open_symbol("▼ ")
+                .node_no_children_symbol("  ");
+            frame.render_stateful_widget(tree, popup_area, &mut popup.state.borrow_mut());
+        }
+        None => frame.render_widget(Paragraph::new(i18n::tr(Text::ChangelogEmpty)).block(block), popup_area),
+    }
+}
+
+/// Changes passing `filter` under their unchanged ancestors, in the order of `nodes`; each node hangs
+/// below its parent in the version it is in, so removals appear where they used to be
+fn build_changelog_tree(changes: &[NodeChange], nodes: &[ChangelogNode], filter: ChangeFilter) -> Vec<TreeItem<'static, NodeId>> {
+    let by_id: HashMap<&str, &ChangelogNode> = nodes.iter().rev().map(|node| (&*node.id, node)).collect();
+    let changes: HashMap<&str, &NodeChange> = changes.iter().filter(|change| filter.matches(change)).map(|change| (change.id.as_str(), change)).collect();
+
+    // Every change and its ancestors; the seen set also ends parent cycles
+    let mut shown = HashSet::new();
+    for id in changes.keys() {
+        let mut current = Some(*id);
+        while let Some(id) = current
+            && shown.insert(id)
+        {
+            current = by_id.get(id).and_then(|node| node.parent.as_deref()).filter(|parent| by_id.contains_key(parent));
+        }
+    }
+
+    let mut children: HashMap<Option<&str>, Vec<&ChangelogNode>> = HashMap::new();
+    for node in nodes.iter().filter(|node| shown.contains(&*node.id)) {
+        // Files with repeated IDs list a node twice; keep the first, as the widget refuses duplicates
+        if by_id.get(&*node.id).is_some_and(|first| !std::ptr::eq(*first, node)) {
+            continue;
+        }
+        let parent = node.parent.as_deref().filter(|parent| shown.contains(parent));
+        children.entry(parent).or_default().push(node);
+    }
+
+    // Nodes on a parent cycle hang below each other only, so they are never reached from the top
+    fn build(
+        parent: Option<&str>,
+        children: &HashMap<Option<&str>, Vec<&ChangelogNode>>,
+        changes: &HashMap<&str, &NodeChange>,
+        by_id: &HashMap<&str, &ChangelogNode>,
+    ) -> Vec<TreeItem<'static, NodeId>> {
+        let Some(nodes) = children.get(&parent) else {
+            return Vec::new();
+        };
+        nodes
+            .iter()
+            .filter_map(|node| {
+                let label = changelog_label(node, changes.get(&*node.id).copied(), by_id);
+                TreeItem::new(Arc::clone(&node.id), label, build(Some(&node.id), children, changes, by_id)).ok()
+            })
+            .collect()
+    }
+    build(None, &children, &changes, &by_id)
+}
+
+fn changelog_label(node: &ChangelogNode, change: Option<&NodeChange>, by_id: &HashMap<&str, &ChangelogNode>) -> Line<'static> {
+    let Some(change) = change else {
+        return Line::styled(format!("{} {}", node.id, node.name), Style::default().fg(Color::DarkGray));
+    };
+    let (symbol, color) = match change.revision {
+        Revision::Added => ("+", Color::Green),
+        Revision::Removed => ("-", Color::Red),
+        Revision::Changed { .. } => ("~", Color::Yellow),
+    };
+    let mut spans = vec![
+        Span::styled(format!("{} ", symbol), Style::default().fg(color).bold()),
+        Span::styled(format!("{} {}", node.id, node.name), Style::default().fg(Color::White)),
+    ];
+    if let Revision::Changed { old_name, old_parent } = &change.revision {
+        if let Some(old_name) = old_name {
+            spans.push(Span::styled(format!("  {}", i18n::trf(Text::ChangelogRenamed, &[old_name])), Style::default().fg(color)));
+        }
+        if let Some(old_parent) = old_parent {
+            let from = match old_parent {
+                Some(parent) => match by_id.get(parent.as_str()) {
+                    Some(parent) => format!("{} {}", parent.id, parent.name),
+                    None => parent.clone(),
+                },
+                None => i18n::tr(Text::ChangelogTopLevel).to_string(),
+            };
+            spans.push(Span::styled(format!("  {}", i18n::trf(Text::ChangelogMoved, &[&from])), Style::default().fg(color)));
+        }
+    }
+    Line::from(spans)
+}
+
+fn render_annotation_editor(frame: &mut Frame, app: &App, editor: &AnnotationEditor) {
+    let popup_area = Rect::centered(frame.area(), Constraint::Percentage(60), Constraint::Length(8));
+
+    frame.render_widget(Clear, popup_area);
+
+    let text = Line::from(Span::styled(format!("{}▏", editor.text), Style::default().fg(Color::White)));
+    let paragraph = Paragraph::new(text).wrap(ratatui::widgets::Wrap { trim: false }).block(
+        Block::default()
+            .title(i18n::trf(
+                match editor.annotation {
+                    Annotation::Note => Text::NoteTitle,
+                    Annotation::Tags => Text::TagsTitle,
+                    Annotation::Child => Text::ChildTitle,
+                },
+                &[&editor.id, &app.display_name(&editor.id).unwrap_or_default()],
+            ))
+            .title_bottom(i18n::tr(match editor.annotation {
+                Annotation::Note => Text::NoteKeys,
+                Annotation::Tags => Text::TagsKeys,
+                Annotation::Child => Text::ChildKeys,
+            }))
+            .borders(Borders::ALL)
+            .style(Style::default().bg(Color::Black).fg(app.datasource.color())),
+    );
+
+    frame.render_widget(paragraph, popup_area);
+}
+
+fn render_diagnostics_popup(frame: &mut Frame, app: &App, scroll: u16) {
+    let popup_area = Rect::centered(frame.area(), Constraint::Percentage(70), Constraint::Percentage(60));
+
+    frame.render_widget(Clear, popup_area);
+
+    let findings = &app.diagnostics[app.datasource.index()];
+    let parse_warnings = app.parse_warnings[app.datasource.index()].iter().map(|warning| {
+        Line::from(vec![Span::styled("warning   ", Style::default().fg(Color::Yellow).bold()), Span::raw(warning.as_str())])
+    });
+    let lines: Vec<Line> = if findings.is_empty() {
+        parse_warnings.chain([Line::styled(i18n::tr(Text::DiagnosticsNone), Style::default().fg(Color::Green))]).collect()
+    } else {
+        parse_warnings
+            .chain(findings.iter().map(|finding| {
+                let (kind, color) = match finding.issue {
+                    Issue::Orphan { .. } => ("orphan    ", Color::Yellow),
+                    Issue::Cycle { .. } => ("cycle     ", Color::Red),
+                    Issue::Duplicate { .. } => ("duplicate ", Color::Red),
+                    Issue::EmptyName => ("name      ", Color::Red),
+                    Issue::TierDepth { .. } | Issue::TierName { .. } => ("tiers     ", Color::Yellow),
+                    Issue::DeprecatedParent { .. } => ("deprecated", Color::Yellow),
+                };
+                Line::from(vec![Span::styled(kind, Style::default().fg(color).bold()), Span::raw(finding.to_string())])
+            }))
+            .collect()
+    };
+    let max_scroll = lines.len().saturating_sub(1) as u16;
+
+    let paragraph = Paragraph::new(lines)
+        .style(Style::default().fg(Color::White))
+        .wrap(ratatui::widgets::Wrap { trim: false })
+        .scroll((scroll.min(max_scroll), 0))
+        .block(
+            Block::default()
+                .title(i18n::trf(Text::DiagnosticsTitle, &[&app.datasource.name(), &app.datasource.taxonomy().version(), &findings.len()]))
+                .title_bottom(i18n::tr(Text::DiagnosticsKeys))
+                .borders(Borders::ALL)
+                .style(Style::default().bg(Color::Black).fg(app.datasource.color())),
+        );
+
+    frame.render_widget(paragraph, popup_area);
+}
+
+fn render_ortb2_popup(frame: &mut Frame, app: &App, channel: Channel) {
+    let popup_area = Rect::centered(frame.area(), Constraint::Percentage(50), Constraint::Percentage(60));
+
+    frame.render_widget(Clear, popup_area);
+
+    let paragraph = match app.ortb2_snippet(channel) {
+        Result::Ok(snippet) => Paragraph::new(snippet).style(Style::default().fg(Color::White)),
+        Err(err) => Paragraph::new(format!("{:#}", err)).style(Style::default().fg(Color::Red)),
+    };
+    let source = i18n::tr(if app.bookmarks.ids(app.datasource.key()).is_empty() { Text::Ortb2SelectedNode } else { Text::Ortb2Bookmarks });
+    let (target, keys) = match app.datasource {
+        Datasource::Audience => ("user.data".to_string(), i18n::tr(Text::Ortb2AudienceKeys)),
+        _ => (format!("{}.content", channel.key()), i18n::tr(Text::Ortb2Keys)),
+    };
+    let paragraph = paragraph
+        .block(
+            Block::default()
+                .title(format!(" ortb2 {} · {} ", target, source))
+                .title_bottom(keys)
+                .borders(Borders::ALL)
+                .style(Style::default().bg(Color::Black).fg(app.datasource.color())),
+        )
+        .wrap(ratatui::widgets::Wrap { trim: false });
+
+    frame.render_widget(paragraph, popup_area);
 }
 
-// Tree building helpers
-fn build_tree_items<T: TaxonomyItem>(items: &[T], filter: &str) -> Vec<TreeItem<'static, String>> {
-    let mut children_map: HashMap<Option<String>, Vec<&T>> = HashMap::new();
+fn render_list_popup(frame: &mut Frame, app: &App, list: ListPopup, selected: usize) {
+    let popup_area = Rect::centered(frame.area(), Constraint::Percentage(60), Constraint::Percentage(60));
 
-    // Group items by parent
-    for item in items {
-        // Treat self-references as root nodes
-        let parent_key = match item.parent() {
-            Some(p) if p == item.unique_id() => None,
-            Some(p) => Some(p.to_string()),
-            None => None,
-        };
-        children_map.entry(parent_key).or_default().push(item);
+    frame.render_widget(Clear, popup_area);
+
+    let ids = app.list_ids(list);
+    let mut lines: Vec<Line> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| {
+            let text = format!("{} {} {}", list.marker(), id, app.display_name(id).unwrap_or(i18n::tr(Text::Unknown)));
+            if i == selected {
+                Line::from(Span::styled(text, Style::default().fg(app.datasource.bright_color()).bg(Color::Rgb(30, 30, 30)).bold()))
+            } else {
+                Line::from(Span::styled(text, Style::default().fg(Color::White)))
+            }
+        })
+        .collect();
+    if lines.is_empty() {
+        lines.push(Line::from(Span::styled(list.empty_message(), Style::default().fg(Color::DarkGray))));
     }
 
-    // Build tree starting from root nodes (no parent)
-    build_tree_recursive(&children_map, None, filter)
+    let inner_height = popup_area.height.saturating_sub(2) as usize;
+    let scroll = selected.saturating_sub(inner_height.saturating_sub(1)) as u16;
+
+    let paragraph = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .title(format!(" {} {} ", app.datasource.name(), list.title()))
+                .title_bottom(i18n::tr(Text::ListKeys))
+                .borders(Borders::ALL)
+                .style(Style::default().bg(Color::Black).fg(app.datasource.color())),
+        )
+        .scroll((scroll, 0));
+
+    frame.render_widget(paragraph, popup_area);
 }
 
-fn build_tree_recursive<'a, T: TaxonomyItem>(
-    children_map: &HashMap<Option<String>, Vec<&'a T>>,
-    parent_id: Option<String>,
-    filter: &str,
-) -> Vec<TreeItem<'static, String>> {
-    let children = match children_map.get(&parent_id) {
-        Some(children) => children,
-        None => return vec![],
-    };
+fn render_export_dialog(frame: &mut Frame, datasource: Datasource, dialog: &ExportDialog) {
+    let popup_area = Rect::centered(frame.area(), Constraint::Percentage(60), Constraint::Length(10));
 
-    children.iter().map(|item| {
-        let id = item.unique_id().to_string();
-        let name = item.name().to_string();
-        let node_children = build_tree_recursive(children_map, Some(id.clone()), filter);
+    frame.render_widget(Clear, popup_area);
 
-        // Format: [bold ID] name with highlighted matches
-        let mut display_spans = Vec::new();
-        // Add highlighted ID spans with bold style
-        for span in highlight_match(&id, filter) {
-            display_spans.push(Span::styled(span.content.to_string(), span.style.bold()));
-        }
-        display_spans.push(Span::raw(" "));
-        // Add highlighted name spans
-        display_spans.extend(highlight_match(&name, filter));
-        let display_text = Line::from(display_spans);
+    let block = Block::default()
+        .title(i18n::trf(Text::ExportTitle, &[&datasource.name()]))
+        .borders(Borders::ALL)
+        .style(Style::default().bg(Color::Black).fg(datasource.color()));
 
-        TreeItem::new(id.clone(), display_text, node_children)
-            .expect("Failed to create tree item")
-    }).collect()
-}
+    let field_line = |field: ExportField, label: &str, value: String| {
+        let marker = if dialog.field == field { "> " } else { "  " };
+        let value_style = if dialog.field == field {
+            Style::default().fg(datasource.bright_color()).bold()
+        } else {
+            Style::default().fg(Color::White)
+        };
+        Line::from(vec![
+            Span::styled(format!("{}{:<8}", marker, label), Style::default().fg(datasource.color()).bold()),
+            Span::styled(value, value_style),
+        ])
+    };
 
-fn count_tree_items(items: &[TreeItem<String>]) -> usize {
-    items.iter().map(|item| {
-        1 + count_tree_items(item.children())
-    }).sum()
+    let mut lines = vec![
+        field_line(ExportField::Format, i18n::tr(Text::ExportFormat), format!("◀ {} ▶", dialog.format.name())),
+        field_line(ExportField::Scope, i18n::tr(Text::ExportScope), format!("◀ {} ▶", dialog.scope.name())),
+        field_line(ExportField::Path, i18n::tr(Text::ExportPath), dialog.path.clone()),
+        Line::from(""),
+    ];
+    match &dialog.error {
+        Some(error) => lines.push(Line::from(Span::styled(error.clone(), Style::default().fg(Color::LightRed)))),
+        None => lines.push(Line::from(Span::styled(
+            i18n::tr(Text::ExportKeys),
+            Style::default().fg(Color::DarkGray),
+        ))),
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(ratatui::widgets::Wrap { trim: false });
+
+    frame.render_widget(paragraph, popup_area);
 }
 
-fn collect_all_tree_paths(items: &[TreeItem<String>], current_path: Vec<String>) -> Vec<Vec<String>> {
-    let mut paths = Vec::new();
-    for item in items {
-        let mut path = current_path.clone();
-        path.push(item.identifier().clone());
-        paths.push(path.clone());
-        // Recursively collect child paths
-        paths.extend(collect_all_tree_paths(item.children(), path));
+/// Siblings of the last node in `path`, or the top level when the path is empty or stale
+fn level_items<'a>(items: &'a [TreeItem<'static, NodeId>], path: &[NodeId]) -> &'a [TreeItem<'static, NodeId>] {
+    let mut level = items;
+    for id in path.iter().take(path.len().saturating_sub(1)) {
+        match level.iter().find(|item| item.identifier() == id) {
+            Some(item) => level = item.children(),
+            None => break,
+        }
     }
-    paths
+    level
 }
 
-fn highlight_match(text: &str, filter: &str) -> Vec<Span<'static>> {
-    if filter.is_empty() {
-        return vec![Span::raw(text.to_string())];
+/// The plain list mode: the current level as numbered lines of text, every state spelled out
+/// instead of shown by color or symbols, no frames, and the terminal cursor on the selected line
+/// so screen readers announce it
+fn render_plain(frame: &mut Frame, app: &mut App) {
+    let area = frame.area();
+    let tree_items = app.filtered_tree_items();
+
+    // The tree widget still renders, off screen, because the tree state's navigation works on
+    // the rows it last rendered; the keys then behave exactly as in the tree view
+    if let Result::Ok(tree) = Tree::new(&tree_items) {
+        let mut scratch = Buffer::empty(area);
+        StatefulWidget::render(tree, area, &mut scratch, &mut app.tree_state);
     }
+    app.viewport_height = area.height.saturating_sub(6) as usize;
+    app.viewport_width = area.width as usize;
 
-    let text_lower = text.to_lowercase();
-    let filter_lower = filter.to_lowercase();
+    let taxonomy = app.datasource.taxonomy();
+    let selected = app.tree_state.selected().to_vec();
+    let level = level_items(&tree_items, &selected);
+    let position = level.iter().position(|item| Some(item.identifier()) == selected.last());
+
+    let mut lines = vec![
+        Line::from(i18n::trf(Text::PlainHeader, &[&taxonomy.name(), &taxonomy.version(), &taxonomy.cattax(), &app.sort_label()])),
+        Line::from(if app.filter_input.is_empty() {
+            i18n::tr(Text::PlainFilterEmpty).to_string()
+        } else {
+            i18n::trf(Text::PlainFilter, &[&app.filter_input])
+        }),
+    ];
+    let parents: Vec<String> = selected
+        .iter()
+        .take(selected.len().saturating_sub(1))
+        .map(|id| format!("{} {}", id, app.display_name(id).unwrap_or_default()))
+        .collect();
+    let mut level_line = if parents.is_empty() { i18n::tr(Text::PlainTop).to_string() } else { i18n::trf(Text::PlainLevel, &[&parents.join(" > ")]) };
+    if let Some(position) = position {
+        level_line = format!("{}. {}", level_line, i18n::trf(Text::PlainPosition, &[&(position + 1), &level.len()]));
+    }
+    lines.push(Line::from(level_line));
+    lines.push(Line::raw(""));
 
-    // Find match position
-    if let Some(pos) = text_lower.find(&filter_lower) {
-        let mut spans = Vec::new();
-        if pos > 0 {
-            spans.push(Span::raw(text[..pos].to_string()));
+    // Numbered entries, scrolled to keep the selected one in view
+    let header_height = lines.len();
+    let list_height = area.height.saturating_sub(header_height as u16 + 2) as usize;
+    let scroll = position.unwrap_or(0).saturating_sub(list_height.saturating_sub(1));
+    if level.is_empty() {
+        lines.push(Line::from(i18n::tr(Text::PlainEmpty)));
+    }
+    let items = app.items();
+    let tagged = app.tags.of(taxonomy);
+    let bookmarks = app.bookmarks.ids(app.datasource.key());
+    for (i, tree_item) in level.iter().enumerate().skip(scroll).take(list_height) {
+        let id = tree_item.identifier();
+        let mut facts = Vec::new();
+        if !tree_item.children().is_empty() {
+            let mut path = selected[..selected.len().saturating_sub(1)].to_vec();
+            path.push(Arc::clone(id));
+            let state = if app.tree_state.opened().contains(&path) { Text::PlainExpanded } else { Text::PlainCollapsed };
+            let count = match tree_item.children().len() {
+                1 => i18n::tr(Text::PlainChild).to_string(),
+                count => i18n::trf(Text::PlainChildren, &[&count]),
+            };
+            facts.push(format!("{}, {}", count, i18n::tr(state)));
         }
-        let end = pos + filter.len();
-        spans.push(Span::styled(
-            text[pos..end].to_string(),
-            Style::default().fg(Color::Black).bg(Color::Yellow)
-        ));
-        if end < text.len() {
-            spans.push(Span::raw(text[end..].to_string()));
+        if let Some(badge) = app.child_index().position(id).and_then(|position| items.get(position)).and_then(|item| item.badge()) {
+            facts.push(badge.label().trim_matches(['[', ']']).to_string());
         }
-        spans
-    } else {
-        vec![Span::raw(text.to_string())]
+        if bookmarks.iter().any(|bookmark| **bookmark == **id) {
+            facts.push(i18n::tr(Text::PlainBookmarked).to_string());
+        }
+        if app.notes.get(taxonomy, id).is_some() {
+            facts.push(i18n::tr(Text::PlainNoted).to_string());
+        }
+        if let Some(tags) = tagged.and_then(|tagged| tagged.get(&**id)).filter(|tags| !tags.is_empty()) {
+            facts.push(i18n::trf(Text::PlainTagged, &[&tags.join(", ")]));
+        }
+        if let Some((usage, metric)) = app.usage()
+            && let Some(position) = app.child_index().position(id)
+        {
+            facts.push(i18n::trf(Text::PlainUsage, &[&usage::compact(usage.total(position, metric))]));
+        }
+
+        let marker = if Some(i) == position { ">" } else { " " };
+        let mut text = format!("{}{:>3}. {} {}", marker, i + 1, id, app.display_name(id).unwrap_or_default());
+        if !facts.is_empty() {
+            text = format!("{}, {}", text, facts.join(", "));
+        }
+        let style = if Some(i) == position { Style::default().bold() } else { Style::default() };
+        lines.push(Line::styled(text, style));
     }
-}
 
-fn calculate_flat_index(
-    items: &[TreeItem<String>],
-    tree_state: &TreeState<String>,
-    current_path: Vec<String>,
-) -> Option<usize> {
-    let selected = tree_state.selected();
-    let opened = tree_state.opened();
-    let mut index = 0;
+    // The details popup reads as plain lines in place of the list
+    if app.show_popup {
+        let content = if app.popup_show_raw { &app.popup_raw } else { &app.popup_content };
+        lines.truncate(header_height);
+        lines.extend(content.iter().map(|(label, value)| Line::from(format!("{}: {}", label, value))));
+    }
 
-    for item in items {
-        let mut item_path = current_path.clone();
-        item_path.push(item.identifier().clone());
+    let [body, help] = area.layout(&Layout::vertical([Constraint::Min(0), Constraint::Length(1)]));
+    frame.render_widget(Paragraph::new(lines).wrap(ratatui::widgets::Wrap { trim: false }), body);
+    let help_text = match &app.toast {
+        Some(toast) => format!("{}{}", toast.level.prefix(), toast.message),
+        None => i18n::tr(if app.show_popup { Text::PlainDetailsKeys } else { Text::PlainKeys }).to_string(),
+    };
+    frame.render_widget(Paragraph::new(help_text), help);
 
-        // Check if this is the selected item
-        if item_path == selected {
-            return Some(index);
-        }
+    if !app.show_popup
+        && let Some(position) = position
+    {
+        let row = (header_height + position - scroll) as u16;
+        frame.set_cursor_position(Position::new(body.x, body.y + row.min(body.height.saturating_sub(1))));
+    }
+
+    // Other popups keep their frames
+    render_overlays(frame, app);
+}
 
-        index += 1;
+fn run_app(terminal: &mut DefaultTerminal, app: &mut App) -> Result<()> {
+    // Redraw after input, on resize, and while a toast waits to expire; idle ticks draw nothing
+    let mut dirty = true;
+    loop {
+        if dirty || app.toast.is_some() {
+            terminal.draw(|frame| ui(frame, app))?;
+            dirty = false;
+        }
 
-        // If this node is opened, recursively check children
-        if opened.contains(&item_path) {
-            if let Some(child_index) = calculate_flat_index(item.children(), tree_state, item_path) {
-                return Some(index + child_index);
+        if event::poll(std::time::Duration::from_millis(100))? {
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    if !app.handle_key(key) {
+                        return Ok(());
+                    }
+                    #[cfg(feature = "plugins")]
+                    app.run_select_hook();
+                    crash::record_state(app.crash_state(key));
+                    dirty = true;
+                }
+                Event::Resize(..) => dirty = true,
+                _ => {}
             }
-            // Count all visible children
-            index += count_visible_items(item.children(), opened, &current_path, item.identifier());
+        }
+        if app.poll_update_check() {
+            dirty = true;
         }
     }
-
-    None
 }
 
-fn count_visible_items(
-    items: &[TreeItem<String>],
-    opened: &HashSet<Vec<String>>,
-    parent_path: &[String],
-    current_id: &str,
-) -> usize {
-    let mut count = 0;
-    for item in items {
-        count += 1; // Count this item
+/// Terminal browser for the IAB Product, Content and Audience taxonomies
+#[derive(Parser, Debug)]
+#[command(version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
 
-        let mut item_path = parent_path.to_vec();
-        item_path.push(current_id.to_string());
-        item_path.push(item.identifier().clone());
+    /// Read defaults from this TOML file instead of ~/.config/iab/config.toml
+    #[arg(long, value_name = "FILE", global = true)]
+    config: Option<PathBuf>,
 
-        // If opened, count children too
-        if opened.contains(&item_path) {
-            count += count_visible_items(item.children(), opened, &item_path[..item_path.len()-1], item.identifier());
-        }
-    }
-    count
-}
+    /// Append a log of loading, filtering, exports and errors to FILE
+    #[arg(long, value_name = "FILE", global = true)]
+    log_file: Option<PathBuf>,
 
-fn count_visible_tree_items(
-    items: &[TreeItem<String>],
-    tree_state: &TreeState<String>,
-) -> usize {
-    let opened = tree_state.opened();
-    let mut count = 0;
+    /// Log debug events with --log-file, and trace events when repeated; IAB_LOG overrides the level
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
 
-    for item in items {
-        count += 1; // Count this item
+    /// Restore datasource, filter, expansion and selection from the last session
+    #[arg(long)]
+    resume: bool,
 
-        let item_path = vec![item.identifier().clone()];
+    /// Load a Rhai plugin script with hooks and palette commands, in addition to
+    /// ~/.config/iab/plugins/*.rhai and the config's plugins; repeatable
+    #[cfg(feature = "plugins")]
+    #[arg(long, value_name = "FILE", conflicts_with = "kiosk")]
+    plugin: Vec<PathBuf>,
 
-        // If opened, count children too
-        if opened.contains(&item_path) {
-            count += count_visible_items(item.children(), opened, &[], item.identifier());
-        }
-    }
+    /// Screen-reader-friendly mode: the current level as a plain numbered list, with no frames and
+    /// no meaning carried by color alone
+    #[arg(long)]
+    plain: bool,
 
-    count
-}
+    /// Restricted mode for shared screens: no exports, clipboard, editing or plugins, and no state
+    /// read or written. Data files come only from the config file; those given on the command line
+    /// are refused, and --report prints to stdout only
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "resume", "file", "extension", "translation", "previous", "usage", "mapping", "table", "crosswalk",
+            "synonyms", "columns",
+        ]
+    )]
+    kiosk: bool,
 
-// TUI rendering
-fn ui(frame: &mut Frame, app: &mut App) {
-    let area = frame.area();
+    /// Colors for dark or light terminal backgrounds
+    #[arg(long, value_enum)]
+    theme: Option<Theme>,
 
-    // Split into sections: header, filter, list, help
-    let layout = Layout::vertical([
-        Constraint::Length(3), // Header with datasource tabs
-        Constraint::Length(3), // Filter input
-        Constraint::Min(0),     // List
-        Constraint::Length(1),  // Help bar
-    ]);
-    let chunks: [Rect; 4] = area.layout(&layout);
+    /// Start with depth coloring on, using these comma-separated colors (e.g. "blue,green,#ff8800")
+    #[arg(long, value_delimiter = ',')]
+    depth_palette: Option<Vec<Color>>,
 
-    // Header with datasource tabs
-    let tabs = Tabs::new(vec!["Product", "Content", "Audience"])
-        .block(Block::default().borders(Borders::ALL).title("Datasource"))
-        .select(app.datasource.index())
-        .style(Style::default().fg(Color::Gray))
-        .highlight_style(Style::default().fg(app.datasource.color()).bold())
-        .divider("|");
+    /// On quit, print viewed details, bookmark changes and exports to PATH (stdout if omitted)
+    #[arg(long, value_name = "PATH", num_args = 0..=1, default_missing_value = "-")]
+    report: Option<PathBuf>,
 
-    frame.render_widget(tabs, chunks[0]);
+    /// Start on the taxonomy with this OpenRTB cattax value (4 Audience, 7 Content, 8 Product)
+    #[arg(long, value_name = "N", value_parser = parse_cattax, conflicts_with = "segtax")]
+    cattax: Option<Taxonomy>,
 
-    // Filter input
-    let filter_text = if app.filter_input.is_empty() {
-        "Type to filter...".to_string()
-    } else {
-        app.filter_input.clone()
-    };
+    /// Start on the taxonomy with this segtax value (same numbering as cattax)
+    #[arg(long, value_name = "N", value_parser = parse_segtax)]
+    segtax: Option<Taxonomy>,
 
-    let filter = Paragraph::new(filter_text)
-        .style(Style::default().fg(Color::White))
-        .block(Block::default().borders(Borders::ALL).title("Filter"));
+    /// Pair internal category strings with IAB nodes; CSV or JSON with an `internal` column, updated on every assignment
+    #[arg(long, value_name = "FILE")]
+    mapping: Option<PathBuf>,
 
-    frame.render_widget(filter, chunks[1]);
+    /// Load a taxonomy from a TSV file instead of the bundled data, e.g. content=vendor.tsv; repeatable
+    #[arg(long, value_name = "TAXONOMY=FILE", value_parser = TaxonomyFile::parse)]
+    file: Vec<TaxonomyFile>,
 
-    // Tree of filtered items
-    let tree_items = app.filtered_tree_items();
-    let total_count = count_tree_items(&tree_items);
+    /// Add the custom nodes of an overlay TSV written by the extension editor (F12), e.g.
+    /// content=custom.tsv; new custom nodes of that taxonomy are saved back to it
+    #[arg(long, value_name = "TAXONOMY=FILE", value_parser = TaxonomyFile::parse)]
+    extension: Vec<TaxonomyFile>,
 
-    let title = format!("Results ({} items)", total_count);
+    /// Show names from a translation, e.g. an IAB-provided translated taxonomy file or a CSV of id
+    /// and name, e.g. content=content-de.tsv; Ctrl+j switches languages; repeatable
+    #[arg(long, value_name = "TAXONOMY=FILE", value_parser = TaxonomyFile::parse)]
+    translation: Vec<TaxonomyFile>,
 
-    let tree = Tree::new(&tree_items)
-        .expect("Failed to create tree widget")
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(title),
-        )
-        .highlight_style(
-            Style::default()
-                .fg(app.datasource.bright_color())
-                .bg(Color::Rgb(30, 30, 30))
-                .bold()
-        )
-        .node_closed_symbol("▶ ")
-        .node_open_symbol("▼ ")
-        .node_no_children_symbol("  ");
+    /// Earlier version of a taxonomy, e.g. content=content-3.0.tsv; Ctrl+v then lists the nodes
+    /// added, removed, renamed or moved since it; repeatable
+    #[arg(long, value_name = "TAXONOMY=FILE", value_parser = TaxonomyFile::parse)]
+    previous: Vec<TaxonomyFile>,
 
-    frame.render_stateful_widget(tree, chunks[2], &mut app.tree_state);
+    /// Show usage numbers from a CSV with an id column and numeric metric columns (impressions,
+    /// revenue, ...) next to the nodes of a taxonomy, e.g. content=usage.csv; repeatable
+    #[arg(long, value_name = "TAXONOMY=FILE", value_parser = TaxonomyFile::parse)]
+    usage: Vec<TaxonomyFile>,
 
-    // Render scrollbar
-    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-        .begin_symbol(Some("↑"))
-        .end_symbol(Some("↓"))
-        .thumb_symbol("█")
-        .track_symbol(Some("│"))
-        .thumb_style(Style::default().fg(app.datasource.color()))
-        .track_style(Style::default().fg(Color::DarkGray));
+    /// Refuse taxonomy files (--file or the config's) that repeat an ID or have a column matching no expected one
+    #[arg(long)]
+    strict: bool,
 
-    let viewport_height = chunks[2].height.saturating_sub(2) as usize; // Subtract borders
-    let scroll_position = calculate_flat_index(&tree_items, &app.tree_state, vec![]).unwrap_or(0);
-    let visible_count = count_visible_tree_items(&tree_items, &app.tree_state);
+    /// How taxonomy files treat double quotes; `none` reads vendor exports with unescaped quotes
+    #[arg(long, value_name = "MODE", value_enum, default_value_t)]
+    quoting: QuotingArg,
 
-    let mut scrollbar_state = ScrollbarState::default()
-        .content_length(visible_count)
-        .viewport_content_length(viewport_height)
-        .position(scroll_position);
+    /// Header renames for taxonomy files: TSV lines of a header in the file and the column it stands for
+    #[arg(long, value_name = "FILE")]
+    columns: Option<PathBuf>,
 
-    frame.render_stateful_widget(scrollbar, chunks[2], &mut scrollbar_state);
+    /// Synonyms for classification: TSV with a category ID and comma-separated terms per line
+    #[arg(long, value_name = "FILE", global = true)]
+    synonyms: Option<PathBuf>,
 
-    // Help bar
-    let help_text = if app.show_popup {
-        "ESC/Enter: Close | Ctrl-q: Quit"
-    } else {
-        "Tab/Shift+Tab: Switch | ↑↓: Navigate | ←→: Collapse/Expand | Enter: Details | ESC/Ctrl-q: Quit"
-    };
-    let help = Paragraph::new(help_text).style(Style::default().fg(Color::DarkGray));
+    /// Content version crosswalk TSV (source and target ID columns, versions named in the headers); repeatable
+    #[arg(long, value_name = "FILE", global = true)]
+    crosswalk: Vec<PathBuf>,
 
-    frame.render_widget(help, chunks[3]);
+    /// Third-party mapping table, CSV or TSV (external ID, IAB ID, optional external name); repeatable
+    #[arg(long, value_name = "FILE", global = true)]
+    table: Vec<PathBuf>,
 
-    // Render popup if active
-    if app.show_popup {
-        render_popup(frame, app);
-    }
-}
+    /// Run without a terminal: replay --keys/--keys-file off-screen and print the final screen.
+    /// Saved state (session, bookmarks, layout) is neither read nor written
+    #[arg(long)]
+    headless: bool,
 
-fn render_popup(frame: &mut Frame, app: &App) {
-    let area = frame.area();
+    /// Keys for --headless, e.g. "Down Down Right" or "Ctrl+f sports Enter"; repeatable
+    #[arg(long, value_name = "KEYS", requires = "headless")]
+    keys: Vec<String>,
 
-    // Create centered popup (60% width, 80% height)
-    let popup_area = Rect::centered(area, Constraint::Percentage(60), Constraint::Percentage(80));
+    /// Read --headless keys from a file, after those of --keys; `#` starts a comment
+    #[arg(long, value_name = "FILE", requires = "headless")]
+    keys_file: Option<PathBuf>,
 
-    // Clear the background
-    frame.render_widget(Clear, popup_area);
+    /// Screen size for --headless
+    #[arg(long, value_name = "COLSxROWS", default_value = "120x40", value_parser = headless::parse_size)]
+    size: (u16, u16),
 
-    // Render the popup block
-    let block = Block::default()
-        .title(format!(" {} Details ", app.datasource.name()))
-        .borders(Borders::ALL)
-        .style(Style::default().bg(Color::Black).fg(app.datasource.color()));
+    /// With --headless, print the browsing state as JSON instead of the screen
+    #[arg(long, requires = "headless")]
+    state: bool,
+}
 
-    frame.render_widget(block, popup_area);
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check the category fields of an OpenRTB bid request against the declared cattax
+    ValidateBidrequest {
+        /// Bid request JSON file
+        file: PathBuf,
+    },
+    /// Check the taxonomies for duplicate IDs, orphaned rows and parent cycles, failing when any are found
+    Validate {
+        /// Check TSV files in place of the bundled taxonomies, e.g. content=vendor.tsv
+        #[arg(value_name = "TAXONOMY=FILE", value_parser = TaxonomyFile::parse)]
+        files: Vec<TaxonomyFile>,
+        /// How the files treat double quotes; `none` reads vendor exports with unescaped quotes
+        #[arg(long, value_name = "MODE", value_enum, default_value_t)]
+        quoting: QuotingArg,
+        /// Header renames: TSV lines of a header in the file and the column it stands for
+        #[arg(long, value_name = "FILE")]
+        columns: Option<PathBuf>,
+        /// Refuse files with duplicate IDs or unexpected columns
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Run every data-integrity check (validate's plus empty names, tier columns against depth and
+    /// live rows under deprecated parents), failing on errors
+    Lint {
+        /// Check TSV files in place of the bundled taxonomies, e.g. content=vendor.tsv
+        #[arg(value_name = "TAXONOMY=FILE", value_parser = TaxonomyFile::parse)]
+        files: Vec<TaxonomyFile>,
+        /// How the files treat double quotes; `none` reads vendor exports with unescaped quotes
+        #[arg(long, value_name = "MODE", value_enum, default_value_t)]
+        quoting: QuotingArg,
+        /// Header renames: TSV lines of a header in the file and the column it stands for
+        #[arg(long, value_name = "FILE")]
+        columns: Option<PathBuf>,
+        /// Refuse files with duplicate IDs or unexpected columns
+        #[arg(long)]
+        strict: bool,
+        #[arg(long, value_enum, default_value_t = commands::LintFormat::Human)]
+        format: commands::LintFormat,
+        /// Fail on warnings too, e.g. for CI on extension files
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+    /// Print the additions, removals and conflicting edits of a TSV file against the bundled
+    /// release as JSON, as the compare view (Ctrl+v) shows them
+    Diff {
+        /// The file to compare, e.g. content=vendor.tsv
+        #[arg(value_name = "TAXONOMY=FILE", value_parser = TaxonomyFile::parse)]
+        file: TaxonomyFile,
+        /// How the file treats double quotes; `none` reads vendor exports with unescaped quotes
+        #[arg(long, value_name = "MODE", value_enum, default_value_t)]
+        quoting: QuotingArg,
+        /// Header renames: TSV lines of a header in the file and the column it stands for
+        #[arg(long, value_name = "FILE")]
+        columns: Option<PathBuf>,
+    },
+    /// Map Content category IDs between taxonomy versions using the --crosswalk files, or
+    /// between IAB and third-party categories using the --table files when --from/--to are omitted
+    Map {
+        /// Version of the given IDs, or its Content cattax value (e.g. 2.2, or 1 for legacy IAB codes)
+        #[arg(long, requires = "to", value_parser = iab::crosswalk::resolve_version)]
+        from: Option<String>,
+        /// Version to map to, or its Content cattax value (e.g. 3.1, or 7)
+        #[arg(long, requires = "from", value_parser = iab::crosswalk::resolve_version)]
+        to: Option<String>,
+        /// Read IDs from stdin, one per line, and print `ID<TAB>targets` lines with NO_MAPPING
+        /// for IDs without an equivalent
+        #[arg(long, requires = "from", conflicts_with = "ids")]
+        stdin: bool,
+        #[arg(required_unless_present = "stdin")]
+        ids: Vec<String>,
+    },
+    /// Export a taxonomy, or the branch below --root, without starting the TUI
+    Export {
+        #[arg(long, default_value = "content", value_parser = parse_taxonomy)]
+        taxonomy: Taxonomy,
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// ID of the category to start from instead of the whole taxonomy
+        #[arg(long)]
+        root: Option<String>,
+        /// Levels to include below the starting categories
+        #[arg(long)]
+        max_depth: Option<usize>,
+        /// CSV (and flat) field delimiter: one character, or `tab`
+        #[arg(long, value_parser = export::parse_delimiter)]
+        delimiter: Option<u8>,
+        /// CSV columns in order: id, parent, name, depth, tiers, tier1, tier2, ..., path, extension
+        #[arg(long, value_delimiter = ',')]
+        columns: Vec<export::CsvColumn>,
+        /// When CSV fields are quoted
+        #[arg(long, value_enum)]
+        quote: Option<export::CsvQuote>,
+        /// Output file (stdout if omitted); a `.svg` path renders DOT output with GraphViz `dot`
+        output: Option<PathBuf>,
+    },
+    /// Suggest categories for free text, an article title or a URL, best match first
+    Classify {
+        #[arg(long, default_value = "content", value_parser = parse_taxonomy)]
+        taxonomy: Taxonomy,
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        #[arg(required = true)]
+        text: Vec<String>,
+    },
+    /// Generate source code with a constant for every category
+    Codegen {
+        language: codegen::Language,
+        #[arg(long, default_value = "content", value_parser = parse_taxonomy)]
+        taxonomy: Taxonomy,
+        /// Output file (stdout if omitted)
+        output: Option<PathBuf>,
+    },
+    /// Print the JSON Schema of a JSON document iab writes, to validate exported files against
+    Schema {
+        kind: schema::Schema,
+        /// Output file (stdout if omitted)
+        output: Option<PathBuf>,
+    },
+    /// Print an `ortb2` fragment declaring the given categories (or Audience segments), for Prebid's setConfig
+    Ortb2 {
+        #[arg(long, default_value = "content", value_parser = parse_taxonomy)]
+        taxonomy: Taxonomy,
+        /// Category IDs, comma separated or repeated
+        #[arg(long, required = true, value_delimiter = ',')]
+        ids: Vec<String>,
+        /// Put the categories under `app.content` instead of `site.content`
+        #[arg(long)]
+        app: bool,
+        /// Publisher domain naming the `user.data` entry of Audience segments
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Execute a command script (search, select, export, map, ...) without the TUI, printing
+    /// one JSON result per command
+    Run {
+        /// Script file, or `-` for stdin
+        script: PathBuf,
+        /// Carry on after a failing command instead of stopping; still exits non-zero
+        #[arg(long)]
+        keep_going: bool,
+    },
+    /// Compare the bundled taxonomy versions with the latest IAB Tech Lab releases
+    CheckUpdates,
+    /// List, show or clear the state kept between runs: bookmarks, notes, tags, the basket,
+    /// layout, the last session and crash reports
+    State {
+        #[command(subcommand)]
+        action: Option<commands::StateAction>,
+    },
+    /// Run a Model Context Protocol server on stdio for AI assistants
+    Mcp,
+    /// Answer JSON-RPC requests (lookup, search, path, map) one per line on stdio, or on a TCP
+    /// socket with --listen, from one warm process
+    Daemon {
+        /// Address to accept connections on, e.g. 127.0.0.1:7878, instead of stdio
+        #[arg(long, value_name = "HOST:PORT")]
+        listen: Option<String>,
+        /// Speak the Language Server Protocol on stdio, for ID completion and hovers in editors
+        #[arg(long, conflicts_with = "listen")]
+        lsp: bool,
+    },
+    /// Serve the taxonomies as a JSON API over HTTP
+    #[cfg(feature = "server")]
+    Serve {
+        #[arg(long, default_value_t = 8080)]
+        port: u16,
+        /// Address to bind; use 0.0.0.0 to accept remote connections
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        /// Also serve a browser UI (tree and search) at `/`
+        #[arg(long)]
+        ui: bool,
+        /// Serve TSV files in place of the bundled taxonomies, e.g. content=vendor.tsv
+        #[arg(value_name = "TAXONOMY=FILE", value_parser = TaxonomyFile::parse)]
+        files: Vec<TaxonomyFile>,
+        /// Reload the files when they change on disk, without restarting the server
+        #[arg(long, requires = "files")]
+        watch: bool,
+        /// Seconds clients may cache taxonomy responses before revalidating them with their ETag
+        #[arg(long, value_name = "SECONDS", default_value_t = server::DEFAULT_MAX_AGE)]
+        max_age: u32,
+        /// Requests per minute each client address may make, in bursts of up to as many
+        #[arg(long, value_name = "REQUESTS", value_parser = clap::value_parser!(u32).range(1..))]
+        rate_limit: Option<u32>,
+        /// Largest request body accepted, in bytes
+        #[arg(long, value_name = "BYTES", default_value_t = limits::DEFAULT_MAX_BODY)]
+        max_body: usize,
+        /// How the files treat double quotes; `none` reads vendor exports with unescaped quotes
+        #[arg(long, value_name = "MODE", value_enum, default_value_t)]
+        quoting: QuotingArg,
+        /// Header renames: TSV lines of a header in the file and the column it stands for
+        #[arg(long, value_name = "FILE")]
+        columns: Option<PathBuf>,
+        /// Refuse files with duplicate IDs or unexpected columns
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Serve the taxonomies over gRPC (see proto/iab.proto)
+    #[cfg(feature = "grpc")]
+    Grpc {
+        #[arg(long, default_value_t = 50051)]
+        port: u16,
+        /// Address to bind; use 0.0.0.0 to accept remote connections
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+}
 
-    // Render the content
-    let inner_area = Rect {
-        x: popup_area.x + 2,
-        y: popup_area.y + 2,
-        width: popup_area.width.saturating_sub(4),
-        height: popup_area.height.saturating_sub(3),
-    };
+/// `--quoting` values; mirrors the lib's `Quoting`, which stays free of clap
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+enum QuotingArg {
+    /// A cell starting with a quote runs to the closing quote, tabs and newlines included; quotes
+    /// inside it are doubled (`""`)
+    #[default]
+    Standard,
+    /// Like standard, but quotes inside a quoted cell are escaped with a backslash (`\"`)
+    Backslash,
+    /// Quotes are ordinary text: every line is one row, split at each tab
+    None,
+}
 
-    let mut lines = Vec::new();
-    for (label, value) in &app.popup_content {
-        lines.push(Line::from(vec![
-            Span::styled(
-                format!("{}: ", label),
-                Style::default().fg(app.datasource.color()).bold(),
-            ),
-        ]));
-        lines.push(Line::from(vec![
-            Span::styled(
-                format!("  {}", value),
-                Style::default().fg(Color::White),
-            ),
-        ]));
-        lines.push(Line::from("")); // Empty line for spacing
+impl From<QuotingArg> for Quoting {
+    fn from(arg: QuotingArg) -> Self {
+        match arg {
+            QuotingArg::Standard => Quoting::Standard,
+            QuotingArg::Backslash => Quoting::Backslash,
+            QuotingArg::None => Quoting::None,
+        }
     }
+}
 
-    let paragraph = Paragraph::new(lines)
-        .style(Style::default().bg(Color::Black))
-        .wrap(ratatui::widgets::Wrap { trim: false });
+fn parse_taxonomy(value: &str) -> Result<Taxonomy> {
+    Taxonomy::from_key(value).with_context(|| format!("unknown taxonomy {} (use product, content or audience)", value))
+}
 
-    frame.render_widget(paragraph, inner_area);
+fn parse_cattax(value: &str) -> Result<Taxonomy> {
+    Taxonomy::for_cattax(value.parse()?)
 }
 
-fn run_app(terminal: &mut DefaultTerminal, mut app: App) -> Result<()> {
-    loop {
-        terminal.draw(|frame| ui(frame, &mut app))?;
+fn parse_segtax(value: &str) -> Result<Taxonomy> {
+    Taxonomy::for_segtax(value.parse()?)
+}
 
-        if event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    if !app.handle_key(key) {
-                        return Ok(());
+fn load_options(strict: bool, quoting: QuotingArg, columns: Option<&Path>) -> Result<LoadOptions> {
+    let columns = columns.map(ColumnMap::load).transpose()?.unwrap_or_default();
+    Ok(LoadOptions { strict, quoting: quoting.into(), columns })
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if let Some(path) = &cli.log_file {
+        logging::init(path, cli.verbose)?;
+    }
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), args = ?std::env::args().collect::<Vec<_>>(), "starting");
+    let result = run(cli);
+    if let Err(err) = &result {
+        tracing::error!("{:#}", err);
+    }
+    result
+}
+
+fn run(cli: Cli) -> Result<()> {
+    // Headless runs start the same everywhere, so only an explicit --config applies to them
+    let config = match &cli.config {
+        Some(path) => Config::load(path)?,
+        None if cli.headless => Config::default(),
+        None => Config::load_default()?,
+    };
+
+    let mut crosswalks = Crosswalks::default();
+    for path in config.crosswalks.iter().chain(&cli.crosswalk) {
+        crosswalks.add(Crosswalk::load(path)?);
+    }
+    let tables = config.tables.iter().chain(&cli.table).map(|path| MappingTable::load(path)).collect::<Result<Vec<_>>>()?;
+
+    let synonyms = match cli.synonyms.as_ref().or(config.synonyms.as_ref()) {
+        Some(path) => suggest::load_synonyms(path)?,
+        None => Synonyms::new(),
+    };
+
+    if let Some(command) = cli.command {
+        return match command {
+            Command::ValidateBidrequest { file } => commands::validate_bidrequest(&file),
+            Command::Validate { files, quoting, columns, strict } => {
+                commands::validate(&files, &load_options(strict, quoting, columns.as_deref())?)
+            }
+            Command::Lint { files, quoting, columns, strict, format, deny_warnings } => {
+                commands::lint(&files, &load_options(strict, quoting, columns.as_deref())?, format, deny_warnings)
+            }
+            Command::Diff { file, quoting, columns } => commands::diff(&file, &load_options(false, quoting, columns.as_deref())?),
+            Command::Map { from: Some(from), to: Some(to), stdin: true, .. } => commands::map_stdin(&crosswalks, &from, &to),
+            Command::Map { from: Some(from), to: Some(to), ids, .. } => commands::map(&crosswalks, &from, &to, &ids),
+            Command::Map { ids, .. } => commands::map_tables(&tables, &ids),
+            Command::Export { taxonomy, format, root, max_depth, delimiter, columns, quote, output } => {
+                let csv = (delimiter.is_some() || !columns.is_empty() || quote.is_some()).then(|| {
+                    let default = if format == ExportFormat::Flat { export::CsvOptions::flat() } else { export::CsvOptions::default() };
+                    export::CsvOptions {
+                        delimiter: delimiter.unwrap_or(default.delimiter),
+                        columns: if columns.is_empty() { default.columns } else { columns },
+                        quote: quote.unwrap_or(default.quote),
                     }
-                }
+                });
+                commands::export(taxonomy, format, root.as_deref(), max_depth, csv, output.as_deref())
             }
+            Command::Classify { taxonomy, limit, text } => commands::classify(taxonomy, &text.join(" "), limit, &synonyms),
+            Command::Codegen { language, taxonomy, output } => commands::codegen(language, taxonomy, output.as_deref()),
+            Command::Schema { kind, output } => commands::schema(kind, output.as_deref()),
+            Command::Ortb2 { taxonomy, ids, app, name } => commands::ortb2(taxonomy, &ids, app, name.as_deref()),
+            Command::Run { script, keep_going } => script::run(&script, &crosswalks, &synonyms, keep_going),
+            Command::CheckUpdates => commands::check_updates(),
+            Command::State { action } => commands::state(action.unwrap_or(commands::StateAction::List)),
+            Command::Mcp => mcp::serve(),
+            Command::Daemon { listen, lsp } => daemon::serve(listen.as_deref(), lsp, crosswalks),
+            #[cfg(feature = "server")]
+            Command::Serve { port, host, ui, files, watch, max_age, rate_limit, max_body, quoting, columns, strict } => {
+                let api_keys = server::api_keys(&config.api_keys)?;
+                let options = server::ServeOptions { host, port, ui, watch, max_age, api_keys, rate_limit, max_body };
+                server::serve(options, files, load_options(strict, quoting, columns.as_deref())?)
+            }
+            #[cfg(feature = "grpc")]
+            Command::Grpc { port, host } => grpc::serve(&host, port, crosswalks),
+        };
+    }
+
+    // The report is the one file a session could write; a kiosk may only print it
+    if cli.kiosk && cli.report.as_deref().is_some_and(|path| path != Path::new("-")) {
+        bail!("--kiosk writes no files; use --report without a path to print the report to stdout");
+    }
+    clipboard::set_backend(config.clipboard.unwrap_or_default());
+    // A kiosk is shared, so it neither shows nor keeps what earlier users starred or noted
+    state::EPHEMERAL.store(cli.headless || cli.kiosk, Ordering::Relaxed);
+    // Like the rest of the config, the environment does not reach headless screens
+    i18n::set(if cli.headless { config.locale.unwrap_or_default() } else { i18n::detect(config.locale) });
+    let session = if cli.resume { Session::load()? } else { None };
+    let mapping = cli.mapping.as_deref().or(config.mapping.as_deref()).map(Mapping::load).transpose()?;
+    // Like the config file, the plugins directory does not apply to headless runs
+    #[cfg(feature = "plugins")]
+    let mut plugin_files = match config::plugin_dir() {
+        Some(dir) if !cli.headless => plugins::discover(&dir)?,
+        _ => Vec::new(),
+    };
+    #[cfg(feature = "plugins")]
+    plugin_files.extend(config.plugins.iter().chain(&cli.plugin).cloned());
+    // Scripts can open URLs and copy, which a kiosk must not
+    #[cfg(feature = "plugins")]
+    if cli.kiosk {
+        plugin_files.clear();
+    }
+    let load_options = load_options(cli.strict, cli.quoting, cli.columns.as_deref())?;
+    // A --file replaces the config file's entry for the same taxonomy
+    let files: Vec<TaxonomyFile> = config
+        .files
+        .into_iter()
+        .filter(|file| cli.file.iter().all(|given| given.taxonomy != file.taxonomy))
+        .chain(cli.file.iter().cloned())
+        .collect();
+
+    let configure = |mut taxonomies: Taxonomies| -> Result<App> {
+        for file in &cli.extension {
+            taxonomies.load_extension(file)?;
+        }
+        let mut app = App::new(taxonomies)?;
+        for file in &cli.extension {
+            app.extension_files[file.taxonomy.index()] = Some(file.path.clone());
+        }
+        for file in &cli.usage {
+            app.set_usage(file.taxonomy, Usage::load(&file.path)?);
+        }
+        // A --translation replaces the config file's entry for the same taxonomy
+        let translations = config.translations.iter().filter(|file| cli.translation.iter().all(|given| given.taxonomy != file.taxonomy));
+        for file in translations.chain(&cli.translation) {
+            app.set_translation(file.taxonomy, Translation::load(&file.path)?);
+        }
+        if !cli.previous.is_empty() {
+            app.previous = Some(Box::new(Taxonomies::load_files(&cli.previous, &load_options, |_| {})?));
+            app.previous_files = cli.previous.clone();
         }
+        app.crosswalks = crosswalks;
+        app.mapping_tables = tables;
+        app.mapping = mapping;
+        app.set_synonyms(synonyms);
+        app.keymap = config.keymap;
+        if let Some(format) = config.export_format {
+            app.export_format = format;
+        }
+        app.plain = cli.plain || config.plain;
+        app.theme = cli.theme.or(config.theme).unwrap_or_default();
+        app.kiosk = cli.kiosk;
+        #[cfg(feature = "plugins")]
+        {
+            app.plugins = Plugins::load(&plugin_files)?;
+        }
+        if let Some(palette) = cli.depth_palette.or(config.depth_palette) {
+            app.depth_colors = true;
+            app.depth_palette = palette;
+        }
+        if let Some(taxonomy) = config.datasource {
+            app.switch_datasource(taxonomy.into());
+        }
+        if let Some(sort_mode) = config.sort {
+            app.sort_mode = sort_mode;
+        }
+        if let Some(session) = session {
+            app.restore_session(session);
+        }
+        if let Some(taxonomy) = cli.cattax.or(cli.segtax) {
+            app.switch_datasource(taxonomy.into());
+        }
+        Ok(app)
+    };
+
+    if cli.headless {
+        let mut script = cli.keys.join("\n");
+        if let Some(path) = &cli.keys_file {
+            script.push('\n');
+            script.push_str(&std::fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?);
+        }
+        let keys = headless::parse_keys(&script)?;
+        let mut app = configure(Taxonomies::load_files(&files, &load_options, |_| {})?)?;
+        let (width, height) = cli.size;
+        let screen = headless::run(&mut app, &keys, width, height)?;
+        if cli.state {
+            println!("{}", serde_json::to_string_pretty(&app.session())?);
+        } else {
+            println!("{}", screen);
+        }
+        return Ok(());
+    }
+
+    let app = ratatui::run(|terminal| -> Result<Option<App>> {
+        crash::install();
+        // Parse in the background so the terminal shows progress instead of freezing
+        let Some(taxonomies) = loading::load_with_spinner(terminal, files.clone(), &load_options)? else {
+            return Ok(None);
+        };
+
+        let mut app = configure(taxonomies)?;
+        if config.check_updates {
+            app.update_check = Some(updates::spawn());
+        }
+
+        // Opt into the kitty keyboard protocol so modifier combinations are reported reliably
+        let enhanced = supports_keyboard_enhancement().unwrap_or(false);
+        if enhanced {
+            std::io::stdout().execute(PushKeyboardEnhancementFlags(
+                KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES | KeyboardEnhancementFlags::REPORT_ALTERNATE_KEYS,
+            ))?;
+            crash::set_keyboard_enhanced(true);
+        }
+        let result = run_app(terminal, &mut app);
+        if enhanced {
+            crash::set_keyboard_enhanced(false);
+            std::io::stdout().execute(PopKeyboardEnhancementFlags)?;
+        }
+        result.map(|_| Some(app))
+    })?;
+
+    let Some(app) = app else {
+        return Ok(());
+    };
+    app.session().save()?;
+
+    match cli.report {
+        Some(path) if path == Path::new("-") => app.report.write(std::io::stdout().lock()),
+        Some(path) => {
+            let file = std::fs::File::create(&path).with_context(|| format!("cannot create {}", path.display()))?;
+            app.report.write(std::io::BufWriter::new(file))
+        }
+        None => Ok(()),
     }
 }
 
-fn main() -> Result<()> {
-    ratatui::run(|terminal| {
-        let app = App::new()?;
-        run_app(terminal, app)
-    })
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(spans: &[Span]) -> Vec<String> {
+        spans.iter().map(|span| span.content.to_string()).collect()
+    }
+
+    #[test]
+    fn ascii_matches_ignore_case() {
+        assert_eq!(match_range("Sports Cars", "CAR"), Some((7, 10)));
+        assert_eq!(texts(&highlight_match("Sports Cars", "car")), ["Sports ", "Car", "s"]);
+        assert_eq!(texts(&highlight_match("Cars", "cars")), ["Cars"]);
+    }
+
+    #[test]
+    fn case_changing_characters_map_back_to_their_bytes() {
+        // "İ" is two bytes but lowercases to three ("i" and a combining dot)
+        assert_eq!(match_range("İstanbul", "stan"), Some((2, 6)));
+        assert_eq!(match_range("İstanbul", "İST"), Some((0, 4)));
+        // A match of part of a lowercased character covers the whole character
+        assert_eq!(match_range("İstanbul", "i"), Some((0, 2)));
+        assert_eq!(texts(&highlight_match("Café ÄPFEL", "äpf")), ["Café ", "ÄPF", "EL"]);
+    }
+
+    #[test]
+    fn no_match_leaves_the_text_whole() {
+        assert_eq!(match_range("İstanbul", "paris"), None);
+        assert_eq!(match_range("İstanbul", ""), None);
+        assert_eq!(texts(&highlight_match("İstanbul", "paris")), ["İstanbul"]);
+    }
 }
@@ -0,0 +1,130 @@
+use anyhow::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One internal category string and the IAB node it is mapped to, if any
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MappingEntry {
+    pub internal: String,
+    #[serde(default)]
+    pub taxonomy: Option<String>,
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Name of the node at assignment time, so the file reads well on its own
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+impl MappingEntry {
+    pub fn is_mapped(&self) -> bool {
+        self.id.is_some()
+    }
+}
+
+/// Internal categories paired with IAB nodes, stored as CSV or JSON (by extension) and saved on every change
+#[derive(Debug)]
+pub struct Mapping {
+    path: PathBuf,
+    pub entries: Vec<MappingEntry>,
+    /// Entry currently being assigned
+    pub selected: usize,
+}
+
+impl Mapping {
+    /// Load a mapping file; it needs at least an `internal` column (or field), the rest is filled in by assigning
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        let entries: Vec<MappingEntry> = if is_json(path) {
+            serde_json::from_str(&data).with_context(|| format!("cannot parse {}", path.display()))?
+        } else {
+            csv::Reader::from_reader(data.as_bytes())
+                .deserialize()
+                .collect::<std::result::Result<_, _>>()
+                .with_context(|| format!("cannot parse {}", path.display()))?
+        };
+        let entries: Vec<MappingEntry> = entries
+            .into_iter()
+            .filter(|entry| !entry.internal.trim().is_empty())
+            .map(|entry| MappingEntry {
+                // Empty CSV cells mean "not mapped"
+                taxonomy: entry.taxonomy.filter(|value| !value.is_empty()),
+                id: entry.id.filter(|value| !value.is_empty()),
+                name: entry.name.filter(|value| !value.is_empty()),
+                ..entry
+            })
+            .collect();
+        if entries.is_empty() {
+            bail!("{} lists no internal categories", path.display());
+        }
+
+        let selected = entries.iter().position(|entry| !entry.is_mapped()).unwrap_or(0);
+        Ok(Self { path: path.to_path_buf(), entries, selected })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let data = if is_json(&self.path) {
+            serde_json::to_string_pretty(&self.entries)?
+        } else {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for entry in &self.entries {
+                writer.serialize(entry)?;
+            }
+            String::from_utf8(writer.into_inner()?)?
+        };
+        fs::write(&self.path, data).with_context(|| format!("cannot write {}", self.path.display()))
+    }
+
+    pub fn file_name(&self) -> String {
+        self.path.file_name().map_or_else(|| self.path.display().to_string(), |name| name.to_string_lossy().into_owned())
+    }
+
+    pub fn mapped_count(&self) -> usize {
+        self.entries.iter().filter(|entry| entry.is_mapped()).count()
+    }
+
+    pub fn current(&self) -> Option<&MappingEntry> {
+        self.entries.get(self.selected)
+    }
+
+    pub fn select_relative(&mut self, forward: bool) {
+        self.selected = if forward {
+            (self.selected + 1).min(self.entries.len().saturating_sub(1))
+        } else {
+            self.selected.saturating_sub(1)
+        };
+    }
+
+    /// First unmapped entry after the selected one, wrapping around
+    fn next_unmapped(&self) -> Option<usize> {
+        let count = self.entries.len();
+        (1..=count)
+            .map(|offset| (self.selected + offset) % count)
+            .find(|&index| !self.entries[index].is_mapped())
+    }
+
+    /// Pair the selected entry with the node, or unpair it when it already points there.
+    /// Returns whether it is mapped afterwards; a new pairing moves on to the next unmapped entry.
+    pub fn toggle(&mut self, taxonomy: &str, id: &str, name: &str) -> bool {
+        let Some(entry) = self.entries.get_mut(self.selected) else {
+            return false;
+        };
+        if entry.taxonomy.as_deref() == Some(taxonomy) && entry.id.as_deref() == Some(id) {
+            entry.taxonomy = None;
+            entry.id = None;
+            entry.name = None;
+            return false;
+        }
+        entry.taxonomy = Some(taxonomy.to_string());
+        entry.id = Some(id.to_string());
+        entry.name = Some(name.to_string());
+        if let Some(next) = self.next_unmapped() {
+            self.selected = next;
+        }
+        true
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
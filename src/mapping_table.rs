@@ -0,0 +1,162 @@
+//! Third-party mapping tables, e.g. Google content categories ↔ IAB Content.
+//!
+//! A table is a CSV or TSV file (tab-separated when its header line contains a tab). The first
+//! column holds the external ID and its header names the system (`Google ID` → "Google"); the
+//! second holds the IAB ID and its header names the taxonomy (`IAB Content ID`, Content when none
+//! is recognised). An optional third column labels the external category; further columns are
+//! ignored. IDs may repeat in either column, so both directions can map to several categories.
+
+use anyhow::*;
+use std::fs;
+use std::path::Path;
+
+use crate::Taxonomy;
+
+/// One row: an external category and the IAB ID it corresponds to
+#[derive(Debug, Clone)]
+pub struct ExternalPair {
+    pub external_id: String,
+    pub external_name: Option<String>,
+    pub iab_id: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct MappingTable {
+    system: String,
+    taxonomy: Taxonomy,
+    pairs: Vec<ExternalPair>,
+}
+
+impl MappingTable {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        let fallback = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        Self::parse(&data, &fallback).with_context(|| format!("cannot parse {}", path.display()))
+    }
+
+    /// Parse table data; `fallback_system` names the system when the first header does not
+    pub fn parse(data: &str, fallback_system: &str) -> Result<Self> {
+        let tabs = data.lines().next().is_some_and(|header| header.contains('\t'));
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(if tabs { b'\t' } else { b',' })
+            .has_headers(true)
+            .flexible(true)
+            .from_reader(data.as_bytes());
+
+        let headers = reader.headers()?.clone();
+        if headers.len() < 2 {
+            bail!("expected an external ID and an IAB ID column");
+        }
+        let system = system_in(&headers[0]).unwrap_or_else(|| fallback_system.to_string());
+        let taxonomy = taxonomy_in(&headers[1]);
+
+        let mut pairs = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            let (Some(external_id), Some(iab_id)) = (record.get(0), record.get(1)) else {
+                continue;
+            };
+            // Categories without an IAB counterpart leave the ID blank
+            let (external_id, iab_id) = (external_id.trim(), iab_id.trim());
+            if external_id.is_empty() || iab_id.is_empty() {
+                continue;
+            }
+            let external_name = record.get(2).map(str::trim).filter(|name| !name.is_empty());
+            pairs.push(ExternalPair {
+                external_id: external_id.to_string(),
+                external_name: external_name.map(str::to_string),
+                iab_id: iab_id.to_string(),
+            });
+        }
+        if pairs.is_empty() {
+            bail!("no mapped rows");
+        }
+
+        Ok(Self { system, taxonomy, pairs })
+    }
+
+    /// Name of the external system, e.g. "Google"
+    pub fn system(&self) -> &str {
+        &self.system
+    }
+
+    pub fn taxonomy(&self) -> Taxonomy {
+        self.taxonomy
+    }
+
+    /// External categories mapped to an IAB ID
+    pub fn from_iab<'a>(&'a self, iab_id: &'a str) -> impl Iterator<Item = &'a ExternalPair> {
+        self.pairs.iter().filter(move |pair| pair.iab_id == iab_id)
+    }
+
+    /// IAB categories an external ID maps to
+    pub fn to_iab<'a>(&'a self, external_id: &'a str) -> impl Iterator<Item = &'a ExternalPair> {
+        self.pairs.iter().filter(move |pair| pair.external_id == external_id)
+    }
+}
+
+impl ExternalPair {
+    /// "ID Name", or just the ID when the table has no label column
+    pub fn external_label(&self) -> String {
+        match &self.external_name {
+            Some(name) => format!("{} {}", self.external_id, name),
+            None => self.external_id.clone(),
+        }
+    }
+}
+
+/// The header without ID wording, e.g. "Google" in "Google Category ID"
+fn system_in(header: &str) -> Option<String> {
+    let words: Vec<&str> = header
+        .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .filter(|word| !word.is_empty() && !["id", "ids", "category", "categories"].contains(&word.to_lowercase().as_str()))
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+fn taxonomy_in(header: &str) -> Taxonomy {
+    let header = header.to_lowercase();
+    Taxonomy::ALL
+        .into_iter()
+        .find(|taxonomy| header.contains(taxonomy.key()))
+        .unwrap_or(Taxonomy::Content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels<'a>(pairs: impl Iterator<Item = &'a ExternalPair>) -> Vec<String> {
+        pairs.map(ExternalPair::external_label).collect()
+    }
+
+    #[test]
+    fn google_style_csv_names_its_system_and_skips_unmapped_rows() {
+        let data = "Google Category ID,IAB Content ID\n1000,150\n1001,\n1002,150\n1000,151\n";
+        let table = MappingTable::parse(data, "fallback").unwrap();
+        assert_eq!(table.system(), "Google");
+        assert_eq!(table.taxonomy(), Taxonomy::Content);
+        assert_eq!(labels(table.from_iab("150")), ["1000", "1002"]);
+        assert_eq!(table.to_iab("1000").map(|pair| pair.iab_id.as_str()).collect::<Vec<_>>(), ["150", "151"]);
+        assert_eq!(table.to_iab("1001").count(), 0);
+    }
+
+    #[test]
+    fn tsv_with_a_label_column() {
+        let data = "vertical_id\tIAB Audience ID\tLabel\tNotes\n7\t3\t Sports fans \tignored\n8\t4\t\t\n";
+        let table = MappingTable::parse(data, "acme-verticals").unwrap();
+        // "id" alone is left out, so "vertical" names the system
+        assert_eq!(table.system(), "vertical");
+        assert_eq!(table.taxonomy(), Taxonomy::Audience);
+        assert_eq!(labels(table.from_iab("3")), ["7 Sports fans"]);
+        assert_eq!(labels(table.from_iab("4")), ["8"]);
+    }
+
+    #[test]
+    fn headers_without_a_system_or_taxonomy_fall_back() {
+        let table = MappingTable::parse("ID\tIAB ID\n1\t2\n", "acme").unwrap();
+        assert_eq!(table.system(), "acme");
+        assert_eq!(table.taxonomy(), Taxonomy::Content);
+        assert!(MappingTable::parse("ID,IAB ID\n1,\n", "acme").is_err());
+    }
+}
@@ -0,0 +1,141 @@
+use anyhow::*;
+use iab::{suggest, Taxonomies, Taxonomy, TaxonomyItem};
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+use crate::jsonrpc::{self, RpcResult, INVALID_PARAMS, METHOD_NOT_FOUND};
+
+/// Protocol revision answered when the client does not name one
+const PROTOCOL_VERSION: &str = "2024-11-05";
+const DEFAULT_LIMIT: usize = 10;
+
+/// Serve the Model Context Protocol over stdio: one JSON-RPC message per line
+pub fn serve() -> Result<()> {
+    let taxonomies = Taxonomies::load(|_| {})?;
+    answer(&taxonomies, std::io::stdin().lock(), std::io::stdout().lock())
+}
+
+/// Answer each line of `input` on `output` until the input ends
+fn answer(taxonomies: &Taxonomies, input: impl BufRead, output: impl Write) -> Result<()> {
+    jsonrpc::serve_lines(input, output, |message| handle(taxonomies, message))
+}
+
+fn handle(taxonomies: &Taxonomies, message: &Value) -> Option<Value> {
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+    jsonrpc::reply(message, |method| match method {
+        "initialize" => Result::Ok(json!({
+            "protocolVersion": params.get("protocolVersion").and_then(Value::as_str).unwrap_or(PROTOCOL_VERSION),
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "iab", "version": env!("CARGO_PKG_VERSION") },
+        })),
+        "ping" => Result::Ok(json!({})),
+        "tools/list" => Result::Ok(json!({ "tools": tools() })),
+        "tools/call" => call_tool(taxonomies, &params),
+        method => Err((METHOD_NOT_FOUND, format!("unknown method {}", method))),
+    })
+}
+
+fn tools() -> Value {
+    let taxonomy = json!({
+        "type": "string",
+        "enum": ["product", "content", "audience"],
+        "description": "Taxonomy to use (default content)",
+    });
+    let limit = json!({ "type": "integer", "minimum": 1, "description": "Maximum number of results (default 10)" });
+    json!([
+        {
+            "name": "lookup_category",
+            "description": "Look up an IAB Tech Lab category by unique ID, with its tiers and ancestors",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "id": { "type": "string" }, "taxonomy": taxonomy },
+                "required": ["id"],
+            },
+        },
+        {
+            "name": "search_categories",
+            "description": "Find IAB Tech Lab categories whose ID or name contains the query",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "query": { "type": "string" }, "taxonomy": taxonomy, "limit": limit },
+                "required": ["query"],
+            },
+        },
+        {
+            "name": "suggest_categories_for_text",
+            "description": "Suggest IAB Tech Lab categories for a piece of content, ranked by how much of each category name appears in the text",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "text": { "type": "string" }, "taxonomy": taxonomy, "limit": limit },
+                "required": ["text"],
+            },
+        },
+    ])
+}
+
+/// Run a tool; failures inside the tool are reported as results with `isError`
+fn call_tool(taxonomies: &Taxonomies, params: &Value) -> RpcResult {
+    let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+    let string = |name: &str| {
+        arguments
+            .get(name)
+            .and_then(Value::as_str)
+            .ok_or((INVALID_PARAMS, format!("missing string argument {}", name)))
+    };
+    let limit = arguments.get("limit").and_then(Value::as_u64).map_or(DEFAULT_LIMIT, |limit| limit as usize);
+    let taxonomy = match arguments.get("taxonomy").and_then(Value::as_str) {
+        None => Taxonomy::Content,
+        Some(key) => Taxonomy::from_key(key).ok_or((INVALID_PARAMS, format!("unknown taxonomy {}", key)))?,
+    };
+
+    let output = match params.get("name").and_then(Value::as_str).unwrap_or_default() {
+        "lookup_category" => {
+            let id = string("id")?;
+            match taxonomies.item(taxonomy, id) {
+                Some(item) => {
+                    let mut node = node_json(item);
+                    let ancestors: Vec<Value> = taxonomies.ancestors(taxonomy, id).into_iter().map(node_json).collect();
+                    node["ancestors"] = Value::Array(ancestors);
+                    node
+                }
+                None => return Result::Ok(tool_error(format!("no {} category with ID {}", taxonomy.name(), id))),
+            }
+        }
+        "search_categories" => {
+            let matches: Vec<Value> =
+                taxonomies.search(taxonomy, string("query")?, limit).into_iter().map(node_json).collect();
+            Value::Array(matches)
+        }
+        "suggest_categories_for_text" => {
+            let suggestions: Vec<Value> = suggest::suggest(taxonomies, taxonomy, string("text")?, limit)
+                .into_iter()
+                .map(|suggestion| {
+                    let mut node = node_json(suggestion.item);
+                    node["score"] = json!((suggestion.score * 100.0).round() / 100.0);
+                    node
+                })
+                .collect();
+            Value::Array(suggestions)
+        }
+        name => return Err((INVALID_PARAMS, format!("unknown tool {}", name))),
+    };
+
+    Result::Ok(json!({
+        "content": [{ "type": "text", "text": serde_json::to_string_pretty(&output).unwrap_or_default() }],
+        "isError": false,
+    }))
+}
+
+fn tool_error(message: String) -> Value {
+    json!({ "content": [{ "type": "text", "text": message }], "isError": true })
+}
+
+pub fn node_json(item: &dyn TaxonomyItem) -> Value {
+    json!({
+        "id": item.unique_id(),
+        "parent": item.parent().filter(|parent| !parent.is_empty()),
+        "name": item.name(),
+        "tiers": item.tiers(),
+        "extension": item.extension().filter(|ext| !ext.is_empty()),
+    })
+}
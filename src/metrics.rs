@@ -0,0 +1,119 @@
+//! Prometheus metrics of `iab serve` at `/metrics`: requests and their latency per endpoint, and
+//! the loaded taxonomies. The text format is simple enough to write by hand, so no metrics crate
+//! is linked in.
+
+use axum::extract::{MatchedPath, Request, State};
+use axum::http::{header, Method};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use iab::Taxonomy;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::server::Live;
+
+/// Upper bounds of the latency histogram buckets, in seconds
+const BUCKETS: [f64; 10] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0];
+
+/// Label of requests that matched no route, so scanners cannot create a series per path
+const UNMATCHED: &str = "unmatched";
+
+#[derive(Default)]
+struct Endpoint {
+    /// Requests by status code
+    statuses: BTreeMap<u16, u64>,
+    /// Requests per bucket, not cumulative
+    buckets: [u64; BUCKETS.len()],
+    count: u64,
+    seconds: f64,
+}
+
+/// Counters of the requests served so far, keyed by method and route
+#[derive(Default)]
+pub(crate) struct Metrics {
+    endpoints: Mutex<BTreeMap<(String, String), Endpoint>>,
+}
+
+impl Metrics {
+    fn record(&self, method: &Method, route: &str, status: u16, seconds: f64) {
+        let mut endpoints = self.endpoints.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let endpoint = endpoints.entry((method.to_string(), route.to_string())).or_default();
+        *endpoint.statuses.entry(status).or_default() += 1;
+        if let Some(bucket) = BUCKETS.iter().position(|bound| seconds <= *bound) {
+            endpoint.buckets[bucket] += 1;
+        }
+        endpoint.count += 1;
+        endpoint.seconds += seconds;
+    }
+
+    /// The exposition text, with the taxonomy gauges read from `live`
+    fn render(&self, live: &Live) -> String {
+        let mut out = String::new();
+        let endpoints = self.endpoints.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        out.push_str("# HELP iab_http_requests_total HTTP requests by method, route and status.\n");
+        out.push_str("# TYPE iab_http_requests_total counter\n");
+        for ((method, route), endpoint) in endpoints.iter() {
+            for (status, count) in &endpoint.statuses {
+                let _ = writeln!(out, "iab_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}", method, escape(route), status, count);
+            }
+        }
+
+        out.push_str("# HELP iab_http_request_duration_seconds Time to answer HTTP requests by method and route.\n");
+        out.push_str("# TYPE iab_http_request_duration_seconds histogram\n");
+        for ((method, route), endpoint) in endpoints.iter() {
+            let labels = format!("method=\"{}\",route=\"{}\"", method, escape(route));
+            let mut cumulative = 0;
+            for (bound, count) in BUCKETS.iter().zip(endpoint.buckets) {
+                cumulative += count;
+                let _ = writeln!(out, "iab_http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, bound, cumulative);
+            }
+            let _ = writeln!(out, "iab_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, endpoint.count);
+            let _ = writeln!(out, "iab_http_request_duration_seconds_sum{{{}}} {}", labels, endpoint.seconds);
+            let _ = writeln!(out, "iab_http_request_duration_seconds_count{{{}}} {}", labels, endpoint.count);
+        }
+        drop(endpoints);
+
+        let state = live.current();
+        out.push_str("# HELP iab_taxonomy_nodes Nodes of each loaded taxonomy.\n");
+        out.push_str("# TYPE iab_taxonomy_nodes gauge\n");
+        for taxonomy in Taxonomy::ALL {
+            let _ = writeln!(
+                out,
+                "iab_taxonomy_nodes{{taxonomy=\"{}\",version=\"{}\"}} {}",
+                taxonomy.key(),
+                taxonomy.version(),
+                state.taxonomies.items(taxonomy).len()
+            );
+        }
+        out.push_str("# HELP iab_taxonomy_loaded_timestamp_seconds When the taxonomies were last loaded, in Unix time.\n");
+        out.push_str("# TYPE iab_taxonomy_loaded_timestamp_seconds gauge\n");
+        let _ = writeln!(out, "iab_taxonomy_loaded_timestamp_seconds {}", state.loaded_at());
+        out.push_str("# HELP iab_build_info The running iab version.\n");
+        out.push_str("# TYPE iab_build_info gauge\n");
+        let _ = writeln!(out, "iab_build_info{{version=\"{}\"}} 1", env!("CARGO_PKG_VERSION"));
+        out
+    }
+}
+
+/// Label values escape backslashes, quotes and line breaks
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Count every request under the route it matched
+pub(crate) async fn track(State(metrics): State<Arc<Metrics>>, request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let route = request.extensions().get::<MatchedPath>().map_or(UNMATCHED.to_string(), |path| path.as_str().to_string());
+    let started = Instant::now();
+    let response = next.run(request).await;
+    metrics.record(&method, &route, response.status().as_u16(), started.elapsed().as_secs_f64());
+    response
+}
+
+/// `GET /metrics`
+pub(crate) async fn metrics(State((metrics, live)): State<(Arc<Metrics>, Arc<Live>)>) -> Response {
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")], metrics.render(&live)).into_response()
+}
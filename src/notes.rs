@@ -0,0 +1,44 @@
+use anyhow::*;
+use iab::Taxonomy;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::state;
+use crate::taxonomy_store::TaxonomyStore;
+
+/// Free-text notes per node, keyed by taxonomy and version (`content-3.1`) and then node ID,
+/// persisted as JSON in the state directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Notes(TaxonomyStore<String>);
+
+impl Notes {
+    /// Load notes from disk; a missing file yields no notes
+    pub fn load() -> Result<Self> {
+        TaxonomyStore::load(&state::NOTES).map(Self)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.0.save(&state::NOTES)
+    }
+
+    pub fn get(&self, taxonomy: Taxonomy, id: &str) -> Option<&str> {
+        self.0.get(taxonomy, id).map(String::as_str)
+    }
+
+    /// Notes of a taxonomy by node ID
+    pub fn of(&self, taxonomy: Taxonomy) -> Option<&BTreeMap<String, String>> {
+        self.0.of(taxonomy)
+    }
+
+    /// IDs with a note, in ID order
+    pub fn ids(&self, taxonomy: Taxonomy) -> Vec<String> {
+        self.of(taxonomy).map(|notes| notes.keys().cloned().collect()).unwrap_or_default()
+    }
+
+    /// Replace the note on `id`; blank text removes it
+    pub fn set(&mut self, taxonomy: Taxonomy, id: &str, text: &str) {
+        let text = text.trim();
+        self.0.set(taxonomy, id, (!text.is_empty()).then(|| text.to_string()));
+    }
+}
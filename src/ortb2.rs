@@ -0,0 +1,88 @@
+//! Ready-to-paste OpenRTB 2.6 fragments for Prebid's `ortb2` config, built from selected category IDs:
+//! content categories for Product and Content, seller-defined audience segments for Audience.
+
+use anyhow::*;
+use serde_json::{Value, json};
+
+use crate::{Taxonomies, Taxonomy};
+
+/// Which distribution channel object carries the content categories
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channel {
+    Site,
+    App,
+}
+
+impl Channel {
+    pub fn key(self) -> &'static str {
+        match self {
+            Channel::Site => "site",
+            Channel::App => "app",
+        }
+    }
+}
+
+/// Like [`content_fragment`], but fails on IDs the taxonomy does not contain
+pub fn content_categories(taxonomies: &Taxonomies, taxonomy: Taxonomy, ids: &[String], channel: Channel) -> Result<Value> {
+    check_known(taxonomies, taxonomy, ids)?;
+    content_fragment(taxonomy, ids, channel)
+}
+
+/// Like [`user_data_fragment`], but fails on IDs the Audience taxonomy does not contain
+pub fn audience_segments(taxonomies: &Taxonomies, ids: &[String], name: Option<&str>) -> Result<Value> {
+    check_known(taxonomies, Taxonomy::Audience, ids)?;
+    Ok(user_data_fragment(ids, name))
+}
+
+fn check_known(taxonomies: &Taxonomies, taxonomy: Taxonomy, ids: &[String]) -> Result<()> {
+    let unknown: Vec<&str> = ids
+        .iter()
+        .filter(|id| taxonomies.item(taxonomy, id).is_none())
+        .map(String::as_str)
+        .collect();
+    if !unknown.is_empty() {
+        bail!("unknown {} IDs: {}", taxonomy.name(), unknown.join(", "));
+    }
+    Ok(())
+}
+
+/// `{"site": {"content": {"cat": [...], "cattax": N}}}` (or `app`) for the IDs, in the given order without duplicates
+pub fn content_fragment(taxonomy: Taxonomy, ids: &[String], channel: Channel) -> Result<Value> {
+    if taxonomy == Taxonomy::Audience {
+        bail!("Audience segments go into user.data, see user_data_fragment");
+    }
+
+    Ok(json!({
+        channel.key(): {
+            "content": {
+                "cat": unique(ids),
+                "cattax": taxonomy.cattax(),
+            }
+        }
+    }))
+}
+
+/// Seller-defined audiences: `{"user": {"data": [{"name": ..., "ext": {"segtax": 4}, "segment": [{"id": ...}]}]}}`,
+/// where `name` is the domain of the publisher declaring the segments
+pub fn user_data_fragment(ids: &[String], name: Option<&str>) -> Value {
+    let segment: Vec<Value> = unique(ids).into_iter().map(|id| json!({ "id": id })).collect();
+    let mut data = json!({
+        "ext": { "segtax": Taxonomy::Audience.cattax() },
+        "segment": segment,
+    });
+    if let Some(name) = name {
+        data["name"] = json!(name);
+    }
+    json!({ "user": { "data": [data] } })
+}
+
+/// The IDs in their given order without duplicates
+fn unique(ids: &[String]) -> Vec<&str> {
+    let mut unique: Vec<&str> = Vec::new();
+    for id in ids {
+        if !unique.contains(&id.as_str()) {
+            unique.push(id);
+        }
+    }
+    unique
+}
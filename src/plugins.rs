@@ -0,0 +1,225 @@
+//! Rhai plugins: scripts from `~/.config/iab/plugins/*.rhai`, the config's `plugins` list or
+//! `--plugin` that react to the TUI and add organization-specific commands to the palette
+//! (`Alt+Enter`) without changing the crate.
+//!
+//! ```rhai
+//! register_command("Open in CMS", "open_in_cms");
+//!
+//! fn open_in_cms(node) {
+//!     open("https://cms.example.com/categories?iab=" + url_encode(node.id));
+//! }
+//!
+//! fn on_select(node) { }
+//! fn on_export(info) { notify(`Exported ${info.rows} rows to ${info.path}`); }
+//! ```
+//!
+//! A node is a map with `taxonomy`, `id`, `name`, `parent` (`()` at the top), `tiers` and `path`;
+//! an export has `taxonomy`, `format`, `scope`, `path` and `rows`. Scripts can call `notify`,
+//! `copy`, `open`, `url_encode` and `register_command`.
+
+use anyhow::*;
+use iab::{Taxonomy, TaxonomyItem};
+use rhai::{AST, Array, Dynamic, Engine, Map, Scope};
+use std::cell::RefCell;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::rc::Rc;
+
+/// Hook run whenever the selected node changes
+const ON_SELECT: &str = "on_select";
+/// Hook run after the export dialog wrote a file
+const ON_EXPORT: &str = "on_export";
+
+/// Operations one call may take, so a runaway loop cannot hang the TUI
+const MAX_OPERATIONS: u64 = 1_000_000;
+
+/// A palette entry registered by a plugin
+#[derive(Debug, Clone)]
+pub struct PluginCommand {
+    pub label: String,
+    plugin: usize,
+    function: String,
+}
+
+struct Plugin {
+    name: String,
+    ast: AST,
+}
+
+/// What scripts hand back to the TUI while they run
+#[derive(Default)]
+struct Shared {
+    /// Plugin whose top level is being evaluated, for `register_command`
+    loading: usize,
+    commands: Vec<PluginCommand>,
+    messages: Vec<String>,
+}
+
+pub struct Plugins {
+    engine: Engine,
+    plugins: Vec<Plugin>,
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl Default for Plugins {
+    fn default() -> Self {
+        let shared = Rc::new(RefCell::new(Shared::default()));
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        let messages = Rc::clone(&shared);
+        engine.register_fn("notify", move |message: &str| messages.borrow_mut().messages.push(message.to_string()));
+        let commands = Rc::clone(&shared);
+        engine.register_fn("register_command", move |label: &str, function: &str| {
+            let mut shared = commands.borrow_mut();
+            let plugin = shared.loading;
+            shared.commands.push(PluginCommand { label: label.to_string(), plugin, function: function.to_string() });
+        });
+        engine.register_fn("copy", |text: &str| -> std::result::Result<(), Box<rhai::EvalAltResult>> {
+            crate::clipboard::copy(text).map(|_| ()).map_err(|err| format!("{:#}", err).into())
+        });
+        engine.register_fn("open", |target: &str| -> std::result::Result<(), Box<rhai::EvalAltResult>> {
+            open(target).map_err(|err| format!("{:#}", err).into())
+        });
+        engine.register_fn("url_encode", url_encode);
+
+        Self { engine, plugins: Vec::new(), shared }
+    }
+}
+
+impl Plugins {
+    /// Compile and run the top level of each script, which registers its commands
+    pub fn load(paths: &[PathBuf]) -> Result<Self> {
+        let mut plugins = Self::default();
+        for path in paths {
+            plugins.add(path)?;
+        }
+        Ok(plugins)
+    }
+
+    fn add(&mut self, path: &Path) -> Result<()> {
+        let source = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        let ast = self.engine.compile(&source).map_err(|err| anyhow!("{}: {}", path.display(), err))?;
+        self.shared.borrow_mut().loading = self.plugins.len();
+        self.engine.run_ast(&ast).map_err(|err| anyhow!("{}: {}", path.display(), err))?;
+        let name = path.file_stem().map_or_else(|| path.display().to_string(), |stem| stem.to_string_lossy().into_owned());
+        self.plugins.push(Plugin { name, ast });
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    pub fn commands(&self) -> Vec<PluginCommand> {
+        self.shared.borrow().commands.clone()
+    }
+
+    /// Call `hook` with `argument` in every plugin defining it; errors name the plugin
+    fn call_hook(&self, hook: &str, argument: Map) -> Vec<Result<()>> {
+        self.plugins
+            .iter()
+            .filter(|plugin| plugin.ast.iter_functions().any(|function| function.name == hook && function.params.len() == 1))
+            .map(|plugin| {
+                self.engine
+                    .call_fn::<Dynamic>(&mut Scope::new(), &plugin.ast, hook, (argument.clone(),))
+                    .map(|_| ())
+                    .map_err(|err| anyhow!("plugin {}: {}: {}", plugin.name, hook, err))
+            })
+            .collect()
+    }
+
+    pub fn on_select(&self, node: Map) -> Vec<Result<()>> {
+        self.call_hook(ON_SELECT, node)
+    }
+
+    pub fn on_export(&self, export: Map) -> Vec<Result<()>> {
+        self.call_hook(ON_EXPORT, export)
+    }
+
+    /// Run a palette command on `node`
+    pub fn run(&self, command: &PluginCommand, node: Map) -> Result<()> {
+        let plugin = &self.plugins[command.plugin];
+        self.engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &plugin.ast, &command.function, (node,))
+            .map(|_| ())
+            .map_err(|err| anyhow!("plugin {}: {}: {}", plugin.name, command.function, err))
+    }
+
+    /// Messages passed to `notify` since the last call
+    pub fn take_messages(&self) -> Vec<String> {
+        std::mem::take(&mut self.shared.borrow_mut().messages)
+    }
+}
+
+/// The node map handed to hooks and commands
+pub fn node(taxonomy: Taxonomy, item: &dyn TaxonomyItem) -> Map {
+    let mut node = Map::new();
+    node.insert("taxonomy".into(), taxonomy.key().into());
+    node.insert("id".into(), item.unique_id().into());
+    node.insert("name".into(), item.name().into());
+    node.insert("parent".into(), item.parent().map_or(Dynamic::UNIT, |parent| parent.into()));
+    let tiers: Array = item.tiers().into_iter().map(|tier| tier.into()).collect();
+    node.insert("path".into(), item.tiers().join(" > ").into());
+    node.insert("tiers".into(), tiers.into());
+    node
+}
+
+/// The export map handed to `on_export`
+pub fn export(taxonomy: Taxonomy, format: &str, scope: &str, path: &str, rows: usize) -> Map {
+    let mut export = Map::new();
+    export.insert("taxonomy".into(), taxonomy.key().into());
+    export.insert("format".into(), format.into());
+    export.insert("scope".into(), scope.into());
+    export.insert("path".into(), path.into());
+    export.insert("rows".into(), (rows as i64).into());
+    export
+}
+
+/// `*.rhai` files in `dir` by name; none when it does not exist
+pub fn discover(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Result::Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_context(|| format!("cannot read {}", dir.display())),
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "rhai"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Open a URL or file with the desktop's handler
+fn open(target: &str) -> Result<()> {
+    let (program, args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("open", &[])
+    } else if cfg!(windows) {
+        // Not `cmd /C start`: cmd would run whatever follows a `&` or `|` in the target
+        ("rundll32", &["url.dll,FileProtocolHandler"])
+    } else {
+        ("xdg-open", &[])
+    };
+    Command::new(program)
+        .args(args)
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("cannot run {}", program))?;
+    Ok(())
+}
+
+/// Percent-encode everything but unreserved URL characters
+fn url_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
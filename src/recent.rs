@@ -0,0 +1,43 @@
+use anyhow::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::state;
+
+const MAX_RECENT: usize = 30;
+
+/// Most recently viewed node IDs per taxonomy, newest first
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Recent {
+    taxonomies: BTreeMap<String, Vec<String>>,
+}
+
+impl Recent {
+    /// Load the history from disk; a missing file yields an empty history
+    pub fn load() -> Result<Self> {
+        Ok(state::RECENT.load()?.unwrap_or_default())
+    }
+
+    pub fn save(&self) -> Result<()> {
+        state::RECENT.save(self)
+    }
+
+    pub fn ids(&self, taxonomy: &str) -> &[String] {
+        self.taxonomies.get(taxonomy).map(|ids| ids.as_slice()).unwrap_or(&[])
+    }
+
+    /// Move `id` to the front of the history, dropping the oldest entries
+    pub fn record(&mut self, taxonomy: &str, id: &str) {
+        let ids = self.taxonomies.entry(taxonomy.to_string()).or_default();
+        ids.retain(|recent| recent != id);
+        ids.insert(0, id.to_string());
+        ids.truncate(MAX_RECENT);
+    }
+
+    pub fn remove(&mut self, taxonomy: &str, id: &str) {
+        if let Some(ids) = self.taxonomies.get_mut(taxonomy) {
+            ids.retain(|recent| recent != id);
+        }
+    }
+}
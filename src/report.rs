@@ -0,0 +1,82 @@
+use anyhow::*;
+use std::io::Write;
+
+/// A node touched during the session, captured with its name for readability
+#[derive(Debug, Clone)]
+struct ReportEntry {
+    taxonomy: String,
+    id: String,
+    name: String,
+}
+
+impl ReportEntry {
+    fn new(taxonomy: &str, id: &str, name: &str) -> Self {
+        Self { taxonomy: taxonomy.to_string(), id: id.to_string(), name: name.to_string() }
+    }
+
+    fn is(&self, taxonomy: &str, id: &str) -> bool {
+        self.taxonomy == taxonomy && self.id == id
+    }
+}
+
+/// What happened during one run, written on exit with `--report`
+#[derive(Debug, Default)]
+pub struct SessionReport {
+    viewed: Vec<ReportEntry>,
+    bookmarks: Vec<(ReportEntry, bool)>,
+    exports: Vec<String>,
+}
+
+impl SessionReport {
+    /// Note that the details of a node were opened; repeated views are listed once
+    pub fn record_view(&mut self, taxonomy: &str, id: &str, name: &str) {
+        if !self.viewed.iter().any(|entry| entry.is(taxonomy, id)) {
+            self.viewed.push(ReportEntry::new(taxonomy, id, name));
+        }
+    }
+
+    /// Note a bookmark change; toggling a node back to its original state drops it again
+    pub fn record_bookmark(&mut self, taxonomy: &str, id: &str, name: &str, starred: bool) {
+        if let Some(pos) = self.bookmarks.iter().position(|(entry, _)| entry.is(taxonomy, id)) {
+            self.bookmarks.remove(pos);
+        } else {
+            self.bookmarks.push((ReportEntry::new(taxonomy, id, name), starred));
+        }
+    }
+
+    pub fn record_export(&mut self, description: String) {
+        self.exports.push(description);
+    }
+
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<()> {
+        writeln!(writer, "# iab session report")?;
+
+        writeln!(writer, "\n## Details viewed")?;
+        if self.viewed.is_empty() {
+            writeln!(writer, "(none)")?;
+        }
+        for entry in &self.viewed {
+            writeln!(writer, "- {} {} {}", entry.taxonomy, entry.id, entry.name)?;
+        }
+
+        writeln!(writer, "\n## Bookmarks")?;
+        if self.bookmarks.is_empty() {
+            writeln!(writer, "(none)")?;
+        }
+        for (entry, starred) in &self.bookmarks {
+            let action = if *starred { "added" } else { "removed" };
+            writeln!(writer, "- {} {} {} {}", action, entry.taxonomy, entry.id, entry.name)?;
+        }
+
+        writeln!(writer, "\n## Exports")?;
+        if self.exports.is_empty() {
+            writeln!(writer, "(none)")?;
+        }
+        for export in &self.exports {
+            writeln!(writer, "- {}", export)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
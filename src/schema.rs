@@ -0,0 +1,179 @@
+//! JSON Schemas of the documents `iab` writes, printed by `iab schema <kind>` so consumers can
+//! validate exported files. They describe the serde output of `ExportRow`, `ExportNode` and
+//! `compare::DiffReport`, and change together with them.
+
+use anyhow::*;
+use serde_json::{Value, json};
+use std::io::Write;
+
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// A document `iab` writes
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Schema {
+    /// One category as a flat row, each element of a rows document
+    Node,
+    /// An array of flat rows, as written by the TUI's "Visible rows" JSON export
+    Rows,
+    /// Nested categories with their children, as written by `iab export --format json`
+    Tree,
+    /// The changes of a custom file against the bundled release, as written by `iab diff`
+    Diff,
+}
+
+impl Schema {
+    pub fn document(self) -> Value {
+        let (id, title, body) = match self {
+            Schema::Node => ("node", "IAB taxonomy node", node()),
+            Schema::Rows => ("rows", "IAB taxonomy rows", json!({ "type": "array", "items": { "$ref": "#/$defs/node" } })),
+            Schema::Tree => ("tree", "IAB taxonomy tree", json!({ "type": "array", "items": { "$ref": "#/$defs/treeNode" } })),
+            Schema::Diff => ("diff", "IAB taxonomy diff report", diff()),
+        };
+        let mut document = json!({
+            "$schema": DRAFT,
+            "$id": format!("urn:iab:schema:{}:{}", id, env!("CARGO_PKG_VERSION")),
+            "title": title,
+        });
+        if let (Value::Object(document), Value::Object(body)) = (&mut document, body) {
+            document.extend(body);
+            if matches!(self, Schema::Rows | Schema::Tree) {
+                document.insert("$defs".into(), defs());
+            }
+        }
+        document
+    }
+}
+
+/// Write the pretty-printed schema
+pub fn write<W: Write>(schema: Schema, mut writer: W) -> Result<()> {
+    serde_json::to_writer_pretty(&mut writer, &schema.document())?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Fields of an `ExportRow`
+fn node_properties() -> Value {
+    json!({
+        "id": { "type": "string", "description": "Unique ID of the category" },
+        "parent": { "type": ["string", "null"], "description": "ID of the parent; null at the top" },
+        "name": { "type": "string" },
+        "depth": { "type": "integer", "minimum": 0, "description": "Levels below the first exported node" },
+        "tiers": { "type": "array", "items": { "type": "string" }, "description": "Tier 1, Tier 2, ... names down to the node" },
+        "extension": { "type": ["string", "null"], "description": "Extension column of the row" },
+    })
+}
+
+const NODE_REQUIRED: [&str; 6] = ["id", "parent", "name", "depth", "tiers", "extension"];
+
+fn node() -> Value {
+    json!({
+        "type": "object",
+        "properties": node_properties(),
+        "required": NODE_REQUIRED,
+        "additionalProperties": false,
+    })
+}
+
+fn defs() -> Value {
+    let mut tree_properties = node_properties();
+    if let Value::Object(properties) = &mut tree_properties {
+        properties.insert("children".into(), json!({ "type": "array", "items": { "$ref": "#/$defs/treeNode" } }));
+    }
+    let mut tree_required: Vec<&str> = NODE_REQUIRED.to_vec();
+    tree_required.push("children");
+    json!({
+        "node": node(),
+        "treeNode": {
+            "type": "object",
+            "properties": tree_properties,
+            "required": tree_required,
+            "additionalProperties": false,
+        },
+    })
+}
+
+fn diff() -> Value {
+    let field = json!({
+        "type": "object",
+        "properties": {
+            "field": { "enum": ["parent", "name", "tiers", "extension"] },
+            "official": { "type": "string" },
+            "custom": { "type": "string" },
+        },
+        "required": ["field", "official", "custom"],
+        "additionalProperties": false,
+    });
+    let change = json!({
+        "type": "object",
+        "properties": {
+            "id": { "type": "string" },
+            "name": { "type": "string", "description": "Custom name, or the official one for removals" },
+            "change": { "enum": ["added", "removed", "conflict"] },
+            "fields": { "type": "array", "items": field, "description": "Fields that differ, for conflicts" },
+        },
+        "required": ["id", "name", "change", "fields"],
+        "additionalProperties": false,
+    });
+    json!({
+        "type": "object",
+        "properties": {
+            "taxonomy": { "enum": ["product", "content", "audience"] },
+            "version": { "type": "string", "description": "Version of the bundled release compared with" },
+            "file": { "type": "string" },
+            "added": { "type": "integer", "minimum": 0 },
+            "removed": { "type": "integer", "minimum": 0 },
+            "conflicts": { "type": "integer", "minimum": 0 },
+            "changes": { "type": "array", "items": change },
+        },
+        "required": ["taxonomy", "version", "file", "added", "removed", "conflicts", "changes"],
+        "additionalProperties": false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{self, ExportFormat};
+    use iab::compare::{self, DiffReport};
+    use iab::{Taxonomies, Taxonomy};
+
+    fn assert_valid(schema: Schema, instance: &Value) {
+        let validator = jsonschema::validator_for(&schema.document()).expect("schema compiles");
+        let errors: Vec<String> = validator.iter_errors(instance).map(|error| error.to_string()).collect();
+        assert!(errors.is_empty(), "{:?} schema rejects the output: {:?}", schema, errors);
+    }
+
+    #[test]
+    fn export_output_matches_its_schemas() {
+        let taxonomies = Taxonomies::load(|_| {}).expect("bundled data loads");
+        let nodes = export::tree(&taxonomies, Taxonomy::Content, None, None).unwrap();
+        let mut tree = Vec::new();
+        export::write_tree_to(&nodes, ExportFormat::Json, Taxonomy::Content, &mut tree).unwrap();
+        assert_valid(Schema::Tree, &serde_json::from_slice(&tree).unwrap());
+
+        let mut rows = Vec::new();
+        export::write_rows_to(&export::flatten(&nodes), ExportFormat::Json, Taxonomy::Content, &mut rows).unwrap();
+        let rows: Value = serde_json::from_slice(&rows).unwrap();
+        assert_valid(Schema::Rows, &rows);
+        assert_valid(Schema::Node, &rows[0]);
+    }
+
+    #[test]
+    fn diff_output_matches_its_schema() {
+        let taxonomies = Taxonomies::load(|_| {}).expect("bundled data loads");
+        // Against the first two official rows: 150 removed, 151 renamed and one addition
+        let official = &taxonomies.items(Taxonomy::Content)[..2];
+        let (custom, _) = iab::taxonomy::read_tsv::<iab::Content>(
+            "Unique ID\tParent\tName\tTier 1\tTier 2\tTier 3\tTier 4\tExtension\n\
+             151\t150\tTheme Parks\tAttractions\tTheme Parks\t\t\t\n\
+             custom-1\t150\tCustom\tAttractions\tCustom\t\t\t\n",
+        )
+        .unwrap();
+        let custom: Vec<&dyn iab::TaxonomyItem> = custom.iter().map(|item| item as &dyn iab::TaxonomyItem).collect();
+
+        let changes = compare::compare(official, &custom);
+        let report = DiffReport::new(Taxonomy::Content, "vendor.tsv".into(), &changes);
+        assert_eq!((report.added, report.removed, report.conflicts), (1, 1, 1));
+        assert_valid(Schema::Diff, &serde_json::to_value(&report).unwrap());
+    }
+}
@@ -0,0 +1,183 @@
+//! `iab run`: execute a command script against the bundled taxonomies without the TUI, printing
+//! one JSON line per command, so hand-made browsing and export steps can be automated.
+//!
+//! Each line holds a command and its arguments; `#` starts a comment line:
+//!
+//! ```text
+//! taxonomy content          # switch taxonomy (content by default)
+//! search <text>             # categories matching the text
+//! select <id>               # the category with its ancestors; later commands work on it
+//! children                  # direct children of the selected category
+//! classify <text>           # suggested categories, best match first
+//! export <format> <file>    # the selected branch, or the whole taxonomy, to a file
+//! map <from> <to> [ids...]  # Content IDs (the selected one by default) through --crosswalk files
+//! ```
+
+use anyhow::*;
+use clap::ValueEnum;
+use iab::crosswalk;
+use iab::suggest::{Suggester, Synonyms};
+use iab::{Crosswalks, Taxonomies, Taxonomy};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+
+use crate::export::{self, ExportFormat};
+use crate::mcp::node_json;
+
+/// Matches listed by `search` and `classify`
+const LIMIT: usize = 20;
+
+/// One printed line: the command's result, or why it failed
+#[derive(Serialize)]
+struct Outcome<'a> {
+    line: usize,
+    command: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+struct Script<'a> {
+    taxonomies: &'a Taxonomies,
+    crosswalks: &'a Crosswalks,
+    synonyms: &'a Synonyms,
+    taxonomy: Taxonomy,
+    selected: Option<String>,
+}
+
+/// Run the script at `path` (`-` reads stdin); stops at the first failing command unless
+/// `keep_going`, and fails when any command did
+pub fn run(path: &Path, crosswalks: &Crosswalks, synonyms: &Synonyms, keep_going: bool) -> Result<()> {
+    let source = if path == Path::new("-") {
+        std::io::read_to_string(std::io::stdin().lock()).context("cannot read the script from stdin")?
+    } else {
+        fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?
+    };
+    let taxonomies = Taxonomies::load(|_| {})?;
+    let mut script = Script { taxonomies: &taxonomies, crosswalks, synonyms, taxonomy: Taxonomy::Content, selected: None };
+
+    let mut failed = Vec::new();
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (command, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let mut outcome = Outcome { line: number + 1, command, result: None, error: None };
+        match script.execute(command, argument.trim()) {
+            Result::Ok(result) => outcome.result = Some(result),
+            Err(err) => {
+                failed.push(outcome.line);
+                outcome.error = Some(format!("{:#}", err));
+            }
+        }
+        println!("{}", serde_json::to_string(&outcome)?);
+        if !failed.is_empty() && !keep_going {
+            break;
+        }
+    }
+
+    match failed.as_slice() {
+        [] => Ok(()),
+        [line] => bail!("command on line {} failed", line),
+        lines => bail!("{} commands failed, the first on line {}", lines.len(), lines[0]),
+    }
+}
+
+impl Script<'_> {
+    fn execute(&mut self, command: &str, argument: &str) -> Result<Value> {
+        match command {
+            "taxonomy" => {
+                self.taxonomy = crate::parse_taxonomy(argument)?;
+                self.selected = None;
+                Ok(json!({ "taxonomy": self.taxonomy.key(), "version": self.taxonomy.version() }))
+            }
+            "search" => {
+                ensure!(!argument.is_empty(), "usage: search <text>");
+                let matches = self.taxonomies.search(self.taxonomy, argument, LIMIT);
+                Ok(Value::Array(matches.into_iter().map(node_json).collect()))
+            }
+            "select" => {
+                let item = self
+                    .taxonomies
+                    .item(self.taxonomy, argument)
+                    .with_context(|| format!("no {} category with ID {}", self.taxonomy.key(), argument))?;
+                let mut node = node_json(item);
+                let ancestors = self.taxonomies.ancestors(self.taxonomy, argument).into_iter().map(node_json).collect();
+                node["ancestors"] = Value::Array(ancestors);
+                self.selected = Some(argument.to_string());
+                Ok(node)
+            }
+            "children" => {
+                let selected = self.selected()?;
+                let index = self.taxonomies.child_index(self.taxonomy);
+                let items = self.taxonomies.items(self.taxonomy);
+                let position = index.position(selected).context("the selected category is gone")?;
+                Ok(Value::Array(index.children(position).map(|child| node_json(items[child])).collect()))
+            }
+            "classify" => {
+                ensure!(!argument.is_empty(), "usage: classify <text>");
+                let suggestions = Suggester::new(self.taxonomies.items(self.taxonomy), self.synonyms).suggest(argument, LIMIT);
+                let suggestions = suggestions
+                    .into_iter()
+                    .map(|suggestion| {
+                        let mut node = node_json(suggestion.item);
+                        node["score"] = json!((suggestion.score * 100.0).round() / 100.0);
+                        node
+                    })
+                    .collect();
+                Ok(Value::Array(suggestions))
+            }
+            "export" => {
+                let (format, path) = argument.split_once(char::is_whitespace).context("usage: export <format> <file>")?;
+                let format = ExportFormat::from_str(format, true).map_err(|err| anyhow!(err))?;
+                let path = Path::new(path.trim());
+                let nodes = export::tree(self.taxonomies, self.taxonomy, self.selected.as_deref(), None)?;
+                export::write_tree(&nodes, format, self.taxonomy, path)?;
+                Ok(json!({
+                    "file": path,
+                    "format": format.name(),
+                    "root": self.selected,
+                    "categories": export::flatten(&nodes).len(),
+                }))
+            }
+            "map" => {
+                let mut words = argument.split_whitespace();
+                let (Some(from), Some(to)) = (words.next(), words.next()) else {
+                    bail!("usage: map <from> <to> [ids...]");
+                };
+                let (from, to) = (crosswalk::resolve_version(from)?, crosswalk::resolve_version(to)?);
+                let mut ids: Vec<String> = words.map(str::to_string).collect();
+                if ids.is_empty() {
+                    ids.push(self.selected()?.to_string());
+                }
+                ensure!(!self.crosswalks.is_empty(), "no crosswalk loaded, pass one with --crosswalk <FILE>");
+                let mapper = self.crosswalks.mapper(&from, &to)?;
+                let mappings: Vec<Value> = ids
+                    .iter()
+                    .map(|id| {
+                        let targets: Vec<Value> = mapper.map(id).iter().map(|target| self.content_json(&to, target)).collect();
+                        json!({ "id": id, "equivalents": targets })
+                    })
+                    .collect();
+                Ok(json!({ "from": from, "to": to, "mappings": mappings }))
+            }
+            _ => bail!("unknown command {:?} (taxonomy, search, select, children, classify, export, map)", command),
+        }
+    }
+
+    fn selected(&self) -> Result<&str> {
+        self.selected.as_deref().context("no category selected, use select <id> first")
+    }
+
+    /// The bundled Content category for IDs of a version sharing its ID space, else the bare ID
+    fn content_json(&self, version: &str, id: &str) -> Value {
+        crosswalk::same_ids(version, Taxonomy::Content.version())
+            .then(|| self.taxonomies.item(Taxonomy::Content, id))
+            .flatten()
+            .map_or_else(|| json!({ "id": id }), node_json)
+    }
+}
@@ -0,0 +1,874 @@
+use anyhow::*;
+use axum::extract::{Path, Query, Request, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use iab::{crosswalk, LoadOptions, Taxonomies, Taxonomy, TaxonomyFile, TaxonomyItem};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::net::SocketAddr;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+use crate::export::ExportRow;
+use crate::limits::{self, RateLimiter};
+use crate::metrics::{self, Metrics};
+
+pub(crate) const DEFAULT_SEARCH_LIMIT: usize = 100;
+
+/// How often `--watch` looks at the modification times of the taxonomy files
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Seconds clients may reuse a taxonomy response without asking again, by default
+pub(crate) const DEFAULT_MAX_AGE: u32 = 86400;
+
+/// `name=key` pairs, comma-separated, added to the config's `[api-keys]`
+const API_KEYS_ENV: &str = "IAB_API_KEYS";
+
+/// A key clients send as `Authorization: Bearer <key>` or `X-API-Key: <key>`; the name is what
+/// request logs show instead of the key
+#[derive(Debug, Clone)]
+pub(crate) struct ApiKey {
+    pub(crate) name: String,
+    key: String,
+}
+
+/// The config's keys followed by those in `IAB_API_KEYS`
+pub(crate) fn api_keys(configured: &BTreeMap<String, String>) -> Result<Vec<ApiKey>> {
+    let mut keys: Vec<ApiKey> = configured.iter().map(|(name, key)| ApiKey { name: name.clone(), key: key.clone() }).collect();
+    if let Result::Ok(value) = std::env::var(API_KEYS_ENV) {
+        for entry in value.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (name, key) = entry.split_once('=').with_context(|| format!("{} entries are name=key pairs", API_KEYS_ENV))?;
+            keys.push(ApiKey { name: name.trim().to_string(), key: key.trim().to_string() });
+        }
+    }
+    if let Some(key) = keys.iter().find(|key| key.key.is_empty()) {
+        bail!("API key {} is empty", key.name);
+    }
+    Ok(keys)
+}
+
+/// How `iab serve` listens and what it serves besides the API
+pub(crate) struct ServeOptions {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    /// Also serve the browser UI at `/`
+    pub(crate) ui: bool,
+    /// Reload the taxonomy files when they change
+    pub(crate) watch: bool,
+    /// Seconds taxonomy responses may be cached
+    pub(crate) max_age: u32,
+    /// Keys of which one must come with every API request; none leaves the API open
+    pub(crate) api_keys: Vec<ApiKey>,
+    /// Requests per minute each client address may make; unlimited when `None`
+    pub(crate) rate_limit: Option<u32>,
+    /// Largest request body accepted, in bytes
+    pub(crate) max_body: usize,
+}
+
+/// Loaded taxonomies, shared by the HTTP and gRPC services
+pub(crate) struct ServerState {
+    pub(crate) taxonomies: Taxonomies,
+    /// ETag of each taxonomy's responses, in `Taxonomy::ALL` order
+    etags: [String; 3],
+    loaded: SystemTime,
+}
+
+impl ServerState {
+    pub(crate) fn new(taxonomies: Taxonomies) -> Self {
+        let etags = Taxonomy::ALL.map(|taxonomy| etag(&taxonomies, taxonomy));
+        Self { taxonomies, etags, loaded: SystemTime::now() }
+    }
+
+    /// Unix time the taxonomies were loaded
+    pub(crate) fn loaded_at(&self) -> u64 {
+        self.loaded.duration_since(SystemTime::UNIX_EPOCH).map_or(0, |since| since.as_secs())
+    }
+
+    /// ETag of the responses under `path`: the taxonomy's for `/{taxonomy}/...`, all of them
+    /// combined for `/taxonomies`
+    fn etag(&self, path: &str) -> Option<String> {
+        let first = path.trim_start_matches('/').split('/').next()?;
+        if first == "taxonomies" {
+            let tags: Vec<&str> = self.etags.iter().map(|etag| etag.trim_matches('"')).collect();
+            return Some(format!("\"{}\"", tags.join("+")));
+        }
+        Taxonomy::from_key(first).map(|taxonomy| self.etags[taxonomy.index()].clone())
+    }
+
+    pub(crate) fn node(&self, taxonomy: Taxonomy, id: &str) -> Option<ExportRow> {
+        let item = self.taxonomies.item(taxonomy, id)?;
+        Some(self.row(taxonomy, item))
+    }
+
+    fn row(&self, taxonomy: Taxonomy, item: &dyn TaxonomyItem) -> ExportRow {
+        ExportRow::from_item(item, self.depth(taxonomy, item.unique_id()))
+    }
+
+    /// Number of ancestors
+    fn depth(&self, taxonomy: Taxonomy, id: &str) -> usize {
+        let index = self.taxonomies.child_index(taxonomy);
+        index.position(id).map_or(0, |position| index.ancestors(position).len())
+    }
+
+    /// Case-insensitive substring match on ID and name, in file order
+    pub(crate) fn search(&self, taxonomy: Taxonomy, query: &str, limit: usize) -> Vec<ExportRow> {
+        self.taxonomies
+            .search(taxonomy, query, limit)
+            .into_iter()
+            .map(|item| self.row(taxonomy, item))
+            .collect()
+    }
+
+    /// Nodes without a known parent, in file order
+    pub(crate) fn roots(&self, taxonomy: Taxonomy) -> Vec<ExportRow> {
+        let index = self.taxonomies.child_index(taxonomy);
+        self.taxonomies
+            .items(taxonomy)
+            .into_iter()
+            .enumerate()
+            .filter(|(position, _)| index.parent(*position).is_none())
+            .map(|(_, item)| ExportRow::from_item(item, 0))
+            .collect()
+    }
+
+    /// Direct children of `id` in file order
+    pub(crate) fn children(&self, taxonomy: Taxonomy, id: &str) -> Vec<ExportRow> {
+        let index = self.taxonomies.child_index(taxonomy);
+        let Some(position) = index.position(id) else {
+            return Vec::new();
+        };
+        let depth = index.ancestors(position).len() + 1;
+        index
+            .children(position)
+            .filter_map(|child| self.taxonomies.item_at(taxonomy, child))
+            .map(|item| ExportRow::from_item(item, depth))
+            .collect()
+    }
+
+    /// Parents of `id` from the root down
+    pub(crate) fn ancestors(&self, taxonomy: Taxonomy, id: &str) -> Vec<ExportRow> {
+        self.taxonomies
+            .ancestors(taxonomy, id)
+            .into_iter()
+            .map(|item| self.row(taxonomy, item))
+            .collect()
+    }
+
+    /// Every node below `id` in depth-first order
+    pub(crate) fn descendants(&self, taxonomy: Taxonomy, id: &str) -> Vec<ExportRow> {
+        let index = self.taxonomies.child_index(taxonomy);
+        let Some(position) = index.position(id) else {
+            return Vec::new();
+        };
+        let base_depth = index.ancestors(position).len();
+        index
+            .descendants(position)
+            .filter_map(|(descendant, distance)| {
+                let item = self.taxonomies.item_at(taxonomy, descendant)?;
+                Some(ExportRow::from_item(item, base_depth + distance))
+            })
+            .collect()
+    }
+}
+
+/// Version-derived ETag of a taxonomy, e.g. `"content-3.1-0.4.0-1f2e..."`: the release, the iab
+/// version (which shapes the JSON) and a hash of the rows, so a `--watch` reload or another `--file`
+/// changes it too
+fn etag(taxonomies: &Taxonomies, taxonomy: Taxonomy) -> String {
+    let mut hasher = DefaultHasher::new();
+    for item in taxonomies.items(taxonomy) {
+        (item.unique_id(), item.parent(), item.name(), item.extension()).hash(&mut hasher);
+    }
+    format!("\"{}-{}-{}-{:016x}\"", taxonomy.key(), taxonomy.version(), env!("CARGO_PKG_VERSION"), hasher.finish())
+}
+
+/// Whether an `If-None-Match` header lists `etag` (weak or strong) or is `*`
+fn matches_etag(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// `ETag` and `Cache-Control` on taxonomy responses, and `304 Not Modified` for a matching
+/// `If-None-Match`. The ETag is that of the whole taxonomy, so the handler runs first and only a
+/// success becomes a `304`; an unknown node stays a `404` even for `If-None-Match: *`. The ETag
+/// is taken before the handler runs, so a `--watch` reload in between pairs old data with a new
+/// tag at worst, which the next request corrects. Responses that needed an API key (`keyed`) are
+/// only cached privately, so a shared cache cannot hand them to clients without one.
+async fn cache_headers(State((live, max_age, keyed)): State<(Arc<Live>, u32, bool)>, request: Request, next: Next) -> Response {
+    let Some(etag) = live.current().etag(request.uri().path()) else {
+        return next.run(request).await;
+    };
+    let Result::Ok(etag_value) = HeaderValue::from_str(&etag) else {
+        return next.run(request).await;
+    };
+    let scope = if keyed { "private" } else { "public" };
+    let cache_control = HeaderValue::from_str(&format!("{}, max-age={}", scope, max_age)).unwrap_or(HeaderValue::from_static("no-cache"));
+
+    let not_modified = request
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| matches_etag(value, &etag));
+    let response = next.run(request).await;
+    if !response.status().is_success() {
+        return response;
+    }
+    let mut response = if not_modified { StatusCode::NOT_MODIFIED.into_response() } else { response };
+    response.headers_mut().insert(header::ETAG, etag_value);
+    response.headers_mut().insert(header::CACHE_CONTROL, cache_control);
+    if keyed {
+        response.headers_mut().insert(header::VARY, HeaderValue::from_static("Authorization, X-API-Key"));
+    }
+    response
+}
+
+/// Compare without returning early, so response times do not tell how much of a key was right
+fn same_key(given: &str, key: &str) -> bool {
+    given.len() == key.len() && given.bytes().zip(key.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// The key a request carries, from `Authorization: Bearer` or `X-API-Key`
+fn request_key(request: &Request) -> Option<&str> {
+    let headers = request.headers();
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    bearer.or_else(|| headers.get("x-api-key").and_then(|value| value.to_str().ok())).map(str::trim)
+}
+
+/// Refuse API requests without a known key with `401`, and log each request with its key's name
+async fn authenticate(State(keys): State<Arc<Vec<ApiKey>>>, request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let Some(key) = request_key(&request).and_then(|given| keys.iter().find(|key| same_key(given, &key.key))) else {
+        tracing::warn!(%method, path, "request without a valid API key");
+        let mut response = ApiError(StatusCode::UNAUTHORIZED, "missing or unknown API key".to_string()).into_response();
+        response.headers_mut().insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+        return response;
+    };
+    let started = Instant::now();
+    let response = next.run(request).await;
+    tracing::info!(key = key.name, %method, path, status = response.status().as_u16(), elapsed = ?started.elapsed(), "request");
+    response
+}
+
+/// The `ServerState` requests read, replaced as a whole when `--watch` reloads the files, so a
+/// request sees either the old or the new taxonomies and never a mix
+pub(crate) struct Live(RwLock<Arc<ServerState>>);
+
+impl Live {
+    pub(crate) fn new(state: ServerState) -> Self {
+        Self(RwLock::new(Arc::new(state)))
+    }
+
+    pub(crate) fn current(&self) -> Arc<ServerState> {
+        Arc::clone(&self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner()))
+    }
+
+    fn replace(&self, state: ServerState) {
+        *self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = Arc::new(state);
+    }
+}
+
+/// Body of every non-2xx response
+#[derive(Serialize, ToSchema)]
+struct ErrorBody {
+    error: String,
+}
+
+/// An `ErrorBody` response with a status code
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(ErrorBody { error: self.1 })).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<Json<T>, ApiError>;
+
+/// Resolve the `/{taxonomy}/{version}` prefix; 3.0 and 3.1 address the same Content IDs
+fn resolve(taxonomy: &str, version: &str) -> std::result::Result<Taxonomy, ApiError> {
+    let not_found = || ApiError(StatusCode::NOT_FOUND, format!("unknown taxonomy {}/{}", taxonomy, version));
+    let resolved = Taxonomy::from_key(taxonomy).ok_or_else(not_found)?;
+    if !crosswalk::same_ids(resolved.version(), version) {
+        return Err(not_found());
+    }
+    std::result::Result::Ok(resolved)
+}
+
+#[derive(Serialize, ToSchema)]
+struct TaxonomyInfo {
+    name: &'static str,
+    version: &'static str,
+    cattax: u32,
+    nodes: usize,
+    path: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/taxonomies",
+    responses((status = 200, description = "Bundled taxonomies", body = [TaxonomyInfo]))
+)]
+async fn list_taxonomies(State(live): State<Arc<Live>>) -> Json<Vec<TaxonomyInfo>> {
+    let state = live.current();
+    Json(
+        Taxonomy::ALL
+            .into_iter()
+            .map(|taxonomy| TaxonomyInfo {
+                name: taxonomy.name(),
+                version: taxonomy.version(),
+                cattax: taxonomy.cattax(),
+                nodes: state.taxonomies.items(taxonomy).len(),
+                path: format!("/{}/{}", taxonomy.key(), taxonomy.version()),
+            })
+            .collect(),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/{taxonomy}/{version}/node/{id}",
+    params(
+        ("taxonomy" = String, Path, description = "product, content or audience"),
+        ("version" = String, Path, description = "Taxonomy version, e.g. 3.1"),
+        ("id" = String, Path, description = "Unique ID of the node"),
+    ),
+    responses(
+        (status = 200, description = "The node", body = ExportRow),
+        (status = 404, description = "Unknown taxonomy or node", body = ErrorBody),
+    )
+)]
+async fn node(
+    State(live): State<Arc<Live>>,
+    Path((taxonomy, version, id)): Path<(String, String, String)>,
+) -> ApiResult<ExportRow> {
+    let state = live.current();
+    let taxonomy = resolve(&taxonomy, &version)?;
+    let row = state
+        .node(taxonomy, &id)
+        .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, format!("unknown node {}", id)))?;
+    std::result::Result::Ok(Json(row))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{taxonomy}/{version}/node/{id}/descendants",
+    params(
+        ("taxonomy" = String, Path, description = "product, content or audience"),
+        ("version" = String, Path, description = "Taxonomy version, e.g. 3.1"),
+        ("id" = String, Path, description = "Unique ID of the node"),
+    ),
+    responses(
+        (status = 200, description = "All nodes below the node, depth first", body = [ExportRow]),
+        (status = 404, description = "Unknown taxonomy or node", body = ErrorBody),
+    )
+)]
+async fn descendants(
+    State(live): State<Arc<Live>>,
+    Path((taxonomy, version, id)): Path<(String, String, String)>,
+) -> ApiResult<Vec<ExportRow>> {
+    let state = live.current();
+    let taxonomy = resolve(&taxonomy, &version)?;
+    if state.taxonomies.item(taxonomy, &id).is_none() {
+        return Err(ApiError(StatusCode::NOT_FOUND, format!("unknown node {}", id)));
+    }
+    std::result::Result::Ok(Json(state.descendants(taxonomy, &id)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{taxonomy}/{version}/roots",
+    params(
+        ("taxonomy" = String, Path, description = "product, content or audience"),
+        ("version" = String, Path, description = "Taxonomy version, e.g. 3.1"),
+    ),
+    responses(
+        (status = 200, description = "Top-level nodes in file order", body = [ExportRow]),
+        (status = 404, description = "Unknown taxonomy", body = ErrorBody),
+    )
+)]
+async fn roots(
+    State(live): State<Arc<Live>>,
+    Path((taxonomy, version)): Path<(String, String)>,
+) -> ApiResult<Vec<ExportRow>> {
+    let state = live.current();
+    let taxonomy = resolve(&taxonomy, &version)?;
+    std::result::Result::Ok(Json(state.roots(taxonomy)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{taxonomy}/{version}/node/{id}/children",
+    params(
+        ("taxonomy" = String, Path, description = "product, content or audience"),
+        ("version" = String, Path, description = "Taxonomy version, e.g. 3.1"),
+        ("id" = String, Path, description = "Unique ID of the node"),
+    ),
+    responses(
+        (status = 200, description = "Direct children of the node in file order", body = [ExportRow]),
+        (status = 404, description = "Unknown taxonomy or node", body = ErrorBody),
+    )
+)]
+async fn children(
+    State(live): State<Arc<Live>>,
+    Path((taxonomy, version, id)): Path<(String, String, String)>,
+) -> ApiResult<Vec<ExportRow>> {
+    let state = live.current();
+    let taxonomy = resolve(&taxonomy, &version)?;
+    if state.taxonomies.item(taxonomy, &id).is_none() {
+        return Err(ApiError(StatusCode::NOT_FOUND, format!("unknown node {}", id)));
+    }
+    std::result::Result::Ok(Json(state.children(taxonomy, &id)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{taxonomy}/{version}/node/{id}/ancestors",
+    params(
+        ("taxonomy" = String, Path, description = "product, content or audience"),
+        ("version" = String, Path, description = "Taxonomy version, e.g. 3.1"),
+        ("id" = String, Path, description = "Unique ID of the node"),
+    ),
+    responses(
+        (status = 200, description = "Parents of the node from the root down", body = [ExportRow]),
+        (status = 404, description = "Unknown taxonomy or node", body = ErrorBody),
+    )
+)]
+async fn ancestors(
+    State(live): State<Arc<Live>>,
+    Path((taxonomy, version, id)): Path<(String, String, String)>,
+) -> ApiResult<Vec<ExportRow>> {
+    let state = live.current();
+    let taxonomy = resolve(&taxonomy, &version)?;
+    if state.taxonomies.item(taxonomy, &id).is_none() {
+        return Err(ApiError(StatusCode::NOT_FOUND, format!("unknown node {}", id)));
+    }
+    std::result::Result::Ok(Json(state.ancestors(taxonomy, &id)))
+}
+
+#[derive(Deserialize, IntoParams)]
+struct SearchParams {
+    /// Text to find in IDs and names, case-insensitive
+    q: String,
+    /// Maximum number of results (default 100)
+    limit: Option<usize>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/{taxonomy}/{version}/search",
+    params(
+        ("taxonomy" = String, Path, description = "product, content or audience"),
+        ("version" = String, Path, description = "Taxonomy version, e.g. 3.1"),
+        SearchParams,
+    ),
+    responses(
+        (status = 200, description = "Matching nodes in file order", body = [ExportRow]),
+        (status = 404, description = "Unknown taxonomy", body = ErrorBody),
+    )
+)]
+async fn search(
+    State(live): State<Arc<Live>>,
+    Path((taxonomy, version)): Path<(String, String)>,
+    Query(params): Query<SearchParams>,
+) -> ApiResult<Vec<ExportRow>> {
+    let state = live.current();
+    let taxonomy = resolve(&taxonomy, &version)?;
+    let limit = params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    std::result::Result::Ok(Json(state.search(taxonomy, &params.q, limit)))
+}
+
+#[derive(Serialize, ToSchema)]
+struct LookupResult {
+    /// Nodes of the IDs found, in request order
+    nodes: Vec<ExportRow>,
+    /// Requested IDs the taxonomy does not have
+    missing: Vec<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/{taxonomy}/{version}/lookup",
+    params(
+        ("taxonomy" = String, Path, description = "product, content or audience"),
+        ("version" = String, Path, description = "Taxonomy version, e.g. 3.1"),
+    ),
+    request_body(content = [String], description = "Unique IDs to look up, at most `--max-body` bytes of JSON"),
+    responses(
+        (status = 200, description = "Nodes of the known IDs and the unknown ones", body = LookupResult),
+        (status = 404, description = "Unknown taxonomy", body = ErrorBody),
+        (status = 413, description = "Body larger than `--max-body`", body = ErrorBody),
+    )
+)]
+async fn lookup(
+    State(live): State<Arc<Live>>,
+    Path((taxonomy, version)): Path<(String, String)>,
+    Json(ids): Json<Vec<String>>,
+) -> ApiResult<LookupResult> {
+    let state = live.current();
+    let taxonomy = resolve(&taxonomy, &version)?;
+    let mut result = LookupResult { nodes: Vec::new(), missing: Vec::new() };
+    for id in ids {
+        match state.node(taxonomy, &id) {
+            Some(row) => result.nodes.push(row),
+            None => result.missing.push(id),
+        }
+    }
+    std::result::Result::Ok(Json(result))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "iab", description = "IAB Tech Lab Product, Content and Audience taxonomies"),
+    paths(list_taxonomies, roots, node, children, ancestors, descendants, search, lookup),
+    components(schemas(TaxonomyInfo, ExportRow, ErrorBody, LookupResult))
+)]
+struct ApiDoc;
+
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Swagger UI pulled from a CDN, pointed at `/openapi.json`
+async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI)
+}
+
+const SWAGGER_UI: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+  <title>iab API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });</script>
+</body>
+</html>
+"##;
+
+/// Browser UI (`--ui`), compiled into the binary so the server stays a single file
+const UI_INDEX: &str = include_str!("../assets/ui/index.html");
+const UI_SCRIPT: &str = include_str!("../assets/ui/app.js");
+const UI_STYLE: &str = include_str!("../assets/ui/style.css");
+
+async fn ui_index() -> Html<&'static str> {
+    Html(UI_INDEX)
+}
+
+async fn ui_script() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/javascript; charset=utf-8")], UI_SCRIPT)
+}
+
+async fn ui_style() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "text/css; charset=utf-8")], UI_STYLE)
+}
+
+fn router(live: Arc<Live>, options: &ServeOptions) -> Router {
+    let state = Arc::clone(&live);
+    let api = Router::new()
+        .route("/taxonomies", get(list_taxonomies))
+        .route("/{taxonomy}/{version}/roots", get(roots))
+        .route("/{taxonomy}/{version}/node/{id}", get(node))
+        .route("/{taxonomy}/{version}/node/{id}/children", get(children))
+        .route("/{taxonomy}/{version}/node/{id}/ancestors", get(ancestors))
+        .route("/{taxonomy}/{version}/node/{id}/descendants", get(descendants))
+        .route("/{taxonomy}/{version}/search", get(search))
+        .route_layer(middleware::from_fn_with_state((state.clone(), options.max_age, !options.api_keys.is_empty()), cache_headers))
+        // The answer depends on the body, so the lookup gets no ETag
+        .route("/{taxonomy}/{version}/lookup", post(lookup))
+        .with_state(state.clone());
+
+    #[cfg(feature = "graphql")]
+    let api = api.merge(crate::graphql::router(state));
+    #[cfg(not(feature = "graphql"))]
+    let _ = state;
+
+    // The docs, metrics and the UI's files hold no taxonomy data and stay open
+    let api = if options.api_keys.is_empty() {
+        api
+    } else {
+        api.route_layer(middleware::from_fn_with_state(Arc::new(options.api_keys.clone()), authenticate))
+    };
+    let metrics = Arc::new(Metrics::default());
+    let router = api
+        .route("/openapi.json", get(openapi))
+        .route("/docs", get(swagger_ui))
+        .merge(Router::new().route("/metrics", get(metrics::metrics)).with_state((Arc::clone(&metrics), live)));
+
+    let router = if options.ui {
+        router
+            .route("/", get(ui_index))
+            .route("/ui/app.js", get(ui_script))
+            .route("/ui/style.css", get(ui_style))
+    } else {
+        router
+    };
+    let router = router.layer(middleware::from_fn_with_state(options.max_body, limits::limit_body));
+    let router = match options.rate_limit {
+        Some(per_minute) => router.layer(middleware::from_fn_with_state(Arc::new(RateLimiter::new(per_minute)), limits::rate_limit)),
+        None => router,
+    };
+    router.layer(middleware::from_fn_with_state(metrics, metrics::track))
+}
+
+/// Serve the JSON API, and the browser UI at `/` with `ui`, until Ctrl-C. `files` replace bundled
+/// taxonomies; with `watch` they are reloaded whenever one changes on disk.
+pub fn serve(options: ServeOptions, files: Vec<TaxonomyFile>, load_options: LoadOptions) -> Result<()> {
+    let live = Arc::new(Live::new(ServerState::new(Taxonomies::load_files(&files, &load_options, |_| {})?)));
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind((options.host.as_str(), options.port))
+            .await
+            .with_context(|| format!("cannot listen on {}:{}", options.host, options.port))?;
+        let address = listener.local_addr()?;
+        eprintln!("Serving taxonomies on http://{}", address);
+        if options.ui {
+            eprintln!("Browser UI on http://{}/", address);
+        }
+        if options.api_keys.is_empty() {
+            if !address.ip().is_loopback() {
+                eprintln!("Warning: the API is open to anyone reaching {}; set [api-keys] in the config or {}", address, API_KEYS_ENV);
+            }
+        } else {
+            eprintln!("API requests need one of {} keys", options.api_keys.len());
+        }
+        if options.watch {
+            tokio::spawn(watch_files(Arc::clone(&live), files, load_options));
+        }
+        axum::serve(listener, router(live, &options).into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(async {
+                let _ = tokio::signal::ctrl_c().await;
+            })
+            .await?;
+        Ok(())
+    })
+}
+
+/// Modification times of `files`; `None` for one that cannot be read right now
+fn modified(files: &[TaxonomyFile]) -> Vec<Option<SystemTime>> {
+    files.iter().map(|file| fs::metadata(&file.path).and_then(|metadata| metadata.modified()).ok()).collect()
+}
+
+/// Poll the files and swap in freshly loaded taxonomies after a change. A file that fails to load
+/// or has no rows, e.g. while it is still being written, keeps the previous data until the next
+/// change.
+async fn watch_files(live: Arc<Live>, files: Vec<TaxonomyFile>, options: LoadOptions) {
+    let files = Arc::new(files);
+    let options = Arc::new(options);
+    let mut seen = modified(&files);
+    loop {
+        tokio::time::sleep(WATCH_INTERVAL).await;
+        let current = modified(&files);
+        if current == seen {
+            continue;
+        }
+        seen = current;
+        let (load_files, load_options) = (Arc::clone(&files), Arc::clone(&options));
+        let loaded = tokio::task::spawn_blocking(move || {
+            let taxonomies = Taxonomies::load_files(&load_files, &load_options, |_| {})?;
+            if let Some(file) = load_files.iter().find(|file| taxonomies.items(file.taxonomy).is_empty()) {
+                bail!("{} has no rows", file.path.display());
+            }
+            Ok(taxonomies)
+        })
+        .await;
+        match loaded {
+            std::result::Result::Ok(Result::Ok(taxonomies)) => {
+                live.replace(ServerState::new(taxonomies));
+                tracing::info!("reloaded the taxonomy files");
+                eprintln!("Reloaded the taxonomy files");
+            }
+            std::result::Result::Ok(Err(err)) => {
+                tracing::warn!("{:#}", err);
+                eprintln!("Keeping the previous data: {:#}", err);
+            }
+            Err(err) => eprintln!("Keeping the previous data: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{self, Body};
+    use tower::ServiceExt;
+
+    fn app_with(api_keys: Vec<ApiKey>, max_body: usize) -> Router {
+        let live = Arc::new(Live::new(ServerState::new(Taxonomies::load(|_| {}).expect("bundled data loads"))));
+        let options = ServeOptions {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            ui: false,
+            watch: false,
+            max_age: DEFAULT_MAX_AGE,
+            api_keys,
+            rate_limit: None,
+            max_body,
+        };
+        router(live, &options)
+    }
+
+    fn app(max_body: usize) -> Router {
+        app_with(Vec::new(), max_body)
+    }
+
+    fn lookup_request(body: String) -> Request {
+        Request::post("/content/3.1/lookup")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .expect("valid request")
+    }
+
+    #[tokio::test]
+    async fn lookup_answers_known_and_missing_ids() {
+        let response = app(limits::DEFAULT_MAX_BODY).oneshot(lookup_request(r#"["150", "nope", "1"]"#.to_string())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(header::ETAG).is_none());
+        let body = body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let result: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let ids: Vec<_> = result["nodes"].as_array().unwrap().iter().map(|node| node["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, ["150", "1"]);
+        assert_eq!(result["missing"], serde_json::json!(["nope"]));
+    }
+
+    #[tokio::test]
+    async fn lookup_refuses_bodies_over_the_limit() {
+        let ids = serde_json::to_string(&vec!["150"; 100]).unwrap();
+        assert_eq!(app(1024).oneshot(lookup_request(ids.clone())).await.unwrap().status(), StatusCode::OK);
+
+        // Refused from the declared length, and without one while reading the body
+        let mut declared = lookup_request(ids.clone());
+        declared.headers_mut().insert(header::CONTENT_LENGTH, HeaderValue::from(ids.len()));
+        assert_eq!(app(64).oneshot(declared).await.unwrap().status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(app(64).oneshot(lookup_request(ids)).await.unwrap().status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    fn roots_request(if_none_match: Option<&str>) -> Request {
+        let mut request = Request::get("/content/3.1/roots");
+        if let Some(tags) = if_none_match {
+            request = request.header(header::IF_NONE_MATCH, tags);
+        }
+        request.body(Body::empty()).expect("valid request")
+    }
+
+    #[tokio::test]
+    async fn matching_etag_answers_not_modified() {
+        let app = app(limits::DEFAULT_MAX_BODY);
+        let response = app.clone().oneshot(roots_request(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()[header::CACHE_CONTROL].to_str().unwrap().starts_with("public, max-age="));
+        let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+
+        for tags in [etag.clone(), format!("W/{}", etag), format!("\"other\", {}", etag), "*".to_string()] {
+            let response = app.clone().oneshot(roots_request(Some(&tags))).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_MODIFIED, "If-None-Match: {}", tags);
+            assert_eq!(response.headers()[header::ETAG], etag.as_str());
+            assert!(body::to_bytes(response.into_body(), usize::MAX).await.unwrap().is_empty());
+        }
+        let response = app.clone().oneshot(roots_request(Some("\"stale\""))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The ETag covers the whole taxonomy, so a node that is not in it is still not found
+        let unknown = Request::get("/content/3.1/node/nope").header(header::IF_NONE_MATCH, "*").body(Body::empty()).unwrap();
+        let response = app.oneshot(unknown).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(response.headers().get(header::ETAG).is_none());
+    }
+
+    #[test]
+    fn if_none_match_lists_are_parsed() {
+        let etag = "\"content-3.1\"";
+        assert!(matches_etag("\"content-3.1\"", etag));
+        assert!(matches_etag("W/\"content-3.1\"", etag));
+        assert!(matches_etag("\"a\",W/\"content-3.1\" , \"b\"", etag));
+        assert!(matches_etag(" * ", etag));
+        assert!(!matches_etag("\"content-3.0\", \"b\"", etag));
+        assert!(!matches_etag("content-3.1", etag));
+        assert!(!matches_etag("", etag));
+    }
+
+    #[tokio::test]
+    async fn api_requests_need_a_known_key() {
+        let app = app_with(vec![ApiKey { name: "dashboard".to_string(), key: "s3cret".to_string() }], limits::DEFAULT_MAX_BODY);
+        let request = |header: Option<(&str, &str)>| {
+            let mut request = Request::get("/taxonomies");
+            if let Some((name, value)) = header {
+                request = request.header(name, value);
+            }
+            request.body(Body::empty()).expect("valid request")
+        };
+
+        let missing = app.clone().oneshot(request(None)).await.unwrap();
+        assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(missing.headers()[header::WWW_AUTHENTICATE], "Bearer");
+        for wrong in [("authorization", "Bearer s3cre"), ("authorization", "Bearer s3cret!"), ("x-api-key", "other"), ("authorization", "s3cret")] {
+            assert_eq!(app.clone().oneshot(request(Some(wrong))).await.unwrap().status(), StatusCode::UNAUTHORIZED, "{:?}", wrong);
+        }
+        for right in [("authorization", "Bearer s3cret"), ("x-api-key", "s3cret"), ("x-api-key", " s3cret ")] {
+            let response = app.clone().oneshot(request(Some(right))).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{:?}", right);
+            // Kept out of shared caches, which would serve it to clients without a key
+            assert!(response.headers()[header::CACHE_CONTROL].to_str().unwrap().starts_with("private, max-age="));
+            assert_eq!(response.headers()[header::VARY], "Authorization, X-API-Key");
+        }
+        // A key in the query string is not accepted, since URLs end up in proxy logs
+        let query = Request::get("/taxonomies?api_key=s3cret").body(Body::empty()).unwrap();
+        assert_eq!(app.clone().oneshot(query).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        // The docs stay open
+        let docs = Request::get("/openapi.json").body(Body::empty()).unwrap();
+        assert_eq!(app.oneshot(docs).await.unwrap().status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn metrics_count_requests_by_route() {
+        let app = app(limits::DEFAULT_MAX_BODY);
+        for path in ["/content/3.1/roots", "/content/3.1/roots", "/wp-login.php", "/.env"] {
+            app.clone().oneshot(Request::get(path).body(Body::empty()).unwrap()).await.unwrap();
+        }
+        let response = app.oneshot(Request::get("/metrics").body(Body::empty()).unwrap()).await.unwrap();
+        let body = body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+
+        let value = |series: &str| -> u64 {
+            let line = text.lines().find(|line| line.starts_with(series)).unwrap_or_else(|| panic!("no {} in\n{}", series, text));
+            line.rsplit(' ').next().unwrap().parse().unwrap()
+        };
+        let roots = r#"method="GET",route="/{taxonomy}/{version}/roots""#;
+        assert_eq!(value(&format!("iab_http_requests_total{{{},status=\"200\"}}", roots)), 2);
+        // Unknown paths share one series instead of adding one each
+        assert_eq!(value(r#"iab_http_requests_total{method="GET",route="unmatched",status="404"}"#), 2);
+
+        let buckets: Vec<u64> = text
+            .lines()
+            .filter(|line| line.starts_with(&format!("iab_http_request_duration_seconds_bucket{{{},", roots)))
+            .map(|line| line.rsplit(' ').next().unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(buckets.len(), 11);
+        assert!(buckets.windows(2).all(|pair| pair[0] <= pair[1]), "buckets are not cumulative: {:?}", buckets);
+        assert_eq!(value(&format!("iab_http_request_duration_seconds_bucket{{{},le=\"+Inf\"}}", roots)), 2);
+        assert_eq!(value(&format!("iab_http_request_duration_seconds_count{{{}}}", roots)), 2);
+    }
+
+    #[test]
+    fn keys_compare_whole() {
+        assert!(same_key("s3cret", "s3cret"));
+        assert!(!same_key("s3cre", "s3cret"));
+        assert!(!same_key("s3cret", "s3cre"));
+        assert!(!same_key("s3cret2", "s3cret"));
+        assert!(!same_key("", "s3cret"));
+        assert!(!same_key("S3cret", "s3cret"));
+    }
+}
@@ -0,0 +1,27 @@
+use anyhow::*;
+use serde::{Deserialize, Serialize};
+
+use crate::state;
+use crate::{Datasource, SortMode};
+
+/// Snapshot of the browsing state, written on exit and restored with `--resume`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Session {
+    pub datasource: Datasource,
+    pub sort_mode: SortMode,
+    pub filter: String,
+    pub zoom_root: Option<String>,
+    pub opened: Vec<Vec<String>>,
+    pub selected: Vec<String>,
+}
+
+impl Session {
+    /// Load the last session, if one was saved
+    pub fn load() -> Result<Option<Self>> {
+        state::SESSION.load()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        state::SESSION.save(self)
+    }
+}
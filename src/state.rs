@@ -0,0 +1,182 @@
+//! Persisted user state under `$XDG_STATE_HOME/iab` (`~/.local/state/iab`): one `Store` per file,
+//! so features share the loading, saving and schema handling, and `iab state` can list and clear
+//! everything the TUI keeps between runs.
+//!
+//! Files are JSON envelopes, `{"schema": 1, "data": ...}`. A store whose layout changes bumps its
+//! `schema` and converts older data in `Store::load`; files from a newer `iab` are refused instead
+//! of being overwritten with less. Files from before the envelope are read as schema 1.
+
+use anyhow::*;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by `--headless` and `--kiosk`, so scripted runs and shared kiosks neither see nor change
+/// the user's saved state
+pub static EPHEMERAL: AtomicBool = AtomicBool::new(false);
+
+/// Prefix of the panic reports `crash.rs` writes next to the stores
+pub const CRASH_PREFIX: &str = "crash-";
+
+/// One persisted file in the state directory
+pub struct Store {
+    /// Name on the `iab state` command line
+    pub name: &'static str,
+    pub file: &'static str,
+    /// Version of the data layout, written into the file
+    pub schema: u64,
+    pub description: &'static str,
+}
+
+pub const BOOKMARKS: Store = Store { name: "bookmarks", file: "bookmarks.json", schema: 1, description: "starred nodes (Ctrl+b)" };
+pub const NOTES: Store = Store { name: "notes", file: "notes.json", schema: 1, description: "notes on nodes (F9)" };
+pub const TAGS: Store = Store { name: "tags", file: "tags.json", schema: 1, description: "tags on nodes (F10)" };
+pub const RECENT: Store = Store { name: "recent", file: "recent.json", schema: 1, description: "recently viewed nodes (Ctrl+r)" };
+pub const BASKET: Store = Store { name: "basket", file: "basket.json", schema: 1, description: "collected nodes (Ctrl+y)" };
+pub const LAYOUT: Store = Store { name: "layout", file: "layout.json", schema: 1, description: "layout and pane sizes (F2-F6)" };
+pub const SESSION: Store = Store { name: "session", file: "session.json", schema: 1, description: "last session, restored with --resume" };
+
+pub const STORES: [&Store; 7] = [&BOOKMARKS, &NOTES, &TAGS, &RECENT, &BASKET, &LAYOUT, &SESSION];
+
+/// Directory for persisted user state (`$XDG_STATE_HOME/iab` or `~/.local/state/iab`); `None`
+/// turns loading and saving state into no-ops
+pub fn dir() -> Option<PathBuf> {
+    if EPHEMERAL.load(Ordering::Relaxed) {
+        return None;
+    }
+    let base = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local").join("state"),
+    };
+    Some(base.join("iab"))
+}
+
+impl Store {
+    pub fn path(&self) -> Option<PathBuf> {
+        dir().map(|dir| dir.join(self.file))
+    }
+
+    /// The stored data; `None` when nothing was saved yet or state is off
+    pub fn load<T: DeserializeOwned>(&self) -> Result<Option<T>> {
+        let Some(path) = self.path() else {
+            return Ok(None);
+        };
+        let data = match fs::read_to_string(&path) {
+            Result::Ok(data) => data,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err).with_context(|| format!("cannot read {}", path.display())),
+        };
+        self.decode(&path, &data).map(Some)
+    }
+
+    /// The data of the contents of `path`, refusing a newer schema than this store's
+    fn decode<T: DeserializeOwned>(&self, path: &Path, data: &str) -> Result<T> {
+        let (schema, data) = self.unwrap(data).with_context(|| format!("cannot parse {}", path.display()))?;
+        if schema > self.schema {
+            bail!("{} has {} schema {}, newer than this iab reads ({})", path.display(), self.name, schema, self.schema);
+        }
+        serde_json::from_value(data).with_context(|| format!("cannot parse {}", path.display()))
+    }
+
+    pub fn save<T: Serialize>(&self, value: &T) -> Result<()> {
+        let Some(path) = self.path() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let envelope = json!({ "schema": self.schema, "data": value });
+        fs::write(&path, serde_json::to_string_pretty(&envelope)?).with_context(|| format!("cannot write {}", path.display()))
+    }
+
+    /// Schema and data of a file's contents
+    fn unwrap(&self, data: &str) -> Result<(u64, Value)> {
+        let value: Value = serde_json::from_str(data)?;
+        match value {
+            Value::Object(mut envelope) if envelope.len() == 2 && envelope.contains_key("data") => {
+                let schema = envelope.get("schema").and_then(Value::as_u64).context("the schema is not a number")?;
+                Ok((schema, envelope.remove("data").unwrap_or_default()))
+            }
+            legacy => Ok((1, legacy)),
+        }
+    }
+
+    /// Schema written in the file, if it exists and parses
+    pub fn file_schema(&self) -> Option<u64> {
+        let data = fs::read_to_string(self.path()?).ok()?;
+        self.unwrap(&data).ok().map(|(schema, _)| schema)
+    }
+
+    /// Delete the file, returning whether there was one
+    pub fn clear(&self) -> Result<bool> {
+        let Some(path) = self.path() else {
+            return Ok(false);
+        };
+        match fs::remove_file(&path) {
+            Result::Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err).with_context(|| format!("cannot remove {}", path.display())),
+        }
+    }
+}
+
+pub fn find(name: &str) -> Result<&'static Store> {
+    STORES.into_iter().find(|store| store.name == name).with_context(|| {
+        let names: Vec<&str> = STORES.iter().map(|store| store.name).collect();
+        format!("unknown state {:?} (use {}, or crashes)", name, names.join(", "))
+    })
+}
+
+/// Crash reports in the state directory, oldest first
+pub fn crash_reports() -> Result<Vec<PathBuf>> {
+    let Some(dir) = dir() else {
+        return Ok(Vec::new());
+    };
+    let entries = match fs::read_dir(&dir) {
+        Result::Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_context(|| format!("cannot read {}", dir.display())),
+    };
+    let mut reports: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.file_name().is_some_and(|name| name.to_string_lossy().starts_with(CRASH_PREFIX)))
+        .collect();
+    reports.sort();
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST: Store = Store { name: "list", file: "list.json", schema: 2, description: "a list of IDs" };
+
+    fn decode(data: &str) -> Result<Vec<String>> {
+        LIST.decode(Path::new("list.json"), data)
+    }
+
+    #[test]
+    fn envelopes_are_unwrapped() {
+        assert_eq!(LIST.unwrap(r#"{"schema": 2, "data": ["1"]}"#).unwrap(), (2, json!(["1"])));
+        assert_eq!(decode(r#"{"data": ["1", "2"], "schema": 1}"#).unwrap(), ["1", "2"]);
+        assert!(decode(r#"{"schema": "2", "data": ["1"]}"#).is_err());
+    }
+
+    #[test]
+    fn files_from_before_the_envelope_are_schema_1() {
+        assert_eq!(LIST.unwrap(r#"["1", "2"]"#).unwrap(), (1, json!(["1", "2"])));
+        assert_eq!(decode(r#"["1", "2"]"#).unwrap(), ["1", "2"]);
+        // Only an object of exactly `schema` and `data` is an envelope
+        assert_eq!(LIST.unwrap(r#"{"data": 1}"#).unwrap(), (1, json!({ "data": 1 })));
+    }
+
+    #[test]
+    fn newer_schemas_are_refused() {
+        let err = decode(r#"{"schema": 3, "data": ["1"]}"#).unwrap_err();
+        assert_eq!(err.to_string(), "list.json has list schema 3, newer than this iab reads (2)");
+    }
+}
@@ -0,0 +1,159 @@
+//! Keyword-based category suggestions for free text.
+//!
+//! Each category is described by the words of its name, its ancestors' tier names and optional
+//! synonyms. Words shared with the text count by their inverse document frequency, so "Cricket"
+//! outweighs a word like "Sports" that many categories carry, and a category ranks high when it
+//! both matches most of its own words and explains most of the text.
+
+use anyhow::*;
+use crate::{Taxonomies, Taxonomy, TaxonomyItem};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+/// Words too common to say anything about a category, including URL noise for classifying addresses
+const STOPWORDS: [&str; 18] = [
+    "and", "the", "for", "with", "other", "from", "that", "this", "are", "was", "you", "your", "http", "https", "www",
+    "com", "html", "php",
+];
+
+/// Ancestor tiers describe a category less precisely than its own name
+const TIER_WEIGHT: f64 = 0.5;
+
+pub struct Suggestion<'a> {
+    pub item: &'a dyn TaxonomyItem,
+    /// Geometric mean of the category's and the text's weighted word shares that match, 0 to 1
+    pub score: f64,
+}
+
+/// Extra words per category ID, e.g. "football" for Soccer
+pub type Synonyms = HashMap<String, Vec<String>>;
+
+/// Read synonyms from a TSV file: category ID, then comma-separated terms
+pub fn load_synonyms(path: &Path) -> Result<Synonyms> {
+    let data = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+    let mut synonyms = Synonyms::new();
+    for line in data.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#')) {
+        let Some((id, terms)) = line.split_once('\t') else {
+            bail!("{}: expected <id>\\t<terms>, got {:?}", path.display(), line);
+        };
+        synonyms
+            .entry(id.trim().to_string())
+            .or_default()
+            .extend(terms.split(',').map(|term| term.trim().to_string()).filter(|term| !term.is_empty()));
+    }
+    Ok(synonyms)
+}
+
+/// Category word weights and document frequencies of one taxonomy, by position in file order.
+/// It holds no references to the rows, so a caller can keep it next to them and query it per text.
+pub struct SuggestIndex {
+    /// Word weights and tier depth of each category
+    documents: Vec<(HashMap<String, f64>, usize)>,
+    idf: HashMap<String, f64>,
+}
+
+impl SuggestIndex {
+    pub fn new(items: &[&dyn TaxonomyItem], synonyms: &Synonyms) -> Self {
+        let documents: Vec<(HashMap<String, f64>, usize)> = items
+            .iter()
+            .map(|item| {
+                let mut weights: HashMap<String, f64> = HashMap::new();
+                let tiers = item.tiers();
+                let ancestors = tiers.split_last().map_or(&[][..], |(_, ancestors)| ancestors);
+                for word in ancestors.iter().flat_map(|tier| words(tier)) {
+                    weights.insert(word, TIER_WEIGHT);
+                }
+                let terms = synonyms.get(item.unique_id()).into_iter().flatten().map(String::as_str);
+                for word in std::iter::once(item.name()).chain(terms).flat_map(words) {
+                    weights.insert(word, 1.0);
+                }
+                (weights, tiers.len())
+            })
+            .collect();
+
+        let mut frequency: HashMap<&str, usize> = HashMap::new();
+        for (weights, _) in &documents {
+            for word in weights.keys() {
+                *frequency.entry(word).or_default() += 1;
+            }
+        }
+        let count = documents.len() as f64;
+        let idf = frequency
+            .into_iter()
+            .map(|(word, frequency)| (word.to_string(), (1.0 + count / frequency as f64).ln()))
+            .collect();
+
+        Self { documents, idf }
+    }
+
+    /// Positions of the categories sharing words with `text` and their scores, best first.
+    /// Equal scores prefer the more specific (deeper) category.
+    pub fn rank(&self, text: &str, limit: usize) -> Vec<(usize, f64)> {
+        let text_words: HashSet<String> = words(text).collect();
+        let idf = |word: &String| self.idf.get(word).copied().unwrap_or(0.0);
+        // Words no category uses cannot be matched, so they do not dilute the text side
+        let text_total: f64 = text_words.iter().map(idf).sum();
+
+        let mut ranked: Vec<(usize, f64)> = self
+            .documents
+            .iter()
+            .enumerate()
+            .filter_map(|(position, (weights, _))| {
+                let total: f64 = weights.iter().map(|(word, weight)| weight * idf(word)).sum();
+                let matched: Vec<(&String, &f64)> = weights.iter().filter(|(word, _)| text_words.contains(*word)).collect();
+                if matched.is_empty() {
+                    return None;
+                }
+                // How much of the category the text covers, and how much of the text the category explains
+                let category_share = matched.iter().map(|(word, weight)| *weight * idf(word)).sum::<f64>() / total;
+                let text_share = matched.iter().map(|(word, _)| idf(word)).sum::<f64>() / text_total;
+                Some((position, (category_share * text_share).sqrt()))
+            })
+            .collect();
+
+        ranked.sort_by(|(a, a_score), (b, b_score)| {
+            b_score.total_cmp(a_score).then_with(|| self.documents[*b].1.cmp(&self.documents[*a].1))
+        });
+        ranked.truncate(limit);
+        ranked
+    }
+}
+
+/// A `SuggestIndex` together with the rows it was built from, built once and queried per text
+pub struct Suggester<'a> {
+    items: Vec<&'a dyn TaxonomyItem>,
+    index: SuggestIndex,
+}
+
+impl<'a> Suggester<'a> {
+    pub fn new(items: Vec<&'a dyn TaxonomyItem>, synonyms: &Synonyms) -> Self {
+        let index = SuggestIndex::new(&items, synonyms);
+        Self { items, index }
+    }
+
+    /// Categories sharing words with `text`, best first
+    pub fn suggest(&self, text: &str, limit: usize) -> Vec<Suggestion<'a>> {
+        self.index
+            .rank(text, limit)
+            .into_iter()
+            .map(|(position, score)| Suggestion { item: self.items[position], score })
+            .collect()
+    }
+}
+
+/// Suggestions from one taxonomy without synonyms
+pub fn suggest<'a>(taxonomies: &'a Taxonomies, taxonomy: Taxonomy, text: &str, limit: usize) -> Vec<Suggestion<'a>> {
+    Suggester::new(taxonomies.items(taxonomy), &Synonyms::new()).suggest(text, limit)
+}
+
+/// Lowercase words of three or more letters, without stopwords and plural "s"
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| word.chars().count() >= 3 && !STOPWORDS.contains(&word.as_str()))
+        .map(|word| match word.strip_suffix('s') {
+            Some(stem) if !stem.ends_with('s') && stem.chars().count() >= 3 => stem.to_string(),
+            _ => word,
+        })
+}
@@ -0,0 +1,65 @@
+use anyhow::*;
+use iab::Taxonomy;
+use ratatui::style::Color;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::state;
+use crate::taxonomy_store::TaxonomyStore;
+
+/// Marker colors, picked per tag name so a tag looks the same everywhere
+const TAG_COLORS: [Color; 8] = [
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::LightYellow,
+    Color::Red,
+    Color::Green,
+];
+
+/// User-defined tags per node, keyed by taxonomy and version (`content-3.1`) and then node ID,
+/// persisted as JSON in the state directory
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Tags(TaxonomyStore<Vec<String>>);
+
+impl Tags {
+    /// Load tags from disk; a missing file yields no tags
+    pub fn load() -> Result<Self> {
+        TaxonomyStore::load(&state::TAGS).map(Self)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.0.save(&state::TAGS)
+    }
+
+    /// Tags of a taxonomy by node ID
+    pub fn of(&self, taxonomy: Taxonomy) -> Option<&BTreeMap<String, Vec<String>>> {
+        self.0.of(taxonomy)
+    }
+
+    pub fn get(&self, taxonomy: Taxonomy, id: &str) -> &[String] {
+        self.0.get(taxonomy, id).map_or(&[], Vec::as_slice)
+    }
+
+    /// Replace the tags of `id` with those in `text`, separated by commas or whitespace; blank
+    /// text removes them
+    pub fn set(&mut self, taxonomy: Taxonomy, id: &str, text: &str) {
+        let mut tags: Vec<String> = text.split([',', ' ']).filter(|tag| !tag.is_empty()).map(normalize).collect();
+        tags.sort();
+        tags.dedup();
+        self.0.set(taxonomy, id, (!tags.is_empty()).then_some(tags));
+    }
+}
+
+/// Tags compare case-insensitively, so they are stored lowercased
+pub fn normalize(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+pub fn color(tag: &str) -> Color {
+    let hash = tag.bytes().fold(0usize, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as usize));
+    TAG_COLORS[hash % TAG_COLORS.len()]
+}
@@ -0,0 +1,1541 @@
+use anyhow::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use memmap2::Mmap;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+const PRODUCT_TSV: &str = include_str!("../product-2.0.tsv");
+const CONTENT_TSV: &str = include_str!("../content-3.1.tsv");
+const AUDIENCE_TSV: &str = include_str!("../audience-1.1.tsv");
+
+/// Common accessors over the rows of every taxonomy
+pub trait TaxonomyItem {
+    fn unique_id(&self) -> &str;
+    fn parent(&self) -> Option<&str>;
+    fn name(&self) -> &str;
+    fn tiers(&self) -> Vec<&str>;
+    fn extension(&self) -> Option<&str>;
+
+    /// Official notes such as Audience's "Zip or postal code" get no badge; only custom IDs and
+    /// cells that are nothing but an extension marker count as organization-specific
+    fn badge(&self) -> Option<Badge> {
+        let note = self.extension().map(|ext| ext.trim().to_lowercase()).unwrap_or_default();
+        if note.contains("deprecat") {
+            Some(Badge::Deprecated)
+        } else if crate::extension::is_custom(self.unique_id()) || EXTENSION_MARKERS.contains(&note.as_str()) {
+            Some(Badge::Extension)
+        } else {
+            None
+        }
+    }
+}
+
+/// Extension column values, lowercased, that mark a row as an organization's own addition
+const EXTENSION_MARKERS: &[&str] = &["ext", "extension", "custom", "custom extension", "vendor", "vendor extension"];
+
+// Status markers derived from the extension/notes column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Badge {
+    Deprecated,
+    Extension,
+}
+
+impl Badge {
+    pub fn label(self) -> &'static str {
+        match self {
+            Badge::Deprecated => "[deprecated]",
+            Badge::Extension => "[ext]",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Content {
+    #[serde(rename = "Unique ID")]
+    unique_id: String,
+    #[serde(rename = "Parent")]
+    parent: Option<String>,
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Tier 1")]
+    tier_1: Option<String>,
+    #[serde(rename = "Tier 2")]
+    tier_2: Option<String>,
+    #[serde(rename = "Tier 3")]
+    tier_3: Option<String>,
+    #[serde(rename = "Tier 4")]
+    tier_4: Option<String>,
+    #[serde(rename = "Extension")]
+    ext: Option<String>,
+}
+
+impl TaxonomyItem for Content {
+    fn unique_id(&self) -> &str {
+        &self.unique_id
+    }
+    fn parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn tiers(&self) -> Vec<&str> {
+        [
+            self.tier_1.as_deref(),
+            self.tier_2.as_deref(),
+            self.tier_3.as_deref(),
+            self.tier_4.as_deref(),
+        ]
+        .iter()
+        .filter_map(|&t| t.filter(|s| !s.is_empty()))
+        .collect()
+    }
+    fn extension(&self) -> Option<&str> {
+        self.ext.as_deref()
+    }
+}
+
+impl TaxonomyItem for &Content {
+    fn unique_id(&self) -> &str {
+        (*self).unique_id()
+    }
+    fn parent(&self) -> Option<&str> {
+        (*self).parent()
+    }
+    fn name(&self) -> &str {
+        (*self).name()
+    }
+    fn tiers(&self) -> Vec<&str> {
+        (*self).tiers()
+    }
+    fn extension(&self) -> Option<&str> {
+        (*self).extension()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Product {
+    #[serde(rename = "Unique ID")]
+    unique_id: String,
+    #[serde(rename = "Parent ID")]
+    parent: Option<String>,
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "Tier 1")]
+    tier_1: Option<String>,
+    #[serde(rename = "Tier 2")]
+    tier_2: Option<String>,
+    #[serde(rename = "Tier 3")]
+    tier_3: Option<String>,
+}
+
+impl TaxonomyItem for Product {
+    fn unique_id(&self) -> &str {
+        &self.unique_id
+    }
+    fn parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn tiers(&self) -> Vec<&str> {
+        [
+            self.tier_1.as_deref(),
+            self.tier_2.as_deref(),
+            self.tier_3.as_deref(),
+        ]
+        .iter()
+        .filter_map(|&t| t.filter(|s| !s.is_empty()))
+        .collect()
+    }
+    fn extension(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl TaxonomyItem for &Product {
+    fn unique_id(&self) -> &str {
+        (*self).unique_id()
+    }
+    fn parent(&self) -> Option<&str> {
+        (*self).parent()
+    }
+    fn name(&self) -> &str {
+        (*self).name()
+    }
+    fn tiers(&self) -> Vec<&str> {
+        (*self).tiers()
+    }
+    fn extension(&self) -> Option<&str> {
+        (*self).extension()
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
+pub struct Audience {
+    #[serde(rename = "Unique ID")]
+    unique_id: String,
+    #[serde(rename = "Parent ID")]
+    parent: Option<String>,
+    #[serde(rename = "Condensed Name (1st, 2nd, Last Tier)")]
+    name: String,
+    #[serde(rename = "Tier 1")]
+    tier_1: Option<String>,
+    #[serde(rename = "Tier 2")]
+    tier_2: Option<String>,
+    #[serde(rename = "Tier 3")]
+    tier_3: Option<String>,
+    #[serde(rename = "Tier 4")]
+    tier_4: Option<String>,
+    #[serde(rename = "Tier 5")]
+    tier_5: Option<String>,
+    #[serde(rename = "Tier 6")]
+    tier_6: Option<String>,
+    #[serde(rename = "*Extension Notes")]
+    ext: Option<String>,
+}
+
+impl TaxonomyItem for Audience {
+    fn unique_id(&self) -> &str {
+        &self.unique_id
+    }
+    fn parent(&self) -> Option<&str> {
+        self.parent.as_deref()
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn tiers(&self) -> Vec<&str> {
+        [
+            self.tier_1.as_deref(),
+            self.tier_2.as_deref(),
+            self.tier_3.as_deref(),
+            self.tier_4.as_deref(),
+            self.tier_5.as_deref(),
+            self.tier_6.as_deref(),
+        ]
+        .iter()
+        .filter_map(|&t| t.filter(|s| !s.is_empty()))
+        .collect()
+    }
+    fn extension(&self) -> Option<&str> {
+        self.ext.as_deref()
+    }
+}
+
+impl TaxonomyItem for &Audience {
+    fn unique_id(&self) -> &str {
+        (*self).unique_id()
+    }
+    fn parent(&self) -> Option<&str> {
+        (*self).parent()
+    }
+    fn name(&self) -> &str {
+        (*self).name()
+    }
+    fn tiers(&self) -> Vec<&str> {
+        (*self).tiers()
+    }
+    fn extension(&self) -> Option<&str> {
+        (*self).extension()
+    }
+}
+
+// Original TSV rows keyed by unique ID, for the raw record view. Rows read from a file stay in
+// its text and are split into cells only when asked for.
+#[derive(Debug, Default)]
+pub struct RawTable {
+    headers: Vec<String>,
+    quoting: Quoting,
+    source: Source,
+    rows: HashMap<String, RawRow>,
+}
+
+/// Text a `RawTable` was parsed from
+#[derive(Debug, Default)]
+enum Source {
+    #[default]
+    Empty,
+    /// Bundled data compiled into the binary
+    Static(&'static str),
+    /// A memory-mapped `--file` and the range of its rows, after any BOM and section header
+    Mapped(Arc<Mmap>, Range<usize>),
+    /// A copy, for text the caller does not keep
+    Shared(Arc<str>),
+}
+
+impl Source {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Source::Empty => &[],
+            Source::Static(text) => text.as_bytes(),
+            Source::Mapped(map, range) => &map[range.clone()],
+            Source::Shared(text) => text.as_bytes(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum RawRow {
+    /// Byte range of the row in the source text
+    Span(Range<usize>),
+    /// Cells of a row added or edited after loading
+    Cells(Vec<String>),
+}
+
+impl RawTable {
+    /// All columns of the row for `id` as (header, value) pairs, verbatim
+    pub fn row(&self, id: &str) -> Option<Vec<(String, String)>> {
+        let row = self.cells(id)?;
+        Some(
+            self.headers
+                .iter()
+                .enumerate()
+                // Some files leave headers blank (Content's extension column)
+                .map(|(i, header)| if header.is_empty() { format!("Column {}", i + 1) } else { header.clone() })
+                .zip(row)
+                .collect(),
+        )
+    }
+
+    /// Number of columns in the file
+    pub fn width(&self) -> usize {
+        self.headers.len()
+    }
+
+    /// Cells of the row for `id` in file column order, ragged rows padded or cut to the headers
+    pub fn cells(&self, id: &str) -> Option<Vec<String>> {
+        self.cells_in(self.source.bytes(), id)
+    }
+
+    /// `cells` with the rows' text given, for a table whose source was not kept
+    fn cells_in(&self, text: &[u8], id: &str) -> Option<Vec<String>> {
+        let span = match self.rows.get(id)? {
+            RawRow::Cells(cells) => return Some(cells.clone()),
+            RawRow::Span(span) => span.clone(),
+        };
+        let mut record = csv::StringRecord::new();
+        reader_builder(self.quoting).has_headers(false).from_reader(text.get(span)?).read_record(&mut record).ok()?;
+        let mut cells: Vec<String> = record.iter().map(str::to_string).collect();
+        cells.resize(self.headers.len(), String::new());
+        Some(cells)
+    }
+
+    pub fn insert(&mut self, id: &str, cells: Vec<String>) {
+        self.rows.insert(id.to_string(), RawRow::Cells(cells));
+    }
+
+    pub fn remove(&mut self, id: &str) {
+        self.rows.remove(id);
+    }
+}
+
+// Data loading functions
+
+/// Parse a taxonomy TSV whose first line holds the column headers
+pub fn read_tsv<T: DeserializeOwned + TaxonomyItem>(data: &str) -> Result<(Vec<T>, RawTable)> {
+    let mut parsed = parse_tsv(data, Quoting::default(), None)?;
+    parsed.raw.source = Source::Shared(Arc::from(data));
+    Ok((parsed.items, parsed.raw))
+}
+
+/// Rows of a TSV, with the line each row starts on and what was repaired to read it
+struct Parsed<T> {
+    items: Vec<T>,
+    raw: RawTable,
+    lines: Vec<u64>,
+    repairs: Repairs,
+    columns: ColumnMatch,
+}
+
+/// Header renames for `--file` TSVs whose columns drifted from the bundled layout, read from
+/// `--columns`
+#[derive(Debug, Clone, Default)]
+pub struct ColumnMap {
+    /// Normalized header in the file → expected header
+    renames: HashMap<String, String>,
+}
+
+impl ColumnMap {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        Self::parse(&data).with_context(|| format!("cannot parse {}", path.display()))
+    }
+
+    /// Parse `<header in the file>\t<expected header>` lines; blank lines and `#` comments are skipped
+    pub fn parse(data: &str) -> Result<Self> {
+        let mut renames = HashMap::new();
+        for line in data.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#')) {
+            let Some((from, to)) = line.split_once('\t') else {
+                bail!("expected <header in the file>\\t<expected header>, got {:?}", line);
+            };
+            renames.insert(normalize_header(from), to.trim().to_string());
+        }
+        Ok(Self { renames })
+    }
+
+    fn is_id_header(&self, header: &str) -> bool {
+        let normalized = normalize_header(header);
+        normalized == "uniqueid" || self.renames.get(&normalized).is_some_and(|to| to == "Unique ID")
+    }
+}
+
+/// Headers other releases use for a column of the bundled layout, normalized
+const HEADER_ALIASES: [&[&str]; 3] = [
+    &["parent", "parentid"],
+    &["name", "condensedname1st2ndlasttier"],
+    &["extension", "extensionnotes"],
+];
+
+/// How the headers of a file were matched to the expected columns
+#[derive(Debug, Default)]
+struct ColumnMatch {
+    /// Header in the file and the expected column it was guessed to be
+    renamed: Vec<(String, String)>,
+    /// 1-based column and header of columns matching nothing, which are ignored
+    unknown: Vec<(u64, String)>,
+    /// Expected columns no header matched
+    missing: Vec<&'static str>,
+}
+
+/// Lowercase letters and digits only, so "Parent ID", "parent_id" and "PARENT-ID" compare equal
+fn normalize_header(header: &str) -> String {
+    header.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+/// Rewrite `headers` to the expected column names: explicit renames first, then the same name
+/// spelled differently, then a known alias from another release
+fn match_columns(headers: &csv::StringRecord, expected: &[&'static str], map: &ColumnMap) -> (csv::StringRecord, ColumnMatch) {
+    let mut matched = ColumnMatch::default();
+    let mut taken: Vec<&str> = Vec::new();
+    let mut resolved = csv::StringRecord::new();
+    for (column, header) in headers.iter().enumerate() {
+        let normalized = normalize_header(header);
+        let mapped = map.renames.get(&normalized).and_then(|to| expected.iter().find(|column| **column == to.as_str()));
+        let target = mapped
+            .or_else(|| expected.iter().find(|column| normalize_header(column) == normalized))
+            .or_else(|| {
+                let aliases = HEADER_ALIASES.iter().find(|aliases| aliases.contains(&normalized.as_str()))?;
+                expected.iter().find(|column| aliases.contains(&normalize_header(column).as_str()))
+            })
+            .filter(|target| !taken.contains(target));
+        match target {
+            Some(target) => {
+                taken.push(target);
+                // Renames asked for with `--columns` go without saying
+                if header != *target && mapped.is_none() {
+                    matched.renamed.push((header.to_string(), target.to_string()));
+                }
+                resolved.push_field(target);
+            }
+            None => {
+                // Unnamed trailing columns, as in the bundled Content file, are not worth a mention
+                if !header.trim().is_empty() {
+                    matched.unknown.push((column as u64 + 1, header.to_string()));
+                }
+                resolved.push_field(header);
+            }
+        }
+    }
+    // Extension notes are optional; the bundled Content file does not even name that column
+    matched.missing = expected
+        .iter()
+        .copied()
+        .filter(|column| !taken.contains(column) && !normalize_header(column).starts_with("extension"))
+        .collect();
+    (resolved, matched)
+}
+
+/// Lines of rows the parser repaired instead of failing on, as spreadsheet exports often need
+#[derive(Default)]
+struct Repairs {
+    /// Rows with nothing but empty cells, skipped
+    blank: Vec<u64>,
+    /// Rows with fewer cells than headers, padded with empty cells
+    padded: Vec<u64>,
+    /// Rows with more cells than headers that were all empty, cut to the header width
+    trimmed: Vec<u64>,
+    /// Rows with more cells than headers that were not empty, cut to the header width
+    dropped: Vec<u64>,
+    /// Rows with a quoted cell running over several lines, usually an unescaped quote
+    multiline: Vec<u64>,
+}
+
+impl Repairs {
+    /// One warning per kind of repair, e.g. "short rows padded to 8 columns on lines 5, 9"
+    fn warnings(&self, columns: usize, line_offset: u64) -> Vec<String> {
+        let kinds = [
+            (&self.blank, "blank rows skipped".to_string()),
+            (&self.padded, format!("short rows padded to {} columns", columns)),
+            (&self.trimmed, "rows with trailing empty columns trimmed".to_string()),
+            (&self.dropped, format!("rows with values beyond column {} cut, values dropped", columns)),
+            (&self.multiline, "likely unescaped quotes (see --quoting) in cells spanning several lines".to_string()),
+        ];
+        kinds
+            .into_iter()
+            .filter(|(lines, _)| !lines.is_empty())
+            .map(|(lines, what)| {
+                let shown: Vec<String> = lines.iter().take(5).map(|line| (line + line_offset).to_string()).collect();
+                let plural = if lines.len() > 1 { "s" } else { "" };
+                let more = match lines.len() - shown.len() {
+                    0 => String::new(),
+                    more => format!(" and {} more", more),
+                };
+                format!("{} on line{} {}{}", what, plural, shown.join(", "), more)
+            })
+            .collect()
+    }
+}
+
+/// TSV reader for `quoting`; ragged rows are repaired by the caller, CRLF line endings and empty
+/// lines the reader handles itself
+fn reader_builder(quoting: Quoting) -> csv::ReaderBuilder {
+    let mut builder = csv::ReaderBuilder::new();
+    builder.delimiter(b'\t').has_headers(true).flexible(true);
+    match quoting {
+        Quoting::Standard => {}
+        Quoting::Backslash => {
+            builder.escape(Some(b'\\')).double_quote(false);
+        }
+        Quoting::None => {
+            builder.quoting(false);
+        }
+    }
+    builder
+}
+
+/// Parse rows; with `columns`, headers are first matched to the expected names. The table keeps
+/// only the rows' byte ranges in `data`; callers set its source.
+fn parse_tsv<T: DeserializeOwned + TaxonomyItem>(
+    data: &str,
+    quoting: Quoting,
+    columns: Option<(&[&'static str], &ColumnMap)>,
+) -> Result<Parsed<T>> {
+    let mut reader = reader_builder(quoting).from_reader(data.as_bytes());
+
+    let (headers, matched) = match columns {
+        Some((expected, map)) => match_columns(reader.headers()?, expected, map),
+        None => (reader.headers()?.clone(), ColumnMatch::default()),
+    };
+    // The raw record keeps the file's own header names
+    let mut raw = RawTable {
+        headers: reader.headers()?.iter().map(|h| h.to_string()).collect(),
+        quoting,
+        source: Source::Empty,
+        rows: HashMap::new(),
+    };
+
+    let mut items = Vec::new();
+    let mut lines = Vec::new();
+    let mut repairs = Repairs::default();
+    // The reader's own line numbers drift on CRLF and empty lines, so count from byte offsets
+    let (mut counted, mut line) = (0, 1);
+    let mut record = csv::StringRecord::new();
+    while reader.read_record(&mut record)? {
+        // Where the reader stopped, just past this row
+        let end = reader.position().byte() as usize;
+        let offset = record.position().map_or(counted, |position| position.byte() as usize);
+        let start = offset + data.as_bytes()[offset..].iter().take_while(|&&b| b == b'\r' || b == b'\n').count();
+        line += data.as_bytes()[counted..start].iter().filter(|&&b| b == b'\n').count() as u64;
+        counted = start;
+        if let Some(position) = record.position() {
+            // Errors raised while deserializing the row report this position
+            let mut position = position.clone();
+            position.set_line(line);
+            record.set_position(Some(position));
+        }
+        if record.iter().any(|field| field.contains('\n')) {
+            repairs.multiline.push(line);
+        }
+        if record.iter().all(|field| field.trim().is_empty()) {
+            repairs.blank.push(line);
+            continue;
+        }
+        if record.len() < headers.len() {
+            repairs.padded.push(line);
+            while record.len() < headers.len() {
+                record.push_field("");
+            }
+        } else if record.len() > headers.len() {
+            if record.iter().skip(headers.len()).any(|field| !field.trim().is_empty()) {
+                repairs.dropped.push(line);
+            } else {
+                repairs.trimmed.push(line);
+            }
+            record.truncate(headers.len());
+        }
+
+        let item: T = record.deserialize(Some(&headers))?;
+        // Like the child index, keep the first of rows sharing an ID; only its place in the text is
+        // kept, and `RawTable::cells` splits it again when asked
+        raw.rows.entry(item.unique_id().to_string()).or_insert(RawRow::Span(start..end));
+        items.push(item);
+        lines.push(line);
+    }
+
+    Ok(Parsed { items, raw, lines, repairs, columns: matched })
+}
+
+/// Parse the bundled data, or the file given for this taxonomy with `--file`, along with warnings
+/// about repaired rows
+fn load_taxonomy<T: DeserializeOwned + TaxonomyItem>(
+    taxonomy: Taxonomy,
+    bundled: &'static str,
+    file: Option<&Path>,
+    options: &LoadOptions,
+) -> Result<(Vec<T>, RawTable, Vec<String>)> {
+    let started = Instant::now();
+    let Some(path) = file else {
+        let rows = skip_section_header(bundled, &ColumnMap::default());
+        let Parsed { items, mut raw, .. } = parse_tsv(rows, Quoting::default(), None)?;
+        raw.source = Source::Static(rows);
+        tracing::info!(taxonomy = taxonomy.key(), rows = items.len(), elapsed = ?started.elapsed(), "loaded bundled taxonomy");
+        return Ok((items, raw, Vec::new()));
+    };
+
+    let error = |line, column, message: String| FileError { path: path.to_path_buf(), line, column, message };
+    let file = File::open(path).map_err(|err| error(None, None, format!("cannot open: {}", err)))?;
+    // SAFETY: the map is read while parsing below and later by the raw record view. A file
+    // truncated in place by another process meanwhile is the one case memory-mapping cannot guard
+    // against; replacing it, as editors and `--watch` setups do, leaves the mapped pages intact.
+    let map = unsafe { Mmap::map(&file) }.map_err(|err| error(None, None, format!("cannot map: {}", err)))?;
+    // Parse straight from the mapped pages instead of reading a second copy into a String; the
+    // raw table keeps reading rows from them
+    let data = std::str::from_utf8(&map).map_err(|err| {
+        let (line, column) = line_column(&map[..err.valid_up_to()]);
+        error(Some(line), Some(column), "invalid UTF-8".to_string())
+    })?;
+
+    // A byte order mark would hide the section header check and the first column name
+    let data = data.strip_prefix('\u{feff}').unwrap_or(data);
+    let rows = skip_section_header(data, &options.columns);
+    let skipped_lines = data[..data.len() - rows.len()].lines().count() as u64;
+    let columns = Some((taxonomy.columns(), &options.columns));
+    let rows_start = rows.as_ptr() as usize - map.as_ptr() as usize;
+    let rows_range = rows_start..rows_start + rows.len();
+    let Parsed { items, mut raw, lines, repairs, columns } = parse_tsv::<T>(rows, options.quoting, columns).map_err(|err| {
+        let Some(csv_error) = err.downcast_ref::<csv::Error>() else {
+            return error(None, None, format!("{:#}", err));
+        };
+        let line = csv_error.position().map(|position| position.line() + skipped_lines);
+        let (column, message) = match csv_error.kind() {
+            csv::ErrorKind::Deserialize { err, .. } => (err.field().map(|field| field + 1), err.kind().to_string()),
+            _ => (None, csv_error.to_string()),
+        };
+        error(line, column, message)
+    })?;
+    raw.source = Source::Mapped(Arc::new(map), rows_range);
+
+    if options.strict
+        && let Some((column, header)) = columns.unknown.first()
+    {
+        let message = format!("unexpected column \"{}\", refused by --strict (rename it with --columns)", header);
+        return Err(error(Some(skipped_lines + 1), Some(*column), message).into());
+    }
+    if options.strict {
+        let mut first_lines = HashMap::new();
+        for (item, line) in items.iter().zip(&lines) {
+            let line = line + skipped_lines;
+            if let Some(first) = first_lines.insert(item.unique_id(), line) {
+                let message = format!("duplicate ID {} (first on line {}), refused by --strict", item.unique_id(), first);
+                return Err(error(Some(line), Some(1), message).into());
+            }
+        }
+    }
+
+    let name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+    let renamed = columns.renamed.iter().map(|(from, to)| format!("\"{}\" as \"{}\"", from, to));
+    let unknown = columns.unknown.iter().map(|(_, header)| format!("\"{}\"", header));
+    let missing = columns.missing.iter().map(|column| format!("\"{}\"", column));
+    let column_notes = [
+        ("reading columns", renamed.collect::<Vec<_>>()),
+        ("ignoring unknown columns", unknown.collect()),
+        ("missing columns", missing.collect()),
+    ];
+    let warnings = column_notes
+        .into_iter()
+        .filter(|(_, headers)| !headers.is_empty())
+        .map(|(what, headers)| format!("{} {}", what, headers.join(", ")))
+        .chain(repairs.warnings(raw.headers.len(), skipped_lines))
+        .map(|warning| format!("{}: {}", name, warning))
+        .collect::<Vec<_>>();
+    tracing::info!(
+        taxonomy = taxonomy.key(),
+        file = %path.display(),
+        rows = items.len(),
+        warnings = warnings.len(),
+        elapsed = ?started.elapsed(),
+        "loaded taxonomy file"
+    );
+    for warning in &warnings {
+        tracing::warn!("{}", warning);
+    }
+    Ok((items, raw, warnings))
+}
+
+/// Append the custom nodes of an overlay TSV (see `extension`) to `items`, returning their IDs;
+/// refuses IDs outside the reserved range or already taken and parents that do not exist
+fn append_extension<T: DeserializeOwned + TaxonomyItem>(
+    taxonomy: Taxonomy,
+    items: &mut Vec<T>,
+    raw: &mut RawTable,
+    path: &Path,
+) -> Result<Vec<String>> {
+    let error = |line, message: String| FileError { path: path.to_path_buf(), line, column: None, message };
+    let data = std::fs::read_to_string(path).map_err(|err| error(None, format!("cannot read: {}", err)))?;
+    let data = data.strip_prefix('\u{feff}').unwrap_or(&data);
+    let parsed = parse_tsv::<T>(data, Quoting::default(), None).map_err(|err| error(None, format!("{:#}", err)))?;
+    // Cells are written back in this order when the overlay is saved
+    if parsed.raw.headers != taxonomy.columns() {
+        return Err(error(Some(1), format!("expected the {} columns {}", taxonomy.name(), taxonomy.columns().join(", "))).into());
+    }
+
+    let mut known: HashSet<String> = items.iter().map(|item| item.unique_id().to_string()).collect();
+    let mut ids = Vec::new();
+    for (item, line) in parsed.items.iter().zip(&parsed.lines) {
+        let id = item.unique_id();
+        if !crate::extension::is_custom(id) {
+            let message = format!("ID {} is outside the custom range {}-{}", id, crate::extension::FIRST_ID, crate::extension::LAST_ID);
+            return Err(error(Some(*line), message).into());
+        }
+        if known.contains(id) {
+            return Err(error(Some(*line), format!("duplicate ID {}", id)).into());
+        }
+        if !item.parent().is_some_and(|parent| known.contains(parent)) {
+            return Err(error(Some(*line), format!("parent {} of {} does not exist", item.parent().unwrap_or_default(), id)).into());
+        }
+        known.insert(id.to_string());
+        ids.push(id.to_string());
+    }
+    // The overlay's text is dropped below, so its few rows are copied
+    for id in &ids {
+        if let Some(cells) = parsed.raw.cells_in(data.as_bytes(), id) {
+            raw.insert(id, cells);
+        }
+    }
+    items.extend(parsed.items);
+    Ok(ids)
+}
+
+/// 1-based line and column (in characters) just past `data`
+fn line_column(data: &[u8]) -> (u64, u64) {
+    let line_start = data.iter().rposition(|&byte| byte == b'\n').map_or(0, |newline| newline + 1);
+    let line = data.iter().filter(|&&byte| byte == b'\n').count() as u64 + 1;
+    let column = String::from_utf8_lossy(&data[line_start..]).chars().count() as u64 + 1;
+    (line, column)
+}
+
+/// Why a `--file` taxonomy could not be loaded, with the position when known
+#[derive(Debug, Clone)]
+pub struct FileError {
+    pub path: PathBuf,
+    pub line: Option<u64>,
+    /// 1-based column (TSV field, or character for encoding errors)
+    pub column: Option<u64>,
+    pub message: String,
+}
+
+impl std::fmt::Display for FileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        if let Some(column) = self.column {
+            write!(f, ":{}", column)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for FileError {}
+
+/// Drop a section header line above the column headers, as in the Content TSV; the column
+/// headers start with the ID column, however `columns` spells it
+fn skip_section_header<'a>(data: &'a str, columns: &ColumnMap) -> &'a str {
+    match data.split_once('\n') {
+        Some((first, rest)) if !columns.is_id_header(first.split('\t').next().unwrap_or_default()) => rest,
+        _ => data,
+    }
+}
+
+/// How `--file` TSVs treat double quotes
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Quoting {
+    /// A cell starting with a quote runs to the closing quote, tabs and newlines included; quotes
+    /// inside it are doubled (`""`)
+    #[default]
+    Standard,
+    /// Like standard, but quotes inside a quoted cell are escaped with a backslash (`\"`)
+    Backslash,
+    /// Quotes are ordinary text: every line is one row, split at each tab
+    None,
+}
+
+/// Parsing options for `--file` TSVs; the bundled data always loads with the defaults
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// Refuse files that repeat an ID or have a column matching no expected one, instead of
+    /// keeping the first row and ignoring the column
+    pub strict: bool,
+    pub quoting: Quoting,
+    pub columns: ColumnMap,
+}
+
+/// A taxonomy TSV to load instead of the bundled one, e.g. a vendor-extended Content file
+#[derive(Debug, Clone)]
+pub struct TaxonomyFile {
+    pub taxonomy: Taxonomy,
+    pub path: PathBuf,
+}
+
+impl TaxonomyFile {
+    /// Parse `content=path/to/file.tsv`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (key, path) = spec.split_once('=').context("expected <taxonomy>=<file>, e.g. content=vendor.tsv")?;
+        let taxonomy = Taxonomy::from_key(key).with_context(|| format!("unknown taxonomy {} (use product, content or audience)", key))?;
+        Ok(Self { taxonomy, path: PathBuf::from(path) })
+    }
+}
+
+/// The taxonomies bundled with this crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Taxonomy {
+    Product,
+    Content,
+    Audience,
+}
+
+impl Taxonomy {
+    pub const ALL: [Taxonomy; 3] = [Taxonomy::Product, Taxonomy::Content, Taxonomy::Audience];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Taxonomy::Product => "Product",
+            Taxonomy::Content => "Content",
+            Taxonomy::Audience => "Audience",
+        }
+    }
+
+    /// Stable lowercase key, used in persisted state and URLs
+    pub fn key(self) -> &'static str {
+        match self {
+            Taxonomy::Product => "product",
+            Taxonomy::Content => "content",
+            Taxonomy::Audience => "audience",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|taxonomy| taxonomy.key() == key)
+    }
+
+    /// Position in `Taxonomy::ALL`, e.g. for `Taxonomies::raw_tables`
+    pub fn index(self) -> usize {
+        match self {
+            Taxonomy::Product => 0,
+            Taxonomy::Content => 1,
+            Taxonomy::Audience => 2,
+        }
+    }
+
+    /// Version of the bundled TSV file
+    pub fn version(self) -> &'static str {
+        match self {
+            Taxonomy::Product => "2.0",
+            Taxonomy::Content => "3.1",
+            Taxonomy::Audience => "1.1",
+        }
+    }
+
+    /// Column headers of the bundled file, as the row struct reads them
+    pub fn columns(self) -> &'static [&'static str] {
+        match self {
+            Taxonomy::Product => &["Unique ID", "Parent ID", "Name", "Tier 1", "Tier 2", "Tier 3"],
+            Taxonomy::Content => &["Unique ID", "Parent", "Name", "Tier 1", "Tier 2", "Tier 3", "Tier 4", "Extension"],
+            Taxonomy::Audience => &[
+                "Unique ID",
+                "Parent ID",
+                "Condensed Name (1st, 2nd, Last Tier)",
+                "Tier 1",
+                "Tier 2",
+                "Tier 3",
+                "Tier 4",
+                "Tier 5",
+                "Tier 6",
+                "*Extension Notes",
+            ],
+        }
+    }
+}
+
+/// Parent and child links of one taxonomy by position in its item list, built once after parsing.
+/// Parents that are empty, the node itself or unknown leave a node without a parent.
+#[derive(Debug, Clone)]
+pub struct ChildIndex {
+    /// Interned IDs by position, cheap to clone into tree identifiers
+    ids: Vec<Arc<str>>,
+    positions: HashMap<Arc<str>, usize>,
+    /// Links by position, so walking the hierarchy never allocates
+    nodes: Vec<Links>,
+    first_root: u32,
+    /// Nodes whose parent ID is not in the taxonomy, chained through `next_sibling`
+    first_orphan: u32,
+    /// Parent cycles found at load, each starting with the member that was made a root
+    cycles: Vec<Vec<usize>>,
+    /// Later rows repeating an earlier row's ID, left out of the hierarchy
+    duplicates: Vec<usize>,
+}
+
+/// Arena links of one node; `NONE` marks a missing link
+#[derive(Debug, Clone, Copy)]
+struct Links {
+    parent: u32,
+    first_child: u32,
+    next_sibling: u32,
+}
+
+const NONE: u32 = u32::MAX;
+
+fn link(position: u32) -> Option<usize> {
+    (position != NONE).then_some(position as usize)
+}
+
+impl ChildIndex {
+    pub fn new<T: TaxonomyItem>(items: &[T]) -> Self {
+        assert!(items.len() < NONE as usize, "too many rows for a u32 index");
+        let ids: Vec<Arc<str>> = items.iter().map(|item| Arc::from(item.unique_id())).collect();
+        let mut positions = HashMap::with_capacity(items.len());
+        let mut duplicates = Vec::new();
+        for (position, id) in ids.iter().enumerate() {
+            // The first of duplicate IDs wins, as in a lookup by scanning
+            if *positions.entry(Arc::clone(id)).or_insert(position) != position {
+                duplicates.push(position);
+            }
+        }
+
+        let mut nodes = vec![Links { parent: NONE, first_child: NONE, next_sibling: NONE }; items.len()];
+        let mut first_root = NONE;
+        let mut first_orphan = NONE;
+        // Prepending in reverse leaves every sibling list in file order
+        for (position, item) in items.iter().enumerate().rev() {
+            // Two nodes with one ID would make tree identifiers ambiguous
+            if positions[item.unique_id()] != position {
+                continue;
+            }
+            let position_u32 = position as u32;
+            match item.parent().filter(|parent| !parent.is_empty() && *parent != item.unique_id()) {
+                None => {
+                    nodes[position].next_sibling = first_root;
+                    first_root = position_u32;
+                }
+                Some(parent) => match positions.get(parent) {
+                    Some(&parent_position) => {
+                        nodes[position].parent = parent_position as u32;
+                        nodes[position].next_sibling = nodes[parent_position].first_child;
+                        nodes[parent_position].first_child = position_u32;
+                    }
+                    None => {
+                        nodes[position].next_sibling = first_orphan;
+                        first_orphan = position_u32;
+                    }
+                },
+            }
+        }
+
+        let mut index = Self { ids, positions, nodes, first_root, first_orphan, cycles: Vec::new(), duplicates };
+        index.break_cycles();
+        index
+    }
+
+    /// Find parent cycles, which no walk from a root or orphan reaches, and cut each one at its
+    /// first member in file order, which becomes a root
+    fn break_cycles(&mut self) {
+        // 0: not visited, 1: on the parent chain being followed, 2: done
+        let mut state = vec![0u8; self.nodes.len()];
+        let mut cycles = Vec::new();
+        for start in 0..self.nodes.len() {
+            let mut chain = Vec::new();
+            let mut current = Some(start);
+            while let Some(position) = current
+                && state[position] == 0
+            {
+                state[position] = 1;
+                chain.push(position);
+                current = self.parent(position);
+            }
+            if let Some(position) = current
+                && state[position] == 1
+            {
+                let members = chain[chain.iter().position(|&member| member == position).unwrap_or(0)..].to_vec();
+                cycles.push(members);
+            }
+            for position in chain {
+                state[position] = 2;
+            }
+        }
+
+        for mut members in cycles {
+            let first = members.iter().enumerate().min_by_key(|(_, position)| **position).map_or(0, |(at, _)| at);
+            members.rotate_left(first);
+            self.detach(members[0]);
+            self.cycles.push(members);
+        }
+    }
+
+    /// Move a node from its parent's children to the roots, keeping both lists in file order
+    fn detach(&mut self, position: usize) {
+        let position_u32 = position as u32;
+        let next_sibling = self.nodes[position].next_sibling;
+        if let Some(parent) = self.parent(position) {
+            match self.children(parent).find(|&child| self.nodes[child].next_sibling == position_u32) {
+                Some(previous) => self.nodes[previous].next_sibling = next_sibling,
+                None => self.nodes[parent].first_child = next_sibling,
+            }
+        }
+        self.nodes[position].parent = NONE;
+
+        match self.roots().take_while(|&root| root < position).last() {
+            Some(previous) => {
+                self.nodes[position].next_sibling = self.nodes[previous].next_sibling;
+                self.nodes[previous].next_sibling = position_u32;
+            }
+            None => {
+                self.nodes[position].next_sibling = self.first_root;
+                self.first_root = position_u32;
+            }
+        }
+    }
+
+    /// Interned ID of the node at `position`
+    pub fn id(&self, position: usize) -> &Arc<str> {
+        &self.ids[position]
+    }
+
+    pub fn position(&self, id: &str) -> Option<usize> {
+        self.positions.get(id).copied()
+    }
+
+    pub fn parent(&self, position: usize) -> Option<usize> {
+        self.nodes.get(position).and_then(|node| link(node.parent))
+    }
+
+    /// Direct children in file order
+    pub fn children(&self, position: usize) -> Siblings<'_> {
+        let first = self.nodes.get(position).map_or(NONE, |node| node.first_child);
+        Siblings { nodes: &self.nodes, next: first }
+    }
+
+    /// Nodes without a parent or with themselves as parent, in file order; orphans are not included
+    pub fn roots(&self) -> Siblings<'_> {
+        Siblings { nodes: &self.nodes, next: self.first_root }
+    }
+
+    /// Nodes whose parent ID does not exist in the taxonomy, in file order
+    pub fn orphans(&self) -> Siblings<'_> {
+        Siblings { nodes: &self.nodes, next: self.first_orphan }
+    }
+
+    /// Parent cycles cut at load, each listed from the member made a root along its parent links
+    pub fn cycles(&self) -> &[Vec<usize>] {
+        &self.cycles
+    }
+
+    /// Rows whose ID an earlier row already uses, in file order; they are not part of the hierarchy
+    pub fn duplicates(&self) -> &[usize] {
+        &self.duplicates
+    }
+
+    /// Whether `position` has a parent ID that does not exist in the taxonomy
+    pub fn is_orphan(&self, position: usize) -> bool {
+        self.orphans().any(|orphan| orphan == position)
+    }
+
+    /// Positions of the parents from the root down, stopping at cycles
+    pub fn ancestors(&self, position: usize) -> Vec<usize> {
+        let mut chain = Vec::new();
+        let mut current = self.parent(position);
+        while let Some(parent) = current {
+            if parent == position || chain.contains(&parent) {
+                break;
+            }
+            chain.push(parent);
+            current = self.parent(parent);
+        }
+        chain.reverse();
+        chain
+    }
+
+    /// The given positions plus all their ancestors and descendants, as kept by the TUI filter
+    pub fn expand_matches(&self, matches: impl IntoIterator<Item = usize>) -> NodeSet {
+        let mut included = NodeSet::new(self.nodes.len());
+        // Nodes whose whole subtree is already included; a match among them adds nothing new
+        let mut covered = vec![false; self.nodes.len()];
+        for position in matches {
+            if covered[position] {
+                continue;
+            }
+            included.insert(position);
+            for ancestor in self.ancestors(position) {
+                included.insert(ancestor);
+            }
+            for (descendant, _) in self.descendants(position) {
+                included.insert(descendant);
+                covered[descendant] = true;
+            }
+            covered[position] = true;
+        }
+        included
+    }
+
+    /// Every node below `position` depth first, with its distance from `position`
+    pub fn descendants(&self, position: usize) -> Descendants<'_> {
+        Descendants { index: self, start: position, current: position, distance: 0 }
+    }
+}
+
+/// A node and its following siblings, in file order
+#[derive(Clone)]
+pub struct Siblings<'a> {
+    nodes: &'a [Links],
+    next: u32,
+}
+
+impl Iterator for Siblings<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let current = link(self.next)?;
+        self.next = self.nodes[current].next_sibling;
+        Some(current)
+    }
+}
+
+/// Pre-order walk below a node that follows the arena links instead of keeping a stack
+pub struct Descendants<'a> {
+    index: &'a ChildIndex,
+    start: usize,
+    current: usize,
+    distance: usize,
+}
+
+impl Iterator for Descendants<'_> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let nodes = &self.index.nodes;
+        // Down to the first child; cycles were cut at load, so this never leads back up
+        if let Some(child) = link(nodes[self.current].first_child) {
+            self.current = child;
+            self.distance += 1;
+            return Some((child, self.distance));
+        }
+        // Otherwise to the next sibling of the nearest node below the start that has one
+        while self.current != self.start {
+            if let Some(sibling) = link(nodes[self.current].next_sibling) {
+                self.current = sibling;
+                return Some((sibling, self.distance));
+            }
+            self.current = link(nodes[self.current].parent)?;
+            self.distance -= 1;
+        }
+        None
+    }
+}
+
+/// Membership of node positions, e.g. those the TUI filter keeps
+#[derive(Debug, Clone)]
+pub struct NodeSet {
+    members: Vec<bool>,
+    len: usize,
+}
+
+impl NodeSet {
+    pub fn new(capacity: usize) -> Self {
+        Self { members: vec![false; capacity], len: 0 }
+    }
+
+    pub fn insert(&mut self, position: usize) {
+        if !std::mem::replace(&mut self.members[position], true) {
+            self.len += 1;
+        }
+    }
+
+    pub fn contains(&self, position: usize) -> bool {
+        self.members.get(position).copied().unwrap_or(false)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Lowercased searchable fields of one node, built at load so filtering never lowercases per keystroke
+#[derive(Debug, Clone)]
+pub struct Haystack {
+    id: String,
+    parent: Option<String>,
+    name: String,
+    /// Tiers and extension, one per line
+    rest: String,
+    /// Name in a loaded translation, empty without one
+    translated: String,
+}
+
+impl Haystack {
+    pub fn new<T: TaxonomyItem + ?Sized>(item: &T) -> Self {
+        let mut rest: Vec<&str> = item.tiers();
+        rest.extend(item.extension());
+        Self {
+            id: item.unique_id().to_lowercase(),
+            parent: item.parent().map(str::to_lowercase),
+            name: item.name().to_lowercase(),
+            rest: rest.join("\n").to_lowercase(),
+            translated: String::new(),
+        }
+    }
+
+    /// Also match the node's name in a translation
+    pub fn with_translation(mut self, name: Option<&str>) -> Self {
+        self.translated = name.unwrap_or_default().to_lowercase();
+        self
+    }
+
+    /// The TUI filter: exact ID or parent ID, or a substring of the name, a tier or the extension
+    pub fn matches(&self, filter_lower: &str) -> bool {
+        filter_lower.is_empty()
+            || self.id == filter_lower
+            || self.parent.as_deref() == Some(filter_lower)
+            || self.name.contains(filter_lower)
+            || self.rest.contains(filter_lower)
+            || (!self.translated.is_empty() && self.translated.contains(filter_lower))
+    }
+
+    /// Substring of the ID or the name, as used by `Taxonomies::search`
+    pub fn matches_id_or_name(&self, query_lower: &str) -> bool {
+        self.id.contains(query_lower) || self.name.contains(query_lower) || (!self.translated.is_empty() && self.translated.contains(query_lower))
+    }
+}
+
+/// Every parsed taxonomy together with its raw TSV table
+pub struct Taxonomies {
+    pub products: Vec<Product>,
+    pub content: Vec<Content>,
+    pub audience: Vec<Audience>,
+    /// Indexed in `Taxonomy::ALL` order
+    pub raw_tables: [RawTable; 3],
+    /// Child indexes, in `Taxonomy::ALL` order
+    pub indexes: [ChildIndex; 3],
+    /// Lowercased search fields by item position, in `Taxonomy::ALL` order
+    pub haystacks: [Vec<Haystack>; 3],
+    /// Rows of `--file` taxonomies that were repaired while parsing, in `Taxonomy::ALL` order
+    pub warnings: [Vec<String>; 3],
+}
+
+impl Taxonomies {
+    /// Parse all bundled taxonomies, calling `progress` after each one is done
+    pub fn load(progress: impl Fn(Taxonomy)) -> Result<Self> {
+        Self::load_files(&[], &LoadOptions::default(), progress)
+    }
+
+    /// Parse all taxonomies, reading those listed in `files` from disk instead of the bundled data
+    pub fn load_files(files: &[TaxonomyFile], options: &LoadOptions, progress: impl Fn(Taxonomy)) -> Result<Self> {
+        let file = |taxonomy: Taxonomy| {
+            files.iter().rev().find(|file| file.taxonomy == taxonomy).map(|file| file.path.as_path())
+        };
+        let (products, product_raw, product_warnings) = load_taxonomy(Taxonomy::Product, PRODUCT_TSV, file(Taxonomy::Product), options)?;
+        progress(Taxonomy::Product);
+        let (content, content_raw, content_warnings) = load_taxonomy(Taxonomy::Content, CONTENT_TSV, file(Taxonomy::Content), options)?;
+        progress(Taxonomy::Content);
+        let (audience, audience_raw, audience_warnings) = load_taxonomy(Taxonomy::Audience, AUDIENCE_TSV, file(Taxonomy::Audience), options)?;
+        progress(Taxonomy::Audience);
+
+        let started = Instant::now();
+        let indexes = [ChildIndex::new(&products), ChildIndex::new(&content), ChildIndex::new(&audience)];
+        let haystacks = [
+            products.iter().map(Haystack::new).collect(),
+            content.iter().map(Haystack::new).collect(),
+            audience.iter().map(Haystack::new).collect(),
+        ];
+        tracing::debug!(elapsed = ?started.elapsed(), "built child indexes and filter haystacks");
+        Ok(Self {
+            products,
+            content,
+            audience,
+            raw_tables: [product_raw, content_raw, audience_raw],
+            indexes,
+            haystacks,
+            warnings: [product_warnings, content_warnings, audience_warnings],
+        })
+    }
+
+    /// Add the custom nodes of an overlay written by the extension editor to its taxonomy,
+    /// returning their IDs
+    pub fn load_extension(&mut self, file: &TaxonomyFile) -> Result<Vec<String>> {
+        let taxonomy = file.taxonomy;
+        let raw = &mut self.raw_tables[taxonomy.index()];
+        let ids = match taxonomy {
+            Taxonomy::Product => append_extension(taxonomy, &mut self.products, raw, &file.path)?,
+            Taxonomy::Content => append_extension(taxonomy, &mut self.content, raw, &file.path)?,
+            Taxonomy::Audience => append_extension(taxonomy, &mut self.audience, raw, &file.path)?,
+        };
+        self.indexes[taxonomy.index()] = match taxonomy {
+            Taxonomy::Product => ChildIndex::new(&self.products),
+            Taxonomy::Content => ChildIndex::new(&self.content),
+            Taxonomy::Audience => ChildIndex::new(&self.audience),
+        };
+        self.haystacks[taxonomy.index()] = self.items(taxonomy).into_iter().map(Haystack::new).collect();
+        tracing::info!(taxonomy = taxonomy.key(), file = %file.path.display(), rows = ids.len(), "loaded extension");
+        Ok(ids)
+    }
+
+    pub fn child_index(&self, taxonomy: Taxonomy) -> &ChildIndex {
+        &self.indexes[taxonomy.index()]
+    }
+
+    /// The node at `position` of one taxonomy's item list
+    pub fn item_at(&self, taxonomy: Taxonomy, position: usize) -> Option<&dyn TaxonomyItem> {
+        match taxonomy {
+            Taxonomy::Product => self.products.get(position).map(|item| item as &dyn TaxonomyItem),
+            Taxonomy::Content => self.content.get(position).map(|item| item as &dyn TaxonomyItem),
+            Taxonomy::Audience => self.audience.get(position).map(|item| item as &dyn TaxonomyItem),
+        }
+    }
+
+    /// Look up a node by unique ID in one taxonomy
+    pub fn item(&self, taxonomy: Taxonomy, id: &str) -> Option<&dyn TaxonomyItem> {
+        self.item_at(taxonomy, self.child_index(taxonomy).position(id)?)
+    }
+
+    /// Parents of `id` from the root down, stopping at unknown parents or cycles
+    pub fn ancestors(&self, taxonomy: Taxonomy, id: &str) -> Vec<&dyn TaxonomyItem> {
+        let Some(position) = self.child_index(taxonomy).position(id) else {
+            return Vec::new();
+        };
+        self.child_index(taxonomy)
+            .ancestors(position)
+            .into_iter()
+            .filter_map(|ancestor| self.item_at(taxonomy, ancestor))
+            .collect()
+    }
+
+    /// All nodes of one taxonomy in file order
+    pub fn items(&self, taxonomy: Taxonomy) -> Vec<&dyn TaxonomyItem> {
+        match taxonomy {
+            Taxonomy::Product => self.products.iter().map(|item| item as &dyn TaxonomyItem).collect(),
+            Taxonomy::Content => self.content.iter().map(|item| item as &dyn TaxonomyItem).collect(),
+            Taxonomy::Audience => self.audience.iter().map(|item| item as &dyn TaxonomyItem).collect(),
+        }
+    }
+
+    /// Up to `limit` nodes whose ID or name contains `query`, ignoring case, in file order
+    pub fn search(&self, taxonomy: Taxonomy, query: &str, limit: usize) -> Vec<&dyn TaxonomyItem> {
+        let query = query.to_lowercase();
+        let haystacks = &self.haystacks[taxonomy.index()];
+        self.items(taxonomy)
+            .into_iter()
+            .zip(haystacks)
+            .filter(|(_, haystack)| haystack.matches_id_or_name(&query))
+            .map(|(item, _)| item)
+            .take(limit)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRODUCT_HEADER: &str = "Unique ID\tParent ID\tName\tTier 1\tTier 2\tTier 3\n";
+
+    /// Product rows of `ID<TAB>parent<TAB>name` lines under the Product header
+    fn products(rows: &str) -> Vec<Product> {
+        let data = format!("{}{}", PRODUCT_HEADER, rows);
+        read_tsv::<Product>(&data).expect("valid fixture").0
+    }
+
+    fn ids(index: &ChildIndex, positions: impl IntoIterator<Item = usize>) -> Vec<String> {
+        positions.into_iter().map(|position| index.id(position).to_string()).collect()
+    }
+
+    #[test]
+    fn self_parent_is_a_root_not_a_cycle() {
+        let index = ChildIndex::new(&products("1\t1\tLoop\n2\t1\tChild\n"));
+        assert_eq!(ids(&index, index.roots()), ["1"]);
+        assert_eq!(ids(&index, index.children(0)), ["2"]);
+        assert!(index.cycles().is_empty());
+    }
+
+    #[test]
+    fn two_cycle_is_cut_at_its_first_member() {
+        let index = ChildIndex::new(&products("1\t\tRoot\n2\t3\tA\n3\t2\tB\n4\t\tOther\n"));
+        assert_eq!(index.cycles(), [vec![1, 2]]);
+        // The cut member joins the roots in file order, keeping its other end as a child
+        assert_eq!(ids(&index, index.roots()), ["1", "2", "4"]);
+        assert_eq!(index.parent(1), None);
+        assert_eq!(ids(&index, index.children(1)), ["3"]);
+        assert_eq!(ids(&index, index.children(2)), Vec::<String>::new());
+        assert_eq!(ids(&index, index.ancestors(2)), ["2"]);
+    }
+
+    #[test]
+    fn cycle_with_siblings_keeps_sibling_order() {
+        // 3 → 2 → 4 → 3, with 5 a sibling of 3 under 4
+        let index = ChildIndex::new(&products("1\t\tRoot\n2\t4\tA\n3\t2\tB\n4\t3\tC\n5\t4\tD\n"));
+        assert_eq!(index.cycles().len(), 1);
+        assert_eq!(index.cycles()[0][0], 1);
+        assert_eq!(ids(&index, index.roots()), ["1", "2"]);
+        assert_eq!(ids(&index, index.children(3)), ["5"]);
+        let below: Vec<_> = index.descendants(1).map(|(position, distance)| (index.id(position).to_string(), distance)).collect();
+        assert_eq!(below, [("3".to_string(), 1), ("4".to_string(), 2), ("5".to_string(), 3)]);
+    }
+
+    #[test]
+    fn unknown_parents_make_orphans() {
+        let index = ChildIndex::new(&products("1\t\tRoot\n2\t99\tLost\n3\t2\tUnder lost\n"));
+        assert_eq!(ids(&index, index.roots()), ["1"]);
+        assert_eq!(ids(&index, index.orphans()), ["2"]);
+        assert!(index.is_orphan(1));
+        assert!(!index.is_orphan(2));
+        assert_eq!(ids(&index, index.children(1)), ["3"]);
+        assert!(index.cycles().is_empty());
+    }
+
+    #[test]
+    fn repeated_ids_stay_out_of_the_hierarchy() {
+        let index = ChildIndex::new(&products("1\t\tRoot\n2\t1\tA\n2\t\tAgain\n"));
+        assert_eq!(index.duplicates(), [2]);
+        assert_eq!(index.position("2"), Some(1));
+        assert_eq!(ids(&index, index.roots()), ["1"]);
+    }
+
+    #[test]
+    fn descendants_walk_depth_first_without_leaving_the_subtree() {
+        let index = ChildIndex::new(&products("1\t\tRoot\n2\t1\tA\n3\t1\tB\n4\t2\tA1\n5\t\tNext\n"));
+        let below: Vec<_> = index.descendants(0).collect();
+        assert_eq!(below, [(1, 1), (3, 2), (2, 1)]);
+        // From an inner node the walk stops before the node's own siblings
+        assert_eq!(index.descendants(1).collect::<Vec<_>>(), [(3, 1)]);
+        assert_eq!(index.descendants(4).count(), 0);
+    }
+
+    #[test]
+    fn expand_matches_adds_ancestors_and_descendants() {
+        let index = ChildIndex::new(&products("1\t\tRoot\n2\t1\tA\n3\t1\tB\n4\t2\tA1\n5\t\tNext\n"));
+        let included = index.expand_matches([1]);
+        assert_eq!((0..5).filter(|&position| included.contains(position)).collect::<Vec<_>>(), [0, 1, 3]);
+        assert_eq!(included.len(), 3);
+    }
+
+    #[test]
+    fn only_extension_markers_and_custom_ids_get_the_ext_badge() {
+        let data = [
+            "Unique ID\tParent ID\tCondensed Name (1st, 2nd, Last Tier)\tTier 1\tTier 2\tTier 3\tTier 4\tTier 5\tTier 6\t*Extension Notes\n",
+            "1\t\tZip\tZip\t\t\t\t\t\tZip or postal code\n",
+            "2\t\tIntent\tIntent\t\t\t\t\t\tSee *Purchase Intent Classification* Extension\n",
+            "3\t\tOld\tOld\t\t\t\t\t\tDeprecated in 1.1\n",
+            "4\t\tOurs\tOurs\t\t\t\t\t\tVendor extension\n",
+            "900001\t1\tCustom\tZip\tCustom\t\t\t\t\tCustom extension\n",
+            "900002\t1\tUnmarked\tZip\tUnmarked\t\t\t\t\t\n",
+        ]
+        .concat();
+        let (items, _) = read_tsv::<Audience>(&data).expect("valid fixture");
+        let badges: Vec<_> = items.iter().map(|item| item.badge()).collect();
+        assert_eq!(badges, [None, None, Some(Badge::Deprecated), Some(Badge::Extension), Some(Badge::Extension), Some(Badge::Extension)]);
+    }
+
+    #[test]
+    fn bundled_notes_are_not_extensions() {
+        let taxonomies = Taxonomies::load(|_| {}).expect("bundled data loads");
+        for taxonomy in Taxonomy::ALL {
+            let marked: Vec<_> = taxonomies.items(taxonomy).into_iter().filter(|item| item.badge() == Some(Badge::Extension)).map(|item| item.unique_id().to_string()).collect();
+            assert!(marked.is_empty(), "{} marks official rows {:?}", taxonomy.name(), marked);
+        }
+    }
+
+    #[test]
+    fn parse_repairs_ragged_crlf_rows() {
+        let data = [
+            "Unique ID\tParent ID\tName\tTier 1\tTier 2\tTier 3\r\n",
+            "1\t\tA\tA\t\t\r\n",
+            "\r\n",
+            "2\t1\tB\r\n",
+            "3\t1\tC\tA\tC\t\t\t\r\n",
+            "4\t1\tD\tA\tD\t\textra\r\n",
+        ]
+        .concat();
+        let parsed = parse_tsv::<Product>(&data, Quoting::Standard, None).expect("repairable fixture");
+
+        let names: Vec<_> = parsed.items.iter().map(|item| item.name()).collect();
+        assert_eq!(names, ["A", "B", "C", "D"]);
+        // Line numbers count the empty line and are not thrown off by the CRs
+        assert_eq!(parsed.lines, [2, 4, 5, 6]);
+        assert_eq!(parsed.repairs.padded, [4]);
+        assert_eq!(parsed.repairs.trimmed, [5]);
+        assert_eq!(parsed.repairs.dropped, [6]);
+        assert_eq!(parsed.raw.cells_in(data.as_bytes(), "2").map(|cells| cells.len()), Some(6));
+        assert_eq!(
+            parsed.repairs.warnings(6, 0),
+            [
+                "short rows padded to 6 columns on line 4",
+                "rows with trailing empty columns trimmed on line 5",
+                "rows with values beyond column 6 cut, values dropped on line 6",
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_skips_blank_rows() {
+        let data = format!("{}1\t\tA\t\t\t\n\t\t\t\t\t\n2\t1\tB\t\t\t\n", PRODUCT_HEADER);
+        let parsed = parse_tsv::<Product>(&data, Quoting::Standard, None).expect("valid fixture");
+        assert_eq!(parsed.items.len(), 2);
+        assert_eq!(parsed.repairs.blank, [3]);
+    }
+
+    #[test]
+    fn raw_rows_are_read_back_from_the_mapped_file() {
+        let path = std::env::temp_dir().join(format!("iab-raw-{}.tsv", std::process::id()));
+        let data = format!("{}1\t\tA\r\n2\t1\t\"B\tquoted\"\tA\tB\t\textra\r\n", PRODUCT_HEADER);
+        std::fs::write(&path, data).expect("writable temp dir");
+        let loaded = load_taxonomy::<Product>(Taxonomy::Product, "", Some(&path), &LoadOptions::default());
+        std::fs::remove_file(&path).ok();
+
+        let (_, mut raw, _) = loaded.expect("repairable file");
+        assert_eq!(raw.cells("1").unwrap(), ["1", "", "A", "", "", ""]);
+        assert_eq!(raw.cells("2").unwrap(), ["2", "1", "B\tquoted", "A", "B", ""]);
+        assert_eq!(raw.row("2").unwrap()[2], ("Name".to_string(), "B\tquoted".to_string()));
+        raw.insert("2", vec!["2".to_string(), "1".to_string(), "Edited".to_string()]);
+        assert_eq!(raw.cells("2").unwrap()[2], "Edited");
+        assert!(raw.cells("3").is_none());
+    }
+
+    #[test]
+    fn bundled_raw_rows_keep_their_cells() {
+        let taxonomies = Taxonomies::load(|_| {}).expect("bundled data loads");
+        let cells = taxonomies.raw_tables[Taxonomy::Content.index()].cells("150").expect("bundled row");
+        assert_eq!(&cells[..3], ["150", "", "Attractions"]);
+        assert_eq!(cells.len(), taxonomies.raw_tables[Taxonomy::Content.index()].width());
+    }
+
+    #[test]
+    fn file_with_byte_order_mark_reads_its_first_column() {
+        let path = std::env::temp_dir().join(format!("iab-bom-{}.tsv", std::process::id()));
+        std::fs::write(&path, format!("\u{feff}{}1\t\tA\tA\t\t\n", PRODUCT_HEADER)).expect("writable temp dir");
+        let loaded = load_taxonomy::<Product>(Taxonomy::Product, "", Some(&path), &LoadOptions::default());
+        std::fs::remove_file(&path).ok();
+
+        let (items, raw, warnings) = loaded.expect("BOM is stripped");
+        assert_eq!(items[0].unique_id(), "1");
+        assert_eq!(raw.headers[0], "Unique ID");
+        assert!(warnings.is_empty(), "unexpected warnings {:?}", warnings);
+    }
+}
@@ -0,0 +1,87 @@
+//! Values per node, keyed by taxonomy and version (`content-3.1`) and then node ID: the storage
+//! notes and tags share, so both persist, look up and prune entries the same way.
+
+use anyhow::*;
+use iab::Taxonomy;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::state::Store;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TaxonomyStore<V> {
+    taxonomies: BTreeMap<String, BTreeMap<String, V>>,
+}
+
+impl<V> Default for TaxonomyStore<V> {
+    fn default() -> Self {
+        Self { taxonomies: BTreeMap::new() }
+    }
+}
+
+impl<V: Serialize + DeserializeOwned> TaxonomyStore<V> {
+    /// Load the values from `store`; a missing file yields none
+    pub fn load(store: &Store) -> Result<Self> {
+        Ok(store.load()?.unwrap_or_default())
+    }
+
+    pub fn save(&self, store: &Store) -> Result<()> {
+        store.save(self)
+    }
+}
+
+impl<V> TaxonomyStore<V> {
+    fn scope(taxonomy: Taxonomy) -> String {
+        format!("{}-{}", taxonomy.key(), taxonomy.version())
+    }
+
+    /// Values of a taxonomy by node ID
+    pub fn of(&self, taxonomy: Taxonomy) -> Option<&BTreeMap<String, V>> {
+        self.taxonomies.get(&Self::scope(taxonomy))
+    }
+
+    pub fn get(&self, taxonomy: Taxonomy, id: &str) -> Option<&V> {
+        self.of(taxonomy)?.get(id)
+    }
+
+    /// Replace the value of `id`; `None` removes it, and the taxonomy's entry with its last value
+    pub fn set(&mut self, taxonomy: Taxonomy, id: &str, value: Option<V>) {
+        let scope = Self::scope(taxonomy);
+        match value {
+            Some(value) => {
+                self.taxonomies.entry(scope).or_default().insert(id.to_string(), value);
+            }
+            None => {
+                if let Some(values) = self.taxonomies.get_mut(&scope) {
+                    values.remove(id);
+                    if values.is_empty() {
+                        self.taxonomies.remove(&scope);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removing_the_last_value_drops_the_taxonomy() {
+        let mut store = TaxonomyStore::default();
+        store.set(Taxonomy::Content, "150", Some("first".to_string()));
+        store.set(Taxonomy::Content, "151", Some("second".to_string()));
+        assert_eq!(serde_json::to_value(&store).unwrap(), serde_json::json!({ "content-3.1": { "150": "first", "151": "second" } }));
+
+        store.set(Taxonomy::Content, "150", None);
+        assert_eq!(store.get(Taxonomy::Content, "151").map(String::as_str), Some("second"));
+        store.set(Taxonomy::Content, "151", None);
+        assert!(store.of(Taxonomy::Content).is_none());
+        // Removing from a taxonomy without values changes nothing
+        store.set(Taxonomy::Audience, "1", None);
+        assert_eq!(serde_json::to_string(&store).unwrap(), "{}");
+    }
+}
@@ -0,0 +1,50 @@
+//! Color themes. The interface is drawn with one palette meant for dark terminals; a theme maps
+//! the colors of the finished frame, so the renderers never have to know which one is active.
+
+use ratatui::buffer::Buffer;
+use ratatui::style::Color;
+
+/// Selection and popup background of the dark palette
+const DARK_HIGHLIGHT: Color = Color::Rgb(30, 30, 30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, clap::ValueEnum)]
+pub enum Theme {
+    /// Light text and bright accents on a dark background, as drawn
+    #[default]
+    Dark,
+    /// Dark text and deeper accents for terminals with a light background
+    Light,
+}
+
+impl Theme {
+    fn color(self, color: Color) -> Color {
+        match self {
+            Theme::Dark => color,
+            Theme::Light => match color {
+                Color::White => Color::Black,
+                Color::Black => Color::White,
+                Color::Gray => Color::DarkGray,
+                Color::DarkGray => Color::Gray,
+                Color::Yellow => Color::Rgb(160, 110, 0),
+                Color::LightYellow => Color::Rgb(125, 85, 0),
+                Color::Cyan => Color::Rgb(0, 120, 140),
+                Color::LightCyan => Color::Rgb(0, 95, 115),
+                Color::Green => Color::Rgb(0, 130, 0),
+                Color::LightGreen => Color::Rgb(0, 100, 0),
+                DARK_HIGHLIGHT => Color::Rgb(225, 225, 225),
+                color => color,
+            },
+        }
+    }
+
+    /// Recolor every cell of a drawn frame
+    pub fn apply(self, buffer: &mut Buffer) {
+        if self == Theme::Dark {
+            return;
+        }
+        for cell in &mut buffer.content {
+            cell.fg = self.color(cell.fg);
+            cell.bg = self.color(cell.bg);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Node names in another language, e.g. an IAB-provided translation of the Content taxonomy.
+//!
+//! A translation is a CSV or TSV file (tab-separated when its header line contains a tab) with an
+//! ID column (`Unique ID` or `id`, else the first) and a name column (`Name`, else the third when
+//! the file has the taxonomy's layout, else the second). Translated taxonomy files can therefore be
+//! loaded as they are. IDs stay those of the original, so only names change.
+
+use anyhow::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default)]
+pub struct Translation {
+    /// Shown when switching to the translation; `load` uses the file name without extension
+    label: String,
+    names: HashMap<String, String>,
+}
+
+impl Translation {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        let label = path.file_stem().map_or_else(String::new, |stem| stem.to_string_lossy().into_owned());
+        Self::parse(&data, &label).with_context(|| format!("cannot parse {}", path.display()))
+    }
+
+    pub fn parse(data: &str, label: &str) -> Result<Self> {
+        let tabs = data.lines().next().is_some_and(|header| header.contains('\t'));
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(if tabs { b'\t' } else { b',' })
+            .has_headers(true)
+            .flexible(true)
+            .from_reader(data.as_bytes());
+
+        let headers = reader.headers()?.clone();
+        if headers.len() < 2 {
+            bail!("expected an ID and a name column");
+        }
+        let normalized: Vec<String> = headers.iter().map(|header| header.trim().to_lowercase().replace(['_', ' '], "")).collect();
+        let id_column = normalized.iter().position(|header| header == "uniqueid" || header == "id").unwrap_or(0);
+        let name_column = normalized
+            .iter()
+            .position(|header| header == "name")
+            .unwrap_or(if headers.len() >= 3 { 2 } else { 1 });
+        if id_column == name_column {
+            bail!("the ID and the name are in the same column");
+        }
+
+        let mut names = HashMap::new();
+        for record in reader.records() {
+            let record = record?;
+            let (Some(id), Some(name)) = (record.get(id_column), record.get(name_column)) else {
+                continue;
+            };
+            let (id, name) = (id.trim(), name.trim());
+            if id.is_empty() || name.is_empty() {
+                continue;
+            }
+            names.insert(id.to_string(), name.to_string());
+        }
+        if names.is_empty() {
+            bail!("no translated names");
+        }
+
+        Ok(Self { label: label.to_string(), names })
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.names.keys().map(String::as_str)
+    }
+
+    /// Translated name of `id`, if the file has one
+    pub fn name(&self, id: &str) -> Option<&str> {
+        self.names.get(id).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn translated_taxonomy_layout_reads_the_name_column() {
+        let data = "Unique ID\tParent\tName\tTier 1\tTier 2\n150\t\tSehenswürdigkeiten\tSehenswürdigkeiten\t\n155\t150\t Museen \tSehenswürdigkeiten\tMuseen\n156\t150\t\t\t\n";
+        let translation = Translation::parse(data, "de").unwrap();
+        assert_eq!(translation.label(), "de");
+        assert_eq!(translation.len(), 2);
+        assert_eq!(translation.name("155"), Some("Museen"));
+        assert_eq!(translation.name("156"), None);
+
+        // With the headers translated too, the ID is the first column and the name the third
+        let data = "Eindeutige ID\tÜbergeordnet\tBezeichnung\tStufe 1\n150\t\tSehenswürdigkeiten\tSehenswürdigkeiten\n";
+        assert_eq!(Translation::parse(data, "de").unwrap().name("150"), Some("Sehenswürdigkeiten"));
+    }
+
+    #[test]
+    fn two_column_csv_reads_id_and_name() {
+        let translation = Translation::parse("id,nom\n150,Attractions touristiques\n\"155\",\"Musées, galeries\"\n", "fr").unwrap();
+        assert_eq!(translation.name("150"), Some("Attractions touristiques"));
+        assert_eq!(translation.name("155"), Some("Musées, galeries"));
+        assert!(Translation::parse("id\n150\n", "fr").is_err());
+    }
+}
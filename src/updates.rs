@@ -0,0 +1,167 @@
+//! Release update checks: the bundled taxonomy versions against the TSV files IAB Tech Lab
+//! publishes in its Taxonomies repository on GitHub, for `iab check-updates` and the opt-in
+//! `check-updates = true` startup check.
+//!
+//! The listing is fetched with `curl`, like the clipboard goes through the platform's copy command,
+//! so no HTTP client or TLS stack has to be linked in. `IAB_RELEASES_URL` points the check at a
+//! mirror (or a saved `file://` listing) with the same JSON layout.
+
+use anyhow::*;
+use iab::Taxonomy;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::io::ErrorKind;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+/// Every file of the IAB Tech Lab Taxonomies repository
+const RELEASES_URL: &str = "https://api.github.com/repos/InteractiveAdvertisingBureau/Taxonomies/git/trees/HEAD?recursive=1";
+
+/// Seconds the whole request may take, so a hanging network does not hold up `check-updates`
+const TIMEOUT_SECONDS: &str = "15";
+
+#[derive(Deserialize)]
+struct Listing {
+    tree: Vec<Entry>,
+}
+
+#[derive(Deserialize)]
+struct Entry {
+    path: String,
+}
+
+/// The bundled and newest published version of one taxonomy
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub taxonomy: Taxonomy,
+    pub bundled: &'static str,
+    /// `None` when the listing has no release file for the taxonomy
+    pub latest: Option<String>,
+}
+
+impl Release {
+    pub fn outdated(&self) -> bool {
+        self.latest.as_deref().is_some_and(|latest| compare_versions(latest, self.bundled) == Ordering::Greater)
+    }
+}
+
+/// Fetch the listing and compare every taxonomy
+pub fn check() -> Result<Vec<Release>> {
+    let url = std::env::var("IAB_RELEASES_URL").unwrap_or_else(|_| RELEASES_URL.to_string());
+    let listing = fetch(&url)?;
+    releases(&listing).with_context(|| format!("unexpected listing from {}", url))
+}
+
+/// Run `check` on a background thread; the receiver yields its result once
+pub fn spawn() -> Receiver<Result<Vec<Release>>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(check());
+    });
+    receiver
+}
+
+/// Compare the bundled versions with the newest release file of each taxonomy in `listing`
+pub fn releases(listing: &str) -> Result<Vec<Release>> {
+    let listing: Listing = serde_json::from_str(listing)?;
+    Ok(Taxonomy::ALL
+        .into_iter()
+        .map(|taxonomy| {
+            let latest = listing
+                .tree
+                .iter()
+                .filter_map(|entry| release_version(taxonomy, &entry.path))
+                .max_by(|a, b| compare_versions(a, b));
+            Release { taxonomy, bundled: taxonomy.version(), latest: latest.map(str::to_string) }
+        })
+        .collect())
+}
+
+/// Start of the published file names, e.g. `Content Taxonomy 3.1.tsv`
+fn file_prefix(taxonomy: Taxonomy) -> &'static str {
+    match taxonomy {
+        Taxonomy::Product => "Ad Product Taxonomy ",
+        Taxonomy::Content => "Content Taxonomy ",
+        Taxonomy::Audience => "Audience Taxonomy ",
+    }
+}
+
+/// Version of a release TSV of `taxonomy`; drafts and translations carry a suffix and are skipped
+fn release_version(taxonomy: Taxonomy, path: &str) -> Option<&str> {
+    let name = path.rsplit('/').next()?;
+    let version = name.strip_prefix(file_prefix(taxonomy))?.strip_suffix(".tsv")?;
+    let numeric = !version.is_empty() && version.split('.').all(|part| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_digit()));
+    numeric.then_some(version)
+}
+
+/// Order dotted versions by their numeric parts, so 3.10 is newer than 3.9 and 3.1 equals 3.1.0
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |version: &str| version.split('.').map(|part| part.parse::<u64>().unwrap_or(0)).collect::<Vec<_>>();
+    let (a, b) = (parts(a), parts(b));
+    (0..a.len().max(b.len()))
+        .map(|index| a.get(index).unwrap_or(&0).cmp(b.get(index).unwrap_or(&0)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+fn fetch(url: &str) -> Result<String> {
+    let output = Command::new("curl")
+        .args(["--fail", "--silent", "--show-error", "--location", "--max-time", TIMEOUT_SECONDS])
+        .args(["--header", "Accept: application/vnd.github+json"])
+        .arg(url)
+        .stdin(Stdio::null())
+        .output();
+    let output = match output {
+        Result::Ok(output) => output,
+        Err(err) if err.kind() == ErrorKind::NotFound => bail!("checking for updates needs curl"),
+        Err(err) => return Err(err).context("cannot run curl"),
+    };
+    if !output.status.success() {
+        bail!("cannot fetch {}: {}", url, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    String::from_utf8(output.stdout).context("the release listing is not UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_compare_by_number() {
+        assert_eq!(compare_versions("3.10", "3.9"), Ordering::Greater);
+        assert_eq!(compare_versions("3.1", "3.1.0"), Ordering::Equal);
+        assert_eq!(compare_versions("2.2", "3.0"), Ordering::Less);
+        assert_eq!(compare_versions("1.1.1", "1.1"), Ordering::Greater);
+    }
+
+    #[test]
+    fn drafts_and_translations_are_not_releases() {
+        assert_eq!(release_version(Taxonomy::Content, "Content Taxonomy 3.1.tsv"), Some("3.1"));
+        assert_eq!(release_version(Taxonomy::Content, "Content Taxonomies/Content Taxonomy 3.0.tsv"), Some("3.0"));
+        assert_eq!(release_version(Taxonomy::Content, "Content Taxonomy 3.2 DRAFT.tsv"), None);
+        assert_eq!(release_version(Taxonomy::Content, "Content Taxonomy 3.1 - French.tsv"), None);
+        assert_eq!(release_version(Taxonomy::Content, "Content Taxonomy 3.1.xlsx"), None);
+        assert_eq!(release_version(Taxonomy::Content, "Audience Taxonomy 1.1.tsv"), None);
+        assert_eq!(release_version(Taxonomy::Product, "Ad Product Taxonomy 2.0.tsv"), Some("2.0"));
+    }
+
+    #[test]
+    fn releases_pick_the_newest_file_per_taxonomy() {
+        let listing = r#"{"sha": "abc", "tree": [
+            {"path": "Content Taxonomies/Content Taxonomy 3.9.tsv", "type": "blob"},
+            {"path": "Content Taxonomies/Content Taxonomy 3.10.tsv", "type": "blob"},
+            {"path": "Content Taxonomies/Content Taxonomy 4.0 DRAFT.tsv", "type": "blob"},
+            {"path": "Audience Taxonomies/Audience Taxonomy 1.1.tsv", "type": "blob"},
+            {"path": "README.md", "type": "blob"}
+        ]}"#;
+        let checked = releases(listing).unwrap();
+        let found: Vec<(Taxonomy, Option<&str>, bool)> =
+            checked.iter().map(|release| (release.taxonomy, release.latest.as_deref(), release.outdated())).collect();
+        assert_eq!(
+            found,
+            [(Taxonomy::Product, None, false), (Taxonomy::Content, Some("3.10"), true), (Taxonomy::Audience, Some("1.1"), false)]
+        );
+        assert!(releases("{}").is_err());
+    }
+}
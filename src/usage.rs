@@ -0,0 +1,163 @@
+use anyhow::*;
+use iab::ChildIndex;
+use ratatui::style::Color;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Heat colors from barely used to the busiest branch
+const HEAT: [Color; 5] = [Color::Blue, Color::Cyan, Color::Green, Color::Yellow, Color::Red];
+
+/// Per-ID usage numbers of one taxonomy from a `--usage` CSV: an `id` column and one numeric
+/// column per metric (impressions, revenue, ...). Totals add up each node's branch, so a tier 1
+/// category shows the traffic of everything below it.
+#[derive(Debug, Default)]
+pub struct Usage {
+    metrics: Vec<String>,
+    /// Values as given, by ID in metric order
+    own: HashMap<String, Vec<f64>>,
+    /// Own value plus the descendants', by item position and metric; see `roll_up`
+    totals: Vec<Vec<f64>>,
+    /// Highest total per metric, the top of the heat scale
+    max: Vec<f64>,
+}
+
+impl Usage {
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = fs::read_to_string(path).with_context(|| format!("cannot read {}", path.display()))?;
+        Self::parse(&data).with_context(|| format!("cannot parse {}", path.display()))
+    }
+
+    /// Parse CSV with an `id` (or `unique id`) column; every other column is a metric, empty cells
+    /// count as zero and thousands separators are ignored
+    pub fn parse(data: &str) -> Result<Self> {
+        let mut reader = csv::Reader::from_reader(data.as_bytes());
+        let headers = reader.headers()?.clone();
+        let id_column = headers
+            .iter()
+            .position(|header| matches!(header.trim().to_lowercase().as_str(), "id" | "unique id" | "unique_id"))
+            .context("no id column")?;
+        let metric_columns: Vec<usize> = (0..headers.len()).filter(|&column| column != id_column).collect();
+        if metric_columns.is_empty() {
+            bail!("no metric columns besides the id, e.g. impressions");
+        }
+
+        let mut own: HashMap<String, Vec<f64>> = HashMap::new();
+        for (row, record) in reader.records().enumerate() {
+            let record = record?;
+            let line = row + 2;
+            let id = record.get(id_column).unwrap_or_default().trim();
+            if id.is_empty() {
+                continue;
+            }
+            let values = metric_columns
+                .iter()
+                .map(|&column| {
+                    let cell: String = record.get(column).unwrap_or_default().chars().filter(|c| !matches!(c, ',' | '_' | ' ')).collect();
+                    if cell.is_empty() {
+                        return Ok(0.0);
+                    }
+                    cell.parse::<f64>().with_context(|| format!("line {}: {} is not a number in column {}", line, cell, &headers[column]))
+                })
+                .collect::<Result<Vec<f64>>>()?;
+            // Repeated IDs, e.g. one row per day, add up
+            let entry = own.entry(id.to_string()).or_insert_with(|| vec![0.0; values.len()]);
+            for (sum, value) in entry.iter_mut().zip(values) {
+                *sum += value;
+            }
+        }
+
+        let metrics = metric_columns.iter().map(|&column| headers[column].trim().to_string()).collect();
+        Ok(Self { metrics, own, totals: Vec::new(), max: Vec::new() })
+    }
+
+    pub fn metrics(&self) -> &[String] {
+        &self.metrics
+    }
+
+    /// Recompute branch totals for the positions of `index`, returning IDs the taxonomy does not have
+    pub fn roll_up(&mut self, index: &ChildIndex, len: usize) -> Vec<String> {
+        self.totals = vec![vec![0.0; self.metrics.len()]; len];
+        let mut unknown = Vec::new();
+        for (id, values) in &self.own {
+            let Some(position) = index.position(id) else {
+                unknown.push(id.clone());
+                continue;
+            };
+            for target in index.ancestors(position).into_iter().chain([position]) {
+                for (total, value) in self.totals[target].iter_mut().zip(values) {
+                    *total += value;
+                }
+            }
+        }
+        self.max = (0..self.metrics.len())
+            .map(|metric| self.totals.iter().map(|totals| totals[metric]).fold(0.0, f64::max))
+            .collect();
+        unknown.sort();
+        unknown
+    }
+
+    pub fn own(&self, id: &str, metric: usize) -> Option<f64> {
+        self.own.get(id).and_then(|values| values.get(metric)).copied()
+    }
+
+    pub fn total(&self, position: usize, metric: usize) -> f64 {
+        self.totals.get(position).and_then(|totals| totals.get(metric)).copied().unwrap_or(0.0)
+    }
+
+    /// Heat color of a branch total on a log scale up to the busiest branch; none for zero
+    pub fn heat(&self, position: usize, metric: usize) -> Option<Color> {
+        let total = self.total(position, metric);
+        let max = self.max.get(metric).copied().unwrap_or(0.0);
+        if total <= 0.0 || max <= 0.0 {
+            return None;
+        }
+        let share = total.ln_1p() / max.ln_1p();
+        Some(HEAT[((share * HEAT.len() as f64) as usize).min(HEAT.len() - 1)])
+    }
+}
+
+/// Short form of a number for the tree, e.g. 1234 → 1.2k, 5600000 → 5.6M
+pub fn compact(value: f64) -> String {
+    let magnitude = value.abs();
+    let (scaled, suffix) = if magnitude >= 1e9 {
+        (value / 1e9, "G")
+    } else if magnitude >= 1e6 {
+        (value / 1e6, "M")
+    } else if magnitude >= 1e3 {
+        (value / 1e3, "k")
+    } else {
+        return if value.fract() == 0.0 { format!("{}", value) } else { format!("{:.2}", value) };
+    };
+    format!("{:.1}{}", scaled, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_ids_add_up_and_roll_up_to_ancestors() {
+        let data = "Unique ID,impressions,revenue\n3,\"1,000\",2.5\n3,500,\n2,1 000,1\n99,7,0\n,5,5\n";
+        let mut usage = Usage::parse(data).unwrap();
+        assert_eq!(usage.metrics(), ["impressions", "revenue"]);
+        assert_eq!(usage.own("3", 0), Some(1500.0));
+        assert_eq!(usage.own("3", 1), Some(2.5));
+
+        let (items, _) = iab::taxonomy::read_tsv::<iab::Product>("Unique ID\tParent ID\tName\tTier 1\tTier 2\tTier 3\n1\t\tRoot\n2\t1\tA\n3\t2\tA1\n4\t\tOther\n").unwrap();
+        let unknown = usage.roll_up(&ChildIndex::new(&items), items.len());
+        assert_eq!(unknown, ["99"]);
+        let impressions: Vec<f64> = (0..items.len()).map(|position| usage.total(position, 0)).collect();
+        assert_eq!(impressions, [2500.0, 2500.0, 1500.0, 0.0]);
+        assert_eq!(usage.total(0, 1), 3.5);
+        assert_eq!(usage.heat(0, 0), Some(Color::Red));
+        assert_eq!(usage.heat(3, 0), None);
+    }
+
+    #[test]
+    fn cells_that_are_not_numbers_name_their_line() {
+        let err = Usage::parse("id,impressions\n1,12\n2,lots\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 3: lots is not a number in column impressions");
+        assert!(Usage::parse("name,impressions\nA,1\n").is_err());
+    }
+}
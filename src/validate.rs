@@ -0,0 +1,247 @@
+//! Structural checks of a loaded taxonomy, as reported by `iab validate`, and the data-integrity
+//! checks `iab lint` adds on top of them.
+
+use serde::Serialize;
+use std::fmt;
+
+use crate::{Badge, NodeSet, Taxonomies, Taxonomy};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The row is shown elsewhere than its data says, or not at all
+    Error,
+    /// The row loads as written but probably is not what was meant
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "check", rename_all = "snake_case")]
+pub enum Issue {
+    /// The row's parent ID does not exist, so it hangs under the TUI's "⚠ Orphans" branch
+    Orphan { parent: String },
+    /// Following parents leads back to the row; the cycle was cut here and the row shown as a root
+    Cycle { members: Vec<String> },
+    /// An earlier row has the same ID; only that one is shown and looked up
+    Duplicate { first_name: String },
+    /// The name column is blank
+    EmptyName,
+    /// The row fills a different number of tier columns than its depth in the hierarchy, which
+    /// the bundled Product and Audience data do in places
+    TierDepth { tiers: usize, depth: usize },
+    /// A tier column names something else than the ancestor at that depth (or, for the last tier,
+    /// the row itself), typically a copy-paste slip in a hand-edited sheet
+    TierName { tier: usize, found: String, expected: String },
+    /// The row is live but its parent is marked deprecated
+    DeprecatedParent { parent: String },
+}
+
+impl Issue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Issue::TierDepth { .. } | Issue::TierName { .. } | Issue::DeprecatedParent { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+}
+
+/// One offending row
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub issue: Issue,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.issue {
+            Issue::Orphan { parent } => write!(f, "{} {}: parent {} does not exist", self.id, self.name, parent),
+            Issue::Cycle { members } => {
+                let chain: Vec<&str> = members.iter().chain(members.first()).map(String::as_str).collect();
+                write!(f, "{} {}: parent cycle {}, shown as a top-level node", self.id, self.name, chain.join(" → "))
+            }
+            Issue::Duplicate { first_name } => {
+                write!(f, "{} {}: ID already used by \"{}\", row left out", self.id, self.name, first_name)
+            }
+            Issue::EmptyName => write!(f, "{}: name is empty", self.id),
+            Issue::TierDepth { tiers, depth } => {
+                write!(f, "{} {}: {} tier columns filled at depth {}", self.id, self.name, tiers, depth)
+            }
+            Issue::TierName { tier, found, expected } => {
+                write!(f, "{} {}: Tier {} reads \"{}\" where the hierarchy has \"{}\"", self.id, self.name, tier, found, expected)
+            }
+            Issue::DeprecatedParent { parent } => {
+                write!(f, "{} {}: parent {} is deprecated", self.id, self.name, parent)
+            }
+        }
+    }
+}
+
+/// Problems in one taxonomy: duplicates and orphans in file order, then cycles
+pub fn validate(taxonomies: &Taxonomies, taxonomy: Taxonomy) -> Vec<Finding> {
+    let index = taxonomies.child_index(taxonomy);
+    let finding = |position: usize, issue: Issue| {
+        let item = taxonomies.item_at(taxonomy, position)?;
+        Some(Finding { id: item.unique_id().to_string(), name: item.name().to_string(), issue })
+    };
+
+    let duplicates = index.duplicates().iter().filter_map(|&position| {
+        let first = taxonomies.item(taxonomy, index.id(position))?;
+        finding(position, Issue::Duplicate { first_name: first.name().to_string() })
+    });
+    let orphans = index.orphans().filter_map(|position| {
+        let parent = taxonomies.item_at(taxonomy, position)?.parent().unwrap_or_default().to_string();
+        finding(position, Issue::Orphan { parent })
+    });
+    let cycles = index.cycles().iter().filter_map(|members| {
+        let ids = members.iter().map(|&member| index.id(member).to_string()).collect();
+        finding(*members.first()?, Issue::Cycle { members: ids })
+    });
+    duplicates.chain(orphans).chain(cycles).collect()
+}
+
+/// Everything `validate` finds, then per row in file order: empty names, tier columns that do
+/// not match the depth or the names along the path, and live rows below deprecated parents
+pub fn lint(taxonomies: &Taxonomies, taxonomy: Taxonomy) -> Vec<Finding> {
+    let index = taxonomies.child_index(taxonomy);
+    let items = taxonomies.items(taxonomy);
+    let mut findings = validate(taxonomies, taxonomy);
+
+    // Rows hanging off an orphan or a cut cycle have no reliable depth; their root is already reported
+    let mut detached = NodeSet::new(items.len());
+    for position in index.orphans().chain(index.cycles().iter().filter_map(|members| members.first().copied())) {
+        detached.insert(position);
+    }
+    let mut duplicates = NodeSet::new(items.len());
+    for &position in index.duplicates() {
+        duplicates.insert(position);
+    }
+
+    for (position, item) in items.into_iter().enumerate() {
+        if duplicates.contains(position) {
+            continue;
+        }
+        let finding = |issue| Finding { id: item.unique_id().to_string(), name: item.name().to_string(), issue };
+
+        if item.name().trim().is_empty() {
+            findings.push(finding(Issue::EmptyName));
+        }
+
+        let ancestors = index.ancestors(position);
+        let root = ancestors.first().copied().unwrap_or(position);
+        if !detached.contains(root) {
+            let tiers = item.tiers();
+            let depth = ancestors.len() + 1;
+            if tiers.len() != depth {
+                findings.push(finding(Issue::TierDepth { tiers: tiers.len(), depth }));
+            } else {
+                let path = ancestors
+                    .iter()
+                    .filter_map(|&ancestor| taxonomies.item_at(taxonomy, ancestor))
+                    .chain([item])
+                    .map(|node| leaf_name(node.name()));
+                // Only the first mismatch per row, as later tiers usually repeat it
+                if let Some((tier, (found, expected))) =
+                    tiers.iter().zip(path).enumerate().find(|(_, (found, expected))| found.trim() != *expected)
+                {
+                    findings.push(finding(Issue::TierName { tier: tier + 1, found: found.to_string(), expected: expected.to_string() }));
+                }
+            }
+        }
+
+        if item.badge() != Some(Badge::Deprecated)
+            && let Some(parent) = index.parent(position).and_then(|parent| taxonomies.item_at(taxonomy, parent))
+            && parent.badge() == Some(Badge::Deprecated)
+        {
+            findings.push(finding(Issue::DeprecatedParent { parent: parent.unique_id().to_string() }));
+        }
+    }
+    findings
+}
+
+/// The last segment of a name, as Audience names spell out their path ("Demographic | Age Range | 18-20 |")
+fn leaf_name(name: &str) -> &str {
+    name.rsplit('|').map(str::trim).find(|segment| !segment.is_empty()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::taxonomy::read_tsv;
+    use crate::{Audience, ChildIndex, Product};
+
+    const AUDIENCE_HEADER: &str =
+        "Unique ID\tParent ID\tCondensed Name (1st, 2nd, Last Tier)\tTier 1\tTier 2\tTier 3\tTier 4\tTier 5\tTier 6\t*Extension Notes\n";
+
+    /// The bundled taxonomies with Audience replaced by `rows` under the Audience header
+    fn with_audience(rows: &[&str]) -> Taxonomies {
+        let mut taxonomies = Taxonomies::load(|_| {}).expect("bundled data loads");
+        let data = format!("{}{}", AUDIENCE_HEADER, rows.concat());
+        taxonomies.audience = read_tsv::<Audience>(&data).expect("valid fixture").0;
+        taxonomies.indexes[Taxonomy::Audience.index()] = ChildIndex::new(&taxonomies.audience);
+        taxonomies
+    }
+
+    /// The bundled taxonomies with Product replaced by `ID<TAB>parent<TAB>name` rows
+    fn with_products(rows: &str) -> Taxonomies {
+        let mut taxonomies = Taxonomies::load(|_| {}).expect("bundled data loads");
+        let data = format!("Unique ID\tParent ID\tName\tTier 1\tTier 2\tTier 3\n{}", rows);
+        taxonomies.products = read_tsv::<Product>(&data).expect("valid fixture").0;
+        taxonomies.indexes[Taxonomy::Product.index()] = ChildIndex::new(&taxonomies.products);
+        taxonomies
+    }
+
+    fn issues(findings: Vec<Finding>) -> Vec<(String, Issue)> {
+        findings.into_iter().map(|finding| (finding.id, finding.issue)).collect()
+    }
+
+    #[test]
+    fn duplicates_then_orphans_then_cycles() {
+        // The orphan, two-cycle and repeated-ID fixtures of the ChildIndex tests in one table
+        let taxonomies = with_products("1\t\tRoot\n2\t99\tLost\n3\t2\tUnder lost\n4\t5\tA\n5\t4\tB\n6\t1\tC\n6\t\tAgain\n");
+        let findings = validate(&taxonomies, Taxonomy::Product);
+        let names: Vec<&str> = findings.iter().map(|finding| finding.name.as_str()).collect();
+        assert_eq!(names, ["Again", "Lost", "A"]);
+        assert_eq!(
+            issues(findings),
+            [
+                ("6".to_string(), Issue::Duplicate { first_name: "C".to_string() }),
+                ("2".to_string(), Issue::Orphan { parent: "99".to_string() }),
+                ("4".to_string(), Issue::Cycle { members: vec!["4".to_string(), "5".to_string()] }),
+            ]
+        );
+    }
+
+    #[test]
+    fn lint_reports_tier_slips_and_live_rows_under_deprecated_parents() {
+        let taxonomies = with_audience(&[
+            "1\t\tDemographic\tDemographic\t\t\t\t\t\t\n",
+            "2\t1\tDemographic | Age Range |\tDemographic\tAge Range\t\t\t\t\t\n",
+            // Audience names spell out the path; only the last segment is compared
+            "3\t2\tDemographic | Age Range | 18-20 |\tDemographic\tAge Range\t18-20\t\t\t\t\n",
+            "4\t2\tDemographic | Age Range | 21-24 |\tDemographic\tAge Rnage\t21-24\t\t\t\t\n",
+            "5\t1\tDemographic | Income |\tDemographic\t\t\t\t\t\t\n",
+            "6\t\tOld\tOld\t\t\t\t\t\tDeprecated in 1.1\n",
+            "7\t6\tStill Here\tOld\tStill Here\t\t\t\t\t\n",
+            "8\t6\tAlso Old\tOld\tAlso Old\t\t\t\t\tDeprecated in 1.1\n",
+        ]);
+        let tier_name = Issue::TierName { tier: 2, found: "Age Rnage".to_string(), expected: "Age Range".to_string() };
+        assert_eq!(
+            issues(lint(&taxonomies, Taxonomy::Audience)),
+            [
+                ("4".to_string(), tier_name),
+                ("5".to_string(), Issue::TierDepth { tiers: 1, depth: 2 }),
+                ("7".to_string(), Issue::DeprecatedParent { parent: "6".to_string() }),
+            ]
+        );
+    }
+
+    #[test]
+    fn leaf_name_skips_the_trailing_pipe() {
+        assert_eq!(leaf_name("A | B | C |"), "C");
+        assert_eq!(leaf_name("Automotive"), "Automotive");
+        assert_eq!(leaf_name(" | "), "");
+    }
+}
@@ -0,0 +1,57 @@
+// IAB Tech Lab Content Taxonomy 3.1
+// Generated by `iab codegen typescript --taxonomy content`, do not edit.
+
+export const VERSION = "3.1";
+/** OpenRTB `cattax` (or `segtax`) value of this taxonomy */
+export const CATTAX = 7;
+
+export type CategoryId =
+  | "1"
+  | "10"
+  | "2"
+  | "9"
+  | "3"
+  | "4"
+  | "5"
+  | "6"
+  | "7"
+  | "8"
+  ;
+
+export interface Category {
+  readonly id: CategoryId;
+  readonly name: string;
+  readonly parent: CategoryId | null;
+}
+
+/** Category IDs by upper snake case tier path */
+export const CATEGORY_IDS = {
+  SPORTS_FITNESS: "1",
+  SPORTS_FITNESS_YOGA: "10",
+  SPORTS_FITNESS_2: "2",
+  SPORTS_FITNESS_3: "9",
+  SPORTS_FITNESS_3_2: "3",
+  CATEGORY_3D_PRINTING: "4",
+  CATEGORY_5: "5",
+  CATEGORY_6: "6",
+  UBER_QUOTES: "7",
+  OEUVRES_D_ART_CA_ET_LA: "8",
+} as const satisfies Record<string, CategoryId>;
+
+/** Every category by ID, parents before children */
+export const CATEGORIES: Readonly<Record<CategoryId, Category>> = {
+  "1": { id: "1", name: "Sports & Fitness", parent: null },
+  "10": { id: "10", name: "Yoga", parent: "1" },
+  "2": { id: "2", name: "Sports/Fitness", parent: null },
+  "9": { id: "9", name: "Sports Fitness 3", parent: null },
+  "3": { id: "3", name: "Sports+Fitness", parent: null },
+  "4": { id: "4", name: "3D Printing", parent: null },
+  "5": { id: "5", name: "!!!", parent: null },
+  "6": { id: "6", name: "???", parent: null },
+  "7": { id: "7", name: "Über \"Quotes\"", parent: null },
+  "8": { id: "8", name: "Œuvres d’art & Çà et là", parent: null },
+};
+
+export function isCategoryId(value: string): value is CategoryId {
+  return Object.prototype.hasOwnProperty.call(CATEGORIES, value);
+}
@@ -0,0 +1,53 @@
+//! Snapshot tests of the TUI: every `tests/headless/*.keys` script is replayed with `--headless`
+//! against the bundled taxonomies and the final screen compared to the `.txt` next to it.
+//!
+//! After an intended change to the interface, rewrite the expected screens with
+//! `IAB_UPDATE_SNAPSHOTS=1 cargo test --test headless` and review the diff.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Screen size of every snapshot, small enough to read in a diff
+const SIZE: &str = "100x30";
+
+fn screen(script: &Path) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_iab"))
+        .args(["--headless", "--size", SIZE, "--keys-file"])
+        .arg(script)
+        .env_remove("IAB_LANG")
+        .output()
+        .expect("iab runs");
+    assert!(output.status.success(), "{}: {}", script.display(), String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).expect("screen is UTF-8")
+}
+
+#[test]
+fn screens_match_snapshots() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/headless");
+    let update = std::env::var_os("IAB_UPDATE_SNAPSHOTS").is_some();
+    let mut scripts: Vec<_> = fs::read_dir(&dir)
+        .expect("tests/headless exists")
+        .map(|entry| entry.expect("readable entry").path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "keys"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty(), "no scripts in {}", dir.display());
+
+    let mut mismatched = Vec::new();
+    for script in &scripts {
+        let actual = screen(script);
+        let expected_path = script.with_extension("txt");
+        if update {
+            fs::write(&expected_path, &actual).expect("writable snapshot");
+            continue;
+        }
+        let expected = fs::read_to_string(&expected_path)
+            .unwrap_or_else(|err| panic!("{}: {} (create it with IAB_UPDATE_SNAPSHOTS=1)", expected_path.display(), err));
+        if actual != expected {
+            eprintln!("--- {}\n{}--- actual\n{}", expected_path.display(), expected, actual);
+            mismatched.push(script.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default());
+        }
+    }
+    assert!(mismatched.is_empty(), "screens differ from their snapshots: {}", mismatched.join(", "));
+}